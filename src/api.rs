@@ -0,0 +1,263 @@
+//! High-level API — one call per task, mirroring the CLI.
+//!
+//! Embedders who want times for a place and a date can use these functions
+//! instead of wiring the resolver, timezone parsing and solver together the
+//! way `main.rs` does. Every failure comes back as an [`Error`].
+//!
+//! ```
+//! use chrono::NaiveDate;
+//! use polaris_chronos::api::{prayer_times, PrayerTimesRequest};
+//!
+//! let request = PrayerTimesRequest::coordinates(21.4225, 39.8262)
+//!     .with_tz("Asia/Riyadh")
+//!     .with_date(NaiveDate::from_ymd_opt(2026, 3, 20).unwrap());
+//! let output = prayer_times(request)?;
+//! println!("Fajr in Mecca: {}", output.events.fajr.time.unwrap());
+//! # Ok::<(), polaris_chronos::api::Error>(())
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::hijri::{self, RamadanInfo};
+use crate::location::{normalize_longitude, LocationError, LocationResolver, ResolveOptions, ResolvedLocation};
+use crate::qibla;
+use crate::solver::{Solver, SolverError, SolverOutput};
+use crate::timezone::{self, TzError};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// Anything that can go wrong in a high-level call.
+#[derive(Debug)]
+pub enum Error {
+    /// The city could not be resolved (not found, ambiguous, network...).
+    Location(LocationError),
+    /// Latitude outside -90..=90 or a non-finite longitude.
+    InvalidCoordinates { lat: f64, lon: f64 },
+    /// A timezone name that is not an IANA zone.
+    Timezone(TzError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Location(e) => write!(f, "{}", e),
+            Error::InvalidCoordinates { lat, lon } => {
+                write!(f, "Invalid coordinates ({}, {}). Lat: -90..90, Lon: any finite number", lat, lon)
+            }
+            Error::Timezone(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<LocationError> for Error {
+    fn from(e: LocationError) -> Self {
+        Error::Location(e)
+    }
+}
+
+impl From<TzError> for Error {
+    fn from(e: TzError) -> Self {
+        Error::Timezone(e)
+    }
+}
+
+impl From<SolverError> for Error {
+    fn from(e: SolverError) -> Self {
+        match e {
+            SolverError::InvalidCoordinates { lat, lon } => Error::InvalidCoordinates { lat, lon },
+            SolverError::Timezone(e) => Error::Timezone(e),
+        }
+    }
+}
+
+/// Where to compute times for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Place {
+    /// A city name, optionally with a country ("Medina, Saudi Arabia").
+    City(String),
+    Coordinates { lat: f64, lon: f64 },
+}
+
+/// Everything `prayer_times` needs; built like the CLI's `compute` flags.
+#[derive(Debug, Clone)]
+pub struct PrayerTimesRequest {
+    pub place: Place,
+    /// Day to compute; today (UTC) when `None`.
+    pub date: Option<NaiveDate>,
+    /// IANA zone replacing the resolved one (UTC for bare coordinates).
+    pub tz: Option<String>,
+    /// ISO country code narrowing a city lookup.
+    pub country: Option<String>,
+    /// Method, strategy and the other computation options.
+    pub config: Config,
+    /// Resolve cities from the cache and built-in dataset only.
+    pub offline: bool,
+}
+
+impl PrayerTimesRequest {
+    pub fn city(name: impl Into<String>) -> Self {
+        Self::for_place(Place::City(name.into()))
+    }
+
+    pub fn coordinates(lat: f64, lon: f64) -> Self {
+        Self::for_place(Place::Coordinates { lat, lon })
+    }
+
+    fn for_place(place: Place) -> Self {
+        Self { place, date: None, tz: None, country: None, config: Config::default(), offline: false }
+    }
+
+    pub fn with_date(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub fn with_tz(mut self, tz: impl Into<String>) -> Self {
+        self.tz = Some(tz.into());
+        self
+    }
+
+    pub fn with_country(mut self, country: impl Into<String>) -> Self {
+        self.country = Some(country.into());
+        self
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+}
+
+/// Resolve the place, apply the timezone and solve the day.
+///
+/// City lookups use the default on-disk cache; see [`prayer_times_with`]
+/// to supply a resolver.
+pub fn prayer_times(request: PrayerTimesRequest) -> Result<SolverOutput, Error> {
+    match request.place {
+        Place::City(_) => prayer_times_with(&mut LocationResolver::new(), request),
+        // Coordinates never touch the resolver or its cache
+        Place::Coordinates { lat, lon } => solve(manual_location(lat, lon)?, &request),
+    }
+}
+
+/// [`prayer_times`] with a caller-provided resolver (cache, provider, ...).
+/// An offline request leaves the resolver's own offline setting as it was.
+pub fn prayer_times_with(resolver: &mut LocationResolver, request: PrayerTimesRequest) -> Result<SolverOutput, Error> {
+    let resolved = match &request.place {
+        Place::City(name) => {
+            let opts = ResolveOptions { country: request.country.clone(), ..ResolveOptions::default() };
+            let was_offline = resolver.is_offline();
+            resolver.set_offline(was_offline || request.offline);
+            let resolved = resolver.resolve_city_with_opts(name, &opts);
+            resolver.set_offline(was_offline);
+            resolved?
+        }
+        &Place::Coordinates { lat, lon } => manual_location(lat, lon)?,
+    };
+    solve(resolved, &request)
+}
+
+fn manual_location(lat: f64, lon: f64) -> Result<ResolvedLocation, Error> {
+    check_coords(lat, lon)?;
+    Ok(LocationResolver::from_manual(lat, lon, None))
+}
+
+/// Apply the request's timezone override and solve its date.
+fn solve(resolved: ResolvedLocation, request: &PrayerTimesRequest) -> Result<SolverOutput, Error> {
+    let resolved = match request.tz.as_deref() {
+        Some(tz) => resolved.with_tz_override(timezone::parse_tz(tz)?.name()),
+        None => resolved,
+    };
+    let date = request.date.unwrap_or_else(|| SystemClock.today_utc());
+    let solver = Solver::try_from_resolved(&resolved)?.with_config(request.config);
+    Ok(solver.solve_with_info(date, false, false, Some(&resolved)))
+}
+
+/// Ramadan start and end for `hijri_year` as seen from (lat, lon); the
+/// next Ramadan (or the current one) when `hijri_year` is `None`.
+pub fn ramadan(lat: f64, lon: f64, hijri_year: Option<u32>) -> Result<RamadanInfo, Error> {
+    let lon = check_coords(lat, lon)?;
+    let year = hijri_year.unwrap_or_else(|| hijri::hijri_year_for_ramadan(&SystemClock));
+    Ok(hijri::find_ramadan(year, lat, lon))
+}
+
+/// Great-circle bearing to the Kaaba, in degrees clockwise from true north.
+pub fn qibla(lat: f64, lon: f64) -> Result<f64, Error> {
+    let lon = check_coords(lat, lon)?;
+    Ok(qibla::qibla_bearing(lat, lon))
+}
+
+/// Validate coordinates, returning the wrapped longitude.
+fn check_coords(lat: f64, lon: f64) -> Result<f64, Error> {
+    if !(-90.0..=90.0).contains(&lat) || !lon.is_finite() {
+        return Err(Error::InvalidCoordinates { lat, lon });
+    }
+    Ok(normalize_longitude(lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::cache::LocationCache;
+    use crate::method::CalculationMethod;
+    use crate::schedule::GapStrategy;
+    use crate::solver::Location;
+
+    fn json(output: &SolverOutput) -> serde_json::Value {
+        serde_json::to_value(output).unwrap()
+    }
+
+    #[test]
+    fn test_prayer_times_matches_solver() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let config = Config::default().with_method(CalculationMethod::ISNA).with_strategy(GapStrategy::Strict);
+        let output = prayer_times(
+            PrayerTimesRequest::coordinates(21.4225, 39.8262)
+                .with_tz("Asia/Riyadh")
+                .with_date(date)
+                .with_config(config),
+        ).unwrap();
+        println!("{:?}", output.events.fajr.time);
+
+        let resolved = LocationResolver::from_manual(21.4225, 39.8262, Some("Asia/Riyadh"));
+        let direct = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh)
+            .with_config(config)
+            .solve_with_info(date, false, false, Some(&resolved));
+        assert_eq!(json(&output), json(&direct));
+    }
+
+    #[test]
+    fn test_prayer_times_city_offline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut resolver = LocationResolver::with_cache(LocationCache::load_from(dir.path().join("cache.json")));
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let request = PrayerTimesRequest::city("Stockholm").with_date(date).with_offline(true);
+        let output = prayer_times_with(&mut resolver, request).unwrap();
+        assert_eq!(output.location.timezone, "Europe/Stockholm");
+        assert!(!resolver.is_offline(), "the resolver's own setting is restored");
+
+        let resolved = LocationResolver::from_manual(output.location.latitude, output.location.longitude, Some("Europe/Stockholm"));
+        let direct = Solver::from_resolved(&resolved).solve(date, false, false);
+        assert_eq!(json(&output)["events"], json(&direct)["events"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad_tz = prayer_times(PrayerTimesRequest::coordinates(21.4, 39.8).with_tz("Mars/Olympus_Mons"));
+        assert!(matches!(bad_tz, Err(Error::Timezone(_))));
+        assert!(matches!(prayer_times(PrayerTimesRequest::coordinates(91.0, 0.0)), Err(Error::InvalidCoordinates { .. })));
+        assert!(matches!(qibla(0.0, f64::NAN), Err(Error::InvalidCoordinates { .. })));
+
+        let bearing = qibla(59.3293, 18.0686).unwrap();
+        assert!((bearing - qibla::qibla_bearing(59.3293, 18.0686)).abs() < 1e-12);
+        let info = ramadan(21.4225, 39.8262, Some(1447)).unwrap();
+        assert_eq!(info.hijri_year, 1447);
+    }
+}
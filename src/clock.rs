@@ -0,0 +1,37 @@
+//! Time sources — where "now" comes from.
+//!
+//! Everything that depends on the current time (the current prayer, cache
+//! TTLs, the upcoming Ramadan, default dates in the API) reads it from a
+//! `Clock`, so tests can pin it with `FixedClock`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A source of the current UTC time.
+pub trait Clock: Send + Sync {
+    fn now_utc(&self) -> DateTime<Utc>;
+
+    /// Today's date in UTC.
+    fn today_utc(&self) -> NaiveDate {
+        self.now_utc().date_naive()
+    }
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock stopped at one instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
@@ -0,0 +1,572 @@
+//! Computation configuration — every parameter that affects a schedule.
+
+use crate::method::{CalculationMethod, IshaRule, MaghribRule, PrayerMethod};
+use crate::schedule::{self, AsrFormula, AsrMadhab, GapStrategy, HighLatitudeRule, MidnightSunRule, PrayerKind, RoundingPolicy, SunriseReference};
+use crate::solar;
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::fmt;
+
+/// Options that change the computed prayer times for a location and date.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Config {
+    pub strategy: GapStrategy,
+    pub method: CalculationMethod,
+    /// Asr altitude formula (the two agree; see `schedule::AsrFormula`).
+    pub asr_formula: AsrFormula,
+    /// Asr shadow length replacing the method's: Shafi'i (factor 1) or
+    /// Hanafi (factor 2).
+    pub asr_madhab: Option<AsrMadhab>,
+    /// Show local times in this zone instead of the location's own zone.
+    #[serde(skip)]
+    pub display_tz: Option<Tz>,
+    /// Attach sidereal time and hour angle to Standard events.
+    pub include_sidereal: bool,
+    /// Attach the sun's azimuth to Standard events.
+    pub include_azimuth: bool,
+    /// Report the makruh windows around sunrise, zenith and sunset.
+    pub include_makruh: bool,
+    /// Report civil, nautical and astronomical dawn and dusk.
+    pub include_twilight_times: bool,
+    /// Report the bearing and distance to the Kaaba.
+    pub include_qibla: bool,
+    /// Keep Fajr at least this many minutes before sunrise (off when `None`).
+    pub min_fajr_sunrise_gap_min: Option<u32>,
+    /// Upper limb or centre of the sun for sunrise and sunset.
+    pub sunrise_reference: SunriseReference,
+    /// Per-event minute offsets applied after computation.
+    pub adjustments: Adjustments,
+    /// Fajr depression angle replacing the method's (e.g. 18° with a fixed Isha).
+    pub fajr_angle_override: Option<f64>,
+    /// Isha rule replacing the method's, e.g. 75 minutes after Maghrib.
+    pub isha_override: Option<IshaRule>,
+    /// Maghrib/Isha placement on days when the sun never sets.
+    pub midnight_sun_rule: MidnightSunRule,
+    /// Fajr/Isha placement when the sun sets but never reaches their angle.
+    pub high_latitude_rule: HighLatitudeRule,
+    /// Round displayed times to whole minutes (seconds kept when `None`).
+    pub rounding: Option<RoundingPolicy>,
+    /// Observer height in metres; lowers the horizon for sunrise and sunset.
+    pub elevation_m: f64,
+    /// Day-scan step in seconds (`schedule::SCAN_RESOLUTION_SECONDS` when `None`).
+    pub scan_resolution_s: Option<u32>,
+    /// Temperature and pressure scaling the horizon refraction.
+    pub atmosphere: solar::Atmosphere,
+}
+
+impl Config {
+    pub fn with_strategy(mut self, strategy: GapStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_method(mut self, method: CalculationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_asr_formula(mut self, asr_formula: AsrFormula) -> Self {
+        self.asr_formula = asr_formula;
+        self
+    }
+
+    pub fn with_asr_madhab(mut self, asr_madhab: Option<AsrMadhab>) -> Self {
+        self.asr_madhab = asr_madhab;
+        self
+    }
+
+    pub fn with_display_tz(mut self, display_tz: Option<Tz>) -> Self {
+        self.display_tz = display_tz;
+        self
+    }
+
+    pub fn with_sidereal(mut self, include_sidereal: bool) -> Self {
+        self.include_sidereal = include_sidereal;
+        self
+    }
+
+    pub fn with_azimuth(mut self, include_azimuth: bool) -> Self {
+        self.include_azimuth = include_azimuth;
+        self
+    }
+
+    pub fn with_makruh(mut self, include_makruh: bool) -> Self {
+        self.include_makruh = include_makruh;
+        self
+    }
+
+    pub fn with_twilight_times(mut self, include_twilight_times: bool) -> Self {
+        self.include_twilight_times = include_twilight_times;
+        self
+    }
+
+    pub fn with_qibla(mut self, include_qibla: bool) -> Self {
+        self.include_qibla = include_qibla;
+        self
+    }
+
+    pub fn with_min_fajr_sunrise_gap(mut self, minutes: Option<u32>) -> Self {
+        self.min_fajr_sunrise_gap_min = minutes;
+        self
+    }
+
+    pub fn with_sunrise_reference(mut self, sunrise_reference: SunriseReference) -> Self {
+        self.sunrise_reference = sunrise_reference;
+        self
+    }
+
+    pub fn with_adjustments(mut self, adjustments: Adjustments) -> Self {
+        self.adjustments = adjustments;
+        self
+    }
+
+    pub fn with_fajr_angle(mut self, angle: Option<f64>) -> Self {
+        self.fajr_angle_override = angle;
+        self
+    }
+
+    pub fn with_isha_rule(mut self, rule: Option<IshaRule>) -> Self {
+        self.isha_override = rule;
+        self
+    }
+
+    pub fn with_midnight_sun_rule(mut self, rule: MidnightSunRule) -> Self {
+        self.midnight_sun_rule = rule;
+        self
+    }
+
+    pub fn with_high_latitude_rule(mut self, rule: HighLatitudeRule) -> Self {
+        self.high_latitude_rule = rule;
+        self
+    }
+
+    pub fn with_rounding(mut self, rounding: Option<RoundingPolicy>) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn with_elevation(mut self, elevation_m: f64) -> Self {
+        self.elevation_m = elevation_m;
+        self
+    }
+
+    pub fn with_scan_resolution(mut self, seconds: Option<u32>) -> Self {
+        self.scan_resolution_s = seconds;
+        self
+    }
+
+    /// [`Config::with_scan_resolution`], rejecting steps outside
+    /// `SCAN_RESOLUTION_RANGE`.
+    pub fn try_with_scan_resolution(self, seconds: Option<u32>) -> Result<Self, String> {
+        let seconds = seconds.map(check_scan_resolution).transpose()?;
+        Ok(self.with_scan_resolution(seconds))
+    }
+
+    pub fn with_atmosphere(mut self, atmosphere: solar::Atmosphere) -> Self {
+        self.atmosphere = atmosphere;
+        self
+    }
+
+    /// Day-scan step in seconds, kept within `SCAN_RESOLUTION_RANGE`.
+    pub fn scan_resolution(&self) -> u32 {
+        let range = SCAN_RESOLUTION_RANGE;
+        self.scan_resolution_s
+            .map_or(schedule::SCAN_RESOLUTION_SECONDS, |s| s.clamp(*range.start(), *range.end()))
+    }
+
+    /// Solar altitude taken as sunrise and sunset: the reference point's
+    /// angle, lowered by the horizon dip at `elevation_m` and by any
+    /// refraction beyond the standard atmosphere's.
+    pub fn horizon_angle(&self) -> f64 {
+        let extra_refraction = self.atmosphere.horizon_refraction() - solar::Atmosphere::STANDARD.horizon_refraction();
+        self.sunrise_reference.horizon_angle() - extra_refraction - solar::horizon_dip(self.elevation_m)
+    }
+
+    /// Stable string covering every output-affecting option, for cache keys.
+    ///
+    /// Any new field added to `Config` must be included here, otherwise
+    /// cached results for different settings collide.
+    pub fn cache_fingerprint(&self) -> String {
+        let display_tz = self.display_tz.map(|tz| tz.name()).unwrap_or("-");
+        let fajr_gap = self.min_fajr_sunrise_gap_min.map(|m| m.to_string()).unwrap_or("-".into());
+        let fajr_angle = self.fajr_angle_override.map(|a| a.to_string()).unwrap_or("-".into());
+        let rounding = self.rounding.map(|r| r.to_string()).unwrap_or("-".into());
+        let isha = match self.isha_override {
+            Some(IshaRule::Angle(a)) => format!("{}deg", a),
+            Some(IshaRule::FixedAfterMaghrib(m)) => format!("{}min", m),
+            None => "-".into(),
+        };
+        let asr_madhab = self.asr_madhab.map_or_else(|| "-".to_string(), |m| m.to_string());
+        format!(
+            "strategy={};method={};asr_formula={};display_tz={};sidereal={};makruh={};fajr_gap={};sunrise_ref={};adjust={};fajr_angle={};isha={};midnight_sun={};rounding={};elevation={};twilight_times={};asr_madhab={};high_lat={};qibla={};azimuth={};resolution={};atmosphere={}/{}",
+            self.strategy, self.method, self.asr_formula, display_tz, self.include_sidereal, self.include_makruh, fajr_gap,
+            self.sunrise_reference, self.adjustments, fajr_angle, isha, self.midnight_sun_rule, rounding, self.elevation_m,
+            self.include_twilight_times, asr_madhab, self.high_latitude_rule,
+            self.include_qibla, self.include_azimuth, self.scan_resolution(),
+            self.atmosphere.temperature_c, self.atmosphere.pressure_mbar,
+        )
+    }
+
+    /// Every setting behind a schedule computed at `lat`, for citing results.
+    pub fn computation(&self, lat: f64) -> Computation {
+        let (isha_angle, isha_minutes) = match self.isha_rule() {
+            IshaRule::Angle(a) => (Some(-a), None),
+            IshaRule::FixedAfterMaghrib(m) => (None, Some(m)),
+        };
+        let maghrib_angle = match self.maghrib_rule() {
+            MaghribRule::Sunset => self.horizon_angle(),
+            MaghribRule::Angle(a) => -a,
+        };
+        let asr_factor = PrayerMethod::asr_factor(self);
+        Computation {
+            engine_version: crate::VERSION,
+            method: self.method,
+            method_name: self.method.description(),
+            fajr_angle: -self.fajr_angle(),
+            isha_angle,
+            isha_minutes,
+            ramadan_isha_minutes: isha_minutes.and(PrayerMethod::ramadan_isha_minutes(self)),
+            maghrib_angle,
+            asr_factor,
+            asr_school: if asr_factor >= 2.0 { "Hanafi" } else { "Shafi" },
+            asr_formula: self.asr_formula,
+            gap_strategy: self.strategy.resolve(lat),
+            midnight_sun_rule: self.midnight_sun_rule,
+            high_latitude_rule: self.strategy.resolve_high_latitude_rule(lat, self.high_latitude_rule),
+            rounding: self.rounding,
+            min_fajr_sunrise_gap_min: self.min_fajr_sunrise_gap_min,
+            adjustments: self.adjustments,
+            sample_resolution_seconds: self.scan_resolution(),
+            refraction: true,
+            sunrise_reference: self.sunrise_reference,
+            elevation_m: self.elevation_m,
+            atmosphere: self.atmosphere,
+            horizon_angle: self.horizon_angle(),
+        }
+    }
+}
+
+/// The configured method with any angle overrides applied.
+impl PrayerMethod for Config {
+    fn fajr_angle(&self) -> f64 {
+        self.fajr_angle_override.unwrap_or(self.method.fajr_angle())
+    }
+
+    fn isha_rule(&self) -> IshaRule {
+        self.isha_override.unwrap_or(self.method.isha_rule())
+    }
+
+    fn maghrib_rule(&self) -> MaghribRule {
+        self.method.maghrib_rule()
+    }
+
+    /// An explicit Isha rule also replaces the method's Ramadan interval.
+    fn ramadan_isha_minutes(&self) -> Option<u32> {
+        match self.isha_override {
+            Some(_) => None,
+            None => self.method.ramadan_isha_minutes(),
+        }
+    }
+
+    fn asr_factor(&self) -> f64 {
+        match self.asr_madhab {
+            Some(madhab) => madhab.shadow_factor(),
+            None => PrayerMethod::asr_factor(&self.method),
+        }
+    }
+}
+
+/// Check a depression angle in degrees below the horizon (0 < angle <= 30).
+pub fn check_depression_angle(angle: f64) -> Result<f64, String> {
+    if angle.is_nan() || angle <= 0.0 || angle > 30.0 {
+        return Err(format!("angle {} out of range; use degrees below the horizon, 0 < angle <= 30", angle));
+    }
+    Ok(angle)
+}
+
+/// Finest and coarsest accepted day-scan steps, in seconds.
+pub const SCAN_RESOLUTION_RANGE: std::ops::RangeInclusive<u32> = 1..=600;
+
+/// Reject day-scan steps outside `SCAN_RESOLUTION_RANGE`.
+pub fn check_scan_resolution(seconds: u32) -> Result<u32, String> {
+    if !SCAN_RESOLUTION_RANGE.contains(&seconds) {
+        return Err(format!("resolution {} out of range; use 1-600 seconds", seconds));
+    }
+    Ok(seconds)
+}
+
+/// Parse a day-scan step in seconds (CLI value parser).
+pub fn parse_scan_resolution(s: &str) -> Result<u32, String> {
+    let seconds: u32 = s.trim().parse().map_err(|_| format!("'{}' is not a whole number of seconds", s.trim()))?;
+    check_scan_resolution(seconds)
+}
+
+/// Coldest and warmest accepted observer air temperatures, in °C.
+pub const TEMPERATURE_RANGE_C: std::ops::RangeInclusive<f64> = -90.0..=60.0;
+
+/// Lowest and highest accepted observer air pressures, in mbar.
+pub const PRESSURE_RANGE_MBAR: std::ops::RangeInclusive<f64> = 300.0..=1100.0;
+
+/// Reject temperatures outside `TEMPERATURE_RANGE_C` and pressures outside
+/// `PRESSURE_RANGE_MBAR`.
+pub fn check_atmosphere(temperature_c: f64, pressure_mbar: f64) -> Result<solar::Atmosphere, String> {
+    if !TEMPERATURE_RANGE_C.contains(&temperature_c) {
+        return Err(format!("temperature {} °C out of range (-90..=60)", temperature_c));
+    }
+    if !PRESSURE_RANGE_MBAR.contains(&pressure_mbar) {
+        return Err(format!("pressure {} mbar out of range (300..=1100)", pressure_mbar));
+    }
+    Ok(solar::Atmosphere { temperature_c, pressure_mbar })
+}
+
+/// Parse a depression angle such as `18` or `17.5` (CLI value parser).
+pub fn parse_depression_angle(s: &str) -> Result<f64, String> {
+    let angle: f64 = s.trim().parse().map_err(|_| format!("'{}' is not a number", s.trim()))?;
+    check_depression_angle(angle)
+}
+
+/// The Isha override from an angle or a fixed interval; giving both is an error.
+pub fn isha_override(angle: Option<f64>, minutes: Option<u32>) -> Result<Option<IshaRule>, String> {
+    match (angle, minutes) {
+        (Some(_), Some(_)) => Err("give either an Isha angle or Isha minutes, not both".into()),
+        (Some(a), None) => Ok(Some(IshaRule::Angle(a))),
+        (None, Some(m)) => Ok(Some(IshaRule::FixedAfterMaghrib(m))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// An `--elevation` value: metres above sea level, or `auto` to look it up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElevationSetting {
+    Auto,
+    Meters(f64),
+}
+
+impl std::str::FromStr for ElevationSetting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(ElevationSetting::Auto);
+        }
+        match s.parse::<f64>() {
+            Ok(m) => check_elevation(m).map(ElevationSetting::Meters),
+            Err(_) => Err(format!("'{}' is not 'auto' or a height in metres", s)),
+        }
+    }
+}
+
+/// Reject heights outside -500..=9000 m (Dead Sea shore to Everest).
+pub fn check_elevation(elevation_m: f64) -> Result<f64, String> {
+    if !(-500.0..=9000.0).contains(&elevation_m) {
+        return Err(format!("elevation {} m out of range (-500..=9000)", elevation_m));
+    }
+    Ok(elevation_m)
+}
+
+// ─── Computation metadata ────────────────────────────────────────
+
+/// Self-describing record of how a schedule was computed.
+///
+/// Angles are solar altitudes in degrees (negative below the horizon).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Computation {
+    pub engine_version: &'static str,
+    pub method: CalculationMethod,
+    pub method_name: &'static str,
+    pub fajr_angle: f64,
+    /// Isha altitude, when the method uses an angle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isha_angle: Option<f64>,
+    /// Minutes after Maghrib, when the method uses a fixed interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isha_minutes: Option<u32>,
+    /// The fixed Isha interval during Ramadan, when the method has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ramadan_isha_minutes: Option<u32>,
+    pub maghrib_angle: f64,
+    pub asr_factor: f64,
+    pub asr_school: &'static str,
+    pub asr_formula: AsrFormula,
+    /// The concrete strategy applied (`Auto` resolved for the latitude).
+    pub gap_strategy: GapStrategy,
+    pub midnight_sun_rule: MidnightSunRule,
+    /// The Fajr/Isha rule applied (`Auto` may override the configured one).
+    pub high_latitude_rule: HighLatitudeRule,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<RoundingPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_fajr_sunrise_gap_min: Option<u32>,
+    #[serde(skip_serializing_if = "Adjustments::is_zero")]
+    pub adjustments: Adjustments,
+    pub sample_resolution_seconds: u32,
+    /// Whether horizon crossings include atmospheric refraction.
+    pub refraction: bool,
+    pub sunrise_reference: SunriseReference,
+    #[serde(skip_serializing_if = "is_sea_level")]
+    pub elevation_m: f64,
+    #[serde(skip_serializing_if = "solar::Atmosphere::is_standard")]
+    pub atmosphere: solar::Atmosphere,
+    /// Solar altitude taken as sunrise and sunset.
+    pub horizon_angle: f64,
+}
+
+fn is_sea_level(elevation_m: &f64) -> bool {
+    *elevation_m == 0.0
+}
+
+// ─── Manual adjustments ──────────────────────────────────────────
+
+/// Whole-minute offsets added to each event, e.g. to match a local mosque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Adjustments {
+    pub fajr: i32,
+    pub sunrise: i32,
+    pub dhuhr: i32,
+    pub asr: i32,
+    pub maghrib: i32,
+    pub isha: i32,
+}
+
+impl Adjustments {
+    pub fn get(&self, kind: PrayerKind) -> i32 {
+        match kind {
+            PrayerKind::Fajr => self.fajr,
+            PrayerKind::Sunrise => self.sunrise,
+            PrayerKind::Dhuhr => self.dhuhr,
+            PrayerKind::Asr => self.asr,
+            PrayerKind::Maghrib => self.maghrib,
+            PrayerKind::Isha => self.isha,
+        }
+    }
+
+    fn get_mut(&mut self, kind: PrayerKind) -> &mut i32 {
+        match kind {
+            PrayerKind::Fajr => &mut self.fajr,
+            PrayerKind::Sunrise => &mut self.sunrise,
+            PrayerKind::Dhuhr => &mut self.dhuhr,
+            PrayerKind::Asr => &mut self.asr,
+            PrayerKind::Maghrib => &mut self.maghrib,
+            PrayerKind::Isha => &mut self.isha,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Adjustments {
+    /// The non-zero offsets as `fajr=+2,isha=-1` (`-` when there are none).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "-");
+        }
+        let parts: Vec<String> = PrayerKind::ALL.iter()
+            .filter(|k| self.get(**k) != 0)
+            .map(|k| format!("{}={:+}", k.name().to_lowercase(), self.get(*k)))
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Parse `prayer=minutes` pairs such as `fajr=+2,isha=-1`.
+///
+/// Errors name the offending token: unknown prayers, missing `=`,
+/// non-integer offsets and repeated keys are all rejected.
+pub fn parse_kv_offsets(s: &str) -> Result<Adjustments, String> {
+    let mut adjustments = Adjustments::default();
+    let mut seen = Vec::new();
+    for token in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let (key, value) = token.split_once('=')
+            .ok_or_else(|| format!("'{}' is not prayer=minutes (e.g. fajr=+2)", token))?;
+        let kind: PrayerKind = key.trim().parse()
+            .map_err(|_| format!("unknown prayer '{}' in '{}'; use fajr, sunrise, dhuhr, asr, maghrib or isha", key.trim(), token))?;
+        let minutes: i32 = value.trim().parse()
+            .map_err(|_| format!("offset '{}' in '{}' is not a whole number of minutes", value.trim(), token))?;
+        if seen.contains(&kind) {
+            return Err(format!("'{}' sets {} more than once", token, kind));
+        }
+        seen.push(kind);
+        *adjustments.get_mut(kind) = minutes;
+    }
+    Ok(adjustments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_covers_method_and_strategy() {
+        let base = Config::default();
+        let isna = base.with_method(CalculationMethod::ISNA);
+        let strict = base.with_strategy(GapStrategy::Strict);
+
+        assert_ne!(base.cache_fingerprint(), isna.cache_fingerprint());
+        assert_ne!(base.cache_fingerprint(), strict.cache_fingerprint());
+        assert_eq!(base.cache_fingerprint(), Config::default().cache_fingerprint());
+
+        let geometric = base.with_asr_formula(AsrFormula::Geometric);
+        assert_ne!(base.cache_fingerprint(), geometric.cache_fingerprint());
+
+        let gap = base.with_min_fajr_sunrise_gap(Some(30));
+        assert_ne!(base.cache_fingerprint(), gap.cache_fingerprint());
+
+        let center = base.with_sunrise_reference(SunriseReference::Center);
+        assert_ne!(base.cache_fingerprint(), center.cache_fingerprint());
+
+        let sidereal = base.with_sidereal(true);
+        assert_ne!(base.cache_fingerprint(), sidereal.cache_fingerprint());
+        assert_ne!(sidereal.cache_fingerprint(), base.with_makruh(true).cache_fingerprint());
+        assert_ne!(sidereal.cache_fingerprint(), base.with_azimuth(true).cache_fingerprint());
+        assert_ne!(base.cache_fingerprint(), base.with_scan_resolution(Some(5)).cache_fingerprint());
+        assert_eq!(base.cache_fingerprint(), base.with_scan_resolution(Some(30)).cache_fingerprint());
+        let cold = check_atmosphere(-40.0, 1010.0).unwrap();
+        assert_ne!(base.cache_fingerprint(), base.with_atmosphere(cold).cache_fingerprint());
+
+        let london = base.with_display_tz(Some(chrono_tz::Europe::London));
+        assert_ne!(base.cache_fingerprint(), london.cache_fingerprint());
+
+        // An explicit Shafi'i override is a different request from the method's default
+        let shafii = base.with_asr_madhab(Some(AsrMadhab::Shafii));
+        assert_ne!(base.cache_fingerprint(), shafii.cache_fingerprint());
+    }
+
+    #[test]
+    fn test_asr_madhab_override() {
+        let base = Config::default();
+        assert_eq!(PrayerMethod::asr_factor(&base), 1.0);
+        assert_eq!(PrayerMethod::asr_factor(&base.with_asr_madhab(Some(AsrMadhab::Hanafi))), 2.0);
+        assert_eq!(PrayerMethod::asr_factor(&base.with_asr_madhab(Some(AsrMadhab::Shafii))), 1.0);
+    }
+
+    #[test]
+    fn test_parse_kv_offsets() {
+        let adj = parse_kv_offsets("fajr=+2, isha=-1,Dhuhr=3").unwrap();
+        assert_eq!((adj.fajr, adj.dhuhr, adj.isha, adj.asr), (2, 3, -1, 0));
+        assert_eq!(adj.to_string(), "fajr=+2,dhuhr=+3,isha=-1");
+        assert!(parse_kv_offsets("").unwrap().is_zero());
+
+        let base = Config::default();
+        assert_ne!(base.cache_fingerprint(), base.with_adjustments(adj).cache_fingerprint());
+    }
+
+    #[test]
+    fn test_parse_kv_offsets_unknown_key() {
+        let err = parse_kv_offsets("fajr=2,tahajjud=5").unwrap_err();
+        println!("{}", err);
+        assert!(err.contains("'tahajjud'"));
+    }
+
+    #[test]
+    fn test_parse_kv_offsets_non_numeric() {
+        let err = parse_kv_offsets("isha=soon").unwrap_err();
+        println!("{}", err);
+        assert!(err.contains("'soon'") && err.contains("isha=soon"));
+        assert!(parse_kv_offsets("fajr=1.5").is_err());
+        assert!(parse_kv_offsets("fajr").unwrap_err().contains("'fajr'"));
+        assert!(parse_kv_offsets("fajr=1,fajr=2").is_err());
+    }
+}
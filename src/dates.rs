@@ -0,0 +1,45 @@
+//! Date input parsing — calendar, ISO week and ordinal dates.
+
+use chrono::{NaiveDate, ParseError};
+
+/// Accepted formats, tried in order: `2026-02-14`, `2026-W07-6`, `2026-045`.
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%G-W%V-%u", "%Y-%j"];
+
+/// Parse a date in any of the accepted formats.
+///
+/// On failure the calendar-date error is returned, since that is the
+/// format most callers intend.
+pub fn parse_date(s: &str) -> Result<NaiveDate, ParseError> {
+    let s = s.trim();
+    let mut first_err = None;
+    for fmt in DATE_FORMATS {
+        match NaiveDate::parse_from_str(s, fmt) {
+            Ok(date) => return Ok(date),
+            Err(e) => { first_err.get_or_insert(e); }
+        }
+    }
+    Err(first_err.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_week_and_ordinal_dates() {
+        let expected = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        assert_eq!(parse_date("2026-02-14").unwrap(), expected);
+        assert_eq!(parse_date("2026-W07-6").unwrap(), expected);
+        assert_eq!(parse_date("2026-045").unwrap(), expected);
+
+        // ISO week years differ from calendar years around New Year
+        assert_eq!(parse_date("2026-W01-1").unwrap(), NaiveDate::from_ymd_opt(2025, 12, 29).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_dates_rejected() {
+        for bad in ["2026-02-30", "2026-W54-1", "2026-W07-8", "2025-366", "tomorrow", ""] {
+            assert!(parse_date(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Export formats — render a solved day for sharing outside the JSON API.
+
+use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::hijri::gregorian_to_hijri;
+use crate::schedule::PrayerKind;
+use crate::solver::SolverOutput;
+use crate::timezone;
+
+// ─── SVG card ────────────────────────────────────────────────────
+
+const CARD_WIDTH: u32 = 360;
+const ROW_HEIGHT: u32 = 36;
+const HEADER_HEIGHT: u32 = 110;
+const FOOTER_HEIGHT: u32 = 40;
+
+/// Render the six daily times as a standalone SVG card.
+///
+/// The card shows the location, Gregorian and Hijri dates, each prayer
+/// with its local time (or "—" when it does not occur) and the method.
+pub fn to_svg(output: &SolverOutput) -> String {
+    let height = HEADER_HEIGHT + ROW_HEIGHT * PrayerKind::ALL.len() as u32 + FOOTER_HEIGHT;
+    let mut svg = String::with_capacity(2048);
+
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n",
+        w = CARD_WIDTH, h = height,
+    ));
+    svg.push_str(&format!(
+        "  <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" rx=\"12\" fill=\"#0f172a\"/>\n",
+        CARD_WIDTH, height,
+    ));
+
+    // Header: location, dates
+    svg.push_str(&format!(
+        "  <text x=\"20\" y=\"40\" font-size=\"22\" font-weight=\"bold\" fill=\"#f8fafc\">{}</text>\n",
+        xml_escape(&output.location.name),
+    ));
+    svg.push_str(&format!(
+        "  <text x=\"20\" y=\"66\" font-size=\"14\" fill=\"#cbd5e1\">{}</text>\n",
+        xml_escape(&output.date),
+    ));
+    if let Some(hijri) = hijri_label(&output.date) {
+        svg.push_str(&format!(
+            "  <text x=\"20\" y=\"88\" font-size=\"14\" fill=\"#cbd5e1\">{}</text>\n",
+            xml_escape(&hijri),
+        ));
+    }
+
+    // Prayer rows
+    for (i, (kind, event)) in output.events.iter().enumerate() {
+        let y = HEADER_HEIGHT + ROW_HEIGHT * i as u32 + 24;
+        let time = event.time.as_deref().map(short_time).unwrap_or("—");
+        svg.push_str(&format!(
+            "  <text x=\"20\" y=\"{}\" font-size=\"18\" fill=\"#e2e8f0\">{}</text>\n",
+            y, kind,
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" font-size=\"18\" text-anchor=\"end\" fill=\"#f8fafc\">{}</text>\n",
+            CARD_WIDTH - 20, y, xml_escape(time),
+        ));
+    }
+
+    // Footer: method and timezone
+    let tz = output.display_timezone.as_deref().unwrap_or(&output.location.timezone);
+    svg.push_str(&format!(
+        "  <text x=\"20\" y=\"{}\" font-size=\"12\" fill=\"#94a3b8\">Method: {} · {}</text>\n",
+        height - 16, output.method, xml_escape(tz),
+    ));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// ─── iCalendar ───────────────────────────────────────────────────
+
+/// Render each timed prayer as a one-minute VEVENT in an RFC 5545 calendar.
+///
+/// Start times are written in UTC (`...Z`), converted from the output's
+/// local times, so no VTIMEZONE definitions are needed; events dated on
+/// the following or previous day (`day_offset`) land on the right instant.
+/// Sunrise is not a prayer and is only included with `include_sunrise`.
+/// UIDs depend only on the coordinates, date and prayer, so importing the
+/// same day twice updates the events instead of duplicating them.
+pub fn to_ical(output: &SolverOutput, include_sunrise: bool) -> String {
+    let tz_name = output.display_timezone.as_deref().unwrap_or(&output.location.timezone);
+    let tz = timezone::parse_tz(tz_name).unwrap_or(chrono_tz::UTC);
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Polaris Chronos//Prayer Times//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-TIMEZONE:{}", tz_name),
+    ];
+
+    let date = NaiveDate::parse_from_str(&output.date, "%Y-%m-%d").ok();
+    for (kind, event) in output.events.iter() {
+        if kind == PrayerKind::Sunrise && !include_sunrise {
+            continue;
+        }
+        let (Some(date), Some(time)) = (date, event.time.as_deref()) else { continue };
+        let Ok(time) = NaiveTime::parse_from_str(time, "%H:%M:%S") else { continue };
+        let local = (date + Duration::days(event.day_offset as i64)).and_time(time);
+        let Some(start) = tz.from_local_datetime(&local).earliest() else { continue };
+
+        lines.push("BEGIN:VEVENT".into());
+        lines.push(format!("UID:{}@polaris-chronos", event_uid(output, date, kind)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", start.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")));
+        lines.push("DURATION:PT1M".into());
+        lines.push(format!("SUMMARY:{}", ical_escape(kind.name())));
+        lines.push(format!("LOCATION:{}", ical_escape(&output.location.name)));
+        if let Some(ref note) = event.note {
+            lines.push(format!("DESCRIPTION:{}", ical_escape(note)));
+        }
+        lines.push("END:VEVENT".into());
+    }
+    lines.push("END:VCALENDAR".into());
+
+    lines.iter().map(|l| fold_line(l)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+/// Stable per-event id: the same place, date and prayer always match.
+fn event_uid(output: &SolverOutput, date: NaiveDate, kind: PrayerKind) -> String {
+    format!(
+        "{}-{}-{:.4}_{:.4}",
+        date.format("%Y%m%d"), kind.name().to_lowercase(), output.location.latitude, output.location.longitude,
+    )
+}
+
+/// Escape TEXT values (RFC 5545 §3.3.11).
+fn ical_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split a content line into 75-octet pieces joined by CRLF + space,
+/// never inside a UTF-8 character.
+fn fold_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space, which counts toward the limit
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
+
+// ─── CSV ─────────────────────────────────────────────────────────
+
+/// Header of `month_to_csv`.
+const CSV_HEADER: &str = "date,fajr,sunrise,dhuhr,asr,maghrib,isha,state";
+
+/// One row per day: the date, the six local times and the day state.
+///
+/// Events that do not occur are empty cells.
+pub fn month_to_csv(outputs: &[SolverOutput]) -> String {
+    let mut csv = String::with_capacity(64 * (outputs.len() + 1));
+    csv.push_str(CSV_HEADER);
+    csv.push('\n');
+    for output in outputs {
+        csv.push_str(&output.date);
+        for (_, event) in output.events.iter() {
+            csv.push(',');
+            csv.push_str(event.time.as_deref().unwrap_or(""));
+        }
+        csv.push_str(&format!(",{}\n", output.state));
+    }
+    csv
+}
+
+/// "HH:MM:SS" → "HH:MM".
+fn short_time(t: &str) -> &str {
+    t.get(..5).unwrap_or(t)
+}
+
+fn hijri_label(date: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some(format!("{} AH", gregorian_to_hijri(date)))
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{Location, Solver};
+
+    /// Minimal well-formedness check: every tag is closed in order and no
+    /// raw `&` appears outside an entity.
+    fn assert_well_formed(xml: &str) {
+        let mut stack: Vec<String> = Vec::new();
+        let mut rest = xml;
+        while let Some(open) = rest.find('<') {
+            let text = &rest[..open];
+            for (i, _) in text.match_indices('&') {
+                let entity = &text[i..];
+                assert!(
+                    ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"].iter().any(|e| entity.starts_with(e)),
+                    "unescaped '&' in {:?}", text,
+                );
+            }
+            let close = rest[open..].find('>').expect("unterminated tag") + open;
+            let tag = &rest[open + 1..close];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop().as_deref(), Some(name.trim()), "mismatched </{}>", name);
+            } else if !tag.ends_with('/') {
+                let name = tag.split_whitespace().next().unwrap();
+                stack.push(name.to_string());
+            }
+            rest = &rest[close + 1..];
+        }
+        assert!(stack.is_empty(), "unclosed tags: {:?}", stack);
+    }
+
+    #[test]
+    fn test_svg_card_contents() {
+        let tz: chrono_tz::Tz = "Africa/Cairo".parse().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let mut output = Solver::new(Location::new(30.0444, 31.2357), tz).solve(date, false, false);
+        output.location.name = "Cairo & <Giza>".to_string();
+
+        let svg = to_svg(&output);
+        println!("{}", svg);
+
+        for label in ["Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"] {
+            assert!(svg.contains(&format!(">{}</text>", label)), "missing {}", label);
+        }
+        assert!(svg.contains("Cairo &amp; &lt;Giza&gt;"));
+        assert!(svg.contains("2026-03-20"));
+        assert!(svg.contains("AH"));
+        assert!(svg.contains("Method: MWL"));
+        assert_well_formed(&svg);
+    }
+
+    #[test]
+    fn test_ical_events() {
+        let tz: chrono_tz::Tz = "Europe/Stockholm".parse().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let solver = Solver::new(Location::new(59.3293, 18.0686), tz);
+        let mut output = solver.solve(date, false, false);
+        output.location.name = "Stockholm, Sweden".to_string();
+        output.events.isha.time = Some("00:15:00".into());
+        output.events.isha.day_offset = 1;
+
+        let ics = to_ical(&output, false);
+        println!("{}", ics);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+        assert!(ics.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 5);
+        assert!(!ics.contains("SUMMARY:Sunrise") && !ics.contains("TZID="));
+        assert!(ics.contains("LOCATION:Stockholm\\, Sweden"));
+        // Stockholm is UTC+1 in March
+        let fajr = NaiveTime::parse_from_str(output.events.fajr.time.as_ref().unwrap(), "%H:%M:%S").unwrap();
+        let fajr_utc = (date.and_time(fajr) - Duration::hours(1)).format("%Y%m%dT%H%M%SZ");
+        assert!(ics.contains(&format!("DTSTART:{}", fajr_utc)));
+        // Isha after midnight belongs to the next calendar day
+        assert!(ics.contains("DTSTART:20260320T231500Z"));
+
+        // Sunrise on request
+        let with_sunrise = to_ical(&output, true);
+        assert_eq!(with_sunrise.matches("BEGIN:VEVENT").count(), 6);
+        assert!(with_sunrise.contains("SUMMARY:Sunrise"));
+
+        // Same place and day: same UIDs; another day: different ones
+        let uids = |ics: &str| ics.lines().filter(|l| l.starts_with("UID:")).map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(uids(&ics), uids(&to_ical(&output, false)));
+        let next = solver.solve(date.succ_opt().unwrap(), false, false);
+        assert!(uids(&to_ical(&next, false)).iter().all(|u| !uids(&ics).contains(u)));
+    }
+
+    #[test]
+    fn test_ical_skips_missing_events() {
+        let tz: chrono_tz::Tz = "Europe/Oslo".parse().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 12, 21).unwrap();
+        let output = Solver::new(Location::new(69.6492, 18.9553), tz)
+            .with_strategy(crate::schedule::GapStrategy::Strict)
+            .solve(date, false, false);
+        let timed = output.events.iter().filter(|(_, e)| e.time.is_some()).count();
+        let ics = to_ical(&output, true);
+        assert!(timed < 6);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), timed);
+        assert!(!ics.contains("SUMMARY:Sunrise"));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let long = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold_line(&long);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+
+    #[test]
+    fn test_month_csv_march() {
+        let tz: chrono_tz::Tz = "Europe/Stockholm".parse().unwrap();
+        let solver = Solver::new(Location::new(59.3293, 18.0686), tz);
+        let first = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let outputs: Vec<_> = first.iter_days().take(31).map(|d| solver.solve(d, false, false)).collect();
+
+        let csv = month_to_csv(&outputs);
+        let rows: Vec<&str> = csv.lines().collect();
+        println!("{}", rows[1]);
+        assert_eq!(rows[0], "date,fajr,sunrise,dhuhr,asr,maghrib,isha,state");
+        assert_eq!(rows.len(), 32);
+        assert!(rows[1].starts_with("2026-03-01,") && rows[31].starts_with("2026-03-31,"));
+        assert!(rows[1..].iter().all(|r| r.split(',').count() == 8 && r.ends_with(",Normal")));
+
+        // Missing events are empty cells, not "null"
+        let mut polar = outputs[0].clone();
+        polar.events.sunrise.time = None;
+        let row = month_to_csv(&[polar]).lines().nth(1).unwrap().to_string();
+        assert_eq!(row.split(',').nth(2), Some(""));
+        assert!(!row.contains("null"));
+    }
+}
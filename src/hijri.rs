@@ -7,11 +7,13 @@
 //! - Ramadan date finder that respects actual lunar visibility
 
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
-use serde::Serialize;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
 use std::f64::consts::PI;
 
-use crate::lunar::{lunar_position, moon_sun_elongation};
-use crate::solar;
+use crate::clock::{Clock, SystemClock};
+use crate::lunar::{lunar_position_in, moon_sun_elongation};
+use crate::solar::{self, Atmosphere};
 
 const DEG: f64 = PI / 180.0;
 
@@ -43,15 +45,86 @@ fn hijri_year_days(year: u32) -> u32 {
 fn hijri_month_days(year: u32, month: u32) -> u32 {
     // Odd months have 30 days, even months have 29 days.
     // Exception: month 12 in leap years has 30 days.
-    if month % 2 == 1 {
-        30
-    } else if month == 12 && is_hijri_leap(year) {
+    if month % 2 == 1 || (month == 12 && is_hijri_leap(year)) {
         30
     } else {
         29
     }
 }
 
+const MONTH_NAMES: [&str; 12] = [
+    "Muharram", "Safar", "Rabi al-Awwal", "Rabi al-Thani",
+    "Jumada al-Ula", "Jumada al-Akhirah", "Rajab", "Sha'ban",
+    "Ramadan", "Shawwal", "Dhu al-Qadah", "Dhu al-Hijjah",
+];
+
+const ARABIC_MONTH_NAMES: [&str; 12] = [
+    "محرم", "صفر", "ربيع الأول", "ربيع الآخر",
+    "جمادى الأولى", "جمادى الآخرة", "رجب", "شعبان",
+    "رمضان", "شوال", "ذو القعدة", "ذو الحجة",
+];
+
+/// English transliteration of a Hijri month (1-12).
+pub fn month_name(month: u32) -> &'static str {
+    MONTH_NAMES[(month.clamp(1, 12) - 1) as usize]
+}
+
+/// Arabic name of a Hijri month (1-12).
+pub fn month_name_ar(month: u32) -> &'static str {
+    ARABIC_MONTH_NAMES[(month.clamp(1, 12) - 1) as usize]
+}
+
+impl HijriDate {
+    /// `YYYY-MM-DD` with zero padding, e.g. `1447-09-15`.
+    pub fn to_iso(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Day, Arabic month name and year, e.g. `15 رمضان 1447`.
+    pub fn to_arabic(&self) -> String {
+        format!("{} {} {}", self.day, month_name_ar(self.month), self.year)
+    }
+}
+
+/// `{year, month, day, month_name, month_name_ar}`.
+impl Serialize for HijriDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("HijriDate", 5)?;
+        s.serialize_field("year", &self.year)?;
+        s.serialize_field("month", &self.month)?;
+        s.serialize_field("day", &self.day)?;
+        s.serialize_field("month_name", month_name(self.month))?;
+        s.serialize_field("month_name_ar", month_name_ar(self.month))?;
+        s.end()
+    }
+}
+
+impl std::fmt::Display for HijriDate {
+    /// Day, English month name and year, e.g. `15 Ramadan 1447`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.day, month_name(self.month), self.year)
+    }
+}
+
+impl std::str::FromStr for HijriDate {
+    type Err = String;
+
+    /// Parse `year-month-day`, e.g. `1447-9-15` or `1447-09-15`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid Hijri date '{}'. Use YYYY-M-D, e.g. 1447-9-15.", s.trim());
+        let parts: Vec<u32> = s.trim().split('-')
+            .map(|p| p.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let [year, month, day] = parts[..] else {
+            return Err(invalid());
+        };
+        if year == 0 || !(1..=12).contains(&month) || day == 0 || day > hijri_month_days(year, month) {
+            return Err(invalid());
+        }
+        Ok(HijriDate { year, month, day })
+    }
+}
+
 /// Convert a Gregorian date to a tabular Hijri date.
 pub fn gregorian_to_hijri(date: NaiveDate) -> HijriDate {
     // Convert to Julian Day Number
@@ -209,7 +282,7 @@ pub fn find_conjunction(near_date: NaiveDate) -> NaiveDateTime {
 
 // ─── Odeh Crescent Visibility ─────────────────────────────────────
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum CrescentZone {
     A, // Naked eye visible
     B, // Optical aid, may be naked eye
@@ -239,19 +312,17 @@ pub struct CrescentVisibility {
     pub crescent_width: f64,
 }
 
-/// Find sunset time (in UTC seconds from midnight) for a given date and location.
-fn find_sunset(date: NaiveDate, lat: f64, lon: f64) -> Option<NaiveDateTime> {
-    let samples = solar::day_scan(date, lat, lon, 60);
-    let sunset_secs = solar::find_crossing(&samples, solar::HORIZON_ANGLE, false)?;
-
-    let h = (sunset_secs / 3600.0).floor() as u32;
-    let m = ((sunset_secs % 3600.0) / 60.0).floor() as u32;
-    let s = (sunset_secs % 60.0).floor() as u32;
-
-    Some(NaiveDateTime::new(
-        date,
-        NaiveTime::from_hms_opt(h.min(23), m.min(59), s.min(59))?,
-    ))
+/// Find the UTC instant of the local evening's sunset on `date`.
+///
+/// The scan is centred on local solar noon, so far-western longitudes get
+/// the sunset of their own evening (early next day in UTC) rather than
+/// the previous one.
+fn find_sunset(date: NaiveDate, lat: f64, lon: f64, atmosphere: &Atmosphere) -> Option<NaiveDateTime> {
+    let extra_refraction = atmosphere.horizon_refraction() - Atmosphere::STANDARD.horizon_refraction();
+    let samples = solar::day_scan_from(date, crate::schedule::scan_window_start(lon), lat, lon, 60);
+    let sunset_secs = solar::find_crossing(&samples, solar::HORIZON_ANGLE - extra_refraction, false)?;
+
+    Some(date.and_time(NaiveTime::MIN) + Duration::seconds(sunset_secs.floor() as i64))
 }
 
 /// Evaluate crescent visibility on a given evening using the Odeh (2004) criterion.
@@ -260,9 +331,21 @@ pub fn evaluate_visibility(
     lat: f64,
     lon: f64,
     conjunction: &NaiveDateTime,
+) -> CrescentVisibility {
+    evaluate_visibility_in(date, lat, lon, conjunction, &Atmosphere::STANDARD)
+}
+
+/// [`evaluate_visibility`] with sunset and lunar refraction for the given
+/// air conditions.
+pub fn evaluate_visibility_in(
+    date: NaiveDate,
+    lat: f64,
+    lon: f64,
+    conjunction: &NaiveDateTime,
+    atmosphere: &Atmosphere,
 ) -> CrescentVisibility {
     // Find sunset on this date
-    let sunset = match find_sunset(date, lat, lon) {
+    let sunset = match find_sunset(date, lat, lon, atmosphere) {
         Some(ss) => ss,
         None => {
             return CrescentVisibility {
@@ -294,7 +377,7 @@ pub fn evaluate_visibility(
     }
 
     // Moon position at sunset
-    let moon = lunar_position(&sunset, lat, lon);
+    let moon = lunar_position_in(&sunset, lat, lon, atmosphere);
     let moon_altitude = moon.altitude;
 
     // Elongation at sunset
@@ -332,6 +415,42 @@ pub fn evaluate_visibility(
     }
 }
 
+// ─── Visibility Map ───────────────────────────────────────────────
+
+/// Finest grid spacing accepted by `visibility_grid`, in degrees.
+pub const MIN_GRID_STEP: f64 = 5.0;
+
+/// Latitude band sampled by `visibility_grid`; beyond it twilight is too
+/// long for sightings to be meaningful.
+pub const GRID_LAT_LIMIT: f64 = 60.0;
+
+/// Odeh zone at each point of a world grid for the evening of `date`.
+///
+/// Each point costs a one-minute day scan plus a lunar position, so a 5°
+/// grid (~1,800 points) takes a few seconds; steps are clamped to
+/// [`MIN_GRID_STEP`]. Points run south to north, then west to east.
+pub fn visibility_grid(
+    date: NaiveDate,
+    conjunction: &NaiveDateTime,
+    lat_step: f64,
+    lon_step: f64,
+) -> Vec<(f64, f64, CrescentZone)> {
+    let lat_step = lat_step.max(MIN_GRID_STEP);
+    let lon_step = lon_step.max(MIN_GRID_STEP);
+    let lats = grid_axis(-GRID_LAT_LIMIT, GRID_LAT_LIMIT, lat_step);
+    let lons = grid_axis(-180.0, 180.0 - lon_step / 2.0, lon_step);
+
+    lats.iter()
+        .flat_map(|&lat| lons.iter().map(move |&lon| (lat, lon)))
+        .map(|(lat, lon)| (lat, lon, evaluate_visibility(date, lat, lon, conjunction).zone))
+        .collect()
+}
+
+fn grid_axis(from: f64, to: f64, step: f64) -> Vec<f64> {
+    let n = ((to - from) / step).floor() as usize;
+    (0..=n).map(|i| from + i as f64 * step).collect()
+}
+
 // ─── Ramadan Finder ───────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize)]
@@ -445,7 +564,12 @@ pub fn find_ramadan(hijri_year: u32, lat: f64, lon: f64) -> RamadanInfo {
 
 /// Determine the current Hijri year for Ramadan lookup.
 pub fn current_hijri_year_for_ramadan() -> u32 {
-    let today = chrono::Utc::now().naive_utc().date();
+    hijri_year_for_ramadan(&SystemClock)
+}
+
+/// The Hijri year of the next (or current) Ramadan as of `clock`'s today.
+pub fn hijri_year_for_ramadan(clock: &dyn Clock) -> u32 {
+    let today = clock.today_utc();
     let hijri = gregorian_to_hijri(today);
     // If we're past Ramadan (month > 9), look at next year's Ramadan
     // If we're before or in Ramadan (month <= 9), use current year
@@ -461,6 +585,31 @@ mod tests {
     use super::*;
     use chrono::Datelike;
 
+    #[test]
+    fn test_hijri_display_and_parse() {
+        let date: HijriDate = "1447-9-15".parse().unwrap();
+        assert_eq!(date, HijriDate { year: 1447, month: 9, day: 15 });
+        assert_eq!(date.to_string(), "15 Ramadan 1447");
+        assert_eq!(date.to_iso(), "1447-09-15");
+        assert_eq!(date.to_iso().parse::<HijriDate>().unwrap(), date);
+
+        assert_eq!(month_name_ar(9), "رمضان");
+        assert_eq!(date.to_arabic(), "15 رمضان 1447");
+
+        for bad in ["1447-13-1", "1447-2-30", "1447-9", "1447/9/15", "0-1-1", "x-1-1"] {
+            assert!(bad.parse::<HijriDate>().is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_hijri_serialize() {
+        let json = serde_json::to_value(HijriDate { year: 1447, month: 9, day: 1 }).unwrap();
+        println!("{}", json);
+        assert_eq!(json, serde_json::json!({
+            "year": 1447, "month": 9, "day": 1, "month_name": "Ramadan", "month_name_ar": "رمضان",
+        }));
+    }
+
     #[test]
     fn test_gregorian_to_hijri_known_date() {
         // 2026-02-17 should be approximately Sha'ban 29 or Ramadan 1, 1447
@@ -504,6 +653,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_visibility_in_cold_air() {
+        let conjunction = find_conjunction(NaiveDate::from_ymd_opt(2026, 2, 17).unwrap());
+        let date = NaiveDate::from_ymd_opt(2026, 2, 18).unwrap();
+        let standard = evaluate_visibility(date, 21.4225, 39.8262, &conjunction);
+        let cold = Atmosphere { temperature_c: -40.0, pressure_mbar: 1010.0 };
+        let vis = evaluate_visibility_in(date, 21.4225, 39.8262, &conjunction, &cold);
+        // Sunset comes later in denser air, and the moon is lifted further
+        assert!(vis.moon_age_hours > standard.moon_age_hours);
+        assert!(vis.moon_altitude != standard.moon_altitude);
+    }
+
     #[test]
     fn test_feb18_mecca_visible() {
         // Feb 18 evening from Mecca: moon is ~27.5h old, should be Zone A or B
@@ -528,6 +689,19 @@ mod tests {
             "Ramadan should be 29 or 30 days, got {}", info.days);
     }
 
+    #[test]
+    fn test_ramadan_year_follows_clock() {
+        use crate::clock::FixedClock;
+        use chrono::TimeZone;
+
+        let at = |y, m, d| FixedClock(chrono::Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap());
+        // Rajab and Ramadan 1447 look at this year's Ramadan
+        assert_eq!(hijri_year_for_ramadan(&at(2026, 1, 10)), 1447);
+        assert_eq!(hijri_year_for_ramadan(&at(2026, 3, 5)), 1447);
+        // Once Shawwal begins, the next Ramadan is 1448
+        assert_eq!(hijri_year_for_ramadan(&at(2026, 4, 15)), 1448);
+    }
+
     #[test]
     fn test_odeh_q_formula() {
         // Unit test: if ARCV = 5.0, elongation = 10.0 degrees
@@ -548,4 +722,38 @@ mod tests {
             "Tromso Ramadan start should be >= Feb 19, got {}", info.start
         );
     }
+
+    #[test]
+    fn test_visibility_grid_east_to_west() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 17).unwrap();
+        let conjunction = find_conjunction(date);
+        let grid = visibility_grid(date, &conjunction, 15.0, 15.0);
+        println!("conjunction {} -> {} points", conjunction, grid.len());
+        for lat in [-30.0, 0.0, 30.0] {
+            let row: String = grid.iter()
+                .filter(|(la, _, _)| *la == lat)
+                .map(|(_, _, z)| format!("{:?}", z))
+                .collect();
+            println!("  {:>5}: {}", lat, row);
+        }
+
+        // 9 latitude rows (-60..60) × 24 longitude columns
+        assert_eq!(grid.len(), 9 * 24);
+
+        // East of Africa the moon is only hours old at sunset
+        assert!(grid.iter().filter(|(_, lon, _)| *lon >= 30.0).all(|(_, _, z)| *z == CrescentZone::D));
+
+        // The far west sees it a dozen hours later, near the equator at A/B
+        let far_west_best = grid.iter()
+            .filter(|(lat, lon, _)| *lon <= -150.0 && lat.abs() <= 30.0)
+            .map(|(_, _, z)| *z)
+            .min()
+            .unwrap();
+        assert!(far_west_best <= CrescentZone::B, "best far-west zone {:?}", far_west_best);
+
+        // The next evening every longitude near the equator sees it
+        let next = visibility_grid(date.succ_opt().unwrap(), &conjunction, 30.0, 30.0);
+        assert!(next.iter().filter(|(lat, _, _)| *lat == 0.0).all(|(_, _, z)| *z == CrescentZone::A),
+            "{:?}", next.iter().filter(|(lat, _, _)| *lat == 0.0).collect::<Vec<_>>());
+    }
 }
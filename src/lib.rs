@@ -1,7 +1,20 @@
+/// Engine version, read from Cargo.toml at compile time.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub mod api;
+pub mod clock;
+pub mod config;
+pub mod dates;
+pub mod export;
 pub mod hijri;
 pub mod location;
 pub mod lunar;
+pub mod method;
+pub mod notify;
+pub mod profile;
+pub mod qibla;
 pub mod schedule;
 pub mod server;
 pub mod solar;
 pub mod solver;
+pub mod timezone;
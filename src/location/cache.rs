@@ -1,10 +1,11 @@
 //! File-based location cache at ~/.polaris/cache.json.
 //!
 //! TTL: 30 days. Case-insensitive keys.
-//! Schema v2: adds display_name, country_code, source_name, confidence.
+//! Schema v2: adds display_name, country_code, source_name, confidence, tz_source, elevation_m.
 //! Backward compatible: missing fields default gracefully.
 
-use super::types::{LocationSource, ResolvedLocation};
+use super::types::{LocationSource, ResolvedLocation, TzSource};
+use crate::clock::{Clock, SystemClock};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -28,6 +29,10 @@ struct CacheEntry {
     source_name: Option<String>,
     #[serde(default = "default_confidence")]
     confidence: f64,
+    #[serde(default)]
+    tz_source: TzSource,
+    #[serde(default)]
+    elevation_m: Option<f64>,
 }
 
 fn default_confidence() -> f64 {
@@ -38,6 +43,7 @@ fn default_confidence() -> f64 {
 pub struct LocationCache {
     path: PathBuf,
     entries: HashMap<String, CacheEntry>,
+    clock: Box<dyn Clock>,
 }
 
 impl LocationCache {
@@ -45,13 +51,23 @@ impl LocationCache {
     pub fn load() -> Self {
         let path = Self::default_path();
         let entries = Self::read_file(&path).unwrap_or_default();
-        Self { path, entries }
+        Self { path, entries, clock: Box::new(SystemClock) }
     }
 
     /// Load cache from a specific path (for testing).
     pub fn load_from(path: PathBuf) -> Self {
         let entries = Self::read_file(&path).unwrap_or_default();
-        Self { path, entries }
+        Self { path, entries, clock: Box::new(SystemClock) }
+    }
+
+    /// Read the time for TTLs and timestamps from `clock`.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    fn now_ms(&self) -> i64 {
+        self.clock.now_utc().timestamp_millis()
     }
 
     fn default_path() -> PathBuf {
@@ -71,7 +87,7 @@ impl LocationCache {
         let key = query.to_lowercase();
         let entry = self.entries.get(&key)?;
 
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = self.now_ms();
         if now - entry.timestamp > CACHE_TTL_MS {
             return None; // expired
         }
@@ -87,12 +103,14 @@ impl LocationCache {
             resolver_confidence: entry.confidence,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: entry.tz_source,
+            elevation_m: entry.elevation_m,
         })
     }
 
     /// Get the most recently cached location (for --auto fallback).
     pub fn most_recent(&self) -> Option<ResolvedLocation> {
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = self.now_ms();
         self.entries
             .values()
             .filter(|e| now - e.timestamp <= CACHE_TTL_MS)
@@ -108,6 +126,8 @@ impl LocationCache {
                 resolver_confidence: e.confidence,
                 disambiguated: false,
                 disambiguation_note: None,
+                tz_source: e.tz_source,
+                elevation_m: e.elevation_m,
             })
     }
 
@@ -120,11 +140,13 @@ impl LocationCache {
             lon: resolved.lon,
             tz: resolved.tz.clone(),
             name: resolved.name.clone(),
-            timestamp: chrono::Utc::now().timestamp_millis(),
+            timestamp: self.now_ms(),
             display_name: resolved.display_name.clone(),
             country_code: resolved.country_code.clone(),
             source_name: Some(resolved.source.to_string()),
             confidence: resolved.resolver_confidence,
+            tz_source: resolved.tz_source,
+            elevation_m: resolved.elevation_m,
         };
         self.entries.insert(key, entry);
         self.persist();
@@ -138,11 +160,13 @@ impl LocationCache {
             lon: resolved.lon,
             tz: resolved.tz.clone(),
             name: resolved.name.clone(),
-            timestamp: chrono::Utc::now().timestamp_millis(),
+            timestamp: self.now_ms(),
             display_name: resolved.display_name.clone(),
             country_code: resolved.country_code.clone(),
             source_name: Some(resolved.source.to_string()),
             confidence: resolved.resolver_confidence,
+            tz_source: resolved.tz_source,
+            elevation_m: resolved.elevation_m,
         };
         self.entries.insert(key, entry);
         // Also cache under the resolved name
@@ -153,16 +177,32 @@ impl LocationCache {
                 lon: resolved.lon,
                 tz: resolved.tz.clone(),
                 name: resolved.name.clone(),
-                timestamp: chrono::Utc::now().timestamp_millis(),
+                timestamp: self.now_ms(),
                 display_name: resolved.display_name.clone(),
                 country_code: resolved.country_code.clone(),
                 source_name: Some(resolved.source.to_string()),
                 confidence: resolved.resolver_confidence,
+                tz_source: resolved.tz_source,
+                elevation_m: resolved.elevation_m,
             });
         }
         self.persist();
     }
 
+    /// Record the elevation of every entry at `lat`/`lon` and persist.
+    pub fn set_elevation(&mut self, lat: f64, lon: f64, elevation_m: f64) {
+        let mut changed = false;
+        for entry in self.entries.values_mut() {
+            if (entry.lat - lat).abs() < 1e-6 && (entry.lon - lon).abs() < 1e-6 {
+                entry.elevation_m = Some(elevation_m);
+                changed = true;
+            }
+        }
+        if changed {
+            self.persist();
+        }
+    }
+
     fn persist(&self) {
         if let Some(parent) = self.path.parent() {
             let _ = fs::create_dir_all(parent);
@@ -176,6 +216,10 @@ impl LocationCache {
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +248,8 @@ mod tests {
             resolver_confidence: 0.92,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         };
         cache.put(&loc);
 
@@ -230,6 +276,8 @@ mod tests {
             resolver_confidence: 0.95,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         };
         cache.put(&loc);
 
@@ -262,6 +310,8 @@ mod tests {
                 resolver_confidence: 0.9,
                 disambiguated: false,
                 disambiguation_note: None,
+                tz_source: TzSource::TimeApi,
+                elevation_m: None,
             });
         }
 
@@ -271,6 +321,38 @@ mod tests {
         assert_eq!(result.name, "Tokyo");
     }
 
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        use crate::clock::FixedClock;
+        use chrono::{Duration, TimeZone, Utc};
+
+        let t0 = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let (cache, _dir) = test_cache();
+        let mut cache = cache.with_clock(FixedClock(t0));
+        cache.put(&ResolvedLocation {
+            name: "Oslo".into(),
+            lat: 59.9139,
+            lon: 10.7522,
+            tz: "Europe/Oslo".into(),
+            source: LocationSource::Nominatim,
+            display_name: None,
+            country_code: Some("NO".into()),
+            resolver_confidence: 0.9,
+            disambiguated: false,
+            disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
+        });
+
+        let cache = cache.with_clock(FixedClock(t0 + Duration::days(30)));
+        assert!(cache.get("oslo").is_some(), "still fresh on day 30");
+        assert!(cache.most_recent().is_some());
+
+        let cache = cache.with_clock(FixedClock(t0 + Duration::days(31)));
+        assert!(cache.get("oslo").is_none(), "expired after 30 days");
+        assert!(cache.most_recent().is_none());
+    }
+
     #[test]
     fn test_most_recent() {
         let (mut cache, _dir) = test_cache();
@@ -285,6 +367,8 @@ mod tests {
             resolver_confidence: 0.5,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         });
         std::thread::sleep(std::time::Duration::from_millis(10));
         cache.put(&ResolvedLocation {
@@ -297,6 +381,8 @@ mod tests {
             resolver_confidence: 0.8,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         });
 
         let recent = cache.most_recent().unwrap();
@@ -341,6 +427,8 @@ mod tests {
             resolver_confidence: 0.9,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         };
         cache.put_with_key("medina", &loc);
 
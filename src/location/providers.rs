@@ -1,190 +1,87 @@
 //! Location providers: Nominatim, IP API, and built-in fallback dataset.
 
-use super::types::{LocationError, LocationSource, ResolvedLocation};
+use super::types::{Granularity, LocationError, LocationSource, ResolvedLocation, TzSource};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+
+// ─── Network accounting ─────────────────────────────────────────
+
+static NETWORK_CALLS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of outbound HTTP requests made by this process so far.
+///
+/// Every provider request goes through [`http_get`], so offline runs can
+/// assert this stays at zero.
+pub fn network_call_count() -> u64 {
+    NETWORK_CALLS.load(Ordering::Relaxed)
+}
+
+/// Start an outbound GET request, counting it.
+fn http_get(url: &str) -> ureq::Request {
+    NETWORK_CALLS.fetch_add(1, Ordering::Relaxed);
+    ureq::get(url)
+}
+
+/// Map a failed request to a `LocationError`, keeping HTTP 429 apart.
+fn request_error(err: ureq::Error) -> LocationError {
+    match err {
+        ureq::Error::Status(429, response) => LocationError::RateLimited {
+            retry_after: response.header("Retry-After").and_then(parse_retry_after),
+        },
+        e => LocationError::Network(e.to_string()),
+    }
+}
+
+/// Parse a `Retry-After` value: delay seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
 
 // ─── Built-in dataset ───────────────────────────────────────────
 
 struct BuiltinCity {
-    names: &'static [&'static str], // canonical + aliases
+    names: Vec<&'static str>, // canonical + aliases
     lat: f64,
     lon: f64,
     tz: &'static str,
     country_code: &'static str,
 }
 
-const BUILTIN_CITIES: &[BuiltinCity] = &[
-    BuiltinCity {
-        names: &["mecca", "makkah", "mekka"],
-        lat: 21.4225, lon: 39.8262, tz: "Asia/Riyadh",
-        country_code: "SA",
-    },
-    BuiltinCity {
-        names: &["medina", "madinah", "al-madinah"],
-        lat: 24.4686, lon: 39.6142, tz: "Asia/Riyadh",
-        country_code: "SA",
-    },
-    BuiltinCity {
-        names: &["stockholm", "stokholm"],
-        lat: 59.3293, lon: 18.0686, tz: "Europe/Stockholm",
-        country_code: "SE",
-    },
-    BuiltinCity {
-        names: &["tromso", "tromsø", "tromsoe"],
-        lat: 69.6492, lon: 18.9553, tz: "Europe/Oslo",
-        country_code: "NO",
-    },
-    BuiltinCity {
-        names: &["svalbard", "longyearbyen"],
-        lat: 78.2232, lon: 15.6267, tz: "Arctic/Longyearbyen",
-        country_code: "NO",
-    },
-    BuiltinCity {
-        names: &["new york", "newyork", "nyc"],
-        lat: 40.7128, lon: -74.0060, tz: "America/New_York",
-        country_code: "US",
-    },
-    BuiltinCity {
-        names: &["tokyo"],
-        lat: 35.6762, lon: 139.6503, tz: "Asia/Tokyo",
-        country_code: "JP",
-    },
-    BuiltinCity {
-        names: &["london"],
-        lat: 51.5074, lon: -0.1278, tz: "Europe/London",
-        country_code: "GB",
-    },
-    BuiltinCity {
-        names: &["cairo", "al-qahirah"],
-        lat: 30.0444, lon: 31.2357, tz: "Africa/Cairo",
-        country_code: "EG",
-    },
-    BuiltinCity {
-        names: &["istanbul"],
-        lat: 41.0082, lon: 28.9784, tz: "Europe/Istanbul",
-        country_code: "TR",
-    },
-    BuiltinCity {
-        names: &["jakarta"],
-        lat: -6.2088, lon: 106.8456, tz: "Asia/Jakarta",
-        country_code: "ID",
-    },
-    BuiltinCity {
-        names: &["kuala lumpur", "kl"],
-        lat: 3.1390, lon: 101.6869, tz: "Asia/Kuala_Lumpur",
-        country_code: "MY",
-    },
-    BuiltinCity {
-        names: &["riyadh"],
-        lat: 24.7136, lon: 46.6753, tz: "Asia/Riyadh",
-        country_code: "SA",
-    },
-    BuiltinCity {
-        names: &["dubai"],
-        lat: 25.2048, lon: 55.2708, tz: "Asia/Dubai",
-        country_code: "AE",
-    },
-    BuiltinCity {
-        names: &["oslo"],
-        lat: 59.9139, lon: 10.7522, tz: "Europe/Oslo",
-        country_code: "NO",
-    },
-    BuiltinCity {
-        names: &["paris"],
-        lat: 48.8566, lon: 2.3522, tz: "Europe/Paris",
-        country_code: "FR",
-    },
-    BuiltinCity {
-        names: &["berlin"],
-        lat: 52.5200, lon: 13.4050, tz: "Europe/Berlin",
-        country_code: "DE",
-    },
-    BuiltinCity {
-        names: &["moscow", "moskva"],
-        lat: 55.7558, lon: 37.6173, tz: "Europe/Moscow",
-        country_code: "RU",
-    },
-    BuiltinCity {
-        names: &["sydney"],
-        lat: -33.8688, lon: 151.2093, tz: "Australia/Sydney",
-        country_code: "AU",
-    },
-    BuiltinCity {
-        names: &["los angeles", "la"],
-        lat: 34.0522, lon: -118.2437, tz: "America/Los_Angeles",
-        country_code: "US",
-    },
-    BuiltinCity {
-        names: &["dhaka", "dacca"],
-        lat: 23.8103, lon: 90.4125, tz: "Asia/Dhaka",
-        country_code: "BD",
-    },
-    BuiltinCity {
-        names: &["casablanca", "dar el beida"],
-        lat: 33.5731, lon: -7.5898, tz: "Africa/Casablanca",
-        country_code: "MA",
-    },
-    BuiltinCity {
-        names: &["mumbai", "bombay"],
-        lat: 19.0760, lon: 72.8777, tz: "Asia/Kolkata",
-        country_code: "IN",
-    },
-    BuiltinCity {
-        names: &["delhi", "new delhi"],
-        lat: 28.6139, lon: 77.2090, tz: "Asia/Kolkata",
-        country_code: "IN",
-    },
-    BuiltinCity {
-        names: &["karachi"],
-        lat: 24.8607, lon: 67.0011, tz: "Asia/Karachi",
-        country_code: "PK",
-    },
-    BuiltinCity {
-        names: &["tehran"],
-        lat: 35.6892, lon: 51.3890, tz: "Asia/Tehran",
-        country_code: "IR",
-    },
-    BuiltinCity {
-        names: &["baghdad"],
-        lat: 33.3152, lon: 44.3661, tz: "Asia/Baghdad",
-        country_code: "IQ",
-    },
-    BuiltinCity {
-        names: &["jerusalem", "al-quds"],
-        lat: 31.7683, lon: 35.2137, tz: "Asia/Jerusalem",
-        country_code: "PS",
-    },
-    BuiltinCity {
-        names: &["gaza", "ghazza"],
-        lat: 31.5017, lon: 34.4668, tz: "Asia/Gaza",
-        country_code: "PS",
-    },
-    BuiltinCity {
-        names: &["ramallah"],
-        lat: 31.9038, lon: 35.2034, tz: "Asia/Hebron",
-        country_code: "PS",
-    },
-    BuiltinCity {
-        names: &["hebron", "al-khalil"],
-        lat: 31.5326, lon: 35.0998, tz: "Asia/Hebron",
-        country_code: "PS",
-    },
-    BuiltinCity {
-        names: &["nablus", "nablous"],
-        lat: 32.2211, lon: 35.2544, tz: "Asia/Hebron",
-        country_code: "PS",
-    },
-    BuiltinCity {
-        names: &["nairobi"],
-        lat: -1.2921, lon: 36.8219, tz: "Africa/Nairobi",
-        country_code: "KE",
-    },
-    BuiltinCity {
-        names: &["lagos"],
-        lat: 6.5244, lon: 3.3792, tz: "Africa/Lagos",
-        country_code: "NG",
-    },
-];
+/// The embedded dataset: `names,lat,lon,tz,country_code` rows with
+/// `|`-separated names; `#` lines and blank lines are skipped.
+const BUILTIN_CITIES_CSV: &str = include_str!("builtin_cities.csv");
+
+static BUILTIN_CITIES: LazyLock<Vec<BuiltinCity>> = LazyLock::new(|| parse_builtin_cities(BUILTIN_CITIES_CSV));
+
+/// Parse the dataset; it ships with the binary, so a bad row is a build bug.
+fn parse_builtin_cities(csv: &'static str) -> Vec<BuiltinCity> {
+    csv.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .skip(1) // header
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [names, lat, lon, tz, country_code] = fields[..] else {
+                panic!("builtin_cities.csv:{}: expected 5 fields, got {}", i + 1, fields.len());
+            };
+            let coord = |v: &str| v.parse::<f64>()
+                .unwrap_or_else(|_| panic!("builtin_cities.csv:{}: bad coordinate '{}'", i + 1, v));
+            BuiltinCity {
+                names: names.split('|').collect(),
+                lat: coord(lat),
+                lon: coord(lon),
+                tz,
+                country_code,
+            }
+        })
+        .collect()
+}
 
 /// Compute edit distance between two strings (Levenshtein).
 fn edit_distance(a: &str, b: &str) -> usize {
@@ -226,7 +123,7 @@ pub fn builtin_lookup_with_country(query: &str, country: Option<&str>) -> Option
 
     // Exact match first
     for city in &candidates {
-        for name in city.names {
+        for name in &city.names {
             if *name == q {
                 return Some(builtin_to_resolved(city));
             }
@@ -235,7 +132,7 @@ pub fn builtin_lookup_with_country(query: &str, country: Option<&str>) -> Option
 
     // Substring match
     for city in &candidates {
-        for name in city.names {
+        for name in &city.names {
             if name.contains(&q) || q.contains(name) {
                 return Some(builtin_to_resolved(city));
             }
@@ -243,17 +140,23 @@ pub fn builtin_lookup_with_country(query: &str, country: Option<&str>) -> Option
     }
 
     // Fuzzy match (edit distance <= 2)
-    let mut best: Option<(&BuiltinCity, usize)> = None;
-    for city in &candidates {
-        for name in city.names {
-            let dist = edit_distance(&q, name);
-            if dist <= 2 && (best.is_none() || dist < best.unwrap().1) {
-                best = Some((city, dist));
-            }
-        }
-    }
+    fuzzy_match(&q, &candidates).map(builtin_to_resolved)
+}
 
-    best.map(|(city, _)| builtin_to_resolved(city))
+/// The candidate with the closest name within edit distance 2, over every
+/// alias of every candidate.
+///
+/// Ties go to the alias nearest the query's length, then to the smaller
+/// canonical name, so the result never depends on dataset order. (A
+/// country hint has already narrowed `candidates`.)
+fn fuzzy_match<'a>(q: &str, candidates: &[&'a BuiltinCity]) -> Option<&'a BuiltinCity> {
+    let q_len = q.chars().count();
+    candidates.iter()
+        .flat_map(|&city| city.names.iter().map(move |name| (city, *name)))
+        .map(|(city, name)| (edit_distance(q, name), name.chars().count().abs_diff(q_len), city.names[0], city))
+        .filter(|&(dist, ..)| dist <= 2)
+        .min_by_key(|&(dist, len_diff, canonical, _)| (dist, len_diff, canonical))
+        .map(|(.., city)| city)
 }
 
 fn builtin_to_resolved(city: &BuiltinCity) -> ResolvedLocation {
@@ -268,9 +171,19 @@ fn builtin_to_resolved(city: &BuiltinCity) -> ResolvedLocation {
         resolver_confidence: 0.95,
         disambiguated: false,
         disambiguation_note: None,
+        tz_source: TzSource::Builtin,
+        elevation_m: None,
     }
 }
 
+/// Every built-in city in a country (ISO 3166-1 alpha-2, any case).
+pub fn builtin_cities_in_country(code: &str) -> Vec<ResolvedLocation> {
+    BUILTIN_CITIES.iter()
+        .filter(|c| c.country_code.eq_ignore_ascii_case(code.trim()))
+        .map(builtin_to_resolved)
+        .collect()
+}
+
 /// A city entry for the public city list API.
 #[derive(Debug, Clone, Serialize)]
 pub struct CityInfo {
@@ -295,6 +208,108 @@ pub fn builtin_city_list() -> Vec<CityInfo> {
 
 // ─── Nominatim provider ─────────────────────────────────────────
 
+/// Online geocoding behind the resolver's city flow.
+///
+/// [`Nominatim`] is the default; tests inject fakes so the fallback chain
+/// (e.g. ambiguous → built-in) runs without network.
+pub trait GeocodeProvider: Send + Sync {
+    /// The best match for a query.
+    fn resolve(&self, query: &str, country_hint: Option<&str>, granularity: Granularity) -> Result<ResolvedLocation, LocationError>;
+
+    /// Scored candidates, best first (for `--topk` and ambiguity reports).
+    /// Providers without a candidate search find none.
+    fn candidates(
+        &self,
+        query: &str,
+        _country_hint: Option<&str>,
+        _limit: usize,
+        _granularity: Granularity,
+    ) -> Result<Vec<NominatimCandidate>, LocationError> {
+        Err(LocationError::NotFound(query.to_string()))
+    }
+
+    /// IANA zone for a candidate's coordinates. Providers without a zone
+    /// lookup fail, and callers fall back to a longitude estimate.
+    fn timezone(&self, lat: f64, lon: f64) -> Result<String, LocationError> {
+        Err(LocationError::NotFound(format_coords(lat, lon)))
+    }
+
+    /// Height above sea level in metres (`--elevation auto`). Providers
+    /// without an elevation source fail.
+    fn elevation(&self, lat: f64, lon: f64) -> Result<f64, LocationError> {
+        Err(LocationError::NotFound(format_coords(lat, lon)))
+    }
+
+    /// The place a postal code belongs to (see [`looks_like_postal_code`]).
+    /// Providers without a postal code search find none.
+    fn postal_code(&self, code: &str, _country_hint: Option<&str>) -> Result<ResolvedLocation, LocationError> {
+        Err(LocationError::NotFound(code.to_string()))
+    }
+}
+
+/// The OpenStreetMap Nominatim geocoder.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nominatim;
+
+impl GeocodeProvider for Nominatim {
+    fn resolve(&self, query: &str, country_hint: Option<&str>, granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+        nominatim_resolve_with_options(query, country_hint, granularity)
+    }
+
+    fn candidates(
+        &self,
+        query: &str,
+        country_hint: Option<&str>,
+        limit: usize,
+        granularity: Granularity,
+    ) -> Result<Vec<NominatimCandidate>, LocationError> {
+        nominatim_resolve_candidates_with(query, country_hint, limit, granularity)
+    }
+
+    fn timezone(&self, lat: f64, lon: f64) -> Result<String, LocationError> {
+        Ok(tz_from_coords(lat, lon))
+    }
+
+    fn elevation(&self, lat: f64, lon: f64) -> Result<f64, LocationError> {
+        elevation(lat, lon)
+    }
+
+    fn postal_code(&self, code: &str, country_hint: Option<&str>) -> Result<ResolvedLocation, LocationError> {
+        nominatim_resolve_postal_code(code, country_hint)
+    }
+}
+
+/// A plain lookup function replaces only [`GeocodeProvider::resolve`];
+/// everything else still goes to Nominatim.
+impl GeocodeProvider for fn(&str, Option<&str>, Granularity) -> Result<ResolvedLocation, LocationError> {
+    fn resolve(&self, query: &str, country_hint: Option<&str>, granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+        self(query, country_hint, granularity)
+    }
+
+    fn candidates(
+        &self,
+        query: &str,
+        country_hint: Option<&str>,
+        limit: usize,
+        granularity: Granularity,
+    ) -> Result<Vec<NominatimCandidate>, LocationError> {
+        Nominatim.candidates(query, country_hint, limit, granularity)
+    }
+
+    fn timezone(&self, lat: f64, lon: f64) -> Result<String, LocationError> {
+        Nominatim.timezone(lat, lon)
+    }
+
+    fn elevation(&self, lat: f64, lon: f64) -> Result<f64, LocationError> {
+        Nominatim.elevation(lat, lon)
+    }
+
+    fn postal_code(&self, code: &str, country_hint: Option<&str>) -> Result<ResolvedLocation, LocationError> {
+        Nominatim.postal_code(code, country_hint)
+    }
+}
+
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct NominatimResult {
     pub lat: String,
@@ -361,13 +376,77 @@ const WELL_KNOWN_CITIES: &[(&str, &str)] = &[
     ("baghdad", "IQ"), ("jerusalem", "PS"),
 ];
 
-fn type_rank(place_type: &str, place_class: &str) -> f64 {
+/// Arabic spellings and common transliterations of well-known cities,
+/// mapped to the names in `WELL_KNOWN_CITIES`.
+const CITY_NAME_ALIASES: &[(&str, &str)] = &[
+    ("مكة", "mecca"), ("مكة المكرمة", "mecca"), ("makka", "mecca"),
+    ("المدينة", "medina"), ("المدينة المنورة", "medina"), ("al-madina", "medina"),
+    ("جدة", "jeddah"), ("jiddah", "jeddah"),
+    ("الرياض", "riyadh"), ("ar-riyad", "riyadh"),
+    ("القاهرة", "cairo"), ("al-qahira", "cairo"), ("al-qahirah", "cairo"),
+    ("القدس", "jerusalem"), ("غزة", "gaza"), ("بيت لحم", "bethlehem"),
+    ("رام الله", "ramallah"), ("الخليل", "hebron"), ("نابلس", "nablus"),
+    ("دمشق", "damascus"), ("dimashq", "damascus"),
+    ("بغداد", "baghdad"), ("إسطنبول", "istanbul"), ("اسطنبول", "istanbul"),
+    ("طهران", "tehran"), ("كراتشي", "karachi"), ("دبي", "dubai"),
+    ("الدوحة", "doha"), ("الدار البيضاء", "casablanca"),
+];
+
+/// Lowercase `query`, drop Arabic diacritics and tatweel, and map known
+/// Arabic or transliterated city names to their English form.
+fn normalize_city_query(query: &str) -> String {
+    let cleaned: String = query.trim()
+        .chars()
+        .filter(|c| !matches!(c, '\u{064B}'..='\u{0652}' | '\u{0640}'))
+        .collect::<String>()
+        .to_lowercase();
+    CITY_NAME_ALIASES.iter()
+        .find(|(alias, _)| *alias == cleaned)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(cleaned)
+}
+
+/// How well a place type fits the requested granularity.
+///
+/// City lookups penalize anything smaller than a town; suburb and POI
+/// lookups rank the finer types they asked for at the top instead.
+fn type_rank(place_type: &str, place_class: &str, granularity: Granularity) -> f64 {
+    match granularity {
+        Granularity::City => match (place_class, place_type) {
+            ("place", "city") | ("boundary", "administrative") => 1.0,
+            ("place", "town") => 0.8,
+            ("place", "village") => 0.4,
+            ("place", "hamlet") => 0.2,
+            _ => 0.5,
+        },
+        Granularity::Suburb => match (place_class, place_type) {
+            ("place", "suburb" | "neighbourhood" | "quarter" | "city_district") => 1.0,
+            ("place", "city" | "town") | ("boundary", "administrative") => 0.8,
+            ("place", "village") => 0.6,
+            ("place", "hamlet") => 0.4,
+            _ => 0.5,
+        },
+        Granularity::Poi => match (place_class, place_type) {
+            ("place", "suburb" | "neighbourhood" | "quarter" | "city_district") => 1.0,
+            ("amenity" | "tourism" | "historic" | "building" | "leisure" | "man_made", _) => 1.0,
+            ("place", _) | ("boundary", "administrative") => 0.8,
+            _ => 0.7,
+        },
+    }
+}
+
+/// Importance prior used when Nominatim returns no `importance`.
+///
+/// Smaller official places are often left unannotated; a flat default
+/// underweights them, so the floor follows the place type instead.
+fn importance_prior(place_type: &str, place_class: &str) -> f64 {
     match (place_class, place_type) {
-        ("place", "city") | ("boundary", "administrative") => 1.0,
-        ("place", "town") => 0.8,
-        ("place", "village") => 0.4,
-        ("place", "hamlet") => 0.2,
-        _ => 0.5,
+        ("place", "city") => 0.6,
+        ("boundary", "administrative") => 0.5,
+        ("place", "town") => 0.45,
+        ("place", "village") => 0.25,
+        ("place", "hamlet") => 0.15,
+        _ => 0.3,
     }
 }
 
@@ -395,7 +474,7 @@ fn country_name_to_code(name: &str) -> Option<String> {
     let n = name.to_lowercase();
     // Common mappings — extend as needed
     let code = match n.as_str() {
-        "saudi arabia" | "المملكة العربية السعودية" => "SA",
+        "saudi arabia" | "المملكة العربية السعودية" | "السعودية" => "SA",
         "united states" | "united states of america" | "usa" | "us" => "US",
         "united kingdom" | "uk" | "great britain" | "england" => "GB",
         "france" => "FR", "germany" | "deutschland" => "DE",
@@ -404,13 +483,13 @@ fn country_name_to_code(name: &str) -> Option<String> {
         "china" | "people's republic of china" => "CN",
         "japan" | "日本" => "JP", "india" => "IN",
         "pakistan" => "PK", "iran" => "IR",
-        "iraq" => "IQ", "turkey" | "türkiye" => "TR",
+        "iraq" | "العراق" => "IQ", "turkey" | "türkiye" => "TR",
         "egypt" | "مصر" => "EG", "israel" => "IL",
-        "palestine" | "palestinian territory" => "PS",
-        "syria" | "syrian arab republic" => "SY",
-        "jordan" => "JO", "lebanon" => "LB",
-        "united arab emirates" | "uae" => "AE",
-        "qatar" => "QA", "kuwait" => "KW",
+        "palestine" | "palestinian territory" | "فلسطين" => "PS",
+        "syria" | "syrian arab republic" | "سوريا" => "SY",
+        "jordan" | "الأردن" => "JO", "lebanon" | "لبنان" => "LB",
+        "united arab emirates" | "uae" | "الإمارات العربية المتحدة" => "AE",
+        "qatar" | "قطر" => "QA", "kuwait" | "الكويت" => "KW",
         "oman" => "OM", "bahrain" => "BH",
         "yemen" => "YE",
         "nigeria" => "NG", "kenya" => "KE",
@@ -444,22 +523,41 @@ fn country_name_to_code(name: &str) -> Option<String> {
         "kazakhstan" => "KZ",
         "azerbaijan" => "AZ",
         "georgia" => "GE",
+        "algeria" | "الجزائر" => "DZ", "tunisia" | "تونس" => "TN",
+        "libya" | "ليبيا" => "LY", "sudan" | "السودان" => "SD",
+        "somalia" | "الصومال" => "SO", "mauritania" | "موريتانيا" => "MR",
+        "senegal" => "SN", "mali" => "ML", "niger" => "NE",
+        "tajikistan" => "TJ", "kyrgyzstan" => "KG", "turkmenistan" => "TM",
+        "bosnia and herzegovina" | "bosnia" => "BA", "albania" => "AL",
+        "kosovo" => "XK", "maldives" => "MV", "brunei" => "BN",
         _ => return None,
     };
     Some(code.to_string())
 }
 
-fn score_candidate(query: &str, candidate: &NominatimResult, country_hint: Option<&str>) -> NominatimCandidate {
-    let importance = candidate.importance.unwrap_or(0.3);
+fn score_candidate(
+    query: &str,
+    candidate: &NominatimResult,
+    country_hint: Option<&str>,
+    granularity: Granularity,
+) -> NominatimCandidate {
     let ptype = candidate.place_type.as_deref().unwrap_or("unknown");
     let pclass = candidate.place_class.as_deref().unwrap_or("unknown");
+    // `addresstype` tells an administrative boundary's level (city vs state)
+    // and stands in when class/type are missing
+    let (rank_type, rank_class) = match candidate.addresstype.as_deref() {
+        Some(addresstype) if candidate.place_class.is_none() || pclass == "boundary" => (addresstype, "place"),
+        _ => (ptype, pclass),
+    };
+    let importance = candidate.importance.unwrap_or_else(|| importance_prior(rank_type, rank_class));
     let country = extract_country_code(&candidate.display_name);
 
-    let type_score = type_rank(ptype, pclass);
-    let name_score = name_similarity(query, &candidate.display_name);
+    let type_score = type_rank(rank_type, rank_class, granularity);
+    let q_lower = normalize_city_query(query);
+    let name_score = name_similarity(query, &candidate.display_name)
+        .max(name_similarity(&q_lower, &candidate.display_name));
 
     // Country bonus: from explicit --country flag or from well-known list
-    let q_lower = query.to_lowercase();
     let mut country_score = 0.5; // neutral
     if let Some(hint) = country_hint {
         if country == hint.to_uppercase() {
@@ -502,11 +600,46 @@ fn score_candidate(query: &str, candidate: &NominatimResult, country_hint: Optio
     }
 }
 
+/// Score raw Nominatim results and sort them best first.
+pub fn rank_candidates(
+    query: &str,
+    results: &[NominatimResult],
+    country_hint: Option<&str>,
+    granularity: Granularity,
+) -> Vec<NominatimCandidate> {
+    let mut candidates: Vec<NominatimCandidate> = results
+        .iter()
+        .map(|r| score_candidate(query, r, country_hint, granularity))
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    candidates
+}
+
+/// Extra Nominatim search parameters for a granularity.
+fn granularity_params(granularity: Granularity) -> &'static str {
+    match granularity {
+        Granularity::City => "",
+        Granularity::Suburb => "&layer=address",
+        Granularity::Poi => "&layer=address,poi",
+    }
+}
+
 /// Resolve a city name via OpenStreetMap Nominatim, returning scored candidates.
 pub fn nominatim_resolve_candidates(
     query: &str,
     country_hint: Option<&str>,
     limit: usize,
+) -> Result<Vec<NominatimCandidate>, LocationError> {
+    nominatim_resolve_candidates_with(query, country_hint, limit, Granularity::City)
+}
+
+/// Like [`nominatim_resolve_candidates`], for a specific granularity.
+pub fn nominatim_resolve_candidates_with(
+    query: &str,
+    country_hint: Option<&str>,
+    limit: usize,
+    granularity: Granularity,
 ) -> Result<Vec<NominatimCandidate>, LocationError> {
     let country_param = if let Some(cc) = country_hint {
         format!("&countrycodes={}", urlencod(cc))
@@ -515,16 +648,17 @@ pub fn nominatim_resolve_candidates(
     };
 
     let url = format!(
-        "https://nominatim.openstreetmap.org/search?q={}&format=json&limit={}&addressdetails=0{}",
+        "https://nominatim.openstreetmap.org/search?q={}&format=json&limit={}&addressdetails=0{}{}",
         urlencod(query),
         limit.clamp(3, 10),
         country_param,
+        granularity_params(granularity),
     );
 
-    let response = ureq::get(&url)
+    let response = http_get(&url)
         .set("User-Agent", "PolarisChronos/0.6 (prayer-time-engine)")
         .call()
-        .map_err(|e| LocationError::Network(e.to_string()))?;
+        .map_err(request_error)?;
 
     let results: Vec<NominatimResult> = response
         .into_json()
@@ -534,27 +668,21 @@ pub fn nominatim_resolve_candidates(
         return Err(LocationError::NotFound(query.to_string()));
     }
 
-    let mut candidates: Vec<NominatimCandidate> = results
-        .iter()
-        .map(|r| score_candidate(query, r, country_hint))
-        .collect();
-
-    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-
-    Ok(candidates)
+    Ok(rank_candidates(query, &results, country_hint, granularity))
 }
 
 /// Resolve a city name via OpenStreetMap Nominatim (legacy single-result).
 pub fn nominatim_resolve(query: &str) -> Result<ResolvedLocation, LocationError> {
-    nominatim_resolve_with_options(query, None)
+    nominatim_resolve_with_options(query, None, Granularity::City)
 }
 
-/// Resolve with country hint.
+/// Resolve with country hint and granularity.
 pub fn nominatim_resolve_with_options(
     query: &str,
     country_hint: Option<&str>,
+    granularity: Granularity,
 ) -> Result<ResolvedLocation, LocationError> {
-    let candidates = nominatim_resolve_candidates(query, country_hint, 5)?;
+    let candidates = nominatim_resolve_candidates_with(query, country_hint, 5, granularity)?;
 
     if candidates.is_empty() {
         return Err(LocationError::NotFound(query.to_string()));
@@ -606,7 +734,7 @@ pub fn nominatim_resolve_with_options(
     }
 
     // Derive timezone
-    let tz = tz_from_coords(top.lat, top.lon);
+    let (tz, tz_source) = tz_from_coords_with_source(top.lat, top.lon);
 
     Ok(ResolvedLocation {
         name: top.name.clone(),
@@ -619,6 +747,108 @@ pub fn nominatim_resolve_with_options(
         resolver_confidence: top.score.min(1.0),
         disambiguated,
         disambiguation_note,
+        tz_source,
+        elevation_m: None,
+    })
+}
+
+// ─── Postal codes ───────────────────────────────────────────────
+
+/// Whether a query reads as a postal code ("10001", "10001-1234",
+/// "SW1A 1AA", "1012 AB") rather than a place name: 3–10 characters of
+/// ASCII letters and digits in one or two parts, each holding a digit or
+/// at most two letters (so "Route 66" stays a name).
+pub fn looks_like_postal_code(query: &str) -> bool {
+    let q = query.trim();
+    let parts: Vec<&str> = q.split([' ', '-']).collect();
+    (3..=10).contains(&q.len())
+        && parts.len() <= 2
+        && q.chars().any(|c| c.is_ascii_digit())
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_alphanumeric())
+                && (part.chars().any(|c| c.is_ascii_digit()) || part.len() <= 2)
+        })
+}
+
+/// Structured Nominatim search for a postal code, narrowed to a country
+/// when one is known.
+fn postal_code_url(code: &str, country_hint: Option<&str>) -> String {
+    let country_param = match country_hint {
+        Some(cc) => format!("&countrycodes={}", urlencod(&cc.to_lowercase())),
+        None => String::new(),
+    };
+    format!(
+        "https://nominatim.openstreetmap.org/search?postalcode={}&format=json&limit=5&addressdetails=0{}",
+        urlencod(&code.trim().to_uppercase()),
+        country_param,
+    )
+}
+
+/// Name for a postal-code result: the first part of `display_name` that
+/// is not the code itself, with the code ("Manhattan (10001)").
+fn postal_place_name(code: &str, display_name: &str) -> String {
+    let code = code.trim().to_uppercase();
+    let same = |part: &str| part.replace(' ', "").eq_ignore_ascii_case(&code.replace(' ', ""));
+    match display_name.split(',').map(str::trim).find(|part| !part.is_empty() && !same(part)) {
+        Some(place) => format!("{} ({})", place, code),
+        None => code,
+    }
+}
+
+/// Resolve a postal code via Nominatim's structured `postalcode=` search.
+///
+/// Without a country hint the first result wins; when the same code
+/// exists in other countries the result carries a note saying so.
+pub fn nominatim_resolve_postal_code(code: &str, country_hint: Option<&str>) -> Result<ResolvedLocation, LocationError> {
+    let response = http_get(&postal_code_url(code, country_hint))
+        .set("User-Agent", "PolarisChronos/0.6 (prayer-time-engine)")
+        .call()
+        .map_err(request_error)?;
+
+    let results: Vec<NominatimResult> = response
+        .into_json()
+        .map_err(|e| LocationError::InvalidResponse(e.to_string()))?;
+    postal_code_location(code, country_hint, &results)
+}
+
+/// Build the location for a postal code from raw Nominatim results.
+fn postal_code_location(
+    code: &str,
+    country_hint: Option<&str>,
+    results: &[NominatimResult],
+) -> Result<ResolvedLocation, LocationError> {
+    let top = results.first().ok_or_else(|| LocationError::NotFound(code.to_string()))?;
+    let lat: f64 = top.lat.parse().map_err(|_| LocationError::InvalidResponse(format!("bad latitude '{}'", top.lat)))?;
+    let lon: f64 = top.lon.parse().map_err(|_| LocationError::InvalidResponse(format!("bad longitude '{}'", top.lon)))?;
+    let country = extract_country_code(&top.display_name);
+
+    let mut others: Vec<String> = results[1..].iter()
+        .map(|r| extract_country_code(&r.display_name))
+        .filter(|cc| !cc.is_empty() && *cc != country)
+        .collect();
+    others.sort();
+    others.dedup();
+    let disambiguation_note = (country_hint.is_none() && !others.is_empty()).then(|| format!(
+        "Postal code {} also exists in {}; pass a country to choose",
+        code.trim().to_uppercase(),
+        others.join(", "),
+    ));
+
+    let (tz, tz_source) = tz_from_coords_with_source(lat, lon);
+    Ok(ResolvedLocation {
+        name: postal_place_name(code, &top.display_name),
+        lat,
+        lon,
+        tz,
+        source: LocationSource::Nominatim,
+        display_name: Some(top.display_name.clone()),
+        country_code: if country.is_empty() { None } else { Some(country) },
+        resolver_confidence: if disambiguation_note.is_some() { 0.6 } else { 0.9 },
+        disambiguated: disambiguation_note.is_some(),
+        disambiguation_note,
+        tz_source,
+        elevation_m: None,
     })
 }
 
@@ -634,12 +864,18 @@ struct IpApiResult {
     country_code: Option<String>,
 }
 
+/// The IP API's zone as a canonical IANA name; `None` when missing or
+/// unparseable, so the caller looks it up from the coordinates instead.
+fn ip_api_timezone(reported: Option<&str>) -> Option<String> {
+    crate::timezone::parse_tz(reported?).ok().map(|tz| tz.name().to_string())
+}
+
 /// Auto-detect location via IP geolocation.
 pub fn ip_geolocate() -> Result<ResolvedLocation, LocationError> {
-    let response = ureq::get("https://ipapi.co/json/")
+    let response = http_get("https://ipapi.co/json/")
         .set("User-Agent", "PolarisChronos/0.6")
         .call()
-        .map_err(|e| LocationError::Network(e.to_string()))?;
+        .map_err(request_error)?;
 
     let r: IpApiResult = response
         .into_json()
@@ -647,7 +883,10 @@ pub fn ip_geolocate() -> Result<ResolvedLocation, LocationError> {
 
     let lat = r.latitude.ok_or_else(|| LocationError::InvalidResponse("no latitude".into()))?;
     let lon = r.longitude.ok_or_else(|| LocationError::InvalidResponse("no longitude".into()))?;
-    let tz = r.timezone.unwrap_or_else(|| tz_from_coords(lat, lon));
+    let (tz, tz_source) = match ip_api_timezone(r.timezone.as_deref()) {
+        Some(tz) => (tz, TzSource::IpApi),
+        None => tz_from_coords_with_source(lat, lon),
+    };
     let city = r.city.unwrap_or_else(|| "Unknown".into());
     let country = r.country_name.unwrap_or_default();
     let cc = r.country_code;
@@ -669,6 +908,8 @@ pub fn ip_geolocate() -> Result<ResolvedLocation, LocationError> {
         resolver_confidence: 0.8,
         disambiguated: false,
         disambiguation_note: None,
+        tz_source,
+        elevation_m: None,
     })
 }
 
@@ -677,12 +918,21 @@ pub fn ip_geolocate() -> Result<ResolvedLocation, LocationError> {
 /// Approximate IANA timezone from longitude (rough but works offline).
 /// This is a fallback — Nominatim results get a better estimate.
 pub fn tz_from_coords(lat: f64, lon: f64) -> String {
+    tz_from_coords_with_source(lat, lon).0
+}
+
+/// Like [`tz_from_coords`], also reporting whether the zone was looked up
+/// or estimated from longitude.
+pub fn tz_from_coords_with_source(lat: f64, lon: f64) -> (String, TzSource) {
     // Try the timezone API first (fast, free, no key)
     if let Ok(tz) = tz_from_api(lat, lon) {
-        return tz;
+        return (tz, TzSource::TimeApi);
     }
+    (tz_from_longitude(lon), TzSource::Estimated)
+}
 
-    // Fallback: rough longitude-based estimation
+/// Rough longitude-based zone estimate, used when the lookup fails.
+pub(crate) fn tz_from_longitude(lon: f64) -> String {
     let offset_hours = (lon / 15.0).round() as i32;
     // Map to common IANA zones by rough offset
     match offset_hours {
@@ -718,11 +968,11 @@ fn tz_from_api(lat: f64, lon: f64) -> Result<String, LocationError> {
         lat, lon
     );
 
-    let response = ureq::get(&url)
+    let response = http_get(&url)
         .set("User-Agent", "PolarisChronos/0.6")
         .timeout(std::time::Duration::from_secs(3))
         .call()
-        .map_err(|e| LocationError::Network(e.to_string()))?;
+        .map_err(request_error)?;
 
     let val: serde_json::Value = response
         .into_json()
@@ -734,6 +984,34 @@ fn tz_from_api(lat: f64, lon: f64) -> Result<String, LocationError> {
         .ok_or_else(|| LocationError::InvalidResponse("no timeZone field".into()))
 }
 
+// ─── Elevation ──────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct OpenElevationResponse {
+    results: Vec<OpenElevationPoint>,
+}
+
+#[derive(Deserialize)]
+struct OpenElevationPoint {
+    elevation: f64,
+}
+
+/// Height above sea level in metres, from the Open-Elevation API.
+pub fn elevation(lat: f64, lon: f64) -> Result<f64, LocationError> {
+    let url = format!("https://api.open-elevation.com/api/v1/lookup?locations={:.5},{:.5}", lat, lon);
+    let response = http_get(&url)
+        .set("User-Agent", "PolarisChronos/0.6")
+        .timeout(std::time::Duration::from_secs(5))
+        .call()
+        .map_err(request_error)?;
+    let body: OpenElevationResponse = response
+        .into_json()
+        .map_err(|e| LocationError::InvalidResponse(e.to_string()))?;
+    body.results.first()
+        .map(|p| p.elevation)
+        .ok_or_else(|| LocationError::InvalidResponse("no elevation in response".into()))
+}
+
 // ─── URL encoding (minimal, no extra dep) ───────────────────────
 
 fn urlencod(s: &str) -> String {
@@ -790,6 +1068,11 @@ pub fn country_display_name(code: &str) -> &str {
         "TM" => "Turkmenistan", "UA" => "Ukraine", "AE" => "United Arab Emirates",
         "GB" => "United Kingdom", "US" => "United States",
         "UZ" => "Uzbekistan", "VN" => "Vietnam", "YE" => "Yemen",
+        "BF" => "Burkina Faso", "TD" => "Chad", "DJ" => "Djibouti",
+        "GM" => "Gambia", "GN" => "Guinea", "XK" => "Kosovo",
+        "MV" => "Maldives", "ML" => "Mali", "MR" => "Mauritania",
+        "NE" => "Niger", "SN" => "Senegal", "SO" => "Somalia",
+        "SD" => "Sudan",
         _ => code,
     }
 }
@@ -828,6 +1111,11 @@ pub fn country_display_name_ar(code: &str) -> &str {
         "TM" => "تركمانستان", "UA" => "أوكرانيا", "AE" => "الإمارات",
         "GB" => "المملكة المتحدة", "US" => "الولايات المتحدة",
         "UZ" => "أوزبكستان", "VN" => "فيتنام", "YE" => "اليمن",
+        "BF" => "بوركينا فاسو", "TD" => "تشاد", "DJ" => "جيبوتي",
+        "GM" => "غامبيا", "GN" => "غينيا", "XK" => "كوسوفو",
+        "MV" => "جزر المالديف", "ML" => "مالي", "MR" => "موريتانيا",
+        "NE" => "النيجر", "SN" => "السنغال", "SO" => "الصومال",
+        "SD" => "السودان",
         _ => code,
     }
 }
@@ -843,6 +1131,66 @@ pub fn format_coords(lat: f64, lon: f64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_postal_code_detection() {
+        for code in ["10001", "10001-1234", "SW1A 1AA", "114 55", "M5V 3L9", "1012 AB", " 75001 "] {
+            assert!(looks_like_postal_code(code), "{}", code);
+        }
+        for name in ["Stockholm", "New York", "12", "59.33,18.07", "Route 66", "Kuala Lumpur 50", "1 2 3", "10001-"] {
+            assert!(!looks_like_postal_code(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_postal_code_url_us_zip() {
+        assert_eq!(
+            postal_code_url("10001", Some("US")),
+            "https://nominatim.openstreetmap.org/search?postalcode=10001&format=json&limit=5&addressdetails=0&countrycodes=us",
+        );
+        assert_eq!(
+            postal_code_url("sw1a 1aa", None),
+            "https://nominatim.openstreetmap.org/search?postalcode=SW1A%201AA&format=json&limit=5&addressdetails=0",
+        );
+    }
+
+    #[test]
+    fn test_postal_code_location() {
+        let result = |lat: &str, lon: &str, display_name: &str| NominatimResult {
+            lat: lat.into(),
+            lon: lon.into(),
+            display_name: display_name.into(),
+            importance: None,
+            place_type: Some("postcode".into()),
+            place_class: Some("place".into()),
+            addresstype: None,
+        };
+        let results = vec![
+            result("40.7484", "-73.9967", "10001, Manhattan, New York County, New York, United States"),
+            result("37.9838", "23.7275", "10001, Athens, Greece"),
+        ];
+
+        let loc = postal_code_location("10001", Some("US"), &results[..1]).unwrap();
+        assert_eq!(loc.name, "Manhattan (10001)");
+        assert_eq!(loc.tz, "America/New_York");
+        assert_eq!(loc.country_code.as_deref(), Some("US"));
+        assert!(!loc.disambiguated);
+
+        // Without a country, the same code elsewhere is pointed out
+        let loc = postal_code_location("10001", None, &results).unwrap();
+        assert_eq!(loc.name, "Manhattan (10001)");
+        assert!(loc.disambiguation_note.unwrap().contains("GR"));
+
+        assert!(matches!(postal_code_location("99999", None, &[]), Err(LocationError::NotFound(_))));
+        assert_eq!(postal_place_name("SW1A 1AA", "SW1A1AA, Westminster, London, United Kingdom"), "Westminster (SW1A 1AA)");
+    }
+
+    #[test]
+    fn test_ip_api_timezone_validated() {
+        assert_eq!(ip_api_timezone(Some("Europe/Kiev")).as_deref(), Some("Europe/Kyiv"));
+        assert_eq!(ip_api_timezone(Some("Mars/Olympus")), None);
+        assert_eq!(ip_api_timezone(None), None);
+    }
+
     #[test]
     fn test_builtin_exact() {
         let loc = builtin_lookup("Mecca").unwrap();
@@ -872,6 +1220,26 @@ mod tests {
         assert_eq!(loc.name, "stockholm");
     }
 
+    #[test]
+    fn test_fuzzy_prefers_closest_alias_in_any_order() {
+        // "medna" is one edit from "medina" but two from "medan"
+        let loc = builtin_lookup("medna").unwrap();
+        assert_eq!(loc.name, "medina");
+
+        let city = |names: Vec<&'static str>| BuiltinCity { names, lat: 0.0, lon: 0.0, tz: "UTC", country_code: "ZZ" };
+        let (medan, medina) = (city(vec!["medan"]), city(vec!["medina", "madinah"]));
+        for candidates in [vec![&medan, &medina], vec![&medina, &medan]] {
+            assert_eq!(fuzzy_match("medna", &candidates).unwrap().names[0], "medina");
+        }
+
+        // Equal distance: the alias nearer the query's length wins, whatever the order
+        let (kan, kano) = (city(vec!["kan"]), city(vec!["kano"]));
+        for candidates in [vec![&kan, &kano], vec![&kano, &kan]] {
+            assert_eq!(fuzzy_match("kanx", &candidates).unwrap().names[0], "kano");
+        }
+        assert!(fuzzy_match("xyzzy", &[&kan, &kano]).is_none());
+    }
+
     #[test]
     fn test_builtin_multi_word() {
         let loc = builtin_lookup("new york").unwrap();
@@ -890,6 +1258,42 @@ mod tests {
         assert!(builtin_lookup("xyznonexistent").is_none());
     }
 
+    #[test]
+    fn test_builtin_dataset_is_valid() {
+        let mut seen = std::collections::HashSet::new();
+        for city in BUILTIN_CITIES.iter() {
+            assert!(city.tz.parse::<chrono_tz::Tz>().is_ok(), "{}: unknown zone {}", city.names[0], city.tz);
+            assert_ne!(country_display_name(city.country_code), city.country_code, "{}: unnamed country", city.names[0]);
+            assert!((-90.0..=90.0).contains(&city.lat) && (-180.0..=180.0).contains(&city.lon), "{}", city.names[0]);
+            for name in &city.names {
+                assert_eq!(*name, name.to_lowercase(), "names are matched lowercase");
+                assert!(seen.insert(*name), "duplicate name {}", name);
+            }
+        }
+        println!("{} built-in cities", BUILTIN_CITIES.len());
+        assert!(BUILTIN_CITIES.len() >= 100);
+    }
+
+    #[test]
+    fn test_builtin_capitals_and_aliases() {
+        for (query, name, tz, country) in [
+            ("Khartoum", "khartoum", "Africa/Khartoum", "SD"),
+            ("tunis", "tunis", "Africa/Tunis", "TN"),
+            ("Amman", "amman", "Asia/Amman", "JO"),
+            ("sana'a", "sanaa", "Asia/Aden", "YE"),
+            ("Muqdisho", "mogadishu", "Africa/Mogadishu", "SO"),
+            ("marrakech", "marrakesh", "Africa/Casablanca", "MA"),
+            ("Chattogram", "chittagong", "Asia/Dhaka", "BD"),
+            ("khartum", "khartoum", "Africa/Khartoum", "SD"), // fuzzy, edit distance 1
+        ] {
+            let loc = builtin_lookup(query).unwrap_or_else(|| panic!("{} not found", query));
+            assert_eq!((loc.name.as_str(), loc.tz.as_str()), (name, tz), "{}", query);
+            assert_eq!(loc.country_code.as_deref(), Some(country));
+        }
+        assert_eq!(builtin_lookup_with_country("tripoli", Some("LY")).unwrap().tz, "Africa/Tripoli");
+        assert_eq!(country_name_to_code("Sudan").as_deref(), Some("SD"));
+    }
+
     #[test]
     fn test_builtin_with_country_filter() {
         let loc = builtin_lookup_with_country("medina", Some("SA")).unwrap();
@@ -914,8 +1318,9 @@ mod tests {
 
     #[test]
     fn test_type_rank() {
-        assert!(type_rank("city", "place") > type_rank("village", "place"));
-        assert!(type_rank("town", "place") > type_rank("hamlet", "place"));
+        let city = Granularity::City;
+        assert!(type_rank("city", "place", city) > type_rank("village", "place", city));
+        assert!(type_rank("town", "place", city) > type_rank("hamlet", "place", city));
     }
 
     #[test]
@@ -1020,4 +1425,102 @@ mod tests {
         assert_eq!(country_display_name("ps"), "Palestine");
         assert_eq!(country_display_name("sa"), "Saudi Arabia");
     }
+
+    #[test]
+    fn test_missing_importance_uses_type_prior() {
+        let city = NominatimResult {
+            lat: "39.80".into(),
+            lon: "-89.64".into(),
+            display_name: "Springfield, Sangamon County, Illinois, United States".into(),
+            importance: None,
+            place_type: Some("city".into()),
+            place_class: Some("place".into()),
+            addresstype: None,
+        };
+        let village = NominatimResult {
+            lat: "42.57".into(),
+            lon: "-72.99".into(),
+            display_name: "Springfield, Windsor County, Vermont, United States".into(),
+            importance: Some(0.55),
+            place_type: Some("village".into()),
+            place_class: Some("place".into()),
+            addresstype: None,
+        };
+
+        let a = score_candidate("Springfield", &city, None, Granularity::City);
+        let b = score_candidate("Springfield", &village, None, Granularity::City);
+        println!("city={:.3} (importance {:.2}) village={:.3}", a.score, a.importance, b.score);
+
+        assert!(a.importance > 0.3, "Unannotated city should get a type-derived prior");
+        assert!(a.score - b.score > DISAMBIGUATION_THRESHOLD,
+            "City without importance must win clearly over a low-importance village");
+    }
+
+    #[test]
+    fn test_poi_granularity_accepts_suburb() {
+        let results = vec![
+            NominatimResult {
+                lat: "60.48".into(),
+                lon: "15.42".into(),
+                display_name: "Södermalm, Borlänge, Dalarna County, Sweden".into(),
+                importance: Some(0.30),
+                place_type: Some("town".into()),
+                place_class: Some("place".into()),
+                addresstype: None,
+            },
+            NominatimResult {
+                lat: "59.31".into(),
+                lon: "18.07".into(),
+                display_name: "Södermalm, Stockholm, Stockholm County, Sweden".into(),
+                importance: Some(0.30),
+                place_type: Some("suburb".into()),
+                place_class: Some("place".into()),
+                addresstype: None,
+            },
+        ];
+
+        let city = rank_candidates("Södermalm", &results, None, Granularity::City);
+        let poi = rank_candidates("Södermalm", &results, None, Granularity::Poi);
+        println!("city: {} {:.3}, poi: {} {:.3}", city[0].place_type, city[0].score, poi[0].place_type, poi[0].score);
+
+        assert_eq!(city[0].place_type, "town", "City granularity should down-rank the suburb");
+        assert_eq!(poi[0].place_type, "suburb");
+        assert!(poi[0].lon > 18.0);
+    }
+
+    #[test]
+    fn test_arabic_query_resolves_mecca() {
+        assert_eq!(normalize_city_query(" مَكَّة "), "mecca");
+        assert_eq!(normalize_city_query("القاهرة"), "cairo");
+        assert_eq!(normalize_city_query("Oslo"), "oslo");
+
+        let results = vec![
+            NominatimResult {
+                lat: "41.20".into(),
+                lon: "-80.78".into(),
+                display_name: "Mecca, Trumbull County, Ohio, United States".into(),
+                importance: Some(0.58),
+                place_type: Some("city".into()),
+                place_class: Some("place".into()),
+                addresstype: None,
+            },
+            NominatimResult {
+                lat: "21.42".into(),
+                lon: "39.83".into(),
+                display_name: "مكة, منطقة مكة المكرمة, السعودية".into(),
+                importance: Some(0.60),
+                place_type: None,
+                place_class: None,
+                addresstype: Some("city".into()),
+            },
+        ];
+
+        let ranked = rank_candidates("مكة", &results, None, Granularity::City);
+        for c in &ranked {
+            println!("{} {} {:.3}", c.display_name, c.country_code, c.score);
+        }
+        assert_eq!(ranked[0].country_code, "SA");
+        assert!(ranked[0].score - ranked[1].score >= DISAMBIGUATION_THRESHOLD,
+            "Arabic Mecca must win without an ambiguity prompt");
+    }
 }
@@ -1,34 +1,84 @@
 //! Location resolver — orchestrates the fallback chain.
 //!
 //! City flow:  Cache → Nominatim (with disambiguation) → simplified query → built-in dataset → error
+//! Postal codes: Cache → Nominatim structured search → error
 //! Auto flow:  IP API → last cached location → error
 
+use std::sync::Arc;
+
 use super::cache::LocationCache;
-use super::providers;
-use super::types::{LocationError, LocationSource, ResolvedLocation, ResolveOptions};
+use super::providers::{self, GeocodeProvider, Nominatim};
+use super::types::{normalize_longitude, Granularity, LocationError, LocationSource, ResolvedLocation, ResolveOptions, TzSource};
+
+/// Online city lookup used by the resolver: `(query, country_hint, granularity) -> location`.
+pub type CityLookup = fn(&str, Option<&str>, Granularity) -> Result<ResolvedLocation, LocationError>;
 
 /// The location resolver with its fallback pipeline.
 pub struct LocationResolver {
     cache: LocationCache,
+    geocoder: CityGeocoder,
+}
+
+/// The steps of the city fallback chain after the cache: geocoder, then
+/// built-in dataset. Cheap to clone, so a shared resolver's lock need not
+/// be held across network calls.
+#[derive(Clone)]
+pub struct CityGeocoder {
     offline: bool,
+    provider: Arc<dyn GeocodeProvider>,
+}
+
+/// A city found by [`CityGeocoder::lookup`], to be stored with
+/// [`LocationResolver::remember`].
+pub struct CityResolution {
+    pub location: ResolvedLocation,
+    /// Cache key to store `location` under; `None` for built-in fallbacks.
+    cache_key: Option<String>,
+}
+
+impl Default for LocationResolver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LocationResolver {
     pub fn new() -> Self {
-        Self {
-            cache: LocationCache::load(),
-            offline: false,
-        }
+        Self::with_cache(LocationCache::load())
     }
 
     /// Create a resolver with a specific cache (for testing).
     pub fn with_cache(cache: LocationCache) -> Self {
-        Self { cache, offline: false }
+        Self { cache, geocoder: CityGeocoder { offline: false, provider: Arc::new(Nominatim) } }
+    }
+
+    /// Replace the online geocoder (Nominatim by default; for testing).
+    pub fn with_provider(mut self, provider: impl GeocodeProvider + 'static) -> Self {
+        self.geocoder.provider = Arc::new(provider);
+        self
+    }
+
+    /// Replace only the online city lookup; candidates still come from Nominatim.
+    pub fn with_lookup(self, lookup: CityLookup) -> Self {
+        self.with_provider(lookup)
     }
 
     /// Set offline mode — skip network calls.
+    ///
+    /// Every provider request is behind this flag, so an offline resolver
+    /// never changes [`providers::network_call_count`].
     pub fn set_offline(&mut self, offline: bool) {
-        self.offline = offline;
+        self.geocoder.offline = offline;
+    }
+
+    /// Whether network calls are skipped (see [`LocationResolver::set_offline`]).
+    pub fn is_offline(&self) -> bool {
+        self.geocoder.offline
+    }
+
+    /// Outbound HTTP requests made by the process so far (all resolvers).
+    pub fn network_calls(&self) -> u64 {
+        providers::network_call_count()
     }
 
     /// Resolve a city name through the full fallback chain (no options).
@@ -36,28 +86,139 @@ impl LocationResolver {
         self.resolve_city_with_opts(query, &ResolveOptions::default())
     }
 
-    /// Resolve a city name with options (country hint, topk debug).
+    /// Re-resolve a city, ignoring and then overwriting its cache entry.
+    ///
+    /// Use when a city was cached with the wrong disambiguation.
+    pub fn refresh(&mut self, query: &str) -> Result<ResolvedLocation, LocationError> {
+        self.resolve_city_with_opts(query, &ResolveOptions { refresh: true, ..Default::default() })
+    }
+
+    /// The cached location for a query, as step 1 of
+    /// [`resolve_city_with_opts`](Self::resolve_city_with_opts) would return
+    /// it; `None` when that step is skipped (country hint, refresh) or misses.
+    ///
+    /// Takes `&self`, so shared callers can try the cache under a read lock.
+    pub fn cached(&self, query: &str, opts: &ResolveOptions) -> Option<ResolvedLocation> {
+        let (city_query, parsed_country) = parse_query_with_hint(query);
+        if opts.refresh || opts.country.is_some() || parsed_country.is_some() {
+            return None;
+        }
+        self.cache.get(&cache_keys(&city_query, query, opts.granularity).0)
+    }
+
+    /// Resolve a city name with options (country hint, topk debug, refresh, granularity).
     pub fn resolve_city_with_opts(
         &mut self,
         query: &str,
         opts: &ResolveOptions,
     ) -> Result<ResolvedLocation, LocationError> {
+        // 1. Check cache (skip if country filter is active and cache might be stale)
+        if let Some(loc) = self.cached(query, opts) {
+            return Ok(loc);
+        }
+        let resolution = self.geocoder.lookup(query, opts)?;
+        Ok(self.remember(resolution))
+    }
+
+    /// The uncached steps of [`resolve_city_with_opts`](Self::resolve_city_with_opts),
+    /// to run without holding the resolver.
+    pub fn geocoder(&self) -> CityGeocoder {
+        self.geocoder.clone()
+    }
+
+    /// Cache a lookup's result the way `resolve_city_with_opts` would.
+    pub fn remember(&mut self, resolution: CityResolution) -> ResolvedLocation {
+        if let Some(key) = &resolution.cache_key {
+            self.cache.put_with_key(key, &resolution.location);
+        }
+        resolution.location
+    }
+
+    /// Look up the elevation of `loc` if it is not yet known, caching it
+    /// with the location. Does nothing offline.
+    pub fn fill_elevation(&mut self, loc: &mut ResolvedLocation) -> Result<(), LocationError> {
+        if loc.elevation_m.is_some() || self.geocoder.offline {
+            return Ok(());
+        }
+        let elevation_m = self.geocoder.provider.elevation(loc.lat, loc.lon)?;
+        loc.elevation_m = Some(elevation_m);
+        self.cache.set_elevation(loc.lat, loc.lon, elevation_m);
+        Ok(())
+    }
+
+    /// Auto-detect location via IP.
+    pub fn resolve_auto(&mut self) -> Result<ResolvedLocation, LocationError> {
+        // 1. Try IP API
+        if !self.geocoder.offline {
+            if let Ok(loc) = providers::ip_geolocate() {
+                self.cache.put(&loc);
+                return Ok(loc);
+            }
+        }
+
+        // 2. Fallback to most recent cached location
+        if let Some(loc) = self.cache.most_recent() {
+            return Ok(loc);
+        }
+
+        Err(LocationError::Network(
+            "Could not auto-detect location. Try --city instead.".into()
+        ))
+    }
+
+    /// Create a ResolvedLocation from manual lat/lon input; the longitude
+    /// wraps into (-180, 180].
+    pub fn from_manual(lat: f64, lon: f64, tz_override: Option<&str>) -> ResolvedLocation {
+        let lon = normalize_longitude(lon);
+        let (tz, tz_source) = match tz_override {
+            Some(tz) => (tz.to_string(), TzSource::Override),
+            None => ("UTC".to_string(), TzSource::Default),
+        };
+
+        ResolvedLocation {
+            name: format!("{:.4}, {:.4}", lat, lon),
+            lat,
+            lon,
+            tz,
+            source: LocationSource::Manual,
+            display_name: None,
+            country_code: None,
+            resolver_confidence: 1.0,
+            disambiguated: false,
+            disambiguation_note: None,
+            tz_source,
+            elevation_m: None,
+        }
+    }
+}
+
+impl CityGeocoder {
+    /// Steps 2-4 of [`LocationResolver::resolve_city_with_opts`], without
+    /// reading or writing the cache.
+    pub fn lookup(&self, query: &str, opts: &ResolveOptions) -> Result<CityResolution, LocationError> {
         // Parse comma-separated queries: "Medina, Saudi Arabia" → city="Medina", country_hint="SA"
         let (city_query, parsed_country) = parse_query_with_hint(query);
         let country_hint = opts.country.as_deref().or(parsed_country.as_deref());
+        let granularity = opts.granularity;
 
-        // 1. Check cache (skip if country filter is active and cache might be stale)
-        if country_hint.is_none() {
-            if let Some(loc) = self.cache.get(&city_query) {
-                return Ok(loc);
+        let (_, query_key) = cache_keys(&city_query, query, granularity);
+        let cacheable = |location| Ok(CityResolution { location, cache_key: Some(query_key.clone()) });
+        let transient = |location| Ok(CityResolution { location, cache_key: None });
+
+        // Postal codes go to the geocoder's structured search; the built-in
+        // dataset has none, so offline they are simply not found
+        if providers::looks_like_postal_code(&city_query) {
+            if self.offline {
+                return Err(LocationError::NotFound(query.to_string()));
             }
+            return cacheable(self.provider.postal_code(&city_query, country_hint)?);
         }
 
         // 2. Try Nominatim with disambiguation (if online)
         if !self.offline {
             // If --topk is set, show candidates and proceed
             if let Some(topk) = opts.topk {
-                match providers::nominatim_resolve_candidates(&city_query, country_hint, topk) {
+                match self.provider.candidates(&city_query, country_hint, topk, granularity) {
                     Ok(candidates) => {
                         eprintln!("  Top-{} candidates for '{}':", topk, query);
                         for (i, c) in candidates.iter().enumerate().take(topk) {
@@ -72,11 +233,8 @@ impl LocationResolver {
                 }
             }
 
-            match providers::nominatim_resolve_with_options(&city_query, country_hint) {
-                Ok(loc) => {
-                    self.cache.put_with_key(query, &loc);
-                    return Ok(loc);
-                }
+            match self.provider.resolve(&city_query, country_hint, granularity) {
+                Ok(loc) => return cacheable(loc),
                 Err(LocationError::Ambiguous { .. }) => {
                     // Before propagating ambiguity, check if built-in has a confident match.
                     // This handles cases like "Medina" where Nominatim doesn't return the
@@ -88,91 +246,60 @@ impl LocationResolver {
                             builtin.name,
                             builtin.country_code.as_deref().unwrap_or("??"),
                         ));
-                        self.cache.put_with_key(query, &builtin);
-                        return Ok(builtin);
+                        return cacheable(builtin);
                     }
                     // No built-in match — propagate ambiguity
                     return Err(LocationError::Ambiguous {
                         query: query.to_string(),
-                        candidates: match providers::nominatim_resolve_candidates(&city_query, None, 5) {
+                        candidates: match self.provider.candidates(&city_query, None, 5, Granularity::City) {
                             Ok(c) => c.iter().take(5).map(|c| super::types::AmbiguousCandidate {
                                 name: c.display_name.clone(),
                                 country: c.country_code.clone(),
                                 country_name: providers::country_display_name(&c.country_code).to_string(),
                                 lat: c.lat,
                                 lon: c.lon,
-                                tz: providers::tz_from_coords(c.lat, c.lon),
+                                tz: self.provider.timezone(c.lat, c.lon)
+                                    .unwrap_or_else(|_| providers::tz_from_longitude(c.lon)),
                                 score: c.score,
                             }).collect(),
                             Err(_) => vec![],
                         },
                     });
                 }
+                Err(e @ LocationError::RateLimited { .. }) => {
+                    // Retrying would only prolong the limit: use built-in data or report it
+                    let builtin = providers::builtin_lookup_with_country(&city_query, country_hint).ok_or(e)?;
+                    eprintln!("  Warning: geocoder rate-limited; using built-in data for {}", builtin.name);
+                    return transient(builtin);
+                }
                 Err(_) => {} // fall through to next attempt
             }
 
             // 3. Try simplified query (remove special chars, lowercase)
             let simplified = simplify_query(&city_query);
             if simplified != city_query.to_lowercase() {
-                match providers::nominatim_resolve_with_options(&simplified, country_hint) {
-                    Ok(loc) => {
-                        self.cache.put_with_key(query, &loc);
-                        return Ok(loc);
-                    }
-                    Err(_) => {}
+                if let Ok(loc) = self.provider.resolve(&simplified, country_hint, granularity) {
+                    return cacheable(loc);
                 }
             }
         }
 
         // 4. Try built-in dataset (always available)
         if let Some(loc) = providers::builtin_lookup_with_country(&city_query, country_hint) {
-            return Ok(loc);
+            return transient(loc);
         }
 
         Err(LocationError::NotFound(query.to_string()))
     }
+}
 
-    /// Auto-detect location via IP.
-    pub fn resolve_auto(&mut self) -> Result<ResolvedLocation, LocationError> {
-        // 1. Try IP API
-        if !self.offline {
-            match providers::ip_geolocate() {
-                Ok(loc) => {
-                    self.cache.put(&loc);
-                    return Ok(loc);
-                }
-                Err(_) => {}
-            }
-        }
-
-        // 2. Fallback to most recent cached location
-        if let Some(loc) = self.cache.most_recent() {
-            return Ok(loc);
-        }
-
-        Err(LocationError::Network(
-            "Could not auto-detect location. Try --city instead.".into()
-        ))
-    }
-
-    /// Create a ResolvedLocation from manual lat/lon input.
-    pub fn from_manual(lat: f64, lon: f64, tz_override: Option<&str>) -> ResolvedLocation {
-        let tz = tz_override
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "UTC".into());
-
-        ResolvedLocation {
-            name: format!("{:.4}, {:.4}", lat, lon),
-            lat,
-            lon,
-            tz,
-            source: LocationSource::Manual,
-            display_name: None,
-            country_code: None,
-            resolver_confidence: 1.0,
-            disambiguated: false,
-            disambiguation_note: None,
-        }
+/// Cache keys `(lookup, store)` for a city query: lookups use the parsed
+/// city, results are stored under the full query too. Finer lookups are
+/// cached apart from the city-level entry.
+fn cache_keys(city_query: &str, query: &str, granularity: Granularity) -> (String, String) {
+    match granularity {
+        Granularity::City => (city_query.to_string(), query.to_string()),
+        g => (format!("{}@{}", city_query, g), format!("{}@{}", query, g)),
     }
 }
 
@@ -262,18 +389,12 @@ fn country_name_to_hint(name: &str) -> Option<String> {
 /// Simplify a query for retry: lowercase, strip accents/diacritics, collapse spaces.
 fn simplify_query(q: &str) -> String {
     q.to_lowercase()
-        .replace('ø', "o")
-        .replace('å', "a")
-        .replace('ä', "a")
-        .replace('ö', "o")
+        .replace(['ø', 'ö', 'õ'], "o")
+        .replace(['å', 'ä', 'ã'], "a")
         .replace('ü', "u")
         .replace('ß', "ss")
-        .replace('é', "e")
-        .replace('è', "e")
-        .replace('ê', "e")
+        .replace(['é', 'è', 'ê'], "e")
         .replace('ñ', "n")
-        .replace('ã', "a")
-        .replace('õ', "o")
         .replace('ç', "c")
         .split_whitespace()
         .collect::<Vec<_>>()
@@ -303,6 +424,274 @@ mod tests {
         assert!((loc.lat - 21.4225).abs() < 0.01);
     }
 
+    #[test]
+    fn test_tz_source_recorded() {
+        let manual = LocationResolver::from_manual(59.91, 10.75, Some("Europe/Oslo"));
+        assert_eq!(manual.tz_source, TzSource::Override);
+        assert_eq!(LocationResolver::from_manual(59.91, 10.75, None).tz_source, TzSource::Default);
+
+        let (mut resolver, _dir) = offline_resolver();
+        let mecca = resolver.resolve_city("Mecca").unwrap();
+        assert_eq!(mecca.tz_source, TzSource::Builtin);
+        assert_eq!(mecca.with_tz_override("UTC").tz_source, TzSource::Override);
+    }
+
+    #[test]
+    fn test_offline_resolve_makes_no_network_calls() {
+        let (mut resolver, _dir) = offline_resolver();
+        let before = resolver.network_calls();
+        for city in ["Mecca", "Stockholm", "Tromsø", "Nowhereville"] {
+            let _ = resolver.resolve_city(city);
+        }
+        let _ = resolver.resolve_auto();
+        assert_eq!(resolver.network_calls(), before);
+    }
+
+    /// A geocoder that finds nothing but knows every place is 1500 m up.
+    struct HighlandGeocoder;
+
+    impl GeocodeProvider for HighlandGeocoder {
+        fn resolve(&self, query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+            Err(LocationError::NotFound(query.to_string()))
+        }
+
+        fn elevation(&self, _lat: f64, _lon: f64) -> Result<f64, LocationError> {
+            Ok(1500.0)
+        }
+    }
+
+    #[test]
+    fn test_auto_elevation_is_cached_and_lowers_horizon() {
+        use crate::config::Config;
+        use crate::solver::Solver;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cache.json");
+        let mut cache = LocationCache::load_from(path.clone());
+        cache.put(&ResolvedLocation {
+            name: "Stockholm".into(),
+            ..LocationResolver::from_manual(59.3293, 18.0686, Some("Europe/Stockholm"))
+        });
+        let mut resolver = LocationResolver::with_cache(cache).with_provider(HighlandGeocoder);
+
+        let mut loc = resolver.resolve_city("Stockholm").unwrap();
+        assert_eq!(loc.elevation_m, None);
+        resolver.fill_elevation(&mut loc).unwrap();
+        assert_eq!(loc.elevation_m, Some(1500.0));
+        assert_eq!(LocationCache::load_from(path).get("stockholm").unwrap().elevation_m, Some(1500.0));
+
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let solve = |elevation_m| Solver::from_resolved(&loc)
+            .with_config(Config::default().with_elevation(elevation_m))
+            .solve(date, false, false);
+        let (sea, high) = (solve(0.0), solve(loc.elevation_m.unwrap()));
+        println!("sunrise {:?} -> {:?}", sea.events.sunrise.time, high.events.sunrise.time);
+        assert!(high.events.sunrise.time < sea.events.sunrise.time, "the dip brings sunrise earlier");
+        assert!(high.events.maghrib.time > sea.events.maghrib.time, "and sunset later");
+        assert_eq!(high.computation.elevation_m, 1500.0);
+
+        // Offline, nothing is looked up
+        let mut offline = LocationResolver::from_manual(21.42, 39.83, None);
+        resolver.set_offline(true);
+        resolver.fill_elevation(&mut offline).unwrap();
+        assert_eq!(offline.elevation_m, None);
+    }
+
+    #[test]
+    fn test_provider_defaults_stay_offline() {
+        // Only what a provider implements may reach the network
+        let provider = HighlandGeocoder;
+        assert!(provider.candidates("Stockholm", None, 5, Granularity::City).is_err());
+        assert!(provider.timezone(59.33, 18.07).is_err());
+        assert_eq!(provider.elevation(59.33, 18.07).unwrap(), 1500.0);
+        assert!(matches!(provider.postal_code("10001", Some("US")), Err(LocationError::NotFound(code)) if code == "10001"));
+    }
+
+    /// A geocoder that only knows one postal code.
+    struct PostalGeocoder;
+
+    impl GeocodeProvider for PostalGeocoder {
+        fn resolve(&self, query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+            panic!("postal code {} sent to free-text search", query);
+        }
+
+        fn postal_code(&self, code: &str, country_hint: Option<&str>) -> Result<ResolvedLocation, LocationError> {
+            assert_eq!(country_hint, Some("US"));
+            match code {
+                "10001" => Ok(ResolvedLocation {
+                    name: "Manhattan (10001)".into(),
+                    source: LocationSource::Nominatim,
+                    ..LocationResolver::from_manual(40.7484, -73.9967, Some("America/New_York"))
+                }),
+                _ => Err(LocationError::NotFound(code.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_postal_code_uses_structured_search() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cache.json");
+        let mut resolver = LocationResolver::with_cache(LocationCache::load_from(path.clone())).with_provider(PostalGeocoder);
+        let opts = ResolveOptions { country: Some("US".into()), ..Default::default() };
+
+        let loc = resolver.resolve_city_with_opts("10001", &opts).unwrap();
+        assert_eq!(loc.name, "Manhattan (10001)");
+        assert_eq!(loc.tz, "America/New_York");
+        assert!(LocationCache::load_from(path).get("10001").is_some(), "cached under the query");
+        assert!(matches!(resolver.resolve_city_with_opts("99999", &opts), Err(LocationError::NotFound(_))));
+
+        // Offline there is no postal data: not found, never a fuzzy built-in match
+        let (mut offline, _dir) = offline_resolver();
+        assert!(matches!(offline.resolve_city("SW1A 1AA"), Err(LocationError::NotFound(_))));
+    }
+
+    /// A geocoder that is always rate-limited, counting its calls.
+    struct RateLimitedGeocoder(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl GeocodeProvider for RateLimitedGeocoder {
+        fn resolve(&self, _query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(LocationError::RateLimited { retry_after: providers::parse_retry_after("30") })
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_skips_retries() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let dir = TempDir::new().unwrap();
+        let cache = LocationCache::load_from(dir.path().join("cache.json"));
+        let mut resolver = LocationResolver::with_cache(cache).with_provider(RateLimitedGeocoder(calls.clone()));
+
+        // "Tromsø" would normally be retried as "tromso"; here it goes straight to built-in
+        let tromso = resolver.resolve_city("Tromsø").unwrap();
+        assert_eq!(tromso.source, LocationSource::Fallback);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let err = resolver.resolve_city("Nowhereville").unwrap_err();
+        println!("{}", err);
+        assert!(matches!(err, LocationError::RateLimited { retry_after: Some(d) } if d.as_secs() == 30));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    /// A geocoder that finds every query ambiguous, answering from fixed data.
+    struct AmbiguousGeocoder;
+
+    impl GeocodeProvider for AmbiguousGeocoder {
+        fn resolve(&self, query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+            Err(LocationError::Ambiguous { query: query.to_string(), candidates: vec![] })
+        }
+
+        fn candidates(&self, query: &str, _hint: Option<&str>, _limit: usize, _granularity: Granularity) -> Result<Vec<providers::NominatimCandidate>, LocationError> {
+            let candidate = |country: &str, lat, lon| providers::NominatimCandidate {
+                name: query.to_string(),
+                display_name: format!("{}, {}", query, country),
+                lat,
+                lon,
+                importance: 0.5,
+                place_type: "city".into(),
+                place_class: "place".into(),
+                country_code: country.into(),
+                score: 0.5,
+            };
+            Ok(vec![candidate("US", 39.80, -89.65), candidate("US", 37.21, -93.29)])
+        }
+
+        fn timezone(&self, _lat: f64, _lon: f64) -> Result<String, LocationError> {
+            Ok("America/Chicago".into())
+        }
+    }
+
+    fn ambiguous_resolver() -> (LocationResolver, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let cache = LocationCache::load_from(dir.path().join("cache.json"));
+        (LocationResolver::with_cache(cache).with_provider(AmbiguousGeocoder), dir)
+    }
+
+    #[test]
+    fn test_ambiguous_medina_falls_back_to_builtin() {
+        let (mut resolver, _dir) = ambiguous_resolver();
+        let before = resolver.network_calls();
+
+        let medina = resolver.resolve_city("Medina").unwrap();
+        println!("{:?}", medina.disambiguation_note);
+        assert_eq!(medina.country_code.as_deref(), Some("SA"));
+        assert_eq!(medina.source, LocationSource::Fallback);
+        assert!(medina.disambiguated);
+        assert!(medina.disambiguation_note.as_deref().unwrap().contains("built-in dataset"));
+
+        // The fallback is cached like any other answer
+        assert_eq!(resolver.resolve_city("Medina").unwrap().source, LocationSource::Cache);
+        assert_eq!(resolver.network_calls(), before);
+    }
+
+    #[test]
+    fn test_ambiguous_without_builtin_reports_candidates() {
+        let (mut resolver, _dir) = ambiguous_resolver();
+        let before = resolver.network_calls();
+
+        match resolver.resolve_city("Springfield") {
+            Err(LocationError::Ambiguous { query, candidates }) => {
+                assert_eq!(query, "Springfield");
+                assert_eq!(candidates.len(), 2);
+                assert!(candidates.iter().all(|c| c.tz == "America/Chicago" && c.country == "US"));
+            }
+            other => panic!("expected Ambiguous, got {:?}", other.map(|l| l.name)),
+        }
+        assert_eq!(resolver.network_calls(), before);
+    }
+
+    #[test]
+    fn test_refresh_replaces_stale_entry() {
+        fn fresh_medina(_query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+            Ok(ResolvedLocation {
+                name: "Medina".into(),
+                lat: 24.4672,
+                lon: 39.6112,
+                tz: "Asia/Riyadh".into(),
+                source: LocationSource::Nominatim,
+                display_name: None,
+                country_code: Some("SA".into()),
+                resolver_confidence: 0.9,
+                disambiguated: false,
+                disambiguation_note: None,
+                tz_source: TzSource::TimeApi,
+                elevation_m: None,
+            })
+        }
+
+        let dir = TempDir::new().unwrap();
+        let mut cache = LocationCache::load_from(dir.path().join("cache.json"));
+        // Wrong Medina (Ohio) cached earlier
+        cache.put_with_key("Medina", &ResolvedLocation {
+            name: "Medina".into(),
+            lat: 41.1384,
+            lon: -81.8637,
+            tz: "America/New_York".into(),
+            source: LocationSource::Nominatim,
+            display_name: None,
+            country_code: Some("US".into()),
+            resolver_confidence: 0.6,
+            disambiguated: false,
+            disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
+        });
+
+        let mut resolver = LocationResolver::with_cache(cache).with_lookup(fresh_medina);
+        assert_eq!(resolver.resolve_city("Medina").unwrap().country_code.as_deref(), Some("US"));
+
+        let fresh = resolver.refresh("Medina").unwrap();
+        assert_eq!(fresh.country_code.as_deref(), Some("SA"));
+        assert_eq!(fresh.source, LocationSource::Nominatim);
+
+        // The cache now serves the fresh entry
+        let cached = resolver.resolve_city("Medina").unwrap();
+        assert_eq!(cached.source, LocationSource::Cache);
+        assert_eq!(cached.country_code.as_deref(), Some("SA"));
+        assert!((cached.lat - 24.4672).abs() < 1e-6);
+    }
+
     #[test]
     fn test_resolve_cache_hit() {
         let dir = TempDir::new().unwrap();
@@ -319,6 +708,8 @@ mod tests {
             resolver_confidence: 0.9,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::TimeApi,
+            elevation_m: None,
         });
 
         let mut resolver = LocationResolver::with_cache(cache);
@@ -381,6 +772,8 @@ mod tests {
             resolver_confidence: 0.8,
             disambiguated: false,
             disambiguation_note: None,
+            tz_source: TzSource::IpApi,
+            elevation_m: None,
         });
 
         let mut resolver = LocationResolver::with_cache(cache);
@@ -421,7 +814,7 @@ mod tests {
     #[test]
     fn test_resolve_medina_builtin_with_country() {
         let (mut resolver, _dir) = offline_resolver();
-        let opts = ResolveOptions { country: Some("SA".to_string()), topk: None };
+        let opts = ResolveOptions { country: Some("SA".to_string()), ..Default::default() };
         let loc = resolver.resolve_city_with_opts("Medina", &opts).unwrap();
         assert_eq!(loc.country_code, Some("SA".to_string()));
         assert_eq!(loc.tz, "Asia/Riyadh");
@@ -25,6 +25,50 @@ impl fmt::Display for LocationSource {
     }
 }
 
+/// How a location's timezone was obtained.
+///
+/// A wrong zone silently shifts every time, so this tells users how far to
+/// trust it: the longitude estimate in particular is crude.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TzSource {
+    /// Recorded in the built-in city dataset.
+    Builtin,
+    /// Looked up from coordinates via timeapi.io.
+    TimeApi,
+    /// Reported by the IP geolocation provider.
+    IpApi,
+    /// Guessed from longitude alone (lookup unavailable).
+    Estimated,
+    /// Given explicitly by the user (`--tz`, `?tz=`).
+    Override,
+    /// Nothing known; UTC assumed.
+    Default,
+    /// Cached before provenance was recorded.
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for TzSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Builtin => write!(f, "Built-in"),
+            Self::TimeApi => write!(f, "timeapi.io"),
+            Self::IpApi => write!(f, "IP"),
+            Self::Estimated => write!(f, "Longitude estimate"),
+            Self::Override => write!(f, "Override"),
+            Self::Default => write!(f, "Default (UTC)"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Wrap a longitude into (-180, 180], so 181 becomes -179. GPS exports
+/// sometimes overshoot the antimeridian; the solar math is periodic anyway.
+pub fn normalize_longitude(lon: f64) -> f64 {
+    let wrapped = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped == -180.0 { 180.0 } else { wrapped }
+}
+
 /// A fully resolved location with coordinates, timezone, and provenance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResolvedLocation {
@@ -48,6 +92,19 @@ pub struct ResolvedLocation {
     /// Human-readable disambiguation note
     #[serde(default)]
     pub disambiguation_note: Option<String>,
+    /// How `tz` was obtained
+    #[serde(default)]
+    pub tz_source: TzSource,
+    /// Height above sea level in metres, when known (`--elevation auto`).
+    #[serde(default)]
+    pub elevation_m: Option<f64>,
+}
+
+impl ResolvedLocation {
+    /// This location with a user-supplied timezone.
+    pub fn with_tz_override(self, tz: impl Into<String>) -> Self {
+        Self { tz: tz.into(), tz_source: TzSource::Override, ..self }
+    }
 }
 
 fn default_confidence() -> f64 {
@@ -78,6 +135,51 @@ pub struct ResolveOptions {
     pub country: Option<String>,
     /// Show top-K candidates (debug mode)
     pub topk: Option<usize>,
+    /// Skip the cache read and overwrite the entry with a fresh result
+    pub refresh: bool,
+    /// How fine a place to look for (city by default)
+    pub granularity: Granularity,
+}
+
+/// How fine-grained a place the resolver should look for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// Cities and towns; smaller places are down-ranked.
+    #[default]
+    City,
+    /// Suburbs, neighbourhoods and quarters within a city.
+    Suburb,
+    /// Any named place, including landmarks and amenities.
+    Poi,
+}
+
+impl Granularity {
+    pub const ALL: [Granularity; 3] = [Granularity::City, Granularity::Suburb, Granularity::Poi];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::City => "city",
+            Self::Suburb => "suburb",
+            Self::Poi => "poi",
+        }
+    }
+}
+
+impl fmt::Display for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|g| g.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown granularity '{}'. Use: city, suburb, poi", s))
+    }
 }
 
 /// Location resolution errors.
@@ -88,6 +190,10 @@ pub enum LocationError {
     CacheMiss,
     InvalidResponse(String),
     NoInput,
+    /// The geocoder answered HTTP 429; `retry_after` is from its `Retry-After`.
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
     /// Ambiguous city name — multiple strong candidates exist.
     Ambiguous {
         query: String,
@@ -115,6 +221,10 @@ impl fmt::Display for LocationError {
             Self::CacheMiss => write!(f, "No cached location available"),
             Self::InvalidResponse(msg) => write!(f, "Invalid API response: {}", msg),
             Self::NoInput => write!(f, "No location specified. Use --city, --auto, or --lat/--lon"),
+            Self::RateLimited { retry_after: Some(d) } => {
+                write!(f, "Rate limited by the geocoder; retry in {}s", d.as_secs())
+            }
+            Self::RateLimited { retry_after: None } => write!(f, "Rate limited by the geocoder; retry later"),
             Self::Ambiguous { query, candidates } => {
                 writeln!(f, "Ambiguous city name: '{}'", query)?;
                 writeln!(f)?;
@@ -6,7 +6,7 @@
 use chrono::NaiveDateTime;
 use std::f64::consts::PI;
 
-use crate::solar::{julian_date, julian_century, normalize_degrees, obliquity_corrected, sun_ecliptic_longitude};
+use crate::solar::{Atmosphere, julian_date, julian_century, local_sidereal_time, normalize_degrees, obliquity_corrected, sun_ecliptic_longitude};
 
 const DEG: f64 = PI / 180.0;
 
@@ -177,17 +177,6 @@ fn moon_ecliptic(t: f64) -> (f64, f64, f64) {
     (longitude, latitude, distance)
 }
 
-/// Local sidereal time in degrees for a given JD and longitude.
-fn local_sidereal_time(jd: f64, lon: f64) -> f64 {
-    let t = julian_century(jd);
-    let gmst = normalize_degrees(
-        280.46061837 + 360.98564736629 * (jd - 2451545.0)
-            + 0.000387933 * t * t
-            - t * t * t / 38710000.0,
-    );
-    normalize_degrees(gmst + lon)
-}
-
 /// Ecliptic to equatorial coordinate transform.
 /// Returns (right_ascension_deg, declination_deg).
 fn ecliptic_to_equatorial(lon: f64, lat: f64, obliquity: f64) -> (f64, f64) {
@@ -234,17 +223,22 @@ fn topocentric_correction(geo_alt: f64, distance_km: f64, observer_lat: f64) ->
 }
 
 /// Apply atmospheric refraction correction.
-fn refraction_correction(apparent_alt: f64) -> f64 {
+fn refraction_correction(apparent_alt: f64, atmosphere: &Atmosphere) -> f64 {
     if apparent_alt < -1.0 {
         return apparent_alt;
     }
-    // Bennett's formula
+    // Bennett's formula, scaled for temperature and pressure
     let r = 1.02 / ((apparent_alt + 10.3 / (apparent_alt + 5.11)) * DEG).tan();
-    apparent_alt + r / 60.0
+    apparent_alt + r * atmosphere.refraction_scale() / 60.0
 }
 
 /// Compute the full lunar position for a given UTC datetime and observer location.
 pub fn lunar_position(dt: &NaiveDateTime, lat: f64, lon: f64) -> LunarPosition {
+    lunar_position_in(dt, lat, lon, &Atmosphere::STANDARD)
+}
+
+/// [`lunar_position`] with refraction for the given air conditions.
+pub fn lunar_position_in(dt: &NaiveDateTime, lat: f64, lon: f64, atmosphere: &Atmosphere) -> LunarPosition {
     let jd = julian_date(dt);
     let t = julian_century(jd);
 
@@ -259,7 +253,7 @@ pub fn lunar_position(dt: &NaiveDateTime, lat: f64, lon: f64) -> LunarPosition {
     let topo_alt = topocentric_correction(geo_alt, distance, lat);
 
     // Apply atmospheric refraction
-    let altitude = refraction_correction(topo_alt);
+    let altitude = refraction_correction(topo_alt, atmosphere);
 
     LunarPosition {
         longitude: moon_lon,
@@ -289,6 +283,28 @@ pub fn moon_sun_elongation(dt: &NaiveDateTime) -> f64 {
     cos_elong.clamp(-1.0, 1.0).acos() / DEG
 }
 
+/// Illuminated fraction of the Moon's disc (0 new .. 1 full).
+///
+/// Uses the phase angle approximated as `180° - elongation` (Meeus 48.3).
+pub fn moon_illumination(dt: &NaiveDateTime) -> f64 {
+    let phase_angle = (180.0 - moon_sun_elongation(dt)) * DEG;
+    (1.0 + phase_angle.cos()) / 2.0
+}
+
+const PHASE_NAMES: [&str; 8] = [
+    "New Moon", "Waxing Crescent", "First Quarter", "Waxing Gibbous",
+    "Full Moon", "Waning Gibbous", "Last Quarter", "Waning Crescent",
+];
+
+/// Name of the Moon's phase: one of eight 45° sectors of its ecliptic
+/// longitude east of the Sun, centred on New, First Quarter, Full and
+/// Last Quarter.
+pub fn moon_phase_name(dt: &NaiveDateTime) -> &'static str {
+    let (moon_lon, _, _) = moon_ecliptic(julian_century(julian_date(dt)));
+    let age = normalize_degrees(moon_lon - sun_ecliptic_longitude(dt) + 22.5);
+    PHASE_NAMES[(age / 45.0) as usize % 8]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,6 +371,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_illumination_and_phase() {
+        let at = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(12, 0, 0).unwrap();
+
+        // The full moon of test_full_moon_elongation
+        let full = at(2025, 1, 13);
+        println!("illumination {:.4}", moon_illumination(&full));
+        assert!(moon_illumination(&full) > 0.98);
+        assert_eq!(moon_phase_name(&full), "Full Moon");
+
+        // The conjunction of test_conjunction_feb17_2026
+        let new = at(2026, 2, 17);
+        assert!(moon_illumination(&new) < 0.02);
+        assert_eq!(moon_phase_name(&new), "New Moon");
+
+        // A week either side of that full moon
+        assert_eq!(moon_phase_name(&at(2025, 1, 6)), "First Quarter");
+        assert_eq!(moon_phase_name(&at(2025, 1, 21)), "Last Quarter");
+        assert_eq!(moon_phase_name(&at(2025, 1, 10)), "Waxing Gibbous");
+        assert_eq!(moon_phase_name(&at(2025, 1, 25)), "Waning Crescent");
+    }
+
     #[test]
     fn test_lunar_position_mecca() {
         // Basic sanity: altitude should be between -90 and 90
@@ -367,4 +405,13 @@ mod tests {
         assert!(pos.azimuth >= 0.0 && pos.azimuth <= 360.0);
         assert!(pos.distance_km > 350000.0 && pos.distance_km < 410000.0);
     }
+
+    #[test]
+    fn test_refraction_scales_with_air_density() {
+        let standard = refraction_correction(0.5, &Atmosphere::STANDARD) - 0.5;
+        let cold = refraction_correction(0.5, &Atmosphere { temperature_c: -40.0, pressure_mbar: 1010.0 }) - 0.5;
+        println!("refraction at 0.5°: standard {:.4}°, -40 °C {:.4}°", standard, cold);
+        assert!((cold / standard - 283.0 / 233.0).abs() < 1e-9);
+        assert_eq!(refraction_correction(-2.0, &Atmosphere::STANDARD), -2.0);
+    }
 }
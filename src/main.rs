@@ -1,9 +1,17 @@
-use chrono::{NaiveDate, Utc};
-use chrono_tz::Tz;
-use clap::{Parser, Subcommand};
-use polaris_chronos::location::{LocationResolver, ResolvedLocation, ResolveOptions};
-use polaris_chronos::schedule::GapStrategy;
-use polaris_chronos::solver::{Solver, render_ascii_timeline};
+use chrono::{Datelike, Utc};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use serde::Serialize;
+use std::io::Write;
+use polaris_chronos::location::{Granularity, LocationError, LocationResolver, ResolvedLocation, ResolveOptions};
+use polaris_chronos::profile;
+use polaris_chronos::config::{self, Adjustments, Config, ElevationSetting};
+use polaris_chronos::dates;
+use polaris_chronos::export;
+use polaris_chronos::notify::Notifier;
+use polaris_chronos::method::{parse_method, CalculationMethod};
+use polaris_chronos::schedule::{parse_strategy, AsrFormula, AsrMadhab, GapStrategy, HighLatitudeRule, MidnightSunRule, RoundingPolicy, SunriseReference};
+use polaris_chronos::solver::{Solver, SolverError, SolverOutput, render_ascii_timeline, render_wave_plot};
+use polaris_chronos::timezone;
 
 /// Polaris Chronos v1.0 — Adaptive Compensation Prayer Time Engine
 ///
@@ -24,10 +32,22 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Compute prayer times for a location.
-    Compute(ComputeArgs),
+    Compute(Box<ComputeArgs>),
 
     /// Start the web server with embedded dashboard.
     Server(ServerArgs),
+
+    /// Time the schedule computation over a range of days.
+    Profile(ProfileArgs),
+
+    /// Print the next prayer as one line (for scripts and status bars).
+    Next(NextArgs),
+
+    /// POST each day's schedule to a webhook shortly after local midnight.
+    ServeNotify(NotifyArgs),
+
+    /// List a timezone's DST offset transitions for a year.
+    TzCheck(TzCheckArgs),
 }
 
 #[derive(Parser)]
@@ -48,18 +68,31 @@ struct ComputeArgs {
     #[arg(long, allow_hyphen_values = true)]
     lat: Option<f64>,
 
-    /// Longitude (-180 to 180). Legacy mode.
+    /// Longitude (-180 to 180; wraps, so 181 = -179). Legacy mode.
     #[arg(long, allow_hyphen_values = true)]
     lon: Option<f64>,
 
-    /// Date (YYYY-MM-DD). Defaults to today.
+    /// Date (YYYY-MM-DD, ISO week 2026-W07-6 or ordinal 2026-045). Defaults to today.
     #[arg(long, short = 'd')]
     date: Option<String>,
 
+    /// First day of a range (with --to and --format ndjson).
+    #[arg(long, value_name = "DATE", requires = "to", conflicts_with_all = ["date", "now", "debug_wave"])]
+    from: Option<String>,
+
+    /// Last day of a range, inclusive.
+    #[arg(long, value_name = "DATE", requires = "from")]
+    to: Option<String>,
+
     /// IANA timezone override (e.g. Europe/Oslo).
     #[arg(long)]
     tz: Option<String>,
 
+    /// Show times in another IANA timezone (e.g. Europe/London) while
+    /// computing for the location's own timezone.
+    #[arg(long)]
+    display_tz: Option<String>,
+
     /// Show current prayer and time to next.
     #[arg(long)]
     now: bool,
@@ -72,7 +105,7 @@ struct ComputeArgs {
     #[arg(long)]
     offline: bool,
 
-    /// Gap strategy for polar states: "strict" or "projected45".
+    /// Gap strategy for polar states: "strict", "projected45", "auto" or "moonsighting".
     #[arg(long, default_value = "projected45", value_parser = parse_strategy)]
     strategy: GapStrategy,
 
@@ -80,6 +113,14 @@ struct ComputeArgs {
     #[arg(long)]
     show_confidence: bool,
 
+    /// Add the qibla bearing and distance to the Kaaba.
+    #[arg(long)]
+    qibla: bool,
+
+    /// Add the Hijri date (shown in the banner and the JSON output).
+    #[arg(long)]
+    hijri: bool,
+
     /// Country hint (ISO 3166-1 alpha-2, e.g. SA, US, FR).
     #[arg(long)]
     country: Option<String>,
@@ -87,6 +128,91 @@ struct ComputeArgs {
     /// Debug: show top-K candidates from Nominatim.
     #[arg(long)]
     topk: Option<usize>,
+
+    /// Re-resolve the city, replacing its cached entry.
+    #[arg(long)]
+    refresh: bool,
+
+    /// How fine a place to resolve: "city", "suburb" or "poi".
+    #[arg(long, default_value = "city")]
+    granularity: Granularity,
+
+    /// Asr altitude formula: "standard" or "geometric" (equivalent; a
+    /// disagreement is reported as a warning).
+    #[arg(long, default_value = "standard")]
+    asr_formula: AsrFormula,
+
+    /// Asr shadow convention: "shafii" (shadow factor 1) or "hanafi" (2).
+    /// Defaults to the method's convention.
+    #[arg(long = "asr", value_name = "MADHAB")]
+    asr_madhab: Option<AsrMadhab>,
+
+    /// Keep Fajr at least this many minutes before sunrise (warns when applied).
+    #[arg(long, value_name = "MINUTES")]
+    min_fajr_gap: Option<u32>,
+
+    /// Sunrise/sunset point of the disc: "upper-limb" (-0.833°) or "center" (-0.583°).
+    #[arg(long, default_value = "upper-limb")]
+    sunrise_reference: SunriseReference,
+
+    /// Calculation method: mwl, isna, egyptian, karachi, ummalqura, tehran or jafari.
+    #[arg(long, default_value = "mwl", value_parser = parse_method)]
+    method: CalculationMethod,
+
+    /// Minute offsets per event, e.g. "fajr=+2,isha=-1".
+    #[arg(long, value_parser = config::parse_kv_offsets)]
+    adjust: Option<Adjustments>,
+
+    /// Fajr angle in degrees below the horizon, replacing the method's.
+    #[arg(long, value_name = "DEG", value_parser = config::parse_depression_angle)]
+    fajr_angle: Option<f64>,
+
+    /// Isha angle in degrees below the horizon, replacing the method's.
+    #[arg(long, value_name = "DEG", value_parser = config::parse_depression_angle)]
+    isha_angle: Option<f64>,
+
+    /// Isha a fixed number of minutes after Maghrib, replacing the method's rule.
+    #[arg(long, value_name = "MINUTES", conflicts_with = "isha_angle")]
+    isha_minutes: Option<u32>,
+
+    /// Maghrib/Isha when the sun never sets: "strategy" (follow --strategy) or
+    /// "nadir-split" (between Asr and solar midnight).
+    #[arg(long, default_value = "strategy")]
+    midnight_sun: MidnightSunRule,
+
+    /// Fajr/Isha when the sun never reaches their angle: "wave" (mapped on
+    /// the altitude wave), "middle-of-night" (midpoint of sunset → sunrise),
+    /// "seventh" (a seventh of the night from sunset and sunrise) or
+    /// "angle-based" (angle/60 of the night).
+    #[arg(long, visible_alias = "high-lat", value_name = "RULE", default_value = "wave")]
+    high_lat_rule: HighLatitudeRule,
+
+    /// Round times to whole minutes: nearest, fajr-down, isha-up, all-down or all-up.
+    #[arg(long, value_name = "POLICY")]
+    round: Option<RoundingPolicy>,
+
+    /// Height above sea level in metres, or "auto" to look it up online;
+    /// lowers the horizon for sunrise and sunset.
+    #[arg(long, value_name = "METERS|auto")]
+    elevation: Option<ElevationSetting>,
+
+    /// Day-scan step in seconds (1-600, default 30); finer steps sharpen
+    /// shallow high-latitude crossings at extra CPU cost.
+    #[arg(long, value_name = "SECONDS", value_parser = config::parse_scan_resolution)]
+    resolution: Option<u32>,
+
+    /// How errors are reported on stderr: "text" or "json".
+    #[arg(long, value_enum, default_value = "text")]
+    error_format: ErrorFormat,
+
+    /// What to print on stdout: "json", "ical" (an iCalendar file) or
+    /// "ndjson" (one compact JSON object per line, streamed per day).
+    #[arg(long, value_enum, default_value = "json")]
+    format: OutputFormat,
+
+    /// With --format ical, also add a Sunrise event (not a prayer).
+    #[arg(long)]
+    ical_sunrise: bool,
 }
 
 #[derive(Parser)]
@@ -98,14 +224,119 @@ struct ServerArgs {
     /// Host to bind to.
     #[arg(long, default_value = "127.0.0.1")]
     host: String,
+
+    /// Require this value in the X-Admin-Token header for admin endpoints
+    /// (disabled without it).
+    #[arg(long)]
+    admin_token: Option<String>,
+
+    /// API requests allowed per client IP per minute; 0 disables the limit.
+    #[arg(long, value_name = "N", default_value = "60")]
+    rate_limit: u32,
+
+    /// Key the rate limit on the last X-Forwarded-For hop; set only when a
+    /// reverse proxy appending the client address fronts every request.
+    #[arg(long)]
+    trusted_proxy: bool,
+
+    /// Reload computed days from ~/.polaris/compute_cache.json at startup
+    /// and save them back on shutdown.
+    #[arg(long)]
+    persist_cache: bool,
 }
 
-fn parse_strategy(s: &str) -> Result<GapStrategy, String> {
-    match s.to_lowercase().as_str() {
-        "strict" => Ok(GapStrategy::Strict),
-        "projected45" | "projected" => Ok(GapStrategy::Projected45),
-        _ => Err(format!("Unknown strategy '{}'. Use 'strict' or 'projected45'.", s)),
-    }
+#[derive(Parser)]
+struct ProfileArgs {
+    #[command(flatten)]
+    location: LocationArgs,
+
+    /// Number of consecutive days to compute.
+    #[arg(long, default_value = "30")]
+    days: u32,
+
+    /// First date (YYYY-MM-DD, 2026-W07-6 or 2026-045). Defaults to today.
+    #[arg(long, short = 'd')]
+    date: Option<String>,
+
+    /// Gap strategy for polar states: "strict", "projected45", "auto" or "moonsighting".
+    #[arg(long, default_value = "projected45", value_parser = parse_strategy)]
+    strategy: GapStrategy,
+
+}
+
+#[derive(Parser)]
+struct NextArgs {
+    /// City name (positional). Example: polaris next Stockholm
+    #[arg(index = 1)]
+    city_positional: Option<String>,
+
+    #[command(flatten)]
+    location: LocationArgs,
+
+    /// IANA timezone override (e.g. Europe/Oslo).
+    #[arg(long)]
+    tz: Option<String>,
+
+    /// Output template. Placeholders: {prayer} {time} {remaining} {minutes}.
+    #[arg(long, default_value = "{prayer} {time} (in {remaining})")]
+    format: String,
+
+    /// Gap strategy for polar states: "strict", "projected45", "auto" or "moonsighting".
+    #[arg(long, default_value = "projected45", value_parser = parse_strategy)]
+    strategy: GapStrategy,
+}
+
+#[derive(Parser)]
+struct TzCheckArgs {
+    /// IANA timezone. Example: polaris tz-check Europe/Berlin
+    #[arg(index = 1)]
+    tz: String,
+
+    /// Year to scan. Defaults to the current year.
+    #[arg(long)]
+    year: Option<i32>,
+}
+
+#[derive(Parser)]
+struct NotifyArgs {
+    #[command(flatten)]
+    location: LocationArgs,
+
+    /// IANA timezone override (e.g. Europe/Oslo).
+    #[arg(long)]
+    tz: Option<String>,
+
+    /// URL that receives the schedule JSON.
+    #[arg(long)]
+    webhook: String,
+
+    /// Extra attempts after a failed post.
+    #[arg(long, default_value = "3")]
+    retries: u32,
+
+    /// Gap strategy for polar states: "strict", "projected45", "auto" or "moonsighting".
+    #[arg(long, default_value = "projected45", value_parser = parse_strategy)]
+    strategy: GapStrategy,
+}
+
+/// Location flags shared by `profile`, `next` and `serve-notify`.
+#[derive(Args)]
+struct LocationArgs {
+    /// City name. Example: --city Stockholm
+    #[arg(long)]
+    city: Option<String>,
+
+    /// Latitude (-90 to 90).
+    #[arg(long, allow_hyphen_values = true)]
+    lat: Option<f64>,
+
+    /// Longitude (-180 to 180; wraps, so 181 = -179).
+    #[arg(long, allow_hyphen_values = true)]
+    lon: Option<f64>,
+
+    /// Offline mode: only use cache and built-in data.
+    #[arg(long)]
+    offline: bool,
 }
 
 fn main() {
@@ -116,11 +347,19 @@ fn main() {
         Ok(cli) => match cli.command {
             Some(Command::Server(args)) => run_server(args),
             Some(Command::Compute(args)) => run_compute(args),
+            Some(Command::Profile(args)) => run_profile(args),
+            Some(Command::Next(args)) => run_next(args),
+            Some(Command::ServeNotify(args)) => run_notify(args),
+            Some(Command::TzCheck(args)) => run_tz_check(args),
             None => {
                 // No subcommand and no args — show help
                 let _ = Cli::parse(); // will print help and exit
             }
         },
+        Err(e) if names_subcommand(std::env::args().nth(1).as_deref()) => {
+            // A known subcommand with a bad value: report it as-is
+            e.exit()
+        }
         Err(_) => {
             // Backward compat: treat all args as compute args
             // Insert "compute" after the binary name so clap can parse it
@@ -134,12 +373,131 @@ fn main() {
     }
 }
 
+/// Whether `arg` is a subcommand name or alias, so a parse error belongs to
+/// that subcommand rather than to a legacy `polaris <city> [flags]` call.
+fn names_subcommand(arg: Option<&str>) -> bool {
+    let Some(arg) = arg else { return false };
+    Cli::command().get_subcommands().any(|sub| {
+        sub.get_name() == arg || sub.get_all_aliases().any(|alias| alias == arg)
+    })
+}
+
 fn run_server(args: ServerArgs) {
     let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(polaris_chronos::server::start(&args.host, args.port));
+    rt.block_on(polaris_chronos::server::start(&args.host, args.port, args.admin_token, args.rate_limit, args.trusted_proxy, args.persist_cache));
+}
+
+fn run_tz_check(args: TzCheckArgs) {
+    let tz = timezone::parse_tz(&args.tz).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+    let year = args.year.unwrap_or_else(|| Utc::now().year());
+
+    let transitions = timezone::offset_transitions(&tz, year);
+    println!("  {} — {}", tz.name(), year);
+    if transitions.is_empty() {
+        let offset = timezone::noon_offset(&tz, chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default());
+        println!("  No offset changes (UTC{} all year)", offset);
+        return;
+    }
+    for t in &transitions {
+        println!("  {}  UTC{} → UTC{}", t.date, t.before, t.after);
+    }
+}
+
+fn run_notify(args: NotifyArgs) {
+    let resolved = resolve_cli_location(&args.location, args.tz.as_deref());
+
+    eprintln!("  {}", resolved.display_line());
+    eprintln!("  Posting daily schedule to {}", args.webhook);
+
+    let notifier = Notifier::new(resolved, args.webhook)
+        .with_config(Config::default().with_strategy(args.strategy))
+        .with_retries(args.retries, std::time::Duration::from_secs(30));
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(notifier.run());
 }
 
-fn run_compute(cli: ComputeArgs) {
+fn run_profile(args: ProfileArgs) {
+    let resolved = resolve_cli_location(&args.location, None);
+    let (name, lat, lon) = (resolved.name, resolved.lat, resolved.lon);
+
+    let start = match &args.date {
+        Some(d) => dates::parse_date(d).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid date '{}': {}", d, e);
+            std::process::exit(1);
+        }),
+        None => Utc::now().naive_utc().date(),
+    };
+
+    let report = profile::profile_schedule(lat, lon, start, args.days, args.strategy);
+
+    println!("  Profile: {} — {} days from {} ({})", name, report.days, start, args.strategy);
+    println!("  total: {:.2} ms   mean: {:.3} ms   p95: {:.3} ms", report.total_ms, report.mean_ms, report.p95_ms);
+    if cfg!(feature = "eval-counter") {
+        println!("  solar_position evaluations: {}", report.evaluations);
+    } else {
+        println!("  solar_position evaluations: n/a (built without eval-counter)");
+    }
+}
+
+fn run_next(args: NextArgs) {
+    let mut location = args.location;
+    location.city = location.city.or(args.city_positional);
+    let resolved = resolve_cli_location(&location, args.tz.as_deref());
+
+    let solver = Solver::from_resolved(&resolved).with_strategy(args.strategy);
+    match solver.next_prayer(Utc::now()) {
+        Some(next) => println!("{}", next.format(&args.format)),
+        None => {
+            eprintln!("Error: No upcoming prayer found for {}", resolved.name);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Resolve the location of `profile`, `next` or `serve-notify` and apply the
+/// `--tz` override. Exits on a failed lookup, a missing location or
+/// coordinates the solver would reject.
+fn resolve_cli_location(args: &LocationArgs, tz: Option<&str>) -> ResolvedLocation {
+    let resolved = match (&args.city, args.lat, args.lon) {
+        (Some(city), _, _) => {
+            let mut resolver = LocationResolver::new();
+            if args.offline {
+                resolver.set_offline(true);
+            }
+            resolver.resolve_city_with_opts(city, &ResolveOptions::default()).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            })
+        }
+        (None, Some(lat), Some(lon)) => LocationResolver::from_manual(lat, lon, None),
+        _ => {
+            eprintln!("Error: No location specified. Use --city or --lat/--lon.");
+            std::process::exit(1);
+        }
+    };
+
+    let (lat, lon) = (resolved.lat, resolved.lon);
+    if !(-90.0..=90.0).contains(&lat) || !lon.is_finite() {
+        eprintln!("Error: {}", SolverError::InvalidCoordinates { lat, lon });
+        std::process::exit(1);
+    }
+
+    match tz {
+        Some(tz_str) => {
+            let tz = timezone::parse_tz(tz_str).unwrap_or_else(|e| {
+                eprintln!("Error: {}. Use IANA format (e.g. Europe/Oslo).", e);
+                std::process::exit(1);
+            });
+            resolved.with_tz_override(tz.name())
+        }
+        None => resolved,
+    }
+}
+
+fn run_compute(cli: Box<ComputeArgs>) {
     // ── Resolve location ────────────────────────────────────────
 
     let mut resolver = LocationResolver::new();
@@ -150,33 +508,46 @@ fn run_compute(cli: ComputeArgs) {
     let opts = ResolveOptions {
         country: cli.country.clone(),
         topk: cli.topk,
+        refresh: cli.refresh,
+        granularity: cli.granularity,
     };
 
     let resolved = resolve_location(&cli, &mut resolver, &opts);
 
     // ── Parse date ──────────────────────────────────────────────
 
+    let parse = |d: &str| dates::parse_date(d).unwrap_or_else(|e| {
+        fail(cli.error_format, CliError::new("invalid_date", format!("Invalid date '{}': {}", d, e)))
+    });
     let date = match &cli.date {
-        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap_or_else(|e| {
-            eprintln!("Error: Invalid date '{}': {}", d, e);
-            std::process::exit(1);
-        }),
+        Some(d) => parse(d),
         None => Utc::now().naive_utc().date(),
     };
+    let range = match (&cli.from, &cli.to) {
+        (Some(from), Some(to)) => {
+            let (from, to) = (parse(from), parse(to));
+            if from > to {
+                fail(cli.error_format, CliError::new("invalid_date", format!("--from {} is after --to {}", from, to)));
+            }
+            if cli.format != OutputFormat::Ndjson {
+                fail(cli.error_format, CliError::new("invalid_format", "--from/--to stream one day per line")
+                    .with_hint("Add --format ndjson."));
+            }
+            Some((from, to))
+        }
+        _ => None,
+    };
 
     // ── Apply timezone override ─────────────────────────────────
 
-    let final_resolved = match &cli.tz {
+    let mut final_resolved = match &cli.tz {
         Some(tz_str) => {
             // Validate the timezone
-            let _: Tz = tz_str.parse().unwrap_or_else(|_| {
-                eprintln!("Error: Unknown timezone '{}'. Use IANA format (e.g. Europe/Oslo).", tz_str);
-                std::process::exit(1);
+            let tz = timezone::parse_tz(tz_str).unwrap_or_else(|e| {
+                fail(cli.error_format, CliError::new("unknown_timezone", e.to_string())
+                    .with_hint("Use IANA format (e.g. Europe/Oslo)."))
             });
-            ResolvedLocation {
-                tz: tz_str.clone(),
-                ..resolved
-            }
+            resolved.with_tz_override(tz.name())
         }
         None => resolved,
     };
@@ -192,61 +563,234 @@ fn run_compute(cli: ComputeArgs) {
 
     // ── Solve ───────────────────────────────────────────────────
 
-    let solver = Solver::from_resolved(&final_resolved).with_strategy(cli.strategy);
-    let output = solver.solve_with_info(date, cli.now, cli.debug_wave, Some(&final_resolved));
+    let elevation_m = match cli.elevation {
+        Some(ElevationSetting::Meters(m)) => m,
+        Some(ElevationSetting::Auto) => {
+            if let Err(e) = resolver.fill_elevation(&mut final_resolved) {
+                eprintln!("  Warning: elevation lookup failed ({}); using sea level", e);
+            }
+            final_resolved.elevation_m.unwrap_or(0.0)
+        }
+        None => 0.0,
+    };
+    let config = compute_config(&cli, elevation_m).unwrap_or_else(|e| {
+        fail(cli.error_format, CliError::new("invalid_argument", e))
+    });
+    let mut solver = Solver::from_resolved(&final_resolved).with_config(config);
+    if let Some(ref display) = cli.display_tz {
+        let display_tz = timezone::parse_tz(display).unwrap_or_else(|e| {
+            fail(cli.error_format, CliError::new("unknown_timezone", format!("--display-tz: {}", e))
+                .with_hint("Use IANA format (e.g. Europe/London)."))
+        });
+        eprintln!("  \u{1F552} Times shown in {}", display_tz);
+        solver = solver.with_display_tz(display_tz);
+    }
+    if let Some((from, to)) = range {
+        stream_ndjson(solver.solve_range_with_info(from, to, Some(&final_resolved)), cli.hijri);
+        return;
+    }
+    let mut output = solver.solve_with_info(date, cli.now, cli.debug_wave, Some(&final_resolved));
+    if cli.hijri {
+        output = output.with_hijri();
+        if let Some(hijri) = output.hijri {
+            eprintln!("  \u{1F319} {} AH", hijri);
+        }
+    }
+    for warning in &output.warnings {
+        eprintln!("  \u{26A0}\u{FE0F}  {}", warning);
+    }
+    if let Some(qibla) = output.qibla {
+        eprintln!("  \u{1F54B} Qibla {:.1}° from true north, {:.0} km", qibla.bearing, qibla.distance_km);
+    }
 
     // ASCII timeline to stderr
     eprint!("{}", render_ascii_timeline(&output.events, output.state, output.gap_strategy, cli.show_confidence));
+    if let Some(ref wave) = output.wave_debug {
+        eprint!("{}", render_wave_plot(wave, 12));
+    }
+
+    // JSON or iCalendar to stdout
+    match cli.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        OutputFormat::Ical => print!("{}", export::to_ical(&output, cli.ical_sunrise)),
+        OutputFormat::Ndjson => stream_ndjson(std::iter::once(output), false),
+    }
+}
 
-    // JSON to stdout
-    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+/// What `compute` prints on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    Json,
+    Ical,
+    Ndjson,
+}
+
+/// Write each output as one JSON line as soon as it is computed. Stops
+/// quietly when the reader goes away (e.g. `| head`).
+fn stream_ndjson(outputs: impl Iterator<Item = SolverOutput>, hijri: bool) {
+    let mut stdout = std::io::stdout().lock();
+    for output in outputs {
+        let output = if hijri { output.with_hijri() } else { output };
+        let line = serde_json::to_string(&output).unwrap();
+        if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+/// The computation settings selected by the compute flags.
+fn compute_config(cli: &ComputeArgs, elevation_m: f64) -> Result<Config, String> {
+    let config = Config::default()
+        .with_strategy(cli.strategy)
+        .with_method(cli.method)
+        .with_asr_formula(cli.asr_formula)
+        .with_asr_madhab(cli.asr_madhab)
+        .with_sunrise_reference(cli.sunrise_reference)
+        .with_adjustments(cli.adjust.unwrap_or_default())
+        .with_midnight_sun_rule(cli.midnight_sun)
+        .with_high_latitude_rule(cli.high_lat_rule)
+        .with_qibla(cli.qibla)
+        .with_rounding(cli.round)
+        .with_elevation(elevation_m)
+        .with_fajr_angle(cli.fajr_angle)
+        .with_isha_rule(config::isha_override(cli.isha_angle, cli.isha_minutes)?)
+        .with_min_fajr_sunrise_gap(cli.min_fajr_gap)
+        .try_with_scan_resolution(cli.resolution)?;
+    Ok(config)
 }
 
 fn resolve_location(cli: &ComputeArgs, resolver: &mut LocationResolver, opts: &ResolveOptions) -> ResolvedLocation {
     // Priority: --city > positional city > --auto > --lat/--lon > error
+    let location_error = |e| fail(cli.error_format, CliError::location(e));
 
     // 1. --city flag
     if let Some(ref city) = cli.city {
-        return resolver.resolve_city_with_opts(city, opts).unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
+        return resolver.resolve_city_with_opts(city, opts).unwrap_or_else(location_error);
     }
 
     // 2. Positional city argument
     if let Some(ref city) = cli.city_positional {
-        return resolver.resolve_city_with_opts(city, opts).unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
+        return resolver.resolve_city_with_opts(city, opts).unwrap_or_else(location_error);
     }
 
     // 3. --auto
     if cli.auto {
-        return resolver.resolve_auto().unwrap_or_else(|e| {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        });
+        return resolver.resolve_auto().unwrap_or_else(location_error);
     }
 
     // 4. Legacy --lat/--lon
     if let (Some(lat), Some(lon)) = (cli.lat, cli.lon) {
-        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
-            eprintln!("Error: Invalid coordinates. Lat: -90..90, Lon: -180..180");
-            std::process::exit(1);
+        if !(-90.0..=90.0).contains(&lat) || !lon.is_finite() {
+            fail(cli.error_format, CliError::new("invalid_coordinates", "Invalid coordinates")
+                .with_hint("Lat: -90..90; Lon wraps to -180..180"));
         }
         return LocationResolver::from_manual(lat, lon, cli.tz.as_deref());
     }
 
     // 5. Nothing provided
-    eprintln!("Error: No location specified.");
-    eprintln!();
-    eprintln!("Usage:");
-    eprintln!("  polaris compute Stockholm");
-    eprintln!("  polaris compute --city \"New York\"");
-    eprintln!("  polaris compute --city \"Medina, Saudi Arabia\"");
-    eprintln!("  polaris compute --city Medina --country SA");
-    eprintln!("  polaris compute --auto");
-    eprintln!("  polaris compute --lat 21.4225 --lon 39.8262 --tz Asia/Riyadh");
-    std::process::exit(1);
+    let usage = [
+        "polaris compute Stockholm",
+        "polaris compute --city \"New York\"",
+        "polaris compute --city \"Medina, Saudi Arabia\"",
+        "polaris compute --city Medina --country SA",
+        "polaris compute --auto",
+        "polaris compute --lat 21.4225 --lon 39.8262 --tz Asia/Riyadh",
+    ];
+    let mut text = String::from("Error: No location specified.\n\nUsage:");
+    for line in usage {
+        text.push_str("\n  ");
+        text.push_str(line);
+    }
+    let mut err = CliError::new("no_location", "No location specified");
+    err.hint = Some("Give a city name, --auto, or --lat/--lon".into());
+    err.text = text;
+    fail(cli.error_format, err)
+}
+
+// ── Error output ────────────────────────────────────────────────
+
+/// How `compute` reports a fatal error on stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ErrorFormat {
+    Text,
+    Json,
+}
+
+/// A fatal CLI error. The JSON form mirrors the server's error bodies,
+/// with `candidates` shaped like its ambiguous-city options.
+#[derive(Serialize)]
+struct CliError {
+    error: String,
+    /// Stable machine-readable kind, e.g. `invalid_date` or `ambiguous`.
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<CliCandidate>>,
+    /// The human-readable form printed under `--error-format text`.
+    #[serde(skip)]
+    text: String,
+}
+
+#[derive(Serialize)]
+struct CliCandidate {
+    name: String,
+    country: String,
+    country_code: String,
+    tz: String,
+    lat: f64,
+    lon: f64,
+}
+
+impl CliError {
+    fn new(code: &'static str, error: impl Into<String>) -> Self {
+        let error = error.into();
+        Self { text: format!("Error: {}", error), error, code, hint: None, candidates: None }
+    }
+
+    /// Add a hint; the text form appends it to the message.
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        let hint = hint.into();
+        self.text = format!("{}. {}", self.text, hint);
+        self.hint = Some(hint);
+        self
+    }
+
+    fn location(err: LocationError) -> Self {
+        let text = format!("Error: {}", err);
+        let code = match &err {
+            LocationError::Ambiguous { .. } => "ambiguous",
+            LocationError::NotFound(_) => "not_found",
+            LocationError::RateLimited { .. } => "rate_limited",
+            LocationError::Network(_) | LocationError::InvalidResponse(_) => "network",
+            LocationError::CacheMiss | LocationError::NoInput => "no_location",
+        };
+        let mut cli_error = Self::new(code, err.to_string());
+        if let LocationError::Ambiguous { query, candidates } = err {
+            // The display form lists the candidates; JSON carries them as data
+            cli_error.error = format!("Ambiguous city name: '{}'", query);
+            cli_error.hint = candidates.first().map(|c| {
+                format!("Try --city \"{}, {}\" or --country {}", query, c.country, c.country)
+            });
+            cli_error.candidates = Some(candidates.into_iter().map(|c| CliCandidate {
+                name: c.name,
+                country: c.country_name,
+                country_code: c.country,
+                tz: c.tz,
+                lat: c.lat,
+                lon: c.lon,
+            }).collect());
+        }
+        cli_error.text = text;
+        cli_error
+    }
+}
+
+/// Report `err` in the requested format and exit with status 1.
+fn fail(format: ErrorFormat, err: CliError) -> ! {
+    match format {
+        ErrorFormat::Text => eprintln!("{}", err.text),
+        ErrorFormat::Json => eprintln!("{}", serde_json::to_string(&err).expect("CliError serializes")),
+    }
+    std::process::exit(1)
 }
@@ -0,0 +1,250 @@
+//! Calculation methods — the twilight angles and rules behind regional
+//! prayer time conventions.
+//!
+//! Angles are stored as positive depressions below the horizon
+//! (e.g. 18.0 means the sun is 18° below the horizon).
+
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// How Isha is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum IshaRule {
+    /// Sun depression angle in degrees.
+    Angle(f64),
+    /// Fixed number of minutes after Maghrib (projected Maghrib included).
+    /// Combines with any Fajr angle, not only the Umm al-Qura preset.
+    FixedAfterMaghrib(u32),
+}
+
+/// How Maghrib is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum MaghribRule {
+    /// Refraction-adjusted sunset.
+    Sunset,
+    /// Sun depression angle in degrees (used by Shia conventions).
+    Angle(f64),
+}
+
+/// The rules a schedule needs from a calculation method.
+///
+/// Implemented by the built-in [`CalculationMethod`] presets; integrators can
+/// implement it for their own conventions and pass it to
+/// [`compute_schedule_with_method`](crate::schedule::compute_schedule_with_method).
+pub trait PrayerMethod {
+    /// Fajr depression angle in degrees.
+    fn fajr_angle(&self) -> f64;
+    fn isha_rule(&self) -> IshaRule;
+    fn maghrib_rule(&self) -> MaghribRule;
+    /// Asr shadow factor: 1.0 (Shafi'i, Maliki, Hanbali) or 2.0 (Hanafi).
+    fn asr_factor(&self) -> f64 {
+        1.0
+    }
+    /// Minutes after Maghrib replacing a fixed Isha interval during Ramadan.
+    fn ramadan_isha_minutes(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// A named calculation method preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum CalculationMethod {
+    /// Muslim World League: Fajr 18°, Isha 17°.
+    #[default]
+    MWL,
+    /// Islamic Society of North America: Fajr 15°, Isha 15°.
+    ISNA,
+    /// Egyptian General Authority of Survey: Fajr 19.5°, Isha 17.5°.
+    Egyptian,
+    /// University of Islamic Sciences, Karachi: Fajr 18°, Isha 18°.
+    Karachi,
+    /// Umm al-Qura University, Makkah: Fajr 18.5°, Isha 90 min after Maghrib.
+    UmmAlQura,
+    /// Institute of Geophysics, University of Tehran: Fajr 17.7°, Isha 14°, Maghrib 4.5°.
+    Tehran,
+    /// Shia Ithna Ashari (Leva Institute, Qum): Fajr 16°, Isha 14°, Maghrib 4°.
+    Jafari,
+}
+
+impl CalculationMethod {
+    /// Every preset, in display order.
+    pub const ALL: [CalculationMethod; 7] = [
+        CalculationMethod::MWL,
+        CalculationMethod::ISNA,
+        CalculationMethod::Egyptian,
+        CalculationMethod::Karachi,
+        CalculationMethod::UmmAlQura,
+        CalculationMethod::Tehran,
+        CalculationMethod::Jafari,
+    ];
+
+    /// Fajr depression angle in degrees.
+    pub fn fajr_angle(&self) -> f64 {
+        match self {
+            Self::MWL => 18.0,
+            Self::ISNA => 15.0,
+            Self::Egyptian => 19.5,
+            Self::Karachi => 18.0,
+            Self::UmmAlQura => 18.5,
+            Self::Tehran => 17.7,
+            Self::Jafari => 16.0,
+        }
+    }
+
+    pub fn isha_rule(&self) -> IshaRule {
+        match self {
+            Self::MWL => IshaRule::Angle(17.0),
+            Self::ISNA => IshaRule::Angle(15.0),
+            Self::Egyptian => IshaRule::Angle(17.5),
+            Self::Karachi => IshaRule::Angle(18.0),
+            Self::UmmAlQura => IshaRule::FixedAfterMaghrib(90),
+            Self::Tehran => IshaRule::Angle(14.0),
+            Self::Jafari => IshaRule::Angle(14.0),
+        }
+    }
+
+    /// Umm al-Qura moves Isha to 120 minutes after Maghrib in Ramadan.
+    pub fn ramadan_isha_minutes(&self) -> Option<u32> {
+        match self {
+            Self::UmmAlQura => Some(120),
+            _ => None,
+        }
+    }
+
+    pub fn maghrib_rule(&self) -> MaghribRule {
+        match self {
+            Self::Tehran => MaghribRule::Angle(4.5),
+            Self::Jafari => MaghribRule::Angle(4.0),
+            _ => MaghribRule::Sunset,
+        }
+    }
+
+    /// Full name of the issuing authority.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::MWL => "Muslim World League",
+            Self::ISNA => "Islamic Society of North America",
+            Self::Egyptian => "Egyptian General Authority of Survey",
+            Self::Karachi => "University of Islamic Sciences, Karachi",
+            Self::UmmAlQura => "Umm al-Qura University, Makkah",
+            Self::Tehran => "Institute of Geophysics, University of Tehran",
+            Self::Jafari => "Shia Ithna Ashari, Leva Institute, Qum",
+        }
+    }
+}
+
+impl PrayerMethod for CalculationMethod {
+    fn fajr_angle(&self) -> f64 {
+        CalculationMethod::fajr_angle(self)
+    }
+
+    fn isha_rule(&self) -> IshaRule {
+        CalculationMethod::isha_rule(self)
+    }
+
+    fn maghrib_rule(&self) -> MaghribRule {
+        CalculationMethod::maghrib_rule(self)
+    }
+
+    fn ramadan_isha_minutes(&self) -> Option<u32> {
+        CalculationMethod::ramadan_isha_minutes(self)
+    }
+}
+
+impl fmt::Display for CalculationMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MWL => write!(f, "MWL"),
+            Self::ISNA => write!(f, "ISNA"),
+            Self::Egyptian => write!(f, "Egyptian"),
+            Self::Karachi => write!(f, "Karachi"),
+            Self::UmmAlQura => write!(f, "UmmAlQura"),
+            Self::Tehran => write!(f, "Tehran"),
+            Self::Jafari => write!(f, "Jafari"),
+        }
+    }
+}
+
+impl FromStr for CalculationMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_method(s)
+    }
+}
+
+/// Fold a user-typed option name to a lookup key: trimmed, lowercase, and
+/// without spaces, dashes or underscores ("Umm al-Qura" → "ummalqura").
+pub fn normalize_key(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Parse a method name or common alias, for both the CLI and the server.
+pub fn parse_method(s: &str) -> Result<CalculationMethod, String> {
+    match normalize_key(s).as_str() {
+        "mwl" | "muslimworldleague" => Ok(CalculationMethod::MWL),
+        "isna" | "northamerica" => Ok(CalculationMethod::ISNA),
+        "egyptian" | "egypt" => Ok(CalculationMethod::Egyptian),
+        "karachi" => Ok(CalculationMethod::Karachi),
+        "ummalqura" | "makkah" | "mecca" => Ok(CalculationMethod::UmmAlQura),
+        "tehran" => Ok(CalculationMethod::Tehran),
+        "jafari" | "shia" | "ithnaashari" => Ok(CalculationMethod::Jafari),
+        _ => Err(format!(
+            "Unknown method '{}'. Use one of: mwl, isna, egyptian, karachi, ummalqura, tehran, jafari.",
+            s.trim()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_round_trip() {
+        for method in CalculationMethod::ALL {
+            let parsed: CalculationMethod = method.to_string().parse().unwrap();
+            assert_eq!(parsed, method);
+        }
+        assert!("unknown".parse::<CalculationMethod>().is_err());
+    }
+
+    #[test]
+    fn test_method_spacing_case_and_aliases() {
+        let cases = [
+            ("MWL ", CalculationMethod::MWL),
+            ("Muslim World League", CalculationMethod::MWL),
+            (" isna", CalculationMethod::ISNA),
+            ("North-America", CalculationMethod::ISNA),
+            ("EGYPT", CalculationMethod::Egyptian),
+            ("Egyptian", CalculationMethod::Egyptian),
+            ("karachi\t", CalculationMethod::Karachi),
+            ("Umm al Qura", CalculationMethod::UmmAlQura),
+            ("umm-al-qura", CalculationMethod::UmmAlQura),
+            ("umm_al_qura", CalculationMethod::UmmAlQura),
+            ("Makkah", CalculationMethod::UmmAlQura),
+            ("Tehran", CalculationMethod::Tehran),
+            ("JAFARI", CalculationMethod::Jafari),
+            ("Ithna Ashari", CalculationMethod::Jafari),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_method(input), Ok(expected), "{:?}", input);
+        }
+        let err = parse_method("  hanbali ").unwrap_err();
+        assert!(err.contains("'hanbali'"), "{}", err);
+    }
+
+    #[test]
+    fn test_method_rules() {
+        assert_eq!(CalculationMethod::default(), CalculationMethod::MWL);
+        assert_eq!(CalculationMethod::UmmAlQura.isha_rule(), IshaRule::FixedAfterMaghrib(90));
+        assert_eq!(CalculationMethod::UmmAlQura.ramadan_isha_minutes(), Some(120));
+        assert_eq!(CalculationMethod::MWL.ramadan_isha_minutes(), None);
+        assert_eq!(CalculationMethod::Tehran.maghrib_rule(), MaghribRule::Angle(4.5));
+        assert_eq!(CalculationMethod::ISNA.maghrib_rule(), MaghribRule::Sunset);
+    }
+}
@@ -0,0 +1,233 @@
+//! Webhook notifier — posts each day's schedule to a URL shortly after
+//! local midnight (`polaris serve-notify`).
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+
+use crate::config::Config;
+use crate::location::ResolvedLocation;
+use crate::solver::Solver;
+use crate::timezone;
+
+/// How long after local midnight the daily post is sent.
+const POST_DELAY_MINUTES: i64 = 5;
+
+/// A webhook delivery that failed after all retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyError {
+    /// The receiver answered with a non-success status.
+    Status(u16),
+    /// The request never got an answer (DNS, connect, timeout).
+    Transport(String),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Status(code) => write!(f, "webhook returned HTTP {}", code),
+            NotifyError::Transport(e) => write!(f, "webhook unreachable: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+/// Posts a location's daily schedule as JSON to a webhook.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    resolved: ResolvedLocation,
+    webhook: String,
+    config: Config,
+    retries: u32,
+    retry_delay: std::time::Duration,
+}
+
+impl Notifier {
+    pub fn new(resolved: ResolvedLocation, webhook: impl Into<String>) -> Self {
+        Self {
+            resolved,
+            webhook: webhook.into(),
+            config: Config::default(),
+            retries: 3,
+            retry_delay: std::time::Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Retry a failed post `retries` more times, `delay` apart.
+    pub fn with_retries(mut self, retries: u32, delay: std::time::Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    fn tz(&self) -> Tz {
+        timezone::parse_tz(&self.resolved.tz).unwrap_or(chrono_tz::UTC)
+    }
+
+    /// The schedule for local `date`, as posted.
+    pub fn body(&self, date: NaiveDate) -> String {
+        let output = Solver::from_resolved(&self.resolved)
+            .with_config(self.config)
+            .solve_with_info(date, false, false, Some(&self.resolved));
+        serde_json::to_string(&output).expect("SolverOutput serializes")
+    }
+
+    /// Post the schedule for local `date`, retrying on failure. Returns the
+    /// receiver's status code. Blocks the calling thread; async callers use
+    /// [`Notifier::post_day_async`].
+    pub fn post_day(&self, date: NaiveDate) -> Result<u16, NotifyError> {
+        let body = self.body(date);
+        let mut attempt = 0;
+        loop {
+            let err = match self.post_once(&body) {
+                Ok(status) => return Ok(status),
+                Err(e) => e,
+            };
+            if attempt >= self.retries {
+                return Err(err);
+            }
+            attempt += 1;
+            eprintln!("  [notify] {} (retry {}/{})", err, attempt, self.retries);
+            std::thread::sleep(self.retry_delay);
+        }
+    }
+
+    /// [`Notifier::post_day`] for async callers: the solve and each HTTP
+    /// attempt run on the blocking pool, and retry delays sleep on the
+    /// runtime instead of holding a thread.
+    pub async fn post_day_async(&self, date: NaiveDate) -> Result<u16, NotifyError> {
+        let notifier = self.clone();
+        let body = tokio::task::spawn_blocking(move || notifier.body(date))
+            .await
+            .map_err(|e| NotifyError::Transport(e.to_string()))?;
+        let mut attempt = 0;
+        loop {
+            let (notifier, body) = (self.clone(), body.clone());
+            let err = match tokio::task::spawn_blocking(move || notifier.post_once(&body)).await {
+                Ok(Ok(status)) => return Ok(status),
+                Ok(Err(e)) => e,
+                Err(e) => NotifyError::Transport(e.to_string()),
+            };
+            if attempt >= self.retries {
+                return Err(err);
+            }
+            attempt += 1;
+            eprintln!("  [notify] {} (retry {}/{})", err, attempt, self.retries);
+            tokio::time::sleep(self.retry_delay).await;
+        }
+    }
+
+    /// One blocking POST of `body` to the webhook.
+    fn post_once(&self, body: &str) -> Result<u16, NotifyError> {
+        let result = ureq::post(&self.webhook)
+            .set("Content-Type", "application/json")
+            .set("User-Agent", concat!("polaris-chronos/", env!("CARGO_PKG_VERSION")))
+            .send_string(body);
+        match result {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(code, _)) => Err(NotifyError::Status(code)),
+            Err(e) => Err(NotifyError::Transport(e.to_string())),
+        }
+    }
+
+    /// When the next daily post is due: a few minutes past the next local midnight.
+    pub fn next_run(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let tz = self.tz();
+        let today = now.with_timezone(&tz).date_naive();
+        let post_time = NaiveTime::MIN + Duration::minutes(POST_DELAY_MINUTES);
+
+        [today, today + Duration::days(1)].into_iter()
+            .filter_map(|d| tz.from_local_datetime(&d.and_time(post_time)).earliest())
+            .map(|t| t.with_timezone(&Utc))
+            .find(|t| *t > now)
+            .unwrap_or(now + Duration::days(1))
+    }
+
+    /// Post today's schedule now, then once per local day, forever.
+    pub async fn run(self) {
+        let tz = self.tz();
+        let mut date = Utc::now().with_timezone(&tz).date_naive();
+        loop {
+            match self.post_day_async(date).await {
+                Ok(status) => eprintln!("  [notify] {} {} -> HTTP {}", self.resolved.name, date, status),
+                Err(e) => eprintln!("  [notify] {} {} failed: {}", self.resolved.name, date, e),
+            }
+
+            let now = Utc::now();
+            let next = self.next_run(now);
+            tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+            date = Utc::now().with_timezone(&tz).date_naive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::LocationResolver;
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    fn stockholm() -> ResolvedLocation {
+        LocationResolver::from_manual(59.3293, 18.0686, Some("Europe/Stockholm"))
+    }
+
+    #[tokio::test]
+    async fn test_single_tick_posts_schedule() {
+        let received: Arc<Mutex<Vec<String>>> = Arc::default();
+        let sink = received.clone();
+        let app = Router::new().route("/hook", post(move |body: String| async move {
+            sink.lock().unwrap().push(body);
+            "ok"
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let notifier = Notifier::new(stockholm(), url);
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let status = tokio::task::spawn_blocking(move || notifier.post_day(date)).await.unwrap();
+        assert_eq!(status, Ok(200));
+
+        let bodies = received.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        let json: serde_json::Value = serde_json::from_str(&bodies[0]).unwrap();
+        println!("{}", json["events"]);
+        assert_eq!(json["date"], "2026-03-20");
+        assert_eq!(json["location"]["timezone"], "Europe/Stockholm");
+        assert!(json["events"]["fajr"]["time"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_post_fails_after_retries() {
+        let app = Router::new().route("/hook", post(|| async { axum::http::StatusCode::SERVICE_UNAVAILABLE }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let notifier = Notifier::new(stockholm(), url).with_retries(1, std::time::Duration::ZERO);
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let result = notifier.post_day_async(date).await;
+        assert_eq!(result, Err(NotifyError::Status(503)));
+    }
+
+    #[test]
+    fn test_next_run_after_local_midnight() {
+        let notifier = Notifier::new(stockholm(), "http://localhost/hook");
+        // 22:00 UTC on 2026-03-20 is 23:00 in Stockholm (CET)
+        let now = Utc.with_ymd_and_hms(2026, 3, 20, 22, 0, 0).unwrap();
+        let next = notifier.next_run(now);
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 3, 20, 23, 5, 0).unwrap());
+
+        // Just after the post time, the next one is a day later
+        let next = notifier.next_run(next + Duration::seconds(1));
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 3, 21, 23, 5, 0).unwrap());
+    }
+}
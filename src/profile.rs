@@ -0,0 +1,68 @@
+//! Lightweight benchmark of the schedule computation.
+//!
+//! Used by `polaris profile` to give a reproducible performance signal:
+//! wall-clock timings per day plus the number of solar position evaluations.
+
+use crate::schedule::{self, GapStrategy};
+use crate::solar;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use std::time::Instant;
+
+/// Timing summary for a run of consecutive days.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileReport {
+    pub days: u32,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub p95_ms: f64,
+    /// `solar_position` evaluations during the run (0 without the `eval-counter` feature).
+    /// The counter is process-wide, so concurrent work is included.
+    pub evaluations: u64,
+}
+
+/// Time `compute_schedule` for `days` consecutive days starting at `start`.
+pub fn profile_schedule(lat: f64, lon: f64, start: NaiveDate, days: u32, strategy: GapStrategy) -> ProfileReport {
+    let evals_before = solar::evaluation_count();
+    let mut timings: Vec<f64> = Vec::with_capacity(days as usize);
+
+    for i in 0..days {
+        let date = start + Duration::days(i as i64);
+        let t0 = Instant::now();
+        let _ = schedule::compute_schedule(date, lat, lon, strategy);
+        timings.push(t0.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    let evaluations = solar::evaluation_count() - evals_before;
+    let total_ms: f64 = timings.iter().sum();
+    let mean_ms = if days > 0 { total_ms / days as f64 } else { 0.0 };
+
+    timings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let p95_ms = if timings.is_empty() {
+        0.0
+    } else {
+        let idx = ((timings.len() as f64 * 0.95).ceil() as usize).clamp(1, timings.len()) - 1;
+        timings[idx]
+    };
+
+    ProfileReport { days, total_ms, mean_ms, p95_ms, evaluations }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_reports_evaluations() {
+        let start = NaiveDate::from_ymd_opt(2025, 12, 1).unwrap();
+        let report = profile_schedule(78.2232, 15.6267, start, 3, GapStrategy::Projected45);
+        println!("{:?}", report);
+
+        assert_eq!(report.days, 3);
+        assert!(report.total_ms > 0.0);
+        assert!(report.p95_ms >= report.mean_ms * 0.5);
+        // eval-counter is enabled for tests through the self dev-dependency.
+        // At least one 30-second day scan (2880 samples) per day
+        assert!(report.evaluations >= 3 * 2880, "got {} evaluations", report.evaluations);
+    }
+}
@@ -0,0 +1,137 @@
+//! Qibla direction — great-circle bearing and distance to the Kaaba.
+
+use serde::Serialize;
+use std::f64::consts::PI;
+
+const DEG: f64 = PI / 180.0;
+
+/// Kaaba coordinates (Masjid al-Haram, Mecca).
+pub const KAABA_LAT: f64 = 21.4225;
+pub const KAABA_LON: f64 = 39.8262;
+
+/// The 16 compass points, clockwise from north.
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+    "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+];
+
+/// Mean Earth radius (IUGG), for great-circle distances.
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Direction and distance to the Kaaba from an observer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Qibla {
+    /// Degrees clockwise from true north [0, 360).
+    pub bearing: f64,
+    pub distance_km: f64,
+}
+
+impl Qibla {
+    pub fn from_location(lat: f64, lon: f64) -> Self {
+        Self { bearing: qibla_bearing(lat, lon), distance_km: qibla_distance_km(lat, lon) }
+    }
+}
+
+/// Initial great-circle bearing from the observer to the Kaaba,
+/// in degrees clockwise from true north [0, 360).
+pub fn qibla_bearing(lat: f64, lon: f64) -> f64 {
+    let phi1 = lat * DEG;
+    let phi2 = KAABA_LAT * DEG;
+    let dlon = (KAABA_LON - lon) * DEG;
+
+    let y = dlon.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlon.cos();
+    (y.atan2(x) / DEG).rem_euclid(360.0)
+}
+
+/// Great-circle (haversine) distance from the observer to the Kaaba, in km.
+pub fn qibla_distance_km(lat: f64, lon: f64) -> f64 {
+    let dphi = (KAABA_LAT - lat) * DEG;
+    let dlon = (KAABA_LON - lon) * DEG;
+    let a = (dphi / 2.0).sin().powi(2)
+        + (lat * DEG).cos() * (KAABA_LAT * DEG).cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Nearest of the 16 compass points for a bearing in degrees ("NNE").
+pub fn compass_point(bearing: f64) -> &'static str {
+    let index = (bearing.rem_euclid(360.0) / 22.5).round() as usize % 16;
+    COMPASS_POINTS[index]
+}
+
+/// Check whether a compass heading points at the qibla.
+///
+/// Returns `(aligned, signed_error)` where `signed_error` is the shortest
+/// angular difference `heading - bearing` in (-180, 180]; positive means the
+/// heading is clockwise of the qibla.
+pub fn qibla_alignment(lat: f64, lon: f64, heading_deg: f64, tolerance_deg: f64) -> (bool, f64) {
+    let bearing = qibla_bearing(lat, lon);
+    let mut error = (heading_deg - bearing).rem_euclid(360.0);
+    if error > 180.0 {
+        error -= 360.0;
+    }
+    (error.abs() <= tolerance_deg.abs(), error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cairo_qibla_bearing() {
+        let bearing = qibla_bearing(30.0444, 31.2357);
+        println!("Cairo qibla: {:.2}°", bearing);
+        assert!((bearing - 136.1).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_bearings_and_distances() {
+        let new_york = Qibla::from_location(40.7128, -74.0060);
+        let jakarta = Qibla::from_location(-6.2088, 106.8456);
+        println!("New York: {:?}\nJakarta: {:?}", new_york, jakarta);
+        assert!((new_york.bearing - 58.5).abs() < 0.5);
+        assert!((jakarta.bearing - 295.1).abs() < 0.5);
+        assert!((new_york.distance_km - 10_300.0).abs() < 50.0);
+        assert!((jakarta.distance_km - 7_920.0).abs() < 50.0);
+        assert!(qibla_distance_km(KAABA_LAT, KAABA_LON) < 1e-6);
+    }
+
+    #[test]
+    fn test_compass_points() {
+        assert_eq!(compass_point(0.0), "N");
+        assert_eq!(compass_point(11.0), "N");
+        assert_eq!(compass_point(11.3), "NNE");
+        assert_eq!(compass_point(58.5), "ENE");
+        assert_eq!(compass_point(295.2), "WNW");
+        assert_eq!(compass_point(359.0), "N");
+        assert_eq!(compass_point(-90.0), "W");
+    }
+
+    #[test]
+    fn test_cairo_alignment() {
+        let (lat, lon) = (30.0444, 31.2357);
+        let bearing = qibla_bearing(lat, lon);
+
+        let (aligned, error) = qibla_alignment(lat, lon, bearing, 5.0);
+        assert!(aligned);
+        assert!(error.abs() < 1e-9);
+
+        let (aligned, error) = qibla_alignment(lat, lon, bearing + 20.0, 5.0);
+        assert!(!aligned);
+        assert!((error - 20.0).abs() < 1e-9);
+
+        let (_, error) = qibla_alignment(lat, lon, bearing - 20.0, 5.0);
+        assert!((error + 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_alignment_wraps_at_north() {
+        // Bearing just east of north; a heading just west of north is a small negative error
+        let (lat, lon) = (-10.0, 39.0);
+        let bearing = qibla_bearing(lat, lon);
+        assert!(!(10.0..=350.0).contains(&bearing), "bearing {}", bearing);
+        let (aligned, error) = qibla_alignment(lat, lon, (bearing - 3.0).rem_euclid(360.0), 5.0);
+        assert!(aligned);
+        assert!((error + 3.0).abs() < 1e-9);
+    }
+}
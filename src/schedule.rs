@@ -4,27 +4,117 @@
 //! horizon, sunrise and sunset are None. Virtual alternatives are provided
 //! separately with explicit method labels.
 
+use crate::config::{Adjustments, Config};
+use crate::method::{IshaRule, MaghribRule, PrayerMethod};
 use crate::solar::{self, AltitudeSample, HORIZON_ANGLE};
-use chrono::NaiveDate;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 use serde::Serialize;
 use std::f64::consts::PI;
 
 const DEG: f64 = PI / 180.0;
 
-const FAJR_ANGLE: f64 = -18.0;  // Astronomical twilight (Muslim World League)
-const ISHA_ANGLE: f64 = -17.0;  // Isha twilight angle
+/// Stand-in Isha angle for placing a virtual Isha when a fixed-interval
+/// rule has no Maghrib to anchor to.
+const FALLBACK_ISHA_ANGLE: f64 = -17.0;
+
+/// Fajr target altitude (negative degrees) for a method.
+fn fajr_altitude(method: &dyn PrayerMethod) -> f64 {
+    -method.fajr_angle()
+}
+
+/// Isha target altitude used for angular placement.
+fn isha_altitude(method: &dyn PrayerMethod) -> f64 {
+    match method.isha_rule() {
+        IshaRule::Angle(a) => -a,
+        IshaRule::FixedAfterMaghrib(_) => FALLBACK_ISHA_ANGLE,
+    }
+}
 
 /// Strategy for handling missing events in polar states.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
 pub enum GapStrategy {
     /// Returns None for missing events (science mode).
     Strict,
     /// Projects durations from 45° latitude (user mode).
+    #[default]
     Projected45,
+    /// Picks a concrete strategy from the latitude (see `resolve`).
+    Auto,
+    /// Projects like `Projected45`, then bounds Fajr/Isha by the
+    /// moonsighting.com seasonal minutes from sunrise/sunset.
+    MoonsightingSeason,
+}
+
+impl std::str::FromStr for GapStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_strategy(s)
+    }
+}
+
+/// Parse a strategy name or alias ("Projected 45", "STRICT "), for both
+/// the CLI and the server.
+pub fn parse_strategy(s: &str) -> Result<GapStrategy, String> {
+    match crate::method::normalize_key(s).as_str() {
+        "strict" | "science" => Ok(GapStrategy::Strict),
+        "projected45" | "projected" | "projection" => Ok(GapStrategy::Projected45),
+        "auto" => Ok(GapStrategy::Auto),
+        "moonsightingseason" | "moonsighting" | "seasonal" => Ok(GapStrategy::MoonsightingSeason),
+        _ => Err(format!(
+            "Unknown strategy '{}'. Use 'strict', 'projected45', 'auto' or 'moonsighting'.",
+            s.trim(),
+        )),
+    }
 }
 
-impl Default for GapStrategy {
-    fn default() -> Self { Self::Projected45 }
+/// Latitude from which `Auto` places Fajr/Isha with the angle-based rule.
+const AUTO_HIGH_LATITUDE: f64 = 48.0;
+
+/// Latitude from which `Auto` projects missing sunrise/sunset.
+const AUTO_PROJECTED_LATITUDE: f64 = 60.0;
+
+impl GapStrategy {
+    /// Every strategy, in display order.
+    pub const ALL: [GapStrategy; 4] = [
+        GapStrategy::Strict,
+        GapStrategy::Projected45,
+        GapStrategy::Auto,
+        GapStrategy::MoonsightingSeason,
+    ];
+
+    /// One-line description for settings UIs.
+    pub fn description(&self) -> &'static str {
+        match self {
+            GapStrategy::Strict => "Leave physically missing events empty (science mode)",
+            GapStrategy::Projected45 => "Project missing sunrise/sunset from an adaptive reference latitude",
+            GapStrategy::Auto => "Strict below 48° latitude, angle-based Fajr/Isha to 60°, projected above",
+            GapStrategy::MoonsightingSeason => "Projected, with Fajr/Isha from moonsighting.com seasonal minutes",
+        }
+    }
+
+    /// The concrete strategy used at `lat`. `Auto` becomes Strict below
+    /// 60°, where sunrise and sunset still happen every day, and
+    /// Projected45 above, where polar states begin.
+    pub fn resolve(self, lat: f64) -> GapStrategy {
+        match self {
+            GapStrategy::Auto if lat.abs() < AUTO_PROJECTED_LATITUDE => GapStrategy::Strict,
+            GapStrategy::Auto => GapStrategy::Projected45,
+            concrete => concrete,
+        }
+    }
+
+    /// The Fajr/Isha rule used at `lat`. Between 48° and 60° `Auto`
+    /// overrides `configured` with `AngleBased`, since twilight persists
+    /// there around the summer solstice; otherwise `configured` applies.
+    pub fn resolve_high_latitude_rule(self, lat: f64, configured: HighLatitudeRule) -> HighLatitudeRule {
+        match self {
+            GapStrategy::Auto if (AUTO_HIGH_LATITUDE..AUTO_PROJECTED_LATITUDE).contains(&lat.abs()) => {
+                HighLatitudeRule::AngleBased
+            }
+            _ => configured,
+        }
+    }
 }
 
 impl std::fmt::Display for GapStrategy {
@@ -32,6 +122,8 @@ impl std::fmt::Display for GapStrategy {
         match self {
             GapStrategy::Strict => write!(f, "Strict"),
             GapStrategy::Projected45 => write!(f, "Projected45"),
+            GapStrategy::Auto => write!(f, "Auto"),
+            GapStrategy::MoonsightingSeason => write!(f, "MoonsightingSeason"),
         }
     }
 }
@@ -61,22 +153,84 @@ pub struct PrayerEvent {
     /// Projection note (only set for Projected/special events).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
-    /// True if this event's local time falls on the next calendar day.
-    #[serde(skip_serializing_if = "std::ops::Not::not")]
-    pub next_day: bool,
+    /// Calendar-day offset of the local time relative to the requested date:
+    /// -1 (previous day), 0 (same day), or +1 (next day).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub day_offset: i8,
+    /// Sidereal time and solar hour angle at the event (opt-in, Standard events only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidereal: Option<SiderealInfo>,
+    /// Sun's azimuth in degrees clockwise from north at the event (opt-in, Standard events only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub azimuth: Option<f64>,
+    /// Which wave-mapping path placed a Virtual Fajr/Isha, and how well it fits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wave_fit: Option<WaveFit>,
+}
+
+/// The path `wave_mapped_time` took to place a twilight event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WaveFitMethod {
+    /// The sun really crosses the target altitude.
+    DirectCrossing,
+    /// The target was rescaled onto the day's altitude wave and crossed there.
+    NormalizedWave,
+    /// No crossing on the wave either; time placed proportionally between nadir and peak.
+    Proportional,
+}
+
+/// How a wave-mapped time was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WaveFit {
+    pub method: WaveFitMethod,
+    /// Degrees between the target altitude and the sun's actual altitude at
+    /// the returned time (≈0 for a direct crossing).
+    pub residual: f64,
+}
+
+/// Where the sky stood at an event, for cross-checking with telescope software.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SiderealInfo {
+    /// Local sidereal time in degrees (0..360).
+    pub lst_degrees: f64,
+    /// Local sidereal time as HH:MM:SS.
+    pub lst: String,
+    /// The sun's local hour angle in degrees (-180..180, negative before noon).
+    pub hour_angle: f64,
+}
+
+impl SiderealInfo {
+    /// Sidereal time and solar hour angle at a UTC instant.
+    pub fn at(dt: &NaiveDateTime, lat: f64, lon: f64) -> Self {
+        let lst = solar::local_sidereal_time(solar::julian_date(dt), lon);
+        Self {
+            lst_degrees: lst,
+            lst: solar::seconds_to_hms(lst * 240.0),
+            hour_angle: solar::solar_position(dt, lat, lon).hour_angle,
+        }
+    }
+}
+
+fn is_zero(v: &i8) -> bool {
+    *v == 0
 }
 
 impl PrayerEvent {
     fn standard(secs: f64) -> Self {
-        Self { time: Some(solar::seconds_to_hms(secs)), method: EventMethod::Standard, confidence: 1.0, note: None, next_day: false }
+        Self { time: Some(solar::seconds_to_hms(secs)), method: EventMethod::Standard, confidence: 1.0, note: None, day_offset: 0, sidereal: None, azimuth: None, wave_fit: None }
     }
 
     fn virtual_event(secs: f64) -> Self {
-        Self { time: Some(solar::seconds_to_hms(secs)), method: EventMethod::Virtual, confidence: 0.7, note: None, next_day: false }
+        Self { time: Some(solar::seconds_to_hms(secs)), method: EventMethod::Virtual, confidence: 0.7, note: None, day_offset: 0, sidereal: None, azimuth: None, wave_fit: None }
+    }
+
+    /// A Virtual event carrying the wave-mapping fit that placed it.
+    fn wave_mapped((secs, fit): (f64, WaveFit)) -> Self {
+        Self { wave_fit: Some(fit), ..Self::virtual_event(secs) }
     }
 
     fn none() -> Self {
-        Self { time: Option::None, method: EventMethod::None, confidence: 0.0, note: None, next_day: false }
+        Self { time: Option::None, method: EventMethod::None, confidence: 0.0, note: None, day_offset: 0, sidereal: None, azimuth: None, wave_fit: None }
     }
 
     fn projected(secs: f64, note: &str) -> Self {
@@ -85,7 +239,10 @@ impl PrayerEvent {
             method: EventMethod::Projected,
             confidence: 0.5,
             note: Some(note.to_string()),
-            next_day: false,
+            day_offset: 0,
+            sidereal: None,
+            azimuth: None,
+            wave_fit: None,
         }
     }
 
@@ -132,6 +289,19 @@ pub struct Schedule {
     pub state: DayState,
     pub events: Events,
     pub solar: SolarInfo,
+    /// The sun sets but never reaches the Fajr/Isha depression, so those
+    /// times are estimated from the altitude wave.
+    pub twilight_persists: bool,
+    /// Times when voluntary prayer is discouraged (UTC).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub makruh_windows: Vec<MakruhWindow>,
+    /// Civil, nautical and astronomical dawn and dusk (UTC), only when
+    /// `Config::include_twilight_times` asks for them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twilight_times: Option<TwilightTimes>,
+    /// Numerical caveats about this day (e.g. a very low Asr sun).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -144,12 +314,246 @@ pub struct Events {
     pub isha: PrayerEvent,
 }
 
+/// The six core daily events, in chronological order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum PrayerKind {
+    Fajr,
+    Sunrise,
+    Dhuhr,
+    Asr,
+    Maghrib,
+    Isha,
+}
+
+impl PrayerKind {
+    /// Every kind, in chronological order.
+    pub const ALL: [PrayerKind; 6] = [
+        PrayerKind::Fajr,
+        PrayerKind::Sunrise,
+        PrayerKind::Dhuhr,
+        PrayerKind::Asr,
+        PrayerKind::Maghrib,
+        PrayerKind::Isha,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrayerKind::Fajr => "Fajr",
+            PrayerKind::Sunrise => "Sunrise",
+            PrayerKind::Dhuhr => "Dhuhr",
+            PrayerKind::Asr => "Asr",
+            PrayerKind::Maghrib => "Maghrib",
+            PrayerKind::Isha => "Isha",
+        }
+    }
+}
+
+impl std::fmt::Display for PrayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for PrayerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PrayerKind::ALL.into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown prayer '{}'. Use fajr, sunrise, dhuhr, asr, maghrib or isha.", s))
+    }
+}
+
+impl Events {
+    pub fn get(&self, kind: PrayerKind) -> &PrayerEvent {
+        match kind {
+            PrayerKind::Fajr => &self.fajr,
+            PrayerKind::Sunrise => &self.sunrise,
+            PrayerKind::Dhuhr => &self.dhuhr,
+            PrayerKind::Asr => &self.asr,
+            PrayerKind::Maghrib => &self.maghrib,
+            PrayerKind::Isha => &self.isha,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: PrayerKind) -> &mut PrayerEvent {
+        match kind {
+            PrayerKind::Fajr => &mut self.fajr,
+            PrayerKind::Sunrise => &mut self.sunrise,
+            PrayerKind::Dhuhr => &mut self.dhuhr,
+            PrayerKind::Asr => &mut self.asr,
+            PrayerKind::Maghrib => &mut self.maghrib,
+            PrayerKind::Isha => &mut self.isha,
+        }
+    }
+
+    /// Events paired with their kind, in chronological order.
+    pub fn iter(&self) -> impl Iterator<Item = (PrayerKind, &PrayerEvent)> {
+        PrayerKind::ALL.into_iter().map(move |kind| (kind, self.get(kind)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (PrayerKind, &mut PrayerEvent)> {
+        let Events { fajr, sunrise, dhuhr, asr, maghrib, isha } = self;
+        [
+            (PrayerKind::Fajr, fajr),
+            (PrayerKind::Sunrise, sunrise),
+            (PrayerKind::Dhuhr, dhuhr),
+            (PrayerKind::Asr, asr),
+            (PrayerKind::Maghrib, maghrib),
+            (PrayerKind::Isha, isha),
+        ].into_iter()
+    }
+
+    /// Build a new set of events by transforming each one.
+    pub fn map(&self, mut f: impl FnMut(PrayerKind, &PrayerEvent) -> PrayerEvent) -> Events {
+        Events {
+            fajr: f(PrayerKind::Fajr, &self.fajr),
+            sunrise: f(PrayerKind::Sunrise, &self.sunrise),
+            dhuhr: f(PrayerKind::Dhuhr, &self.dhuhr),
+            asr: f(PrayerKind::Asr, &self.asr),
+            maghrib: f(PrayerKind::Maghrib, &self.maghrib),
+            isha: f(PrayerKind::Isha, &self.isha),
+        }
+    }
+}
+
+/// Combined (jam') prayer times for travellers, at the earlier member's time.
+///
+/// A pair is only combined when both of its prayers exist.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CombinedTimes {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dhuhr_asr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maghrib_isha: Option<String>,
+}
+
+impl CombinedTimes {
+    pub fn from_events(events: &Events) -> Self {
+        let pair = |first: &PrayerEvent, second: &PrayerEvent| match (&first.time, &second.time) {
+            (Some(t), Some(_)) => Some(t.clone()),
+            _ => None,
+        };
+        Self {
+            dhuhr_asr: pair(&events.dhuhr, &events.asr),
+            maghrib_isha: pair(&events.maghrib, &events.isha),
+        }
+    }
+}
+
+// ─── Makruh windows ───────────────────────────────────────────────
+
+/// Minutes after sunrise and before sunset in which voluntary prayer is discouraged.
+const MAKRUH_HORIZON_MINUTES: f64 = 15.0;
+
+/// Minutes before the zenith (Dhuhr) in which voluntary prayer is discouraged.
+const MAKRUH_ZENITH_MINUTES: f64 = 5.0;
+
+/// Why a makruh window applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MakruhReason {
+    Sunrise,
+    Zenith,
+    Sunset,
+}
+
+/// A span in which voluntary (nafl) prayer is discouraged.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MakruhWindow {
+    pub start: String,
+    pub end: String,
+    pub reason: MakruhReason,
+}
+
+impl MakruhWindow {
+    fn new(start: f64, end: f64, reason: MakruhReason) -> Self {
+        Self { start: solar::seconds_to_hms(start), end: solar::seconds_to_hms(end), reason }
+    }
+}
+
+/// Windows after sunrise, before the zenith and before sunset, in that order.
+///
+/// Each needs its anchor: without a sunrise or sunset (polar Strict days)
+/// only the zenith window remains. `sunset` is the horizon crossing, which
+/// differs from Maghrib for angle-based Maghrib methods.
+fn makruh_windows(events: &Events, sunset: Option<f64>) -> Vec<MakruhWindow> {
+    let secs = |e: &PrayerEvent| e.time.as_deref().map(hms_to_seconds);
+    let horizon = MAKRUH_HORIZON_MINUTES * 60.0;
+    let zenith = MAKRUH_ZENITH_MINUTES * 60.0;
+
+    let mut windows = Vec::new();
+    if let Some(sunrise) = secs(&events.sunrise) {
+        windows.push(MakruhWindow::new(sunrise, sunrise + horizon, MakruhReason::Sunrise));
+    }
+    if let Some(dhuhr) = secs(&events.dhuhr) {
+        windows.push(MakruhWindow::new(dhuhr - zenith, dhuhr, MakruhReason::Zenith));
+    }
+    if let Some(sunset) = sunset {
+        windows.push(MakruhWindow::new(sunset - horizon, sunset, MakruhReason::Sunset));
+    }
+    windows
+}
+
+// ─── Twilight times ───────────────────────────────────────────────
+
+/// Depression of the sun at the end of civil, nautical and astronomical twilight.
+pub const TWILIGHT_ANGLES: [f64; 3] = [6.0, 12.0, 18.0];
+
+/// Dawn and dusk at -6°, -12° and -18°, each `None` where the sun never
+/// gets that far below the horizon.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TwilightTimes {
+    pub civil_dawn: Option<String>,
+    pub nautical_dawn: Option<String>,
+    pub astronomical_dawn: Option<String>,
+    pub civil_dusk: Option<String>,
+    pub nautical_dusk: Option<String>,
+    pub astronomical_dusk: Option<String>,
+}
+
+impl TwilightTimes {
+    /// Crossings of each twilight angle in a day's altitude scan.
+    pub fn from_samples(samples: &[AltitudeSample]) -> Self {
+        let [civil, nautical, astronomical] = TWILIGHT_ANGLES.map(|a| {
+            let at = |ascending| solar::find_crossing(samples, -a, ascending).map(solar::seconds_to_hms);
+            (at(true), at(false))
+        });
+        Self {
+            civil_dawn: civil.0,
+            nautical_dawn: nautical.0,
+            astronomical_dawn: astronomical.0,
+            civil_dusk: civil.1,
+            nautical_dusk: nautical.1,
+            astronomical_dusk: astronomical.1,
+        }
+    }
+
+    /// Apply `f` to every time that exists (e.g. UTC → local).
+    pub fn map(&self, f: impl Fn(&str) -> String) -> Self {
+        let m = |t: &Option<String>| t.as_deref().map(&f);
+        Self {
+            civil_dawn: m(&self.civil_dawn),
+            nautical_dawn: m(&self.nautical_dawn),
+            astronomical_dawn: m(&self.astronomical_dawn),
+            civil_dusk: m(&self.civil_dusk),
+            nautical_dusk: m(&self.nautical_dusk),
+            astronomical_dusk: m(&self.astronomical_dusk),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SolarInfo {
     pub max_altitude: f64,
     pub min_altitude: f64,
     pub peak_utc: String,
+    /// Interpolated lowest point of the sun within the day scan.
     pub nadir_utc: String,
+    /// Solar anti-transit following Dhuhr, which anchors the night when
+    /// the sun never rises or never sets (polar states only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtual_midnight_utc: Option<String>,
 }
 
 /// Determine the DayState from a day scan.
@@ -165,33 +569,350 @@ pub fn classify_day(samples: &[AltitudeSample]) -> DayState {
 
 // ─── Asr computation ──────────────────────────────────────────────
 
+/// Which of the two equivalent Asr altitude formulas drives the schedule.
+///
+/// They agree for any peak above the horizon; both are always evaluated and
+/// a disagreement is reported as a schedule warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum AsrFormula {
+    /// Inverse-tangent form: alt = atan(1 / (factor + tan(z_noon))).
+    #[default]
+    Standard,
+    /// Zenith form: alt = 90° - atan(factor + tan(z_noon)).
+    Geometric,
+}
+
+impl AsrFormula {
+    pub const ALL: [AsrFormula; 2] = [AsrFormula::Standard, AsrFormula::Geometric];
+}
+
+impl std::fmt::Display for AsrFormula {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsrFormula::Standard => write!(f, "standard"),
+            AsrFormula::Geometric => write!(f, "geometric"),
+        }
+    }
+}
+
+impl std::str::FromStr for AsrFormula {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::method::normalize_key(s).as_str() {
+            "standard" => Ok(AsrFormula::Standard),
+            "geometric" => Ok(AsrFormula::Geometric),
+            _ => Err(format!("Unknown Asr formula '{}'. Use 'standard' or 'geometric'.", s)),
+        }
+    }
+}
+
+/// Juristic school for the Asr shadow length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum AsrMadhab {
+    /// Shadow equals the object plus its noon shadow (Shafi'i, Maliki, Hanbali).
+    #[default]
+    Shafii,
+    /// Shadow equals twice the object plus its noon shadow.
+    Hanafi,
+}
+
+impl AsrMadhab {
+    pub const ALL: [AsrMadhab; 2] = [AsrMadhab::Shafii, AsrMadhab::Hanafi];
+
+    /// Shadow factor in `tan(z_asr) = factor + tan(z_noon)`.
+    pub fn shadow_factor(&self) -> f64 {
+        match self {
+            AsrMadhab::Shafii => 1.0,
+            AsrMadhab::Hanafi => 2.0,
+        }
+    }
+}
+
+impl std::fmt::Display for AsrMadhab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsrMadhab::Shafii => write!(f, "shafii"),
+            AsrMadhab::Hanafi => write!(f, "hanafi"),
+        }
+    }
+}
+
+impl std::str::FromStr for AsrMadhab {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::method::normalize_key(s).as_str() {
+            "shafii" | "shafi" | "shafi'i" | "standard" | "asrshafii" => Ok(AsrMadhab::Shafii),
+            "hanafi" | "asrhanafi" => Ok(AsrMadhab::Hanafi),
+            _ => Err(format!("Unknown Asr madhab '{}'. Use 'shafii' or 'hanafi'.", s)),
+        }
+    }
+}
+
+/// Which point of the solar disc defines sunrise and sunset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum SunriseReference {
+    /// Upper limb on the horizon (-0.833°: refraction plus semidiameter).
+    #[default]
+    UpperLimb,
+    /// Centre of the disc on the horizon (-0.583°: refraction only).
+    Center,
+}
+
+impl SunriseReference {
+    pub const ALL: [SunriseReference; 2] = [SunriseReference::UpperLimb, SunriseReference::Center];
+
+    /// Solar altitude at which sunrise and sunset are taken.
+    pub fn horizon_angle(&self) -> f64 {
+        match self {
+            SunriseReference::UpperLimb => HORIZON_ANGLE,
+            SunriseReference::Center => solar::CENTER_HORIZON_ANGLE,
+        }
+    }
+}
+
+impl std::fmt::Display for SunriseReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SunriseReference::UpperLimb => write!(f, "upper-limb"),
+            SunriseReference::Center => write!(f, "center"),
+        }
+    }
+}
+
+impl std::str::FromStr for SunriseReference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::method::normalize_key(s).as_str() {
+            "upperlimb" | "limb" => Ok(SunriseReference::UpperLimb),
+            "center" | "centre" => Ok(SunriseReference::Center),
+            _ => Err(format!("Unknown sunrise reference '{}'. Use 'upper-limb' or 'center'.", s)),
+        }
+    }
+}
+
+/// How Maghrib and Isha are placed on days when the sun never sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum MidnightSunRule {
+    /// Whatever the gap strategy gives (none under Strict, projected otherwise).
+    #[default]
+    Strategy,
+    /// Split the evening toward solar midnight: Maghrib halfway from Asr to
+    /// solar midnight, Isha halfway from Maghrib to solar midnight.
+    NadirSplit,
+}
+
+impl MidnightSunRule {
+    pub const ALL: [MidnightSunRule; 2] = [MidnightSunRule::Strategy, MidnightSunRule::NadirSplit];
+}
+
+impl std::fmt::Display for MidnightSunRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidnightSunRule::Strategy => write!(f, "strategy"),
+            MidnightSunRule::NadirSplit => write!(f, "nadir-split"),
+        }
+    }
+}
+
+impl std::str::FromStr for MidnightSunRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::method::normalize_key(s).as_str() {
+            "strategy" => Ok(MidnightSunRule::Strategy),
+            "nadirsplit" | "nadir" | "midpoint" => Ok(MidnightSunRule::NadirSplit),
+            _ => Err(format!("Unknown midnight-sun rule '{}'. Use 'strategy' or 'nadir-split'.", s)),
+        }
+    }
+}
+
+/// How Fajr and Isha are placed on a normal day when the sun never
+/// reaches their depression angle (persistent twilight).
+///
+/// Only Fajr and Isha are affected, and only when sunrise and sunset
+/// exist. `GapStrategy` handles days without them: under MidnightSun and
+/// PolarNight it projects sunrise/Maghrib and these rules do not apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum HighLatitudeRule {
+    /// Map the angle onto the day's altitude wave (`WaveFit`).
+    #[default]
+    WaveMapped,
+    /// Place the missing event at the midpoint of sunset → sunrise.
+    MiddleOfNight,
+    /// Isha one seventh of the night after sunset, Fajr one seventh before sunrise.
+    SeventhOfNight,
+    /// A share of the night of angle/60: 18° Fajr is 0.3 of the night before sunrise.
+    AngleBased,
+}
+
+impl HighLatitudeRule {
+    pub const ALL: [HighLatitudeRule; 4] = [
+        HighLatitudeRule::WaveMapped,
+        HighLatitudeRule::MiddleOfNight,
+        HighLatitudeRule::SeventhOfNight,
+        HighLatitudeRule::AngleBased,
+    ];
+}
+
+impl std::fmt::Display for HighLatitudeRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HighLatitudeRule::WaveMapped => write!(f, "wave"),
+            HighLatitudeRule::MiddleOfNight => write!(f, "middle-of-night"),
+            HighLatitudeRule::SeventhOfNight => write!(f, "seventh"),
+            HighLatitudeRule::AngleBased => write!(f, "angle-based"),
+        }
+    }
+}
+
+impl std::str::FromStr for HighLatitudeRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match crate::method::normalize_key(s).as_str() {
+            "wave" | "wavemapped" => Ok(HighLatitudeRule::WaveMapped),
+            "middleofnight" | "middle" | "midnight" => Ok(HighLatitudeRule::MiddleOfNight),
+            "seventh" | "seventhofnight" | "oneseventh" => Ok(HighLatitudeRule::SeventhOfNight),
+            "anglebased" | "angle" => Ok(HighLatitudeRule::AngleBased),
+            _ => Err(format!(
+                "Unknown high-latitude rule '{}'. Use 'wave', 'middle-of-night', 'seventh' or 'angle-based'.", s)),
+        }
+    }
+}
+
+/// How displayed times are rounded to whole minutes.
+///
+/// Published timetables often round conservatively for fasting: Fajr down
+/// (the fast starts no later) and Isha up (the prayer is not called early).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RoundingPolicy {
+    /// Every event to the nearest minute.
+    Nearest,
+    /// Fajr down, the rest to the nearest minute.
+    FajrDown,
+    /// Isha up, the rest to the nearest minute.
+    IshaUp,
+    AllDown,
+    AllUp,
+}
+
+impl RoundingPolicy {
+    pub const ALL: [RoundingPolicy; 5] = [
+        RoundingPolicy::Nearest, RoundingPolicy::FajrDown, RoundingPolicy::IshaUp,
+        RoundingPolicy::AllDown, RoundingPolicy::AllUp,
+    ];
+
+    /// Round `t` to a whole minute as this policy prescribes for `kind`.
+    pub fn round(self, kind: PrayerKind, t: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = t.timestamp();
+        let down = secs - secs.rem_euclid(60);
+        let up = if down == secs { secs } else { down + 60 };
+        let rounded = match (self, kind) {
+            (RoundingPolicy::AllDown, _) | (RoundingPolicy::FajrDown, PrayerKind::Fajr) => down,
+            (RoundingPolicy::AllUp, _) | (RoundingPolicy::IshaUp, PrayerKind::Isha) => up,
+            _ if secs - down >= 30 => up,
+            _ => down,
+        };
+        DateTime::from_timestamp(rounded, 0).unwrap_or(t)
+    }
+}
+
+impl std::fmt::Display for RoundingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoundingPolicy::Nearest => write!(f, "nearest"),
+            RoundingPolicy::FajrDown => write!(f, "fajr-down"),
+            RoundingPolicy::IshaUp => write!(f, "isha-up"),
+            RoundingPolicy::AllDown => write!(f, "all-down"),
+            RoundingPolicy::AllUp => write!(f, "all-up"),
+        }
+    }
+}
+
+impl std::str::FromStr for RoundingPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RoundingPolicy::ALL.into_iter()
+            .find(|p| p.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!(
+                "Unknown rounding '{}'. Use nearest, fajr-down, isha-up, all-down or all-up.", s))
+    }
+}
+
+/// Peaks below this altitude make Asr very sensitive to refraction.
+const LOW_PEAK_ALTITUDE: f64 = 5.0;
+
+/// Largest tolerated disagreement between the two Asr formulas (degrees).
+const ASR_FORMULA_TOLERANCE: f64 = 0.01;
+
 /// Geometric Asr altitude using zenith formulation.
 ///
-/// The shadow formula: shadow_asr = shadow_noon + object_height
-/// In angular terms: tan(z_asr) = 1 + tan(z_noon)
-/// Therefore: z_asr = atan(1 + tan(z_noon))
+/// The shadow formula: shadow_asr = shadow_noon + factor * object_height
+/// In angular terms: tan(z_asr) = factor + tan(z_noon)
+/// Therefore: z_asr = atan(factor + tan(z_noon))
 ///            alt_asr = 90° - z_asr
 ///
-/// This is equivalent to: alt_asr = atan(1 / (1 + tan(z_noon)))
+/// This is equivalent to: alt_asr = atan(1 / (factor + tan(z_noon)))
 /// since atan(x) + atan(1/x) = 90° for x > 0.
-#[cfg(test)]
-fn geometric_asr_altitude(peak_altitude: f64) -> f64 {
+fn geometric_asr_altitude(peak_altitude: f64, shadow_factor: f64) -> f64 {
     let z_noon_rad = (90.0 - peak_altitude) * DEG;
-    let tan_z_asr = 1.0 + z_noon_rad.tan();
+    let tan_z_asr = shadow_factor + z_noon_rad.tan();
     if tan_z_asr <= 0.0 { return 0.0; }
     let z_asr_rad = tan_z_asr.atan();
     (90.0 - z_asr_rad / DEG).max(0.0)
 }
 
 /// Standard Asr altitude — equivalent formulation via inverse tangent.
-/// alt_asr = atan(1 / (1 + tan(90° - peak)))
-fn standard_asr_altitude(peak_altitude: f64) -> f64 {
+/// alt_asr = atan(1 / (factor + tan(90° - peak)))
+/// Shadow factor: 1 = Shafi'i, 2 = Hanafi.
+fn standard_asr_altitude(peak_altitude: f64, shadow_factor: f64) -> f64 {
     let z_noon_rad = (90.0 - peak_altitude) * DEG;
-    let denom = 1.0 + z_noon_rad.tan();
+    let denom = shadow_factor + z_noon_rad.tan();
     if denom <= 0.0 { return 0.0; }
     (1.0 / denom).atan() / DEG
 }
 
+/// Asr target altitude for the day's peak, with any warnings about it.
+///
+/// Returns `None` when the sun's centre never rises above 0°: the noon
+/// shadow is unbounded and the shadow rule has no solution, so the caller
+/// falls back to virtual placement.
+fn asr_target(peak_altitude: f64, shadow_factor: f64, formula: AsrFormula) -> (Option<f64>, Vec<String>) {
+    let mut warnings = Vec::new();
+    if peak_altitude <= 0.0 {
+        warnings.push(format!(
+            "Asr shadow rule undefined: sun peaks at {:.2}°; Asr placed virtually",
+            peak_altitude,
+        ));
+        return (None, warnings);
+    }
+
+    let standard = standard_asr_altitude(peak_altitude, shadow_factor);
+    let geometric = geometric_asr_altitude(peak_altitude, shadow_factor);
+    if (standard - geometric).abs() > ASR_FORMULA_TOLERANCE {
+        warnings.push(format!(
+            "Asr formulas disagree at peak {:.2}°: standard {:.4}°, geometric {:.4}°",
+            peak_altitude, standard, geometric,
+        ));
+    }
+    if peak_altitude < LOW_PEAK_ALTITUDE {
+        warnings.push(format!(
+            "Low sun (peak {:.2}°): Asr altitude {:.4}° (geometric {:.4}°) is sensitive to refraction",
+            peak_altitude, standard, geometric,
+        ));
+    }
+
+    let altitude = match formula {
+        AsrFormula::Standard => standard,
+        AsrFormula::Geometric => geometric,
+    };
+    (Some(altitude), warnings)
+}
+
 /// Virtual Asr for polar conditions using angular descent from peak.
 ///
 /// In polar night, we compute Asr as the point where the sun has descended
@@ -206,10 +927,11 @@ fn virtual_asr_seconds(
     samples: &[AltitudeSample],
     peak: &AltitudeSample,
     nadir: &AltitudeSample,
+    shadow_factor: f64,
 ) -> f64 {
     // Reference ratio derived from a 55° peak day (Mecca baseline)
     let reference_peak = 55.0;
-    let reference_asr = standard_asr_altitude(reference_peak);
+    let reference_asr = standard_asr_altitude(reference_peak, shadow_factor);
     let asr_ratio = reference_asr / reference_peak; // ~0.576
 
     // Target altitude on the wave = nadir + (peak - nadir) * asr_ratio
@@ -229,7 +951,7 @@ fn virtual_asr_seconds(
 
     // Fallback: 55% of afternoon arc
     let half_cycle = wrapped_duration(peak.seconds, nadir.seconds);
-    (peak.seconds + half_cycle * 0.55) % 86400.0
+    (peak.seconds + half_cycle * ASR_AFTERNOON_FRACTION) % 86400.0
 }
 
 // ─── Twilight computation (normalized wave) ─────────────────────
@@ -245,10 +967,15 @@ fn wave_mapped_time(
     nadir: &AltitudeSample,
     target_angle: f64,
     ascending: bool,
-) -> f64 {
+) -> (f64, WaveFit) {
+    let fit = |secs: f64, method| {
+        let residual = (altitude_at(samples, secs) - target_angle).abs();
+        (secs, WaveFit { method, residual })
+    };
+
     // First try direct crossing (if the wave actually reaches this angle)
     if let Some(secs) = solar::find_crossing(samples, target_angle, ascending) {
-        return secs;
+        return fit(secs, WaveFitMethod::DirectCrossing);
     }
 
     // Normalize target within the wave
@@ -269,7 +996,7 @@ fn wave_mapped_time(
         for w in candidates.windows(2) {
             if w[0].altitude <= mapped_alt && w[1].altitude > mapped_alt {
                 let frac = (mapped_alt - w[0].altitude) / (w[1].altitude - w[0].altitude);
-                return w[0].seconds + frac * (w[1].seconds - w[0].seconds);
+                return fit(w[0].seconds + frac * (w[1].seconds - w[0].seconds), WaveFitMethod::NormalizedWave);
             }
         }
     } else {
@@ -282,18 +1009,35 @@ fn wave_mapped_time(
         for w in candidates.windows(2) {
             if w[0].altitude >= mapped_alt && w[1].altitude < mapped_alt {
                 let frac = (mapped_alt - w[0].altitude) / (w[1].altitude - w[0].altitude);
-                return w[0].seconds + frac * (w[1].seconds - w[0].seconds);
+                return fit(w[0].seconds + frac * (w[1].seconds - w[0].seconds), WaveFitMethod::NormalizedWave);
             }
         }
     }
 
     // Final fallback: proportional time
     let half = wrapped_duration(nadir.seconds, peak.seconds);
-    if ascending {
+    let secs = if ascending {
         (nadir.seconds + half * norm_target) % 86400.0
     } else {
         (peak.seconds + half * (1.0 - norm_target)) % 86400.0
-    }
+    };
+    fit(secs, WaveFitMethod::Proportional)
+}
+
+/// Sun altitude at `secs`, linearly interpolated between scan samples.
+fn altitude_at(samples: &[AltitudeSample], secs: f64) -> f64 {
+    let secs = samples.first().map_or(secs, |first| {
+        first.seconds + (secs - first.seconds).rem_euclid(86400.0)
+    });
+    samples.windows(2)
+        .find(|w| w[0].seconds <= secs && secs <= w[1].seconds)
+        .map(|w| {
+            let span = w[1].seconds - w[0].seconds;
+            let frac = if span > 0.0 { (secs - w[0].seconds) / span } else { 0.0 };
+            w[0].altitude + frac * (w[1].altitude - w[0].altitude)
+        })
+        .or_else(|| samples.last().map(|s| s.altitude))
+        .unwrap_or(0.0)
 }
 
 // ─── Utility ────────────────────────────────────────────────────
@@ -304,30 +1048,124 @@ fn wrapped_duration(from: f64, to: f64) -> f64 {
 
 // ─── Schedule builders ──────────────────────────────────────────
 
+/// Start of the 24-hour scan window, in seconds from UTC midnight, centred
+/// on the approximate solar noon at this longitude.
+///
+/// Scanning a plain UTC day cuts the solar day in two for longitudes far from
+/// Greenwich, so the first crossing found can belong to the previous solar day.
+pub fn scan_window_start(lon: f64) -> i64 {
+    -(lon * 240.0 / 30.0).round() as i64 * 30
+}
+
 pub fn compute_schedule(date: NaiveDate, lat: f64, lon: f64, strategy: GapStrategy) -> Schedule {
-    let samples = solar::day_scan(date, lat, lon, 30);
+    compute_schedule_with(date, lat, lon, &Config::default().with_strategy(strategy))
+}
+
+/// Solar anti-transit following the day's peak, in seconds from UTC midnight of `date`.
+pub fn virtual_midnight_seconds(date: NaiveDate, lat: f64, lon: f64, peak: &AltitudeSample) -> f64 {
+    solar::refine_extremum(date, lat, lon, peak.seconds + 43200.0).seconds
+}
+
+/// Compute the schedule with a full configuration (method, strategy, ...).
+///
+/// The method's angles are taken from `config`, so Fajr/Isha overrides apply.
+pub fn compute_schedule_with(date: NaiveDate, lat: f64, lon: f64, config: &Config) -> Schedule {
+    compute_schedule_with_method(date, lat, lon, config, config)
+}
+
+/// Compute the schedule for any [`PrayerMethod`], including custom ones.
+///
+/// `method` takes the place of `config.method`; the other options apply as usual.
+pub fn compute_schedule_with_method(
+    date: NaiveDate,
+    lat: f64,
+    lon: f64,
+    config: &Config,
+    method: &dyn PrayerMethod,
+) -> Schedule {
+    let resolution = config.scan_resolution();
+    let samples = day_scan_samples_with(date, lat, lon, resolution);
     let peak = solar::find_peak(&samples);
     let nadir = solar::find_nadir(&samples);
     let state = classify_day(&samples);
 
+    let fine_nadir = solar::refine_extremum(date, lat, lon, nadir.seconds);
+    let virtual_midnight = match state {
+        DayState::Normal => None,
+        _ => Some(virtual_midnight_seconds(date, lat, lon, &peak)),
+    };
+
     let solar_info = SolarInfo {
         max_altitude: peak.altitude,
-        min_altitude: nadir.altitude,
+        min_altitude: fine_nadir.altitude.min(nadir.altitude),
         peak_utc: solar::seconds_to_hms(peak.seconds),
-        nadir_utc: solar::seconds_to_hms(nadir.seconds),
+        nadir_utc: solar::seconds_to_hms(fine_nadir.seconds),
+        virtual_midnight_utc: virtual_midnight.map(solar::seconds_to_hms),
     };
 
+    let (asr_alt, mut warnings) = match state {
+        DayState::PolarNight => (None, Vec::new()),
+        _ => asr_target(peak.altitude, method.asr_factor(), config.asr_formula),
+    };
+
+    let horizon = config.horizon_angle();
     let mut events = match state {
-        DayState::Normal => build_normal(&samples, &peak, &nadir),
-        DayState::MidnightSun => build_midnight_sun(&samples, &peak, &nadir),
-        DayState::PolarNight => build_polar_night(&samples, &peak, &nadir),
+        DayState::Normal => build_normal(&samples, &peak, &nadir, method, asr_alt, horizon),
+        DayState::MidnightSun => build_midnight_sun(&samples, &peak, &nadir, method, asr_alt),
+        DayState::PolarNight => build_polar_night(&samples, &peak, &nadir, method),
     };
 
-    if strategy == GapStrategy::Projected45 && state != DayState::Normal {
-        apply_projection(&mut events, date, lat, lon);
+    let high_latitude_rule = config.strategy.resolve_high_latitude_rule(lat, config.high_latitude_rule);
+    if state == DayState::Normal && high_latitude_rule != HighLatitudeRule::WaveMapped {
+        let sunset = solar::find_crossing(&samples, horizon, false);
+        apply_high_latitude_rule(&mut events, high_latitude_rule, method, sunset);
+    }
+
+    let strategy = config.strategy.resolve(lat);
+    if strategy != GapStrategy::Strict && state != DayState::Normal {
+        apply_projection(&mut events, date, lat, lon, peak.seconds, horizon, resolution);
+    }
+
+    if strategy == GapStrategy::MoonsightingSeason {
+        let sunset = solar::find_crossing(&samples, horizon, false)
+            .or(events.maghrib.time.as_deref().map(hms_to_seconds));
+        apply_moonsighting_season(&mut events, date, lat, sunset);
+    }
+
+    if let IshaRule::FixedAfterMaghrib(minutes) = method.isha_rule() {
+        let in_ramadan = crate::hijri::gregorian_to_hijri(date).month == 9;
+        let minutes = method.ramadan_isha_minutes().filter(|_| in_ramadan).unwrap_or(minutes);
+        apply_fixed_isha(&mut events, minutes);
+    }
+
+    if let (DayState::MidnightSun, MidnightSunRule::NadirSplit, Some(midnight)) =
+        (state, config.midnight_sun_rule, virtual_midnight)
+    {
+        apply_nadir_split(&mut events, peak.seconds, midnight);
+    }
+
+    if let Some(minutes) = config.min_fajr_sunrise_gap_min {
+        warnings.extend(apply_fajr_sunrise_gap(&mut events, minutes));
+    }
+
+    // Sunset as a horizon crossing, or the Maghrib that stands in for it
+    let sunset = solar::find_crossing(&samples, horizon, false).or(match method.maghrib_rule() {
+        MaghribRule::Sunset => events.maghrib.time.as_deref().map(hms_to_seconds),
+        MaghribRule::Angle(_) => None,
+    });
+    let makruh_windows = makruh_windows(&events, sunset);
+    let twilight_times = config.include_twilight_times.then(|| TwilightTimes::from_samples(&samples));
+
+    apply_adjustments(&mut events, &config.adjustments);
+
+    let twilight_persists = state == DayState::Normal
+        && [&events.fajr, &events.isha].iter().any(|e| e.method == EventMethod::Virtual);
+    if twilight_persists {
+        warnings.push("Fajr/Isha are estimated due to persistent twilight".to_string());
     }
 
-    Schedule { state, events, solar: solar_info }
+    warnings.dedup();
+    Schedule { state, events, solar: solar_info, twilight_persists, makruh_windows, twilight_times, warnings }
 }
 
 /// Compute the adaptive reference latitude for projection (Aqrab al-Bilad).
@@ -354,16 +1192,27 @@ pub fn compute_reference_lat(lat: f64) -> f64 {
 /// 1. Compute an adaptive reference latitude based on user position
 /// 2. Scan the same date at that reference to get sunrise/sunset durations
 /// 3. Apply those durations relative to the user's local solar noon
-fn apply_projection(events: &mut Events, date: NaiveDate, lat: f64, lon: f64) {
+///
+/// `local_noon` is the peak of the user's own day scan, which the caller
+/// already has; only the reference latitude is scanned here.
+fn apply_projection(
+    events: &mut Events,
+    date: NaiveDate,
+    lat: f64,
+    lon: f64,
+    local_noon: f64,
+    horizon: f64,
+    resolution: u32,
+) {
     let ref_lat = compute_reference_lat(lat);
 
     // Scan the reference day
-    let ref_samples = solar::day_scan(date, ref_lat, lon, 30);
+    let ref_samples = day_scan_samples_with(date, ref_lat, lon, resolution);
     let ref_peak = solar::find_peak(&ref_samples);
 
     // Find reference sunrise and sunset
-    let ref_sunrise = solar::find_crossing(&ref_samples, HORIZON_ANGLE, true);
-    let ref_sunset = solar::find_crossing(&ref_samples, HORIZON_ANGLE, false);
+    let ref_sunrise = solar::find_crossing(&ref_samples, horizon, true);
+    let ref_sunset = solar::find_crossing(&ref_samples, horizon, false);
 
     // Both must exist at 45° for projection to work
     let (ref_sunrise_secs, ref_sunset_secs) = match (ref_sunrise, ref_sunset) {
@@ -376,11 +1225,6 @@ fn apply_projection(events: &mut Events, date: NaiveDate, lat: f64, lon: f64) {
     let morning_duration = wrapped_duration(ref_sunrise_secs, ref_noon);
     let evening_duration = wrapped_duration(ref_noon, ref_sunset_secs);
 
-    // Get the user's local solar noon
-    let local_samples = solar::day_scan(date, lat, lon, 30);
-    let local_peak = solar::find_peak(&local_samples);
-    let local_noon = local_peak.seconds;
-
     let note = format!("Adaptive projection anchored to {:.1}° reference latitude", ref_lat);
 
     // Project sunrise: local_noon - morning_duration
@@ -396,33 +1240,252 @@ fn apply_projection(events: &mut Events, date: NaiveDate, lat: f64, lon: f64) {
     }
 }
 
+// ─── Moonsighting.com seasonal twilight ─────────────────────────
+
+/// Latitude from which the seasonal curves give way to one seventh of the night.
+const MOONSIGHTING_SEVENTH_LATITUDE: f64 = 55.0;
+
+/// Days since the winter solstice as moonsighting.com counts them
+/// (21 December in the north, 21 June in the south).
+fn days_since_solstice(date: NaiveDate, lat: f64) -> f64 {
+    let days_in_year = if date.leap_year() { 366 } else { 365 };
+    let day = date.ordinal() as i32;
+    let dss = if lat >= 0.0 {
+        (day + 10) % days_in_year
+    } else {
+        (day - if date.leap_year() { 173 } else { 172 }).rem_euclid(days_in_year)
+    };
+    dss as f64
+}
+
+/// Piecewise-linear seasonal curve through the four anchor values
+/// (winter solstice, equinoxes, early and late summer).
+fn seasonal_minutes(dss: f64, [a, b, c, d]: [f64; 4]) -> f64 {
+    match dss {
+        x if x < 91.0 => a + (b - a) / 91.0 * x,
+        x if x < 137.0 => b + (c - b) / 46.0 * (x - 91.0),
+        x if x < 183.0 => c + (d - c) / 46.0 * (x - 137.0),
+        x if x < 229.0 => d + (c - d) / 46.0 * (x - 183.0),
+        x if x < 275.0 => c + (b - c) / 46.0 * (x - 229.0),
+        x => b + (a - b) / 91.0 * (x - 275.0),
+    }
+}
+
+/// Minutes from Fajr to sunrise and from sunset to Isha (general shafaq)
+/// under the moonsighting.com seasonal method, for |lat| below 55°.
+fn moonsighting_season_minutes(date: NaiveDate, lat: f64) -> (f64, f64) {
+    let dss = days_since_solstice(date, lat);
+    let l = lat.abs() / 55.0;
+    let fajr = seasonal_minutes(dss, [75.0 + 28.65 * l, 75.0 + 19.44 * l, 75.0 + 32.74 * l, 75.0 + 48.10 * l]);
+    let isha = seasonal_minutes(dss, [75.0 + 25.60 * l, 75.0 + 2.05 * l, 75.0 - 9.21 * l, 75.0 + 6.14 * l]);
+    (fajr, isha)
+}
+
+/// Bound Fajr and Isha by the moonsighting.com seasonal intervals.
+///
+/// The seasonal time replaces the angular one when the angle is not reached
+/// (a Virtual time) or lies further from sunrise/sunset. From 55° the night
+/// is split in sevenths instead. Like a fixed Isha, each event inherits the
+/// method of the sunrise or sunset it is anchored to.
+fn apply_moonsighting_season(events: &mut Events, date: NaiveDate, lat: f64, sunset: Option<f64>) {
+    let sunrise = events.sunrise.time.as_deref().map(hms_to_seconds);
+    let seventh = match (sunrise, sunset) {
+        (Some(rise), Some(set)) if lat.abs() >= MOONSIGHTING_SEVENTH_LATITUDE => {
+            Some(wrapped_duration(set, rise) / 60.0 / 7.0)
+        }
+        _ => None,
+    };
+    let (fajr_min, isha_min) = match seventh {
+        Some(m) => (m, m),
+        None => moonsighting_season_minutes(date, lat),
+    };
+    let seventh = seventh.is_some();
+
+    if let Some(rise) = sunrise {
+        let angular = events.fajr.time.as_deref().map(|t| wrapped_duration(hms_to_seconds(t), rise));
+        if seventh || events.fajr.method != EventMethod::Standard || angular.is_none_or(|gap| gap > fajr_min * 60.0) {
+            events.fajr = PrayerEvent {
+                method: events.sunrise.method,
+                confidence: events.sunrise.confidence,
+                note: Some(format!("Moonsighting seasonal: {:.0} min before sunrise", fajr_min)),
+                ..PrayerEvent::standard(rise - fajr_min * 60.0)
+            };
+        }
+    }
+
+    if let Some(set) = sunset {
+        let angular = events.isha.time.as_deref().map(|t| wrapped_duration(set, hms_to_seconds(t)));
+        if seventh || events.isha.method != EventMethod::Standard || angular.is_none_or(|gap| gap > isha_min * 60.0) {
+            events.isha = PrayerEvent {
+                method: events.maghrib.method,
+                confidence: events.maghrib.confidence,
+                note: Some(format!("Moonsighting seasonal: {:.0} min after sunset", isha_min)),
+                ..PrayerEvent::standard(set + isha_min * 60.0)
+            };
+        }
+    }
+}
+
+/// Replace wave-mapped Fajr/Isha with a share of the night from sunset to
+/// sunrise. Direct crossings are kept; without a sunset or sunrise (polar
+/// states) nothing changes and the gap strategy applies.
+fn apply_high_latitude_rule(events: &mut Events, rule: HighLatitudeRule, method: &dyn PrayerMethod, sunset: Option<f64>) {
+    let (Some(sunset), Some(sunrise)) = (sunset, events.sunrise.time.as_deref().map(hms_to_seconds)) else {
+        return;
+    };
+    let night = wrapped_duration(sunset, sunrise);
+    let (fajr, isha, note) = match rule {
+        HighLatitudeRule::WaveMapped => return,
+        HighLatitudeRule::MiddleOfNight => (sunset + night / 2.0, sunset + night / 2.0, "Middle of the night rule"),
+        HighLatitudeRule::SeventhOfNight => (sunrise - night / 7.0, sunset + night / 7.0, "One-seventh of the night rule"),
+        HighLatitudeRule::AngleBased => (
+            sunrise - night * method.fajr_angle() / 60.0,
+            sunset + night * -isha_altitude(method) / 60.0,
+            "Angle-based night portion rule",
+        ),
+    };
+    for (event, secs) in [(&mut events.fajr, fajr), (&mut events.isha, isha)] {
+        if event.method == EventMethod::Virtual {
+            *event = PrayerEvent { note: Some(note.to_string()), ..PrayerEvent::virtual_event(secs) };
+        }
+    }
+}
+
+/// Place Isha a fixed interval after Maghrib (Umm al-Qura style).
+///
+/// Isha inherits Maghrib's derivation method, so a projected Maghrib gives a
+/// projected Isha. Without any Maghrib the angular placement is kept.
+fn apply_fixed_isha(events: &mut Events, minutes: u32) {
+    if let Some(ref maghrib) = events.maghrib.time {
+        let secs = hms_to_seconds(maghrib) + minutes as f64 * 60.0;
+        events.isha = PrayerEvent {
+            time: Some(solar::seconds_to_hms(secs)),
+            method: events.maghrib.method,
+            confidence: events.maghrib.confidence,
+            note: Some(format!("{} min after Maghrib", minutes)),
+            day_offset: 0,
+            sidereal: None,
+            azimuth: None,
+            wave_fit: None,
+        };
+    }
+}
+
+/// Place Maghrib and Isha between Asr and solar midnight on a midnight-sun
+/// day (`MidnightSunRule::NadirSplit`), replacing any projected values.
+fn apply_nadir_split(events: &mut Events, dhuhr: f64, midnight: f64) {
+    let asr = events.asr.time.as_deref()
+        .map(|t| dhuhr + wrapped_duration(dhuhr, hms_to_seconds(t)))
+        .unwrap_or(dhuhr);
+    let maghrib = asr + (midnight - asr) / 2.0;
+    let isha = maghrib + (midnight - maghrib) / 2.0;
+
+    let note = "Midnight sun: split between Asr and solar midnight";
+    events.maghrib = PrayerEvent { note: Some(note.to_string()), ..PrayerEvent::virtual_event(maghrib) };
+    events.isha = PrayerEvent { note: Some(note.to_string()), ..PrayerEvent::virtual_event(isha) };
+}
+
+/// Move Fajr back to at least `minutes` before sunrise, returning a warning
+/// when the computed time was closer than that (or after sunrise).
+fn apply_fajr_sunrise_gap(events: &mut Events, minutes: u32) -> Option<String> {
+    let fajr = hms_to_seconds(events.fajr.time.as_ref()?);
+    let sunrise = hms_to_seconds(events.sunrise.time.as_ref()?);
+    // Both lie within the same scan window; wrap in case they straddle midnight
+    let gap = (sunrise - fajr + 43200.0).rem_euclid(86400.0) - 43200.0;
+    let min_gap = minutes as f64 * 60.0;
+    if gap >= min_gap {
+        return None;
+    }
+
+    events.fajr.time = Some(solar::seconds_to_hms(sunrise - min_gap));
+    let note = format!("clamped to {} min before sunrise", minutes);
+    events.fajr.note = Some(match events.fajr.note.take() {
+        Some(n) => format!("{}; {}", n, note),
+        None => note,
+    });
+    Some(format!(
+        "Fajr was {:.0} min before sunrise; moved to {} min before (min_fajr_sunrise_gap)",
+        gap / 60.0, minutes,
+    ))
+}
+
+/// Shift events by the configured whole-minute offsets, noting each change.
+fn apply_adjustments(events: &mut Events, adjustments: &Adjustments) {
+    for kind in PrayerKind::ALL {
+        let minutes = adjustments.get(kind);
+        let event = events.get_mut(kind);
+        let Some(time) = event.time.as_ref().filter(|_| minutes != 0) else {
+            continue;
+        };
+        event.time = Some(solar::seconds_to_hms(hms_to_seconds(time) + minutes as f64 * 60.0));
+        let note = format!("adjusted {:+} min", minutes);
+        event.note = Some(match event.note.take() {
+            Some(n) => format!("{}; {}", n, note),
+            None => note,
+        });
+    }
+}
+
+/// Share of the afternoon (Dhuhr → end of day) at which a virtual Asr is placed.
+const ASR_AFTERNOON_FRACTION: f64 = 0.55;
+
+/// Asr from the shadow-rule altitude, falling back to virtual placement
+/// when there is no target or the sun never descends through it.
+///
+/// With a real sunset (`afternoon_end`), the virtual Asr stays inside the
+/// Dhuhr → sunset afternoon; otherwise it follows the altitude wave.
+fn asr_event(
+    samples: &[AltitudeSample],
+    peak: &AltitudeSample,
+    nadir: &AltitudeSample,
+    asr_alt: Option<f64>,
+    shadow_factor: f64,
+    afternoon_end: Option<f64>,
+) -> PrayerEvent {
+    if let Some(secs) = asr_alt.and_then(|alt| solar::find_crossing(samples, alt, false)) {
+        return PrayerEvent::standard(secs);
+    }
+    let secs = match afternoon_end {
+        Some(end) => peak.seconds + (end - peak.seconds) * ASR_AFTERNOON_FRACTION,
+        None => virtual_asr_seconds(samples, peak, nadir, shadow_factor),
+    };
+    PrayerEvent::virtual_event(secs)
+}
+
 fn build_normal(
     samples: &[AltitudeSample],
     peak: &AltitudeSample,
     nadir: &AltitudeSample,
+    method: &dyn PrayerMethod,
+    asr_alt: Option<f64>,
+    horizon: f64,
 ) -> Events {
-    let sunrise_secs = solar::find_crossing(samples, HORIZON_ANGLE, true)
+    let sunrise_secs = solar::find_crossing(samples, horizon, true)
         .unwrap_or(peak.seconds - 6.0 * 3600.0);
-    let sunset_secs = solar::find_crossing(samples, HORIZON_ANGLE, false)
+    let sunset_secs = solar::find_crossing(samples, horizon, false)
         .unwrap_or(peak.seconds + 6.0 * 3600.0);
+    let maghrib_secs = match method.maghrib_rule() {
+        MaghribRule::Sunset => sunset_secs,
+        MaghribRule::Angle(a) => solar::find_crossing(samples, -a, false).unwrap_or(sunset_secs),
+    };
+    let fajr_angle = fajr_altitude(method);
+    let isha_angle = isha_altitude(method);
 
     let dhuhr_secs = peak.seconds;
 
-    // Asr: geometric (standard shadow formula)
-    let asr_alt = standard_asr_altitude(peak.altitude);
-    let asr_secs = solar::find_crossing(samples, asr_alt, false)
-        .unwrap_or_else(|| virtual_asr_seconds(samples, peak, nadir));
+    // Asr: shadow-rule crossing, or virtual when the sun stays too low
+    let asr = asr_event(samples, peak, nadir, asr_alt, method.asr_factor(), Some(sunset_secs));
 
     // Fajr/Isha: direct crossing or wave-mapped
-    let fajr_secs = wave_mapped_time(samples, peak, nadir, FAJR_ANGLE, true);
-    let isha_secs = wave_mapped_time(samples, peak, nadir, ISHA_ANGLE, false);
+    let (fajr_secs, fajr_fit) = wave_mapped_time(samples, peak, nadir, fajr_angle, true);
+    let (isha_secs, isha_fit) = wave_mapped_time(samples, peak, nadir, isha_angle, false);
 
-    let fajr_method = if solar::find_crossing(samples, FAJR_ANGLE, true).is_some() {
+    let fajr_method = if fajr_fit.method == WaveFitMethod::DirectCrossing {
         EventMethod::Standard
     } else {
         EventMethod::Virtual
     };
-    let isha_method = if solar::find_crossing(samples, ISHA_ANGLE, false).is_some() {
+    let isha_method = if isha_fit.method == WaveFitMethod::DirectCrossing {
         EventMethod::Standard
     } else {
         EventMethod::Virtual
@@ -432,12 +1495,12 @@ fn build_normal(
     let isha_confidence = if isha_method == EventMethod::Standard { 1.0 } else { 0.7 };
 
     Events {
-        fajr: PrayerEvent { time: Some(solar::seconds_to_hms(fajr_secs)), method: fajr_method, confidence: fajr_confidence, note: None, next_day: false },
+        fajr: PrayerEvent { time: Some(solar::seconds_to_hms(fajr_secs)), method: fajr_method, confidence: fajr_confidence, note: None, day_offset: 0, sidereal: None, azimuth: None, wave_fit: (fajr_method == EventMethod::Virtual).then_some(fajr_fit) },
         sunrise: PrayerEvent::standard(sunrise_secs),
         dhuhr: PrayerEvent::standard(dhuhr_secs),
-        asr: PrayerEvent::standard(asr_secs),
-        maghrib: PrayerEvent::standard(sunset_secs),
-        isha: PrayerEvent { time: Some(solar::seconds_to_hms(isha_secs)), method: isha_method, confidence: isha_confidence, note: None, next_day: false },
+        asr,
+        maghrib: PrayerEvent::standard(maghrib_secs),
+        isha: PrayerEvent { time: Some(solar::seconds_to_hms(isha_secs)), method: isha_method, confidence: isha_confidence, note: None, day_offset: 0, sidereal: None, azimuth: None, wave_fit: (isha_method == EventMethod::Virtual).then_some(isha_fit) },
     }
 }
 
@@ -445,31 +1508,25 @@ fn build_midnight_sun(
     samples: &[AltitudeSample],
     peak: &AltitudeSample,
     nadir: &AltitudeSample,
+    method: &dyn PrayerMethod,
+    asr_alt: Option<f64>,
 ) -> Events {
     // Sun never sets → sunrise and maghrib DO NOT EXIST physically
     let dhuhr_secs = peak.seconds;
 
     // Asr: the sun does reach Asr altitude (it's above horizon all day)
-    let asr_alt = standard_asr_altitude(peak.altitude);
-    let asr_secs = solar::find_crossing(samples, asr_alt, false)
-        .unwrap_or_else(|| virtual_asr_seconds(samples, peak, nadir));
-    let asr_method = if solar::find_crossing(samples, asr_alt, false).is_some() {
-        EventMethod::Standard
-    } else {
-        EventMethod::Virtual
-    };
-    let asr_confidence = if asr_method == EventMethod::Standard { 1.0 } else { 0.7 };
+    let asr = asr_event(samples, peak, nadir, asr_alt, method.asr_factor(), None);
 
-    let fajr_secs = wave_mapped_time(samples, peak, nadir, FAJR_ANGLE, true);
-    let isha_secs = wave_mapped_time(samples, peak, nadir, ISHA_ANGLE, false);
+    let fajr = wave_mapped_time(samples, peak, nadir, fajr_altitude(method), true);
+    let isha = wave_mapped_time(samples, peak, nadir, isha_altitude(method), false);
 
     Events {
-        fajr: PrayerEvent::virtual_event(fajr_secs),
+        fajr: PrayerEvent::wave_mapped(fajr),
         sunrise: PrayerEvent::none(),   // Sun never set, so it never rises
         dhuhr: PrayerEvent::standard(dhuhr_secs),
-        asr: PrayerEvent { time: Some(solar::seconds_to_hms(asr_secs)), method: asr_method, confidence: asr_confidence, note: None, next_day: false },
+        asr,
         maghrib: PrayerEvent::none(),   // Sun never sets
-        isha: PrayerEvent::virtual_event(isha_secs),
+        isha: PrayerEvent::wave_mapped(isha),
     }
 }
 
@@ -477,38 +1534,106 @@ fn build_polar_night(
     samples: &[AltitudeSample],
     peak: &AltitudeSample,
     nadir: &AltitudeSample,
+    method: &dyn PrayerMethod,
 ) -> Events {
     // Sun never rises → sunrise and maghrib DO NOT EXIST physically
     let dhuhr_secs = peak.seconds; // Virtual noon at peak altitude (below horizon)
 
     // Fajr/Isha first — these define the virtual day boundaries
-    let fajr_secs = wave_mapped_time(samples, peak, nadir, FAJR_ANGLE, true);
-    let isha_secs = wave_mapped_time(samples, peak, nadir, ISHA_ANGLE, false);
+    let fajr = wave_mapped_time(samples, peak, nadir, fajr_altitude(method), true);
+    let isha = wave_mapped_time(samples, peak, nadir, isha_altitude(method), false);
+    let isha_secs = isha.0;
 
     // Virtual Asr: placed proportionally in the afternoon of the virtual day.
     // The "virtual afternoon" runs from dhuhr to isha. In a standard day,
     // Asr falls at roughly 55-60% of the afternoon. We use the same ratio.
     let afternoon = wrapped_duration(dhuhr_secs, isha_secs);
-    let asr_secs = (dhuhr_secs + afternoon * 0.55) % 86400.0;
+    let asr_secs = (dhuhr_secs + afternoon * ASR_AFTERNOON_FRACTION) % 86400.0;
 
     Events {
-        fajr: PrayerEvent::virtual_event(fajr_secs),
+        fajr: PrayerEvent::wave_mapped(fajr),
         sunrise: PrayerEvent::none(),
         dhuhr: PrayerEvent::virtual_event(dhuhr_secs),
         asr: PrayerEvent::virtual_event(asr_secs),
         maghrib: PrayerEvent::none(),
-        isha: PrayerEvent::virtual_event(isha_secs),
+        isha: PrayerEvent::wave_mapped(isha),
+    }
+}
+
+/// Which side of solar noon a crossing is searched on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Limb {
+    /// Ascending, before solar noon.
+    Morning,
+    /// Descending, after solar noon.
+    Evening,
+}
+
+impl std::fmt::Display for Limb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limb::Morning => write!(f, "morning"),
+            Limb::Evening => write!(f, "evening"),
+        }
+    }
+}
+
+impl std::str::FromStr for Limb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "morning" | "am" | "rising" => Ok(Limb::Morning),
+            "evening" | "pm" | "setting" => Ok(Limb::Evening),
+            _ => Err(format!("Unknown limb '{}'. Use 'morning' or 'evening'.", s)),
+        }
     }
 }
 
-/// Return the day scan samples (for debug-wave mode).
+/// Time (UTC HH:MM:SS) at which the sun crosses `altitude` degrees on the
+/// given limb, or `None` if it never reaches that altitude.
+pub fn altitude_crossing(date: NaiveDate, lat: f64, lon: f64, altitude: f64, limb: Limb) -> Option<String> {
+    let samples = day_scan_samples(date, lat, lon);
+    solar::find_crossing(&samples, altitude, limb == Limb::Morning).map(solar::seconds_to_hms)
+}
+
+/// Fajr time (UTC HH:MM:SS) for each candidate depression angle, for calibration.
+///
+/// Angles are positive depressions; `None` where the sun never sinks that
+/// far, so no morning crossing exists.
+pub fn fajr_sweep(date: NaiveDate, lat: f64, lon: f64, angles: &[f64]) -> Vec<(f64, Option<String>)> {
+    let samples = day_scan_samples(date, lat, lon);
+    angles
+        .iter()
+        .map(|&angle| {
+            let time = solar::find_crossing(&samples, -angle, true).map(solar::seconds_to_hms);
+            (angle, time)
+        })
+        .collect()
+}
+
+/// Sampling step of the day scan, in seconds.
+pub const SCAN_RESOLUTION_SECONDS: u32 = 30;
+
+/// Return the day scan samples centred on solar noon (also used by debug-wave mode).
 pub fn day_scan_samples(date: NaiveDate, lat: f64, lon: f64) -> Vec<AltitudeSample> {
-    solar::day_scan(date, lat, lon, 30)
+    day_scan_samples_with(date, lat, lon, SCAN_RESOLUTION_SECONDS)
+}
+
+/// [`day_scan_samples`] every `resolution` seconds.
+///
+/// Crossings are interpolated linearly between samples, so a finer step
+/// mostly matters where the sun crosses an angle at a shallow slope, as
+/// near the polar-day and polar-night edges.
+pub fn day_scan_samples_with(date: NaiveDate, lat: f64, lon: f64, resolution: u32) -> Vec<AltitudeSample> {
+    solar::day_scan_from(date, scan_window_start(lon), lat, lon, resolution)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::method::CalculationMethod;
     use chrono::NaiveDate;
 
     #[test]
@@ -600,21 +1725,35 @@ mod tests {
 
     #[test]
     fn test_standard_asr_altitude() {
-        let asr_alt = standard_asr_altitude(60.0);
+        let asr_alt = standard_asr_altitude(60.0, 1.0);
         println!("Asr altitude for peak 60°: {:.4}°", asr_alt);
         assert!((asr_alt - 32.37).abs() < 0.5);
 
-        let asr_alt_90 = standard_asr_altitude(90.0);
+        let asr_alt_90 = standard_asr_altitude(90.0, 1.0);
         println!("Asr altitude for peak 90°: {:.4}°", asr_alt_90);
         assert!((asr_alt_90 - 45.0).abs() < 0.1);
     }
 
+    #[test]
+    fn test_hanafi_asr_altitude() {
+        let shafii = standard_asr_altitude(60.0, AsrMadhab::Shafii.shadow_factor());
+        let hanafi = standard_asr_altitude(60.0, AsrMadhab::Hanafi.shadow_factor());
+        println!("Peak 60°: Shafi'i {:.4}°, Hanafi {:.4}°", shafii, hanafi);
+        assert!((shafii - 32.3737).abs() < 1e-3);
+        assert!((hanafi - 21.2060).abs() < 1e-3);
+        assert!(shafii - hanafi > 10.0);
+        assert_eq!("Hanafi".parse::<AsrMadhab>(), Ok(AsrMadhab::Hanafi));
+        assert_eq!("shafi'i".parse::<AsrMadhab>(), Ok(AsrMadhab::Shafii));
+        assert_eq!("asr-hanafi".parse::<AsrMadhab>(), Ok(AsrMadhab::Hanafi));
+        assert_eq!(" Asr_Shafii ".parse::<AsrMadhab>(), Ok(AsrMadhab::Shafii));
+    }
+
     #[test]
     fn test_geometric_vs_standard_asr() {
         // Both formulas should give equivalent results for normal peaks
         for peak in [30.0, 45.0, 60.0, 75.0, 90.0] {
-            let geo = geometric_asr_altitude(peak);
-            let std = standard_asr_altitude(peak);
+            let geo = geometric_asr_altitude(peak, 1.0);
+            let std = standard_asr_altitude(peak, 1.0);
             println!("Peak {:.0}°: geometric={:.4}°, standard={:.4}°", peak, geo, std);
             // They use different formulations but should converge
             assert!((geo - std).abs() < 1.0,
@@ -675,6 +1814,51 @@ mod tests {
         assert!(noon < mg, "Noon ({}) must be before projected maghrib ({})", noon, mg);
     }
 
+    #[test]
+    fn test_parse_strategy_variants() {
+        for input in ["strict", "STRICT ", " Strict", "science"] {
+            assert_eq!(parse_strategy(input), Ok(GapStrategy::Strict), "{:?}", input);
+        }
+        for input in ["projected45", "Projected 45", "projected-45", "PROJECTED", "projection"] {
+            assert_eq!(parse_strategy(input), Ok(GapStrategy::Projected45), "{:?}", input);
+        }
+        assert_eq!(parse_strategy(" Auto\n"), Ok(GapStrategy::Auto));
+        assert_eq!(parse_strategy("moonsighting"), Ok(GapStrategy::MoonsightingSeason));
+        for strategy in GapStrategy::ALL {
+            assert_eq!(strategy.to_string().parse::<GapStrategy>(), Ok(strategy));
+        }
+        assert!(parse_strategy("projected 30").unwrap_err().contains("'projected 30'"));
+    }
+
+    #[test]
+    fn test_parse_option_names_like_strategies() {
+        for input in ["middle-of-night", "Middle of Night", "middle_of_night", " MIDDLE "] {
+            assert_eq!(input.parse::<HighLatitudeRule>(), Ok(HighLatitudeRule::MiddleOfNight), "{:?}", input);
+        }
+        assert_eq!("Angle Based".parse::<HighLatitudeRule>(), Ok(HighLatitudeRule::AngleBased));
+        assert_eq!("one_seventh".parse::<HighLatitudeRule>(), Ok(HighLatitudeRule::SeventhOfNight));
+        for input in ["upper-limb", "Upper Limb", "UPPER_LIMB", "upperlimb"] {
+            assert_eq!(input.parse::<SunriseReference>(), Ok(SunriseReference::UpperLimb), "{:?}", input);
+        }
+        assert_eq!(" Centre".parse::<SunriseReference>(), Ok(SunriseReference::Center));
+        assert_eq!("Nadir Split".parse::<MidnightSunRule>(), Ok(MidnightSunRule::NadirSplit));
+        assert_eq!("GEOMETRIC ".parse::<AsrFormula>(), Ok(AsrFormula::Geometric));
+
+        for rule in HighLatitudeRule::ALL {
+            assert_eq!(rule.to_string().parse::<HighLatitudeRule>(), Ok(rule));
+        }
+        for reference in SunriseReference::ALL {
+            assert_eq!(reference.to_string().parse::<SunriseReference>(), Ok(reference));
+        }
+        for rule in MidnightSunRule::ALL {
+            assert_eq!(rule.to_string().parse::<MidnightSunRule>(), Ok(rule));
+        }
+        for formula in [AsrFormula::Standard, AsrFormula::Geometric] {
+            assert_eq!(formula.to_string().parse::<AsrFormula>(), Ok(formula));
+        }
+        assert!("quarter".parse::<HighLatitudeRule>().is_err());
+    }
+
     #[test]
     fn test_mecca_normal_unaffected_by_strategy() {
         let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
@@ -800,6 +1984,29 @@ mod tests {
         assert!(note.contains("54."), "Note should reflect dynamic ref lat, got: {}", note);
     }
 
+    #[test]
+    fn test_projection_anchor_matches_fresh_scan() {
+        // Projection reuses the schedule's own scan for local noon; the times
+        // must equal those anchored on a separate scan of the same day
+        let (lat, lon) = (78.2232, 15.6267);
+        let start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        for date in start.iter_days().take(30) {
+            let schedule = compute_schedule(date, lat, lon, GapStrategy::Projected45);
+
+            let mut expected = Events {
+                sunrise: PrayerEvent::none(),
+                maghrib: PrayerEvent::none(),
+                ..schedule.events.clone()
+            };
+            let local_noon = solar::find_peak(&day_scan_samples(date, lat, lon)).seconds;
+            apply_projection(&mut expected, date, lat, lon, local_noon, Config::default().horizon_angle(), SCAN_RESOLUTION_SECONDS);
+
+            assert_eq!(schedule.events.sunrise.time, expected.sunrise.time, "{}", date);
+            assert_eq!(schedule.events.maghrib.time, expected.maghrib.time, "{}", date);
+            assert_eq!(schedule.events.maghrib.method, EventMethod::Projected);
+        }
+    }
+
     #[test]
     fn test_mecca_regression_unchanged_v62() {
         // Full regression: Mecca Feb 14 must be identical to v6 outputs
@@ -811,8 +2018,8 @@ mod tests {
         assert_eq!(schedule.events.maghrib.method, EventMethod::Standard);
         assert_eq!(schedule.events.sunrise.confidence, 1.0);
         assert_eq!(schedule.events.maghrib.confidence, 1.0);
-        assert!(!schedule.events.sunrise.next_day);
-        assert!(!schedule.events.maghrib.next_day);
+        assert_eq!(schedule.events.sunrise.day_offset, 0);
+        assert_eq!(schedule.events.maghrib.day_offset, 0);
         assert!(schedule.events.sunrise.note.is_none());
         assert!(schedule.events.maghrib.note.is_none());
 
@@ -824,4 +2031,598 @@ mod tests {
         assert!(e.asr.time.as_ref().unwrap() < e.maghrib.time.as_ref().unwrap());
         assert!(e.maghrib.time.as_ref().unwrap() < e.isha.time.as_ref().unwrap());
     }
+
+    // ─── Calculation Method Tests ────────────────────────────────
+
+    #[test]
+    fn test_default_config_matches_mwl() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let legacy = compute_schedule(date, 21.4225, 39.8262, GapStrategy::Strict);
+        let config = Config::default().with_strategy(GapStrategy::Strict).with_method(CalculationMethod::MWL);
+        let mwl = compute_schedule_with(date, 21.4225, 39.8262, &config);
+        assert_eq!(legacy.events.fajr.time, mwl.events.fajr.time);
+        assert_eq!(legacy.events.isha.time, mwl.events.isha.time);
+    }
+
+    #[test]
+    fn test_methods_change_twilight_times() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let run = |m| compute_schedule_with(date, 21.4225, 39.8262, &Config::default().with_method(m));
+        let mwl = run(CalculationMethod::MWL);
+        let isna = run(CalculationMethod::ISNA);
+        let egypt = run(CalculationMethod::Egyptian);
+
+        // Smaller Fajr angle → later Fajr; larger → earlier
+        assert!(isna.events.fajr.time > mwl.events.fajr.time);
+        assert!(egypt.events.fajr.time < mwl.events.fajr.time);
+        assert_eq!(isna.events.sunrise.time, mwl.events.sunrise.time);
+    }
+
+    #[test]
+    fn test_twilight_crossings_only_when_requested() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let plain = compute_schedule_with(date, 21.4225, 39.8262, &Config::default());
+        assert!(plain.twilight_times.is_none());
+
+        let config = Config::default().with_twilight_times(true);
+        let full = compute_schedule_with(date, 21.4225, 39.8262, &config);
+        assert!(full.twilight_times.unwrap().civil_dawn.is_some());
+    }
+
+    #[test]
+    fn test_umm_al_qura_fixed_isha() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let config = Config::default().with_method(CalculationMethod::UmmAlQura);
+        let s = compute_schedule_with(date, 21.4225, 39.8262, &config);
+        let maghrib = hms_to_seconds(s.events.maghrib.time.as_ref().unwrap());
+        let isha = hms_to_seconds(s.events.isha.time.as_ref().unwrap());
+        assert!((isha - maghrib - 5400.0).abs() < 1.5, "Isha must be 90 min after Maghrib");
+        assert_eq!(s.events.isha.method, EventMethod::Standard);
+    }
+
+    #[test]
+    fn test_umm_al_qura_ramadan_isha() {
+        // 1 March 2026 falls in Ramadan 1447
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert_eq!(crate::hijri::gregorian_to_hijri(date).month, 9);
+        let gap = |config: &Config| {
+            let s = compute_schedule_with(date, 21.4225, 39.8262, config);
+            println!("  maghrib={:?} isha={:?} {:?}", s.events.maghrib.time, s.events.isha.time, s.events.isha.note);
+            hms_to_seconds(s.events.isha.time.as_ref().unwrap()) - hms_to_seconds(s.events.maghrib.time.as_ref().unwrap())
+        };
+
+        let uaq = Config::default().with_method(CalculationMethod::UmmAlQura);
+        assert!((gap(&uaq) - 7200.0).abs() < 1.5, "Isha must be 120 min after Maghrib in Ramadan");
+        assert_eq!(uaq.computation(21.4225).ramadan_isha_minutes, Some(120));
+
+        // An explicit interval wins over the Ramadan one
+        let custom = uaq.with_isha_rule(Some(IshaRule::FixedAfterMaghrib(75)));
+        assert!((gap(&custom) - 4500.0).abs() < 1.5);
+        assert_eq!(custom.computation(21.4225).ramadan_isha_minutes, None);
+    }
+
+    #[test]
+    fn test_middle_of_night_tromso_may() {
+        // Tromsø in mid-May: the sun still sets but never reaches 18°
+        let date = NaiveDate::from_ymd_opt(2026, 5, 12).unwrap();
+        let (lat, lon) = (69.6492, 18.9553);
+        let wave = compute_schedule_with(date, lat, lon, &Config::default());
+        assert_eq!(wave.state, DayState::Normal);
+        assert_eq!(wave.events.fajr.method, EventMethod::Virtual);
+        assert!(wave.events.fajr.wave_fit.is_some());
+
+        let config = Config::default().with_high_latitude_rule(HighLatitudeRule::MiddleOfNight);
+        let s = compute_schedule_with(date, lat, lon, &config);
+        println!("  sunset={:?} fajr={:?} isha={:?} sunrise={:?}",
+            s.events.maghrib.time, s.events.fajr.time, s.events.isha.time, s.events.sunrise.time);
+        for e in [&s.events.fajr, &s.events.isha] {
+            assert_eq!(e.method, EventMethod::Virtual);
+            assert_eq!(e.note.as_deref(), Some("Middle of the night rule"));
+            assert!(e.wave_fit.is_none());
+        }
+        assert_eq!(s.events.fajr.time, s.events.isha.time);
+
+        // The midpoint is equidistant from sunset and sunrise
+        let sunset = hms_to_seconds(s.events.maghrib.time.as_ref().unwrap());
+        let sunrise = hms_to_seconds(s.events.sunrise.time.as_ref().unwrap());
+        let middle = hms_to_seconds(s.events.fajr.time.as_ref().unwrap());
+        assert!((wrapped_duration(sunset, middle) - wrapped_duration(middle, sunrise)).abs() < 2.0);
+
+        // Where 18° is reached the rule changes nothing
+        let winter = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let a = compute_schedule_with(winter, lat, lon, &Config::default());
+        let b = compute_schedule_with(winter, lat, lon, &config);
+        assert_eq!(a.events.fajr.time, b.events.fajr.time);
+        assert_eq!(a.events.isha.time, b.events.isha.time);
+    }
+
+    #[test]
+    fn test_seventh_of_night_65n_june() {
+        // 65°N in June: a short night that never reaches 18°
+        let date = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap();
+        let (lat, lon) = (65.0, 25.47);
+        let angle = compute_schedule_with(date, lat, lon, &Config::default());
+        let config = Config::default().with_high_latitude_rule(HighLatitudeRule::SeventhOfNight);
+        let s = compute_schedule_with(date, lat, lon, &config);
+        assert_eq!(s.state, DayState::Normal);
+        println!("  angle:   fajr={:?} isha={:?}", angle.events.fajr.time, angle.events.isha.time);
+        println!("  seventh: fajr={:?} isha={:?}", s.events.fajr.time, s.events.isha.time);
+
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        let (sunset, sunrise) = (secs(&s.events.maghrib), secs(&s.events.sunrise));
+        let seventh = wrapped_duration(sunset, sunrise) / 7.0;
+        assert!((wrapped_duration(secs(&s.events.fajr), sunrise) - seventh).abs() < 2.0);
+        assert!((wrapped_duration(sunset, secs(&s.events.isha)) - seventh).abs() < 2.0);
+        assert_eq!(s.events.isha.note.as_deref(), Some("One-seventh of the night rule"));
+        assert_ne!(s.events.fajr.time, angle.events.fajr.time);
+        assert_ne!(s.events.isha.time, angle.events.isha.time);
+
+        // Midnight sun: no sunset, so the projection still applies
+        let polar = compute_schedule_with(date, 69.6492, 18.9553, &config);
+        let projected = compute_schedule_with(date, 69.6492, 18.9553, &Config::default());
+        assert_eq!(polar.state, DayState::MidnightSun);
+        assert_eq!(polar.events.isha.time, projected.events.isha.time);
+        assert_eq!("seventh".parse::<HighLatitudeRule>(), Ok(HighLatitudeRule::SeventhOfNight));
+    }
+
+    #[test]
+    fn test_angle_based_vs_seventh_of_night() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap();
+        let (lat, lon) = (65.0, 25.47);
+        let solve = |rule| compute_schedule_with(date, lat, lon, &Config::default().with_high_latitude_rule(rule));
+        let angle = solve(HighLatitudeRule::AngleBased);
+        let seventh = solve(HighLatitudeRule::SeventhOfNight);
+        println!("  angle-based: fajr={:?} isha={:?}", angle.events.fajr.time, angle.events.isha.time);
+        println!("  seventh:     fajr={:?} isha={:?}", seventh.events.fajr.time, seventh.events.isha.time);
+
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        let (sunset, sunrise) = (secs(&angle.events.maghrib), secs(&angle.events.sunrise));
+        let night = wrapped_duration(sunset, sunrise);
+        assert!((wrapped_duration(secs(&angle.events.fajr), sunrise) - night * 18.0 / 60.0).abs() < 2.0);
+        assert!((wrapped_duration(sunset, secs(&angle.events.isha)) - night * 17.0 / 60.0).abs() < 2.0);
+
+        // 18/60 and 17/60 of the night are both more than a seventh, so
+        // angle-based Fajr comes earlier and Isha later
+        let before_sunrise = |s: &Schedule| wrapped_duration(secs(&s.events.fajr), sunrise);
+        let after_sunset = |s: &Schedule| wrapped_duration(sunset, secs(&s.events.isha));
+        assert!(before_sunrise(&angle) > before_sunrise(&seventh));
+        assert!(after_sunset(&angle) > after_sunset(&seventh));
+        assert_eq!(angle.events.fajr.note.as_deref(), Some("Angle-based night portion rule"));
+    }
+
+    #[test]
+    fn test_tehran_maghrib_after_sunset() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let mwl = compute_schedule_with(date, 35.6892, 51.3890, &Config::default());
+        let tehran = compute_schedule_with(date, 35.6892, 51.3890,
+            &Config::default().with_method(CalculationMethod::Tehran));
+        let delay = hms_to_seconds(tehran.events.maghrib.time.as_ref().unwrap())
+            - hms_to_seconds(mwl.events.maghrib.time.as_ref().unwrap());
+        assert!(delay > 10.0 * 60.0 && delay < 30.0 * 60.0, "4.5° Maghrib should follow sunset, delay {}s", delay);
+    }
+
+    #[test]
+    fn test_fixed_isha_follows_projected_maghrib() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let config = Config::default().with_method(CalculationMethod::UmmAlQura);
+        let s = compute_schedule_with(date, 69.6492, 18.9553, &config);
+        assert_eq!(s.events.maghrib.method, EventMethod::Projected);
+        assert_eq!(s.events.isha.method, EventMethod::Projected);
+    }
+
+    #[test]
+    fn test_fajr_18_with_fixed_75_min_isha() {
+        // Berlin at the equinox: an 18° Fajr with Isha 75 min after Maghrib
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let config = Config::default()
+            .with_method(CalculationMethod::ISNA)
+            .with_fajr_angle(Some(18.0))
+            .with_isha_rule(Some(IshaRule::FixedAfterMaghrib(75)));
+        let s = compute_schedule_with(date, 52.52, 13.405, &config);
+        let mwl = compute_schedule_with(date, 52.52, 13.405, &Config::default());
+        println!("fajr={:?} maghrib={:?} isha={:?}", s.events.fajr.time, s.events.maghrib.time, s.events.isha.time);
+
+        assert_eq!(s.events.fajr.time, mwl.events.fajr.time, "Fajr must use the 18° override");
+        let maghrib = hms_to_seconds(s.events.maghrib.time.as_ref().unwrap());
+        let isha = hms_to_seconds(s.events.isha.time.as_ref().unwrap());
+        assert!((isha - maghrib - 4500.0).abs() < 1.5, "Isha must be 75 min after Maghrib");
+
+        // Same rule on a midnight-sun day follows the projected Maghrib
+        let summer = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let polar = compute_schedule_with(summer, 69.6492, 18.9553, &config);
+        let maghrib = hms_to_seconds(polar.events.maghrib.time.as_ref().unwrap());
+        let isha = hms_to_seconds(polar.events.isha.time.as_ref().unwrap());
+        assert_eq!(polar.events.isha.method, EventMethod::Projected);
+        assert!((isha - maghrib - 4500.0).abs() < 1.5);
+    }
+
+    #[test]
+    fn test_wave_fit_midnight_sun_fajr() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let s = compute_schedule_with(date, 69.6492, 18.9553, &Config::default().with_strategy(GapStrategy::Strict));
+        assert_eq!(s.state, DayState::MidnightSun);
+        let fit = s.events.fajr.wave_fit.expect("virtual Fajr carries its fit");
+        println!("fajr {:?} fit {:?}", s.events.fajr.time, fit);
+        assert_ne!(fit.method, WaveFitMethod::DirectCrossing);
+        // The sun stays above the horizon, far from the -18° target
+        assert!(fit.residual > 18.0, "residual {}", fit.residual);
+
+        // Real crossings carry no fit
+        let cairo = compute_schedule_with(NaiveDate::from_ymd_opt(2026, 3, 20).unwrap(), 30.0444, 31.2357, &Config::default());
+        assert!(cairo.events.fajr.wave_fit.is_none());
+    }
+
+    #[test]
+    fn test_rounding_policies() {
+        let at = |h, m, s| NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(h, m, s).unwrap().and_utc();
+        let hm = |t: DateTime<Utc>| t.format("%H:%M:%S").to_string();
+        let fajr = at(5, 17, 40);
+        let isha = at(19, 2, 10);
+
+        assert_eq!(hm(RoundingPolicy::Nearest.round(PrayerKind::Fajr, fajr)), "05:18:00");
+        assert_eq!(hm(RoundingPolicy::Nearest.round(PrayerKind::Isha, isha)), "19:02:00");
+        assert_eq!(hm(RoundingPolicy::FajrDown.round(PrayerKind::Fajr, fajr)), "05:17:00");
+        assert_eq!(hm(RoundingPolicy::IshaUp.round(PrayerKind::Isha, isha)), "19:03:00");
+        // Each targets only its own prayer
+        assert_eq!(hm(RoundingPolicy::FajrDown.round(PrayerKind::Isha, isha)), "19:02:00");
+        assert_eq!(hm(RoundingPolicy::IshaUp.round(PrayerKind::Fajr, fajr)), "05:18:00");
+        assert_eq!(hm(RoundingPolicy::AllUp.round(PrayerKind::Dhuhr, at(12, 0, 0))), "12:00:00");
+        assert_eq!(RoundingPolicy::AllUp.round(PrayerKind::Isha, at(23, 59, 1)), at(23, 59, 0) + chrono::Duration::minutes(1));
+
+        assert_eq!("Fajr-Down".parse::<RoundingPolicy>(), Ok(RoundingPolicy::FajrDown));
+        assert!("ceil".parse::<RoundingPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_nadir_split_tromso_midsummer() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let config = Config::default()
+            .with_strategy(GapStrategy::Strict)
+            .with_midnight_sun_rule(MidnightSunRule::NadirSplit);
+        let s = compute_schedule_with(date, 69.6492, 18.9553, &config);
+        assert_eq!(s.state, DayState::MidnightSun);
+        println!("asr={:?} maghrib={:?} isha={:?} midnight={:?}",
+            s.events.asr.time, s.events.maghrib.time, s.events.isha.time, s.solar.virtual_midnight_utc);
+
+        let dhuhr = hms_to_seconds(s.events.dhuhr.time.as_ref().unwrap());
+        let after_dhuhr = |t: &Option<String>| wrapped_duration(dhuhr, hms_to_seconds(t.as_ref().unwrap()));
+        let (asr, maghrib, isha) = (after_dhuhr(&s.events.asr.time), after_dhuhr(&s.events.maghrib.time), after_dhuhr(&s.events.isha.time));
+        let midnight = after_dhuhr(&s.solar.virtual_midnight_utc);
+        assert!(asr < maghrib && maghrib < isha && isha < midnight);
+        assert_eq!(s.events.maghrib.method, EventMethod::Virtual);
+        assert_eq!(s.events.isha.method, EventMethod::Virtual);
+        assert!(s.events.isha.note.as_deref().unwrap().contains("solar midnight"));
+
+        // The default rule leaves Maghrib absent under Strict
+        let strict = compute_schedule_with(date, 69.6492, 18.9553, &Config::default().with_strategy(GapStrategy::Strict));
+        assert_eq!(strict.events.maghrib.method, EventMethod::None);
+    }
+
+    #[test]
+    fn test_fajr_sweep_cairo_monotonic() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let angles: Vec<f64> = (0..=10).map(|i| 15.0 + i as f64 * 0.5).collect();
+        let sweep = fajr_sweep(date, 30.0444, 31.2357, &angles);
+
+        assert_eq!(sweep.len(), angles.len());
+        let secs: Vec<f64> = sweep.iter()
+            .map(|(angle, t)| {
+                let t = t.as_ref().unwrap_or_else(|| panic!("{}° not reached in Cairo", angle));
+                println!("  {:>4.1}° -> {}", angle, t);
+                hms_to_seconds(t)
+            })
+            .collect();
+
+        // Deeper angle → earlier Fajr
+        for w in secs.windows(2) {
+            assert!(w[1] < w[0], "sweep not monotonic: {:?}", secs);
+        }
+
+        // Matches the MWL schedule at 18°
+        let mwl = compute_schedule(date, 30.0444, 31.2357, GapStrategy::Strict);
+        let at_18 = sweep.iter().find(|(a, _)| *a == 18.0).unwrap().1.clone();
+        assert_eq!(at_18, mwl.events.fajr.time);
+    }
+
+    #[test]
+    fn test_fajr_sweep_unreached_angle() {
+        // Oslo at the solstice: the sun never sinks 18° below the horizon
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let sweep = fajr_sweep(date, 59.9139, 10.7522, &[6.0, 18.0]);
+        assert!(sweep[0].1.is_some());
+        assert!(sweep[1].1.is_none());
+    }
+
+    #[test]
+    fn test_events_iter_chronological() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let mut events = compute_schedule(date, 21.4225, 39.8262, GapStrategy::Strict).events;
+
+        let kinds: Vec<PrayerKind> = events.iter().map(|(k, _)| k).collect();
+        assert_eq!(kinds, PrayerKind::ALL.to_vec());
+
+        let secs: Vec<f64> = events.iter().map(|(_, e)| e.seconds_or(f64::NAN)).collect();
+        for w in secs.windows(2) {
+            assert!(w[0] < w[1], "events out of order: {:?}", secs);
+        }
+
+        for (_, event) in events.iter_mut() {
+            event.confidence = 0.25;
+        }
+        assert!(events.iter().all(|(_, e)| e.confidence == 0.25));
+        assert_eq!(events.get(PrayerKind::Isha).confidence, 0.25);
+    }
+
+    #[test]
+    fn test_interpolated_nadir_svalbard() {
+        let (lat, lon) = (78.2232, 15.6267);
+        let solstice = NaiveDate::from_ymd_opt(2026, 12, 21).unwrap();
+
+        let s = compute_schedule(solstice, lat, lon, GapStrategy::Strict);
+        assert_eq!(s.state, DayState::PolarNight);
+        let raw = solar::find_nadir(&day_scan_samples(solstice, lat, lon));
+        assert!(s.solar.min_altitude <= raw.altitude);
+
+        // Anti-transit at 15.6°E near the December solstice: ~22:56 UTC
+        let nadir = hms_to_seconds(&s.solar.nadir_utc);
+        println!("  raw={} fine={}", solar::seconds_to_hms(raw.seconds), s.solar.nadir_utc);
+        assert!((22.75 * 3600.0..23.2 * 3600.0).contains(&nadir), "nadir at {}", s.solar.nadir_utc);
+
+        // Over a week the night's anti-transit drifts ~30s/day; the raw scan
+        // minimum snaps to the 30s grid, the interpolated one follows the trend.
+        let mut raw_series = Vec::new();
+        let mut fine_series = Vec::new();
+        for i in -3..=3 {
+            let date = solstice + chrono::Duration::days(i);
+            let samples = day_scan_samples(date, lat, lon);
+            let peak = solar::find_peak(&samples);
+            let night: Vec<AltitudeSample> = samples.iter().copied().filter(|x| x.seconds > peak.seconds).collect();
+            raw_series.push(solar::find_nadir(&night).seconds);
+
+            let s = compute_schedule(date, lat, lon, GapStrategy::Strict);
+            fine_series.push(hms_to_seconds(s.solar.virtual_midnight_utc.as_ref().unwrap()));
+        }
+
+        // Largest deviation from a straight-line trend
+        let residual = |ys: &[f64]| {
+            let n = ys.len() as f64;
+            let mx = (n - 1.0) / 2.0;
+            let my = ys.iter().sum::<f64>() / n;
+            let sxy: f64 = ys.iter().enumerate().map(|(i, y)| (i as f64 - mx) * (y - my)).sum();
+            let sxx: f64 = (0..ys.len()).map(|i| (i as f64 - mx).powi(2)).sum();
+            let slope = sxy / sxx;
+            ys.iter().enumerate()
+                .map(|(i, y)| (y - (my + slope * (i as f64 - mx))).abs())
+                .fold(0.0, f64::max)
+        };
+        let (r_raw, r_fine) = (residual(&raw_series), residual(&fine_series));
+        println!("  max residual raw={:.1}s fine={:.1}s", r_raw, r_fine);
+        assert!(r_fine < 2.0);
+        assert!(r_fine < r_raw);
+    }
+
+    #[test]
+    fn test_no_virtual_midnight_on_normal_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let s = compute_schedule(date, 21.4225, 39.8262, GapStrategy::Strict);
+        assert!(s.solar.virtual_midnight_utc.is_none());
+    }
+
+    #[test]
+    fn test_custom_crossing_between_fajr_and_sunrise() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let (lat, lon) = (30.0444, 31.2357);
+        let s = compute_schedule(date, lat, lon, GapStrategy::Strict);
+
+        let nautical = altitude_crossing(date, lat, lon, -10.0, Limb::Morning).unwrap();
+        let fajr = hms_to_seconds(s.events.fajr.time.as_ref().unwrap());
+        let sunrise = hms_to_seconds(s.events.sunrise.time.as_ref().unwrap());
+        let t = hms_to_seconds(&nautical);
+        println!("  Cairo fajr={} -10°={} sunrise={}", solar::seconds_to_hms(fajr), nautical, solar::seconds_to_hms(sunrise));
+        assert!(fajr < t && t < sunrise);
+
+        let evening = hms_to_seconds(&altitude_crossing(date, lat, lon, -10.0, Limb::Evening).unwrap());
+        assert!(evening > hms_to_seconds(s.events.maghrib.time.as_ref().unwrap()));
+
+        // Cairo's sun never climbs to 80° in March
+        assert!(altitude_crossing(date, lat, lon, 80.0, Limb::Morning).is_none());
+    }
+
+    #[test]
+    fn test_custom_prayer_method() {
+        /// Fajr 17.5°, Isha 17°, Hanafi Asr.
+        struct Custom;
+
+        impl PrayerMethod for Custom {
+            fn fajr_angle(&self) -> f64 { 17.5 }
+            fn isha_rule(&self) -> IshaRule { IshaRule::Angle(17.0) }
+            fn maghrib_rule(&self) -> MaghribRule { MaghribRule::Sunset }
+            fn asr_factor(&self) -> f64 { 2.0 }
+        }
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let (lat, lon) = (30.0444, 31.2357);
+        let strict = Config::default().with_strategy(GapStrategy::Strict);
+        let custom = compute_schedule_with_method(date, lat, lon, &strict, &Custom);
+        let mwl = compute_schedule_with_method(date, lat, lon, &strict, &CalculationMethod::MWL);
+
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+
+        // Shallower Fajr angle → later Fajr, matching a direct 17.5° crossing
+        assert!(secs(&custom.events.fajr) > secs(&mwl.events.fajr));
+        assert_eq!(custom.events.fajr.time, altitude_crossing(date, lat, lon, -17.5, Limb::Morning));
+
+        // Hanafi Asr (shadow = 2× object + noon shadow) comes well after Shafi'i Asr
+        let delay = secs(&custom.events.asr) - secs(&mwl.events.asr);
+        println!("  Hanafi Asr delay: {:.0} min", delay / 60.0);
+        assert!(delay > 30.0 * 60.0 && delay < 90.0 * 60.0, "delay {}s", delay);
+        assert!(secs(&custom.events.asr) < secs(&custom.events.maghrib));
+
+        // Same Isha angle and sunset rule as MWL
+        assert_eq!(custom.events.isha.time, mwl.events.isha.time);
+        assert_eq!(custom.events.maghrib.time, mwl.events.maghrib.time);
+    }
+
+    #[test]
+    fn test_low_peak_asr_reported() {
+        // Tromsø in late January: a Normal day with the sun peaking near 2°
+        let (lat, lon) = (69.6492, 18.9553);
+        let date = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let s = compute_schedule(date, lat, lon, GapStrategy::Strict);
+        println!("  peak {:.2}° asr={:?} warnings={:?}", s.solar.max_altitude, s.events.asr.time, s.warnings);
+
+        assert_eq!(s.state, DayState::Normal);
+        assert!(s.solar.max_altitude > 1.5 && s.solar.max_altitude < 3.0);
+        assert_eq!(s.events.asr.method, EventMethod::Standard);
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        assert!(secs(&s.events.dhuhr) < secs(&s.events.asr) && secs(&s.events.asr) < secs(&s.events.maghrib));
+
+        // Both formulas are evaluated and surfaced, not silently collapsed
+        let warning = s.warnings.iter().find(|w| w.starts_with("Low sun")).expect("low-peak warning");
+        assert!(warning.contains("geometric"));
+        let (standard, geometric) = (standard_asr_altitude(s.solar.max_altitude, 1.0), geometric_asr_altitude(s.solar.max_altitude, 1.0));
+        assert!((standard - geometric).abs() <= ASR_FORMULA_TOLERANCE);
+
+        let geo = compute_schedule_with(date, lat, lon,
+            &Config::default().with_strategy(GapStrategy::Strict).with_asr_formula(AsrFormula::Geometric));
+        assert_eq!(geo.events.asr.time, s.events.asr.time);
+    }
+
+    #[test]
+    fn test_sub_horizon_peak_asr_is_virtual_afternoon() {
+        // Sun's upper limb clears the horizon but its centre stays below 0°
+        let date = NaiveDate::from_ymd_opt(2026, 1, 16).unwrap();
+        let s = compute_schedule(date, 69.6492, 18.9553, GapStrategy::Strict);
+        assert_eq!(s.state, DayState::Normal);
+        assert!(s.solar.max_altitude <= 0.0);
+
+        assert_eq!(s.events.asr.method, EventMethod::Virtual);
+        assert!(s.warnings.iter().any(|w| w.contains("undefined")));
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        assert!(secs(&s.events.dhuhr) < secs(&s.events.asr) && secs(&s.events.asr) < secs(&s.events.maghrib));
+    }
+
+    #[test]
+    fn test_upper_limb_sunrise_before_center_cairo() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let (lat, lon) = (30.0444, 31.2357);
+        let limb = compute_schedule_with(date, lat, lon, &Config::default());
+        let center = compute_schedule_with(date, lat, lon,
+            &Config::default().with_sunrise_reference(SunriseReference::Center));
+
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        let sunrise_lead = secs(&center.events.sunrise) - secs(&limb.events.sunrise);
+        let sunset_lag = secs(&limb.events.maghrib) - secs(&center.events.maghrib);
+        println!("  Cairo sunrise lead={:.0}s sunset lag={:.0}s", sunrise_lead, sunset_lag);
+        assert!((30.0..180.0).contains(&sunrise_lead));
+        assert!((30.0..180.0).contains(&sunset_lag));
+        assert_eq!(limb.events.dhuhr.time, center.events.dhuhr.time);
+    }
+
+    #[test]
+    fn test_adjustments_shift_events() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let (lat, lon) = (21.4225, 39.8262);
+        let base = compute_schedule_with(date, lat, lon, &Config::default());
+        let adjustments = crate::config::parse_kv_offsets("fajr=+2,isha=-1").unwrap();
+        let adjusted = compute_schedule_with(date, lat, lon, &Config::default().with_adjustments(adjustments));
+
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+        assert_eq!(secs(&adjusted.events.fajr) - secs(&base.events.fajr), 120.0);
+        assert_eq!(secs(&adjusted.events.isha) - secs(&base.events.isha), -60.0);
+        assert_eq!(adjusted.events.dhuhr.time, base.events.dhuhr.time);
+        assert_eq!(adjusted.events.fajr.note.as_deref(), Some("adjusted +2 min"));
+    }
+
+    #[test]
+    fn test_no_warnings_on_ordinary_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let s = compute_schedule(date, 21.4225, 39.8262, GapStrategy::Strict);
+        assert!(s.warnings.is_empty(), "{:?}", s.warnings);
+    }
+
+    #[test]
+    fn test_tromso_persistent_twilight_flagged() {
+        // Mid-May in Tromsø: the sun still sets but stays above -18°
+        let date = NaiveDate::from_ymd_opt(2026, 5, 15).unwrap();
+        let s = compute_schedule(date, 69.6492, 18.9553, GapStrategy::Projected45);
+        println!("Tromsø {}: state={} min_alt={:.2}° fajr={:?} ({:?})",
+            date, s.state, s.solar.min_altitude, s.events.fajr.time, s.events.fajr.method);
+
+        assert_eq!(s.state, DayState::Normal);
+        assert!(s.solar.min_altitude > -18.0);
+        assert!(s.twilight_persists);
+        assert_eq!(s.events.fajr.method, EventMethod::Virtual);
+        assert!((s.events.fajr.confidence - 0.7).abs() < 1e-6);
+        assert!((s.events.isha.confidence - 0.7).abs() < 1e-6);
+        assert!(s.warnings.iter().any(|w| w.contains("persistent twilight")));
+
+        let winter = compute_schedule(NaiveDate::from_ymd_opt(2026, 2, 14).unwrap(), 69.6492, 18.9553, GapStrategy::Projected45);
+        assert!(!winter.twilight_persists);
+    }
+
+    #[test]
+    fn test_fajr_sunrise_gap_guard() {
+        /// A far too shallow 3° Fajr that lands just before sunrise.
+        struct Shallow;
+
+        impl PrayerMethod for Shallow {
+            fn fajr_angle(&self) -> f64 { 3.0 }
+            fn isha_rule(&self) -> IshaRule { IshaRule::Angle(17.0) }
+            fn maghrib_rule(&self) -> MaghribRule { MaghribRule::Sunset }
+        }
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let (lat, lon) = (30.0444, 31.2357);
+        let base = Config::default().with_strategy(GapStrategy::Strict);
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+
+        let raw = compute_schedule_with_method(date, lat, lon, &base, &Shallow);
+        let raw_gap = secs(&raw.events.sunrise) - secs(&raw.events.fajr);
+        println!("  raw Fajr→sunrise gap: {:.1} min", raw_gap / 60.0);
+        assert!(raw_gap < 30.0 * 60.0);
+        assert!(raw.warnings.is_empty(), "guard is off by default");
+
+        let guarded = compute_schedule_with_method(date, lat, lon, &base.with_min_fajr_sunrise_gap(Some(30)), &Shallow);
+        println!("  {:?}", guarded.warnings);
+        assert!(guarded.warnings.iter().any(|w| w.contains("min_fajr_sunrise_gap")));
+        assert_eq!(secs(&guarded.events.sunrise) - secs(&guarded.events.fajr), 30.0 * 60.0);
+        assert!(guarded.events.fajr.note.as_deref().unwrap().contains("30 min before sunrise"));
+
+        // A normal MWL Fajr is far outside the guard
+        let mwl = compute_schedule_with(date, lat, lon, &base.with_min_fajr_sunrise_gap(Some(30)));
+        assert!(mwl.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_moonsighting_season_midsummer_52n() {
+        // Amsterdam at the June solstice: 18° is never reached
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let (lat, lon) = (52.3676, 4.9041);
+        let secs = |e: &PrayerEvent| hms_to_seconds(e.time.as_ref().unwrap());
+
+        let angular = compute_schedule(date, lat, lon, GapStrategy::Strict);
+        assert_eq!(angular.events.fajr.method, EventMethod::Virtual);
+
+        let s = compute_schedule(date, lat, lon, GapStrategy::MoonsightingSeason);
+        for e in [&s.events.fajr, &s.events.isha] {
+            println!("  {:?} {:?}", e.time, e.note);
+            assert_eq!(e.method, EventMethod::Standard);
+        }
+        let fajr_gap = (secs(&s.events.sunrise) - secs(&s.events.fajr)) / 60.0;
+        let isha_gap = (secs(&s.events.isha) - secs(&s.events.maghrib)) / 60.0;
+        println!("  Fajr {:.1} min before sunrise, Isha {:.1} min after sunset", fajr_gap, isha_gap);
+        assert!((fajr_gap - 120.5).abs() < 0.5, "fajr gap {:.1}", fajr_gap);
+        assert!((isha_gap - 80.5).abs() < 0.5, "isha gap {:.1}", isha_gap);
+        assert!(secs(&s.events.fajr) < secs(&s.events.sunrise));
+        assert!(secs(&s.events.maghrib) < secs(&s.events.isha));
+        assert!(!s.twilight_persists);
+
+        // In winter 18° is reached, but Fajr is never earlier than the seasonal bound
+        let winter = compute_schedule(NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(), lat, lon, GapStrategy::MoonsightingSeason);
+        let (fajr_min, _) = moonsighting_season_minutes(NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(), lat);
+        let winter_gap = (secs(&winter.events.sunrise) - secs(&winter.events.fajr)) / 60.0;
+        println!("  winter Fajr {:.1} min before sunrise (seasonal {:.1})", winter_gap, fajr_min);
+        assert!(winter_gap <= fajr_min + 0.1);
+    }
 }
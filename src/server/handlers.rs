@@ -1,16 +1,21 @@
-use axum::extract::{Query, State};
-use axum::http::{header, StatusCode};
+use axum::extract::{Extension, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, StatusCode};
 use axum::response::{Html, IntoResponse, Json, Response};
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Instant;
 
-use crate::location::{builtin_city_list, ResolveOptions, country_display_name, format_coords};
-use crate::location::types::LocationError;
-use crate::schedule::GapStrategy;
-use crate::solver::Solver;
+use crate::location::{builtin_cities_in_country, builtin_city_list, normalize_longitude, Granularity, LocationResolver, ResolvedLocation, ResolveOptions, TzSource, country_display_name, format_coords};
+use crate::location::types::{AmbiguousCandidate, LocationError};
+use crate::config::{self, Config};
+use crate::method::{CalculationMethod, IshaRule, MaghribRule};
+use crate::qibla::{compass_point, Qibla};
+use crate::schedule::{AsrMadhab, CombinedTimes, GapStrategy, HighLatitudeRule, Limb, MidnightSunRule, PrayerKind, RoundingPolicy, SunriseReference};
+use crate::solver::{prayer_extremes, schedule_drift, CompactDay, DriftReport, PrayerExtremes, PrayerWindow, Solver, SolverOutput};
+use crate::timezone;
 
+use super::rate_limit;
 use super::state::{AppState, ComputeCache};
 use super::static_files;
 
@@ -34,7 +39,7 @@ impl IntoResponse for ApiError {
     }
 }
 
-fn api_error(status: StatusCode, msg: impl Into<String>) -> ApiError {
+pub(super) fn api_error(status: StatusCode, msg: impl Into<String>) -> ApiError {
     ApiError(status, msg.into())
 }
 
@@ -60,12 +65,30 @@ pub async fn script() -> Response {
         .into_response()
 }
 
+// ─── GET /healthz ────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct Health {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub uptime_secs: u64,
+}
+
+/// Liveness/readiness probe: never touches the resolver or the caches.
+pub async fn healthz(State(state): State<Arc<AppState>>) -> Json<Health> {
+    Json(Health { status: "ok", version: crate::VERSION, uptime_secs: state.started.elapsed().as_secs() })
+}
+
 // ─── GET /api/resolve ────────────────────────────────────────────
 
 #[derive(Deserialize)]
 pub struct ResolveQuery {
     pub query: Option<String>,
     pub country: Option<String>,
+    /// Bypass the location cache and overwrite its entry.
+    pub refresh: Option<bool>,
+    /// How fine a place to resolve: city (default), suburb or poi.
+    pub granularity: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -81,10 +104,12 @@ pub struct ResolveResponse {
     pub formatted_coords: String,
     pub source: String,
     pub confidence: f64,
+    pub granularity: Granularity,
+    pub tz_source: TzSource,
 }
 
 #[derive(Serialize)]
-struct AmbiguousOption {
+pub struct AmbiguousOption {
     name: String,
     country: String,
     country_code: String,
@@ -93,6 +118,19 @@ struct AmbiguousOption {
     lon: f64,
 }
 
+impl From<&AmbiguousCandidate> for AmbiguousOption {
+    fn from(c: &AmbiguousCandidate) -> Self {
+        Self {
+            name: c.name.clone(),
+            country: c.country_name.clone(),
+            country_code: c.country.clone(),
+            tz: c.tz.clone(),
+            lat: c.lat,
+            lon: c.lon,
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct AmbiguousResponse {
     multiple: bool,
@@ -111,37 +149,22 @@ pub async fn resolve(
         return Err(api_error(StatusCode::BAD_REQUEST, "Missing 'query' parameter").into_response());
     }
 
+    let granularity = match params.granularity.as_deref() {
+        Some(g) => g.parse::<Granularity>()
+            .map_err(|e| api_error(StatusCode::BAD_REQUEST, e).into_response())?,
+        None => Granularity::City,
+    };
+
     let opts = ResolveOptions {
         country: params.country.clone(),
         topk: None,
+        refresh: params.refresh.unwrap_or(false),
+        granularity,
     };
 
-    let resolved = {
-        let mut resolver = state.resolver.lock().unwrap();
-        resolver.resolve_city_with_opts(query, &opts)
-    };
+    let resolved = state.resolve_city(query, &opts);
 
-    let resolved = match resolved {
-        Ok(r) => r,
-        Err(LocationError::Ambiguous { query: q, candidates }) => {
-            let resp = AmbiguousResponse {
-                multiple: true,
-                query: q,
-                options: candidates.iter().map(|c| AmbiguousOption {
-                    name: c.name.clone(),
-                    country: c.country_name.clone(),
-                    country_code: c.country.clone(),
-                    tz: c.tz.clone(),
-                    lat: c.lat,
-                    lon: c.lon,
-                }).collect(),
-            };
-            return Err((StatusCode::MULTIPLE_CHOICES, Json(resp)).into_response());
-        }
-        Err(e) => {
-            return Err(api_error(StatusCode::NOT_FOUND, format!("{}", e)).into_response());
-        }
-    };
+    let resolved = resolved.map_err(location_error_response)?;
 
     let elapsed = start.elapsed();
     eprintln!("[{}] GET /api/resolve?query={} -> {} ({:.1}ms)",
@@ -151,23 +174,108 @@ pub async fn resolve(
         elapsed.as_secs_f64() * 1000.0,
     );
 
+    Ok(Json(resolve_response(resolved, granularity)))
+}
+
+fn resolve_response(resolved: ResolvedLocation, granularity: Granularity) -> ResolveResponse {
     let country = resolved.country_code.as_deref().and_then(|cc| {
         let name = country_display_name(cc);
         if name == cc { None } else { Some(name.to_string()) }
     });
 
-    Ok(Json(ResolveResponse {
-        name: resolved.name.clone(),
+    ResolveResponse {
+        name: resolved.name,
         lat: resolved.lat,
         lon: resolved.lon,
-        tz: resolved.tz.clone(),
         tz_label: format!("{} (Local Time)", resolved.tz),
-        country_code: resolved.country_code.clone(),
+        tz: resolved.tz,
+        country_code: resolved.country_code,
         country,
         formatted_coords: format_coords(resolved.lat, resolved.lon),
         source: format!("{}", resolved.source),
         confidence: resolved.resolver_confidence,
-    }))
+        granularity,
+        tz_source: resolved.tz_source,
+    }
+}
+
+// ─── POST /api/resolve-batch ─────────────────────────────────────
+
+/// Most queries a single batch may hold.
+const MAX_RESOLVE_BATCH: usize = 50;
+
+/// One entry of a batch resolve, in request order.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchResolveResult {
+    Ok {
+        query: String,
+        #[serde(flatten)]
+        location: ResolveResponse,
+    },
+    Ambiguous {
+        query: String,
+        options: Vec<AmbiguousOption>,
+    },
+    NotFound {
+        query: String,
+        error: String,
+    },
+    /// Network failures, rate limits and blank queries.
+    Error {
+        query: String,
+        error: String,
+    },
+}
+
+/// Resolve up to 50 city names at once; each goes through the same
+/// fallback chain as `/api/resolve` and fails on its own.
+///
+/// Under a rate limit, every query that misses the location cache costs
+/// the client one more request; once the budget runs out the remaining
+/// misses fail with `error`.
+pub async fn resolve_batch(
+    State(state): State<Arc<AppState>>,
+    client: Option<Extension<rate_limit::Client>>,
+    Json(queries): Json<Vec<String>>,
+) -> Result<Json<Vec<BatchResolveResult>>, ApiError> {
+    let start = Instant::now();
+
+    if queries.len() > MAX_RESOLVE_BATCH {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("A batch holds at most {} queries, got {}", MAX_RESOLVE_BATCH, queries.len())));
+    }
+
+    let results: Vec<_> = queries.into_iter().map(|query| {
+        let trimmed = query.trim();
+        if trimmed.is_empty() {
+            return BatchResolveResult::Error { query, error: "Empty query".into() };
+        }
+        let opts = ResolveOptions::default();
+        let cached = state.resolver.read().unwrap().cached(trimmed, &opts);
+        if cached.is_none() {
+            if let Err(error) = rate_limit::charge(&state, client.as_ref().map(|c| c.0)) {
+                return BatchResolveResult::Error { query, error };
+            }
+        }
+        match cached.map_or_else(|| state.resolve_city(trimmed, &opts), Ok) {
+            Ok(resolved) => BatchResolveResult::Ok { query, location: resolve_response(resolved, Granularity::City) },
+            Err(LocationError::Ambiguous { candidates, .. }) => BatchResolveResult::Ambiguous {
+                query,
+                options: candidates.iter().map(AmbiguousOption::from).collect(),
+            },
+            Err(e @ LocationError::NotFound(_)) => BatchResolveResult::NotFound { query, error: e.to_string() },
+            Err(e) => BatchResolveResult::Error { query, error: e.to_string() },
+        }
+    }).collect();
+
+    eprintln!("[{}] POST /api/resolve-batch -> {} queries ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        results.len(),
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(results))
 }
 
 // ─── GET /api/times ──────────────────────────────────────────────
@@ -181,113 +289,206 @@ pub struct TimesQuery {
     pub tz: Option<String>,
     pub date: Option<String>,
     pub strategy: Option<String>,
+    pub method: Option<String>,
+    pub display_tz: Option<String>,
+    /// Comma-separated extras: `sidereal`, `azimuth`, `makruh`, `twilight_times`, `qibla`.
+    pub include: Option<String>,
+    /// Add combined Dhuhr+Asr / Maghrib+Isha times for travellers.
+    pub combine: Option<bool>,
+    /// Minimum minutes between Fajr and sunrise (guard off when absent).
+    pub min_fajr_gap: Option<u32>,
+    /// `upper-limb` (default) or `center` for sunrise and sunset.
+    pub sunrise_reference: Option<String>,
+    /// Minute offsets per event, e.g. `fajr=+2,isha=-1`.
+    pub adjust: Option<String>,
+    /// Fajr depression angle replacing the method's.
+    pub fajr_angle: Option<f64>,
+    /// Isha depression angle replacing the method's.
+    pub isha_angle: Option<f64>,
+    /// Isha this many minutes after Maghrib instead of the method's rule.
+    pub isha_minutes: Option<u32>,
+    /// `strategy` (default) or `nadir-split` for midnight-sun days.
+    pub midnight_sun: Option<String>,
+    /// `wave` (default) or `middle-of-night` when Fajr/Isha angles are not reached.
+    pub high_lat_rule: Option<String>,
+    /// Round times to minutes: `nearest`, `fajr-down`, `isha-up`, `all-down`, `all-up`.
+    pub round: Option<String>,
+    /// Asr shadow convention: `shafii` (default) or `hanafi`.
+    pub asr: Option<String>,
+    /// Observer height in metres (-500..=9000); lowers the horizon for sunrise and sunset.
+    pub elevation: Option<f64>,
 }
 
-pub async fn prayer_times(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<TimesQuery>,
-) -> Result<impl IntoResponse, Response> {
-    let start = Instant::now();
+impl TimesQuery {
+    fn location(&self) -> LocationParams<'_> {
+        LocationParams {
+            city: self.city.as_deref(),
+            country: self.country.as_deref(),
+            lat: self.lat,
+            lon: self.lon,
+            tz: self.tz.as_deref(),
+        }
+    }
 
-    // Resolve location
-    let resolved = if let Some(ref city) = params.city {
-        let opts = ResolveOptions {
-            country: params.country.clone(),
-            topk: None,
-        };
-        let mut resolver = state.resolver.lock().unwrap();
-        match resolver.resolve_city_with_opts(city, &opts) {
-            Ok(r) => r,
-            Err(LocationError::Ambiguous { query, candidates }) => {
-                let resp = AmbiguousResponse {
-                    multiple: true,
-                    query,
-                    options: candidates.iter().map(|c| AmbiguousOption {
-                        name: c.name.clone(),
-                        country: c.country_name.clone(),
-                        country_code: c.country.clone(),
-                        tz: c.tz.clone(),
-                        lat: c.lat,
-                        lon: c.lon,
-                    }).collect(),
-                };
-                return Err((StatusCode::MULTIPLE_CHOICES, Json(resp)).into_response());
-            }
-            Err(e) => return Err(api_error(StatusCode::NOT_FOUND, format!("{}", e)).into_response()),
+    fn config(&self) -> Result<Config, ApiError> {
+        let mut config = parse_config(self.strategy.as_deref(), self.method.as_deref())?
+            .with_display_tz(parse_display_tz(self.display_tz.as_deref())?)
+            .with_min_fajr_sunrise_gap(self.min_fajr_gap);
+        if let Some(reference) = self.sunrise_reference.as_deref() {
+            let reference: SunriseReference = reference.parse()
+                .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_sunrise_reference(reference);
         }
-    } else if let (Some(lat), Some(lon)) = (params.lat, params.lon) {
-        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
-            return Err(api_error(StatusCode::BAD_REQUEST,
-                "Invalid coordinates. Lat: -90..90, Lon: -180..180").into_response());
+        if let Some(rule) = self.midnight_sun.as_deref() {
+            let rule: MidnightSunRule = rule.parse()
+                .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_midnight_sun_rule(rule);
         }
-        crate::location::LocationResolver::from_manual(lat, lon, params.tz.as_deref())
-    } else {
-        return Err(api_error(StatusCode::BAD_REQUEST,
-            "Provide 'city' or 'lat'+'lon' parameters").into_response());
-    };
-
-    // Apply timezone override
-    let final_resolved = if let Some(ref tz_str) = params.tz {
-        let _: chrono_tz::Tz = tz_str.parse().map_err(|_| {
-            api_error(StatusCode::BAD_REQUEST, format!("Unknown timezone '{}'", tz_str)).into_response()
-        })?;
-        crate::location::ResolvedLocation {
-            tz: tz_str.clone(),
-            ..resolved
+        if let Some(rule) = self.high_lat_rule.as_deref() {
+            let rule: HighLatitudeRule = rule.parse()
+                .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_high_latitude_rule(rule);
         }
-    } else {
-        resolved
-    };
+        if let Some(madhab) = self.asr.as_deref() {
+            let madhab: AsrMadhab = madhab.parse()
+                .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_asr_madhab(Some(madhab));
+        }
+        if let Some(rounding) = self.round.as_deref() {
+            let rounding: RoundingPolicy = rounding.parse()
+                .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_rounding(Some(rounding));
+        }
+        if let Some(adjust) = self.adjust.as_deref() {
+            let adjustments = config::parse_kv_offsets(adjust)
+                .map_err(|e| api_error(StatusCode::BAD_REQUEST, format!("Invalid adjust: {}", e)))?;
+            config = config.with_adjustments(adjustments);
+        }
+        let bad_angle = |e: String| api_error(StatusCode::BAD_REQUEST, e);
+        let fajr_angle = self.fajr_angle.map(config::check_depression_angle).transpose().map_err(bad_angle)?;
+        let isha_angle = self.isha_angle.map(config::check_depression_angle).transpose().map_err(bad_angle)?;
+        let isha = config::isha_override(isha_angle, self.isha_minutes).map_err(bad_angle)?;
+        config = config.with_fajr_angle(fajr_angle).with_isha_rule(isha);
+        if let Some(elevation) = self.elevation {
+            let elevation = config::check_elevation(elevation)
+                .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+            config = config.with_elevation(elevation);
+        }
+        for extra in self.include.as_deref().unwrap_or("").split(',').map(str::trim) {
+            match extra {
+                "" => {}
+                "sidereal" => config = config.with_sidereal(true),
+                "azimuth" => config = config.with_azimuth(true),
+                "makruh" => config = config.with_makruh(true),
+                "twilight_times" => config = config.with_twilight_times(true),
+                "qibla" => config = config.with_qibla(true),
+                other => return Err(api_error(StatusCode::BAD_REQUEST,
+                    format!("Unknown include '{}'. Valid: sidereal, azimuth, makruh, twilight_times, qibla", other))),
+            }
+        }
+        Ok(config)
+    }
 
-    // Parse date
-    let date = match &params.date {
-        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|e| {
-            api_error(StatusCode::BAD_REQUEST, format!("Invalid date '{}': {}", d, e)).into_response()
-        })?,
-        None => Utc::now().naive_utc().date(),
-    };
+    fn date(&self, today: NaiveDate) -> Result<NaiveDate, ApiError> {
+        parse_date(self.date.as_deref(), today)
+    }
+}
+
+pub async fn prayer_times(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TimesQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Response> {
+    let start = Instant::now();
 
-    // Parse strategy
-    let strategy = parse_strategy(params.strategy.as_deref()).map_err(|e| e.into_response())?;
-    let strategy_str = format!("{}", strategy);
+    let final_resolved = resolve_request(&state, &params.location())?;
+    let date = params.date(state.today()).map_err(|e| e.into_response())?;
+    let config = params.config().map_err(|e| e.into_response())?;
+
+    // The day is deterministic: answer a matching If-None-Match without computing
+    let cache_key = ComputeCache::key(final_resolved.lat, final_resolved.lon, &final_resolved.tz,
+        &date.to_string(), &config.cache_fingerprint());
+    let combine = params.combine.unwrap_or(false);
+    let etag = ComputeCache::etag(&cache_key, &format!("{};combine={}", final_resolved.name, combine));
+    if etag_matches(&headers, &etag) {
+        eprintln!("[{}] GET /api/times city={} date={} -> 304 ({:.1}ms)",
+            Utc::now().format("%H:%M:%S"),
+            final_resolved.name, date,
+            start.elapsed().as_secs_f64() * 1000.0,
+        );
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
 
-    // Check cache
-    let cache_key = ComputeCache::key(
-        final_resolved.lat, final_resolved.lon,
-        &date.to_string(), &strategy_str,
+    let log_cached = || eprintln!("[{}] GET /api/times city={} date={} -> CACHED ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        final_resolved.name, date,
+        start.elapsed().as_secs_f64() * 1000.0,
     );
 
-    {
-        let mut cache = state.cache.lock().unwrap();
-        if let Some(cached) = cache.get(&cache_key) {
-            let elapsed = start.elapsed();
-            eprintln!("[{}] GET /api/times city={} date={} -> CACHED ({:.1}ms)",
-                Utc::now().format("%H:%M:%S"),
-                final_resolved.name, date,
-                elapsed.as_secs_f64() * 1000.0,
-            );
-            return Ok(Json(cached));
-        }
+    // Plain days go out as cached, which covers days restored from disk
+    let cached_json = if combine { None } else { state.cache.lock().unwrap().get_json(&cache_key) };
+    if let Some(json) = cached_json {
+        log_cached();
+        let headers = [
+            (HeaderName::from_static("x-cache"), "HIT".to_string()),
+            (header::ETAG, etag),
+            (header::CONTENT_TYPE, "application/json".to_string()),
+        ];
+        return Ok((headers, json).into_response());
     }
 
-    // Solve
-    let solver = Solver::from_resolved(&final_resolved).with_strategy(strategy);
-    let output = solver.solve_with_info(date, false, false, Some(&final_resolved));
+    let (mut output, cached) = compute_day(&state, &final_resolved, date, config).await;
+    if combine {
+        output.combined = Some(CombinedTimes::from_events(&output.events));
+    }
 
-    // Store in cache
-    {
-        let mut cache = state.cache.lock().unwrap();
-        cache.put(cache_key, output.clone());
+    let elapsed = start.elapsed();
+    if cached {
+        log_cached();
+    } else {
+        eprintln!("[{}] GET /api/times city={} date={} -> {} ({:.1}ms)",
+            Utc::now().format("%H:%M:%S"),
+            final_resolved.name, date, output.state,
+            elapsed.as_secs_f64() * 1000.0,
+        );
     }
 
+    let cache_status = if cached { "HIT" } else { "MISS" };
+    Ok(([(HeaderName::from_static("x-cache"), cache_status.to_string()), (header::ETAG, etag)], Json(output)).into_response())
+}
+
+/// Whether `If-None-Match` lists `etag` (weak comparison) or is `*`.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers.get_all(header::IF_NONE_MATCH).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+// ─── GET /api/times.svg ──────────────────────────────────────────
+
+pub async fn prayer_times_svg(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TimesQuery>,
+) -> Result<impl IntoResponse, Response> {
+    let start = Instant::now();
+
+    let final_resolved = resolve_request(&state, &params.location())?;
+    let date = params.date(state.today()).map_err(|e| e.into_response())?;
+    let config = params.config().map_err(|e| e.into_response())?;
+
+    let (output, _) = compute_day(&state, &final_resolved, date, config).await;
+    let svg = crate::export::to_svg(&output);
+
     let elapsed = start.elapsed();
-    eprintln!("[{}] GET /api/times city={} date={} -> {} ({:.1}ms)",
+    eprintln!("[{}] GET /api/times.svg city={} date={} ({:.1}ms)",
         Utc::now().format("%H:%M:%S"),
-        final_resolved.name, date, output.state,
+        final_resolved.name, date,
         elapsed.as_secs_f64() * 1000.0,
     );
 
-    Ok(Json(output))
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml; charset=utf-8")], svg))
 }
 
 // ─── GET /api/month ──────────────────────────────────────────────
@@ -302,6 +503,12 @@ pub struct MonthQuery {
     pub year: Option<i32>,
     pub month: Option<u32>,
     pub strategy: Option<String>,
+    pub method: Option<String>,
+    pub display_tz: Option<String>,
+    /// Add each day's change from the previous day (`drift`).
+    pub drift: Option<bool>,
+    /// `json` (default) or `csv`.
+    pub format: Option<String>,
 }
 
 pub async fn month_times(
@@ -310,200 +517,1363 @@ pub async fn month_times(
 ) -> Result<impl IntoResponse, Response> {
     let start = Instant::now();
 
-    // Resolve location
-    let resolved = if let Some(ref city) = params.city {
-        let opts = ResolveOptions {
-            country: params.country.clone(),
-            topk: None,
-        };
-        let mut resolver = state.resolver.lock().unwrap();
-        match resolver.resolve_city_with_opts(city, &opts) {
-            Ok(r) => r,
-            Err(LocationError::Ambiguous { query, candidates }) => {
-                let resp = AmbiguousResponse {
-                    multiple: true,
-                    query,
-                    options: candidates.iter().map(|c| AmbiguousOption {
-                        name: c.name.clone(),
-                        country: c.country_name.clone(),
-                        country_code: c.country.clone(),
-                        tz: c.tz.clone(),
-                        lat: c.lat,
-                        lon: c.lon,
-                    }).collect(),
-                };
-                return Err((StatusCode::MULTIPLE_CHOICES, Json(resp)).into_response());
-            }
-            Err(e) => return Err(api_error(StatusCode::NOT_FOUND, format!("{}", e)).into_response()),
-        }
-    } else if let (Some(lat), Some(lon)) = (params.lat, params.lon) {
-        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
-            return Err(api_error(StatusCode::BAD_REQUEST,
-                "Invalid coordinates. Lat: -90..90, Lon: -180..180").into_response());
-        }
-        crate::location::LocationResolver::from_manual(lat, lon, params.tz.as_deref())
-    } else {
-        return Err(api_error(StatusCode::BAD_REQUEST,
-            "Provide 'city' or 'lat'+'lon' parameters").into_response());
+    let final_resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+
+    let today = state.today();
+    let year = params.year.unwrap_or(today.year());
+    let month = params.month.unwrap_or(today.month());
+
+    check_year(year).map_err(|e| e.into_response())?;
+    let days_in_month = days_in_month(year, month)
+        .ok_or_else(|| api_error(StatusCode::BAD_REQUEST, "Month must be 1-12").into_response())?;
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let csv = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("Unknown format '{}'. Valid: json, csv", other)).into_response()),
     };
 
-    // Apply timezone override
-    let final_resolved = if let Some(ref tz_str) = params.tz {
-        let _: chrono_tz::Tz = tz_str.parse().map_err(|_| {
-            api_error(StatusCode::BAD_REQUEST, format!("Unknown timezone '{}'", tz_str)).into_response()
-        })?;
-        crate::location::ResolvedLocation {
-            tz: tz_str.clone(),
-            ..resolved
-        }
+    let config = parse_config(params.strategy.as_deref(), params.method.as_deref())
+        .and_then(|c| Ok(c.with_display_tz(parse_display_tz(params.display_tz.as_deref())?)))
+        .map_err(|e| e.into_response())?;
+
+    let results: Vec<SolverOutput> = if params.drift.unwrap_or(false) {
+        // One extra leading day, so the 1st has a predecessor too
+        let start = first.pred_opt().unwrap_or(first);
+        let days = compute_days(&state, &final_resolved, start.iter_days().take(days_in_month as usize + 1), config).await;
+        days.windows(2)
+            .map(|pair| SolverOutput { drift: Some(schedule_drift(&pair[0], &pair[1])), ..pair[1].clone() }.with_hijri())
+            .collect()
     } else {
-        resolved
+        compute_days(&state, &final_resolved, first.iter_days().take(days_in_month as usize), config).await
+            .into_iter()
+            .map(SolverOutput::with_hijri)
+            .collect()
     };
 
-    let today = Utc::now().naive_utc().date();
-    let year = params.year.unwrap_or(today.year());
-    let month = params.month.unwrap_or(today.month());
+    let elapsed = start.elapsed();
+    eprintln!("[{}] GET /api/month city={} {}/{} -> {} days ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        final_resolved.name, year, month,
+        days_in_month,
+        elapsed.as_secs_f64() * 1000.0,
+    );
 
-    if !(1..=12).contains(&month) {
-        return Err(api_error(StatusCode::BAD_REQUEST, "Month must be 1-12").into_response());
+    if csv {
+        let filename = format!("polaris-{}-{}-{:02}.csv", filename_slug(&final_resolved.name), year, month);
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            ],
+            crate::export::month_to_csv(&results),
+        ).into_response());
     }
+    Ok(Json(results).into_response())
+}
 
-    let strategy = parse_strategy(params.strategy.as_deref()).map_err(|e| e.into_response())?;
-    let strategy_str = format!("{}", strategy);
+/// Lowercase ASCII words of a place's first name part, joined by '-'
+/// ("Stockholm, Sweden" → "stockholm").
+fn filename_slug(name: &str) -> String {
+    let first = name.split(',').next().unwrap_or(name);
+    let slug = first
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() { "location".to_string() } else { slug }
+}
 
-    // Compute all days in the month
-    let first = NaiveDate::from_ymd_opt(year, month, 1)
-        .ok_or_else(|| api_error(StatusCode::BAD_REQUEST, format!("Invalid year/month: {}/{}", year, month)).into_response())?;
+// ─── GET /api/year ───────────────────────────────────────────────
 
-    let days_in_month = if month == 12 {
-        NaiveDate::from_ymd_opt(year + 1, 1, 1)
-    } else {
-        NaiveDate::from_ymd_opt(year, month + 1, 1)
-    }.unwrap().signed_duration_since(first).num_days() as u32;
-
-    let solver = Solver::from_resolved(&final_resolved).with_strategy(strategy);
-    let mut results = Vec::with_capacity(days_in_month as usize);
-    let mut cache = state.cache.lock().unwrap();
-
-    for day in 1..=days_in_month {
-        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
-        let cache_key = ComputeCache::key(
-            final_resolved.lat, final_resolved.lon,
-            &date.to_string(), &strategy_str,
-        );
+#[derive(Deserialize)]
+pub struct YearQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    pub year: Option<i32>,
+    pub strategy: Option<String>,
+    pub method: Option<String>,
+    pub display_tz: Option<String>,
+    /// 1-based page number (default 1).
+    pub page: Option<usize>,
+    /// Days per page (default: the whole year).
+    pub per_page: Option<usize>,
+    /// `full` (default) or `compact`.
+    pub schema: Option<String>,
+}
 
-        if let Some(cached) = cache.get(&cache_key) {
-            results.push(cached);
-        } else {
-            let output = solver.solve_with_info(date, false, false, Some(&final_resolved));
-            cache.put(cache_key, output.clone());
-            results.push(output);
-        }
+#[derive(Serialize)]
+pub struct YearResponse<T> {
+    pub year: i32,
+    pub total_days: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub total_pages: usize,
+    pub days: Vec<T>,
+}
+
+pub async fn year_times(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<YearQuery>,
+) -> Result<Response, Response> {
+    let start = Instant::now();
+
+    let final_resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+
+    let year = params.year.unwrap_or(state.today().year());
+    check_year(year).map_err(|e| e.into_response())?;
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let total_days = if first.leap_year() { 366 } else { 365 };
+
+    let compact = match params.schema.as_deref() {
+        None | Some("full") => false,
+        Some("compact") => true,
+        Some(other) => return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("Unknown schema '{}'. Valid: full, compact", other)).into_response()),
+    };
+
+    let per_page = params.per_page.unwrap_or(total_days);
+    if per_page == 0 {
+        return Err(api_error(StatusCode::BAD_REQUEST, "per_page must be at least 1").into_response());
     }
+    let total_pages = total_days.div_ceil(per_page);
+    let page = params.page.unwrap_or(1);
+    if page == 0 || page > total_pages {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("page must be 1-{}", total_pages)).into_response());
+    }
+
+    let config = parse_config(params.strategy.as_deref(), params.method.as_deref())
+        .and_then(|c| Ok(c.with_display_tz(parse_display_tz(params.display_tz.as_deref())?)))
+        .map_err(|e| e.into_response())?;
+
+    let dates = first.iter_days().take(total_days).skip((page - 1) * per_page).take(per_page);
+    let days = compute_days(&state, &final_resolved, dates, config).await;
 
     let elapsed = start.elapsed();
-    eprintln!("[{}] GET /api/month city={} {}/{} -> {} days ({:.1}ms)",
+    eprintln!("[{}] GET /api/year city={} {} page {}/{} -> {} days ({:.1}ms)",
         Utc::now().format("%H:%M:%S"),
-        final_resolved.name, year, month,
-        days_in_month,
+        final_resolved.name, year, page, total_pages,
+        days.len(),
         elapsed.as_secs_f64() * 1000.0,
     );
 
-    Ok(Json(results))
+    if compact {
+        let days: Vec<CompactDay> = days.iter().map(CompactDay::from).collect();
+        Ok(Json(YearResponse { year, total_days, page, per_page, total_pages, days }).into_response())
+    } else {
+        Ok(Json(YearResponse { year, total_days, page, per_page, total_pages, days }).into_response())
+    }
 }
 
-// ─── GET /api/hijri ──────────────────────────────────────────────
+// ─── GET /api/fajr-sweep ─────────────────────────────────────────
+
+/// Upper bound on sweep rows, to keep calibration requests cheap.
+const MAX_SWEEP_STEPS: usize = 200;
 
 #[derive(Deserialize)]
-pub struct HijriQuery {
-    pub lat: f64,
-    pub lon: f64,
-    pub tz: String,
-    pub hijri_year: Option<u32>,
+pub struct SweepQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    pub date: Option<String>,
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+    pub step: Option<f64>,
 }
 
 #[derive(Serialize)]
-pub struct HijriResponse {
-    pub hijri_date: HijriDateInfo,
-    pub ramadan: crate::hijri::RamadanInfo,
-    pub location: HijriLocation,
+pub struct SweepRow {
+    pub angle: f64,
+    pub fajr: Option<String>,
 }
 
 #[derive(Serialize)]
-pub struct HijriDateInfo {
-    pub year: u32,
-    pub month: u32,
-    pub day: u32,
+pub struct SweepResponse {
+    pub location: String,
+    pub date: String,
+    pub timezone: String,
+    pub sweep: Vec<SweepRow>,
+}
+
+pub async fn fajr_sweep(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SweepQuery>,
+) -> Result<Json<SweepResponse>, Response> {
+    let start = Instant::now();
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+
+    let date = parse_date(params.date.as_deref(), state.today()).map_err(|e| e.into_response())?;
+
+    let angles = sweep_angles(params.from.unwrap_or(15.0), params.to.unwrap_or(20.0), params.step.unwrap_or(0.5))
+        .map_err(|e| e.into_response())?;
+
+    let solver = Solver::from_resolved(&resolved);
+    let sweep = solver.fajr_sweep(date, &angles)
+        .into_iter()
+        .map(|(angle, fajr)| SweepRow { angle, fajr })
+        .collect();
+
+    let elapsed = start.elapsed();
+    eprintln!("[{}] GET /api/fajr-sweep city={} date={} -> {} angles ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, date, angles.len(),
+        elapsed.as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(SweepResponse {
+        location: resolved.name.clone(),
+        date: date.to_string(),
+        timezone: resolved.tz.clone(),
+        sweep,
+    }))
+}
+
+/// Expand `from..=to` by `step` into a list of depression angles.
+fn sweep_angles(from: f64, to: f64, step: f64) -> Result<Vec<f64>, ApiError> {
+    if !(0.0..=90.0).contains(&from) || !(0.0..=90.0).contains(&to) || from > to {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Angles must satisfy 0 <= from <= to <= 90"));
+    }
+    if step <= 0.0 || !step.is_finite() {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Step must be positive"));
+    }
+    let count = ((to - from) / step + 1e-9).floor() as usize + 1;
+    if count > MAX_SWEEP_STEPS {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("Sweep too large ({} steps, max {})", count, MAX_SWEEP_STEPS)));
+    }
+    // Round to avoid accumulating float noise like 17.499999
+    Ok((0..count).map(|i| ((from + i as f64 * step) * 1000.0).round() / 1000.0).collect())
+}
+
+// ─── GET /api/crossing ───────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CrossingQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    pub date: Option<String>,
+    pub angle: Option<f64>,
+    pub limb: Option<String>,
 }
 
 #[derive(Serialize)]
-pub struct HijriLocation {
-    pub lat: f64,
-    pub lon: f64,
-    pub tz: String,
+pub struct CrossingResponse {
+    pub location: String,
+    pub date: String,
+    pub timezone: String,
+    pub angle: f64,
+    pub limb: Limb,
+    /// Local time of the crossing, or null if the sun never reaches `angle`.
+    pub time: Option<String>,
 }
 
-pub async fn hijri_info(
-    Query(params): Query<HijriQuery>,
-) -> Result<impl IntoResponse, Response> {
+pub async fn crossing(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CrossingQuery>,
+) -> Result<Json<CrossingResponse>, Response> {
     let start = Instant::now();
 
-    if !(-90.0..=90.0).contains(&params.lat) || !(-180.0..=180.0).contains(&params.lon) {
-        return Err(api_error(StatusCode::BAD_REQUEST,
-            "Invalid coordinates. Lat: -90..90, Lon: -180..180").into_response());
+    let angle = params.angle
+        .ok_or_else(|| api_error(StatusCode::BAD_REQUEST, "Missing 'angle' parameter").into_response())?;
+    if !(-90.0..=90.0).contains(&angle) {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Angle must be between -90 and 90").into_response());
     }
-
-    let _tz: chrono_tz::Tz = params.tz.parse().map_err(|_| {
-        api_error(StatusCode::BAD_REQUEST, format!("Unknown timezone '{}'", params.tz)).into_response()
+    let limb: Limb = params.limb.as_deref().unwrap_or("morning").parse()
+        .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e).into_response())?;
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
     })?;
 
-    let today = Utc::now().naive_utc().date();
-    let hijri_today = crate::hijri::gregorian_to_hijri(today);
-
-    let hijri_year = params.hijri_year.unwrap_or_else(|| {
-        crate::hijri::current_hijri_year_for_ramadan()
-    });
+    let date = parse_date(params.date.as_deref(), state.today()).map_err(|e| e.into_response())?;
 
-    let ramadan = crate::hijri::find_ramadan(hijri_year, params.lat, params.lon);
+    let time = Solver::from_resolved(&resolved).crossing(date, angle, limb);
 
     let elapsed = start.elapsed();
-    eprintln!("[{}] GET /api/hijri lat={:.2} lon={:.2} -> Ramadan {} starts {} ({:.1}ms)",
+    eprintln!("[{}] GET /api/crossing city={} date={} angle={} {} -> {} ({:.1}ms)",
         Utc::now().format("%H:%M:%S"),
-        params.lat, params.lon,
-        hijri_year, ramadan.start,
+        resolved.name, date, angle, limb,
+        time.as_deref().unwrap_or("none"),
         elapsed.as_secs_f64() * 1000.0,
     );
 
-    Ok(Json(HijriResponse {
-        hijri_date: HijriDateInfo {
-            year: hijri_today.year,
-            month: hijri_today.month,
-            day: hijri_today.day,
-        },
-        ramadan,
-        location: HijriLocation {
-            lat: params.lat,
-            lon: params.lon,
-            tz: params.tz,
-        },
+    Ok(Json(CrossingResponse {
+        location: resolved.name.clone(),
+        date: date.to_string(),
+        timezone: resolved.tz.clone(),
+        angle,
+        limb,
+        time,
     }))
 }
 
-// ─── GET /api/cities ─────────────────────────────────────────────
+// ─── GET /api/qibla ──────────────────────────────────────────────
 
-pub async fn city_list() -> Json<Vec<crate::location::CityInfo>> {
-    Json(builtin_city_list())
+#[derive(Deserialize)]
+pub struct QiblaQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
 }
 
-// ─── Helpers ─────────────────────────────────────────────────────
+#[derive(Serialize)]
+pub struct QiblaResponse {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Degrees clockwise from true north.
+    pub bearing: f64,
+    /// Nearest of the 16 compass points, e.g. "SE".
+    pub compass: &'static str,
+    pub distance_km: f64,
+}
 
-fn parse_strategy(s: Option<&str>) -> Result<GapStrategy, ApiError> {
-    match s {
-        Some("strict") => Ok(GapStrategy::Strict),
-        Some("projected45") | Some("projected") | None => Ok(GapStrategy::Projected45),
-        Some(other) => Err(api_error(
-            StatusCode::BAD_REQUEST,
-            format!("Unknown strategy '{}'. Use 'strict' or 'projected45'.", other),
-        )),
+pub async fn qibla(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<QiblaQuery>,
+) -> Result<Json<QiblaResponse>, Response> {
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: None,
+    })?;
+
+    let qibla = Qibla::from_location(resolved.lat, resolved.lon);
+    eprintln!("[{}] GET /api/qibla city={} -> {:.1}° ({:.0} km)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, qibla.bearing, qibla.distance_km,
+    );
+
+    Ok(Json(QiblaResponse {
+        name: resolved.name,
+        lat: resolved.lat,
+        lon: resolved.lon,
+        bearing: qibla.bearing,
+        compass: compass_point(qibla.bearing),
+        distance_km: qibla.distance_km,
+    }))
+}
+
+// ─── GET /api/ramadan ────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct RamadanQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Hijri year; the upcoming (or current) Ramadan when absent.
+    pub year: Option<u32>,
+}
+
+pub async fn ramadan(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RamadanQuery>,
+) -> Result<Json<crate::hijri::RamadanInfo>, Response> {
+    let start = Instant::now();
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: None,
+    })?;
+
+    let hijri_year = match params.year {
+        Some(year @ 1..=9999) => year,
+        Some(year) => return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("Invalid Hijri year {}. Use 1-9999", year)).into_response()),
+        None => crate::hijri::hijri_year_for_ramadan(state.clock.as_ref()),
+    };
+    let info = crate::hijri::find_ramadan(hijri_year, resolved.lat, resolved.lon);
+
+    eprintln!("[{}] GET /api/ramadan city={} -> Ramadan {} starts {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, hijri_year, info.start,
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(info))
+}
+
+// ─── GET /api/window ─────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct WindowQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    /// RFC 3339 instant (default: now).
+    pub at: Option<String>,
+    pub strategy: Option<String>,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WindowResponse {
+    pub location: String,
+    pub timezone: String,
+    pub at: String,
+    #[serde(flatten)]
+    pub window: PrayerWindow,
+}
+
+pub async fn prayer_window(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WindowQuery>,
+) -> Result<Json<WindowResponse>, Response> {
+    let start = Instant::now();
+
+    let at = match params.at.as_deref() {
+        Some(s) => DateTime::parse_from_rfc3339(s)
+            .map_err(|e| api_error(StatusCode::BAD_REQUEST, format!("Invalid 'at' '{}': {}", s, e)).into_response())?
+            .with_timezone(&chrono_tz::UTC),
+        None => state.clock.now_utc().with_timezone(&chrono_tz::UTC),
+    };
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+    let config = parse_config(params.strategy.as_deref(), params.method.as_deref())
+        .map_err(|e| e.into_response())?;
+
+    let window = Solver::from_resolved(&resolved).with_config(config).current_window(at);
+
+    let elapsed = start.elapsed();
+    eprintln!("[{}] GET /api/window city={} at={} -> {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, at.to_rfc3339(), window.current,
+        elapsed.as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(WindowResponse {
+        location: resolved.name.clone(),
+        timezone: resolved.tz.clone(),
+        at: at.to_rfc3339(),
+        window,
+    }))
+}
+
+// ─── GET /api/crescent-map ───────────────────────────────────────
+
+/// Default grid spacing for the crescent map, in degrees.
+const CRESCENT_MAP_STEP: f64 = 10.0;
+
+#[derive(Deserialize)]
+pub struct CrescentMapQuery {
+    pub date: Option<String>,
+    /// Grid spacing in degrees (default 10, minimum 5).
+    pub lat_step: Option<f64>,
+    pub lon_step: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct CrescentPoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub zone: crate::hijri::CrescentZone,
+}
+
+#[derive(Serialize)]
+pub struct CrescentMapResponse {
+    pub date: String,
+    pub conjunction_utc: String,
+    pub lat_step: f64,
+    pub lon_step: f64,
+    pub points: Vec<CrescentPoint>,
+}
+
+pub async fn crescent_map(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CrescentMapQuery>,
+) -> Result<Json<CrescentMapResponse>, Response> {
+    let start = Instant::now();
+
+    let date = parse_date(params.date.as_deref(), state.today()).map_err(|e| e.into_response())?;
+    let lat_step = params.lat_step.unwrap_or(CRESCENT_MAP_STEP).max(crate::hijri::MIN_GRID_STEP);
+    let lon_step = params.lon_step.unwrap_or(CRESCENT_MAP_STEP).max(crate::hijri::MIN_GRID_STEP);
+    if !lat_step.is_finite() || !lon_step.is_finite() {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Grid steps must be finite").into_response());
+    }
+
+    // A few hundred to a few thousand sunset scans: keep them off the async workers
+    let (conjunction, grid) = tokio::task::spawn_blocking(move || {
+        let conjunction = crate::hijri::find_conjunction(date);
+        let grid = crate::hijri::visibility_grid(date, &conjunction, lat_step, lon_step);
+        (conjunction, grid)
+    }).await.map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+
+    let elapsed = start.elapsed();
+    eprintln!("[{}] GET /api/crescent-map date={} step={}x{} -> {} points ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        date, lat_step, lon_step, grid.len(),
+        elapsed.as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(CrescentMapResponse {
+        date: date.to_string(),
+        conjunction_utc: conjunction.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        lat_step,
+        lon_step,
+        points: grid.into_iter().map(|(lat, lon, zone)| CrescentPoint { lat, lon, zone }).collect(),
+    }))
+}
+
+// ─── GET /api/moon ───────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct MoonQuery {
+    pub date: Option<String>,
+}
+
+/// The Moon at 12:00 UTC on `date`.
+#[derive(Serialize)]
+pub struct MoonResponse {
+    pub date: String,
+    /// Illuminated fraction of the disc (0..1).
+    pub illumination: f64,
+    /// e.g. "Waxing Crescent".
+    pub phase: &'static str,
+    /// Moon-Sun separation in degrees.
+    pub elongation: f64,
+    pub distance_km: f64,
+}
+
+pub async fn moon(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MoonQuery>,
+) -> Result<Json<MoonResponse>, ApiError> {
+    let date = parse_date(params.date.as_deref(), state.today())?;
+    let noon = date.and_hms_opt(12, 0, 0).unwrap();
+    Ok(Json(MoonResponse {
+        date: date.to_string(),
+        illumination: crate::lunar::moon_illumination(&noon),
+        phase: crate::lunar::moon_phase_name(&noon),
+        elongation: crate::lunar::moon_sun_elongation(&noon),
+        // Geocentric distance; the observer only matters for altitude
+        distance_km: crate::lunar::lunar_position(&noon, 0.0, 0.0).distance_km,
+    }))
+}
+
+// ─── GET /api/analemma ───────────────────────────────────────────
+
+/// Default days between analemma samples.
+const ANALEMMA_STEP: u32 = 5;
+
+#[derive(Deserialize)]
+pub struct AnalemmaQuery {
+    pub lat: f64,
+    pub lon: f64,
+    pub year: Option<i32>,
+    /// Days between samples (default 5, 1..=31).
+    pub step: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct AnalemmaSample {
+    pub date: String,
+    pub altitude: f64,
+    pub azimuth: f64,
+    pub declination: f64,
+    /// Minutes.
+    pub equation_of_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct AnalemmaResponse {
+    pub lat: f64,
+    pub lon: f64,
+    pub year: i32,
+    pub step_days: u32,
+    pub points: Vec<AnalemmaSample>,
+}
+
+pub async fn analemma(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AnalemmaQuery>,
+) -> Result<Json<AnalemmaResponse>, Response> {
+    let lon = check_coords(params.lat, params.lon).map_err(|e| e.into_response())?;
+    let year = params.year.unwrap_or_else(|| state.today().year());
+    if NaiveDate::from_ymd_opt(year, 1, 1).is_none() {
+        return Err(api_error(StatusCode::BAD_REQUEST, format!("Invalid year: {}", year)).into_response());
+    }
+    let step_days = params.step.unwrap_or(ANALEMMA_STEP).clamp(1, 31);
+
+    let points = crate::solar::analemma(year, params.lat, lon, step_days)
+        .into_iter()
+        .map(|p| AnalemmaSample {
+            date: p.date.to_string(),
+            altitude: p.altitude,
+            azimuth: p.azimuth,
+            declination: p.declination,
+            equation_of_time: p.equation_of_time,
+        })
+        .collect();
+
+    Ok(Json(AnalemmaResponse { lat: params.lat, lon: params.lon, year, step_days, points }))
+}
+
+// ─── GET /api/hijri ──────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct HijriQuery {
+    pub lat: f64,
+    pub lon: f64,
+    pub tz: String,
+    pub hijri_year: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct HijriResponse {
+    pub hijri_date: HijriDateInfo,
+    pub ramadan: crate::hijri::RamadanInfo,
+    pub location: HijriLocation,
+}
+
+#[derive(Serialize)]
+pub struct HijriDateInfo {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    /// e.g. `15 Ramadan 1447`.
+    pub formatted: String,
+    /// e.g. `15 رمضان 1447`.
+    pub formatted_ar: String,
+}
+
+impl From<crate::hijri::HijriDate> for HijriDateInfo {
+    fn from(date: crate::hijri::HijriDate) -> Self {
+        Self {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+            formatted: date.to_string(),
+            formatted_ar: date.to_arabic(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct HijriLocation {
+    pub lat: f64,
+    pub lon: f64,
+    pub tz: String,
+}
+
+pub async fn hijri_info(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HijriQuery>,
+) -> Result<impl IntoResponse, Response> {
+    let start = Instant::now();
+
+    let lon = check_coords(params.lat, params.lon).map_err(|e| e.into_response())?;
+
+    timezone::parse_tz(&params.tz).map_err(|e| {
+        api_error(StatusCode::BAD_REQUEST, e.to_string()).into_response()
+    })?;
+
+    let today = state.today();
+    let hijri_today = crate::hijri::gregorian_to_hijri(today);
+
+    let hijri_year = params.hijri_year.unwrap_or_else(|| {
+        crate::hijri::hijri_year_for_ramadan(state.clock.as_ref())
+    });
+
+    let ramadan = crate::hijri::find_ramadan(hijri_year, params.lat, lon);
+
+    let elapsed = start.elapsed();
+    eprintln!("[{}] GET /api/hijri lat={:.2} lon={:.2} -> Ramadan {} starts {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        params.lat, lon,
+        hijri_year, ramadan.start,
+        elapsed.as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(HijriResponse {
+        hijri_date: HijriDateInfo::from(hijri_today),
+        ramadan,
+        location: HijriLocation {
+            lat: params.lat,
+            lon,
+            tz: params.tz,
+        },
+    }))
+}
+
+// ─── GET /api/hijri/convert, /api/gregorian ──────────────────────
+
+#[derive(Deserialize)]
+pub struct HijriConvertQuery {
+    /// Gregorian date; today when absent.
+    pub date: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GregorianQuery {
+    pub hy: u32,
+    pub hm: u32,
+    pub hd: u32,
+}
+
+/// One day in both calendars (tabular Hijri).
+#[derive(Serialize)]
+pub struct CalendarConversion {
+    pub gregorian: String,
+    pub hijri: HijriDateInfo,
+    /// Transliterated month name, e.g. `Ramadan`.
+    pub month_name: &'static str,
+    /// Arabic month name, e.g. `رمضان`.
+    pub month_name_ar: &'static str,
+}
+
+impl CalendarConversion {
+    fn new(gregorian: NaiveDate, hijri: crate::hijri::HijriDate) -> Self {
+        Self {
+            gregorian: gregorian.to_string(),
+            month_name: crate::hijri::month_name(hijri.month),
+            month_name_ar: crate::hijri::month_name_ar(hijri.month),
+            hijri: HijriDateInfo::from(hijri),
+        }
+    }
+}
+
+pub async fn hijri_convert(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HijriConvertQuery>,
+) -> Result<Json<CalendarConversion>, ApiError> {
+    let date = parse_date(params.date.as_deref(), state.today())?;
+    Ok(Json(CalendarConversion::new(date, crate::hijri::gregorian_to_hijri(date))))
+}
+
+pub async fn gregorian_convert(
+    Query(params): Query<GregorianQuery>,
+) -> Result<Json<CalendarConversion>, ApiError> {
+    let hijri: crate::hijri::HijriDate = format!("{}-{}-{}", params.hy, params.hm, params.hd)
+        .parse()
+        .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(CalendarConversion::new(crate::hijri::hijri_to_gregorian(hijri), hijri)))
+}
+
+// ─── GET /api/cities ─────────────────────────────────────────────
+
+pub async fn city_list() -> Json<Vec<crate::location::CityInfo>> {
+    Json(builtin_city_list())
+}
+
+// ─── GET /api/country ────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CountryQuery {
+    /// ISO 3166-1 alpha-2 code, e.g. `SA`.
+    pub code: String,
+    pub date: Option<String>,
+    pub strategy: Option<String>,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CountryResponse {
+    pub country: String,
+    pub country_name: String,
+    pub date: String,
+    /// One schedule per built-in city, keyed by city name.
+    pub cities: std::collections::BTreeMap<String, SolverOutput>,
+}
+
+pub async fn country_times(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CountryQuery>,
+) -> Result<Json<CountryResponse>, ApiError> {
+    let start = Instant::now();
+
+    let code = params.code.trim().to_uppercase();
+    let locations = builtin_cities_in_country(&code);
+    if locations.is_empty() {
+        return Err(api_error(StatusCode::NOT_FOUND, format!("No listed cities for country '{}'", code)));
+    }
+    let date = parse_date(params.date.as_deref(), state.today())?;
+    let config = parse_config(params.strategy.as_deref(), params.method.as_deref())?;
+
+    let mut cities = std::collections::BTreeMap::new();
+    for resolved in &locations {
+        cities.insert(resolved.name.clone(), compute_day(&state, resolved, date, config).await.0);
+    }
+
+    eprintln!("[{}] GET /api/country code={} date={} -> {} cities ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        code, date, locations.len(),
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(CountryResponse {
+        country_name: country_display_name(&code).to_string(),
+        country: code,
+        date: date.to_string(),
+        cities,
+    }))
+}
+
+// ─── GET /api/compare ────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    pub date: Option<String>,
+    pub strategy: Option<String>,
+    pub method_a: String,
+    pub method_b: String,
+}
+
+#[derive(Serialize)]
+pub struct CompareResponse {
+    pub method_a: CalculationMethod,
+    pub method_b: CalculationMethod,
+    pub a: SolverOutput,
+    pub b: SolverOutput,
+    /// Signed minutes from `a` to `b` per event (positive: `b` is later).
+    pub differences: DriftReport,
+}
+
+pub async fn compare_methods(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CompareQuery>,
+) -> Result<Json<CompareResponse>, Response> {
+    let start = Instant::now();
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+    let date = parse_date(params.date.as_deref(), state.today()).map_err(|e| e.into_response())?;
+    let base = Config::default().with_strategy(parse_strategy(params.strategy.as_deref()).map_err(|e| e.into_response())?);
+    let method_a = parse_method(Some(&params.method_a)).map_err(|e| e.into_response())?;
+    let method_b = parse_method(Some(&params.method_b)).map_err(|e| e.into_response())?;
+
+    // Each method has its own cache entry
+    let (a, _) = compute_day(&state, &resolved, date, base.with_method(method_a)).await;
+    let (b, _) = compute_day(&state, &resolved, date, base.with_method(method_b)).await;
+    let differences = schedule_drift(&a, &b);
+
+    eprintln!("[{}] GET /api/compare {} {} {} vs {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, date, method_a, method_b,
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(CompareResponse { method_a, method_b, a, b, differences }))
+}
+
+// ─── GET /api/extremes ───────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ExtremesQuery {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub tz: Option<String>,
+    pub prayer: String,
+    pub year: Option<i32>,
+    pub strategy: Option<String>,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExtremesResponse {
+    pub location: String,
+    pub year: i32,
+    #[serde(flatten)]
+    pub extremes: PrayerExtremes,
+}
+
+pub async fn prayer_extremes_year(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExtremesQuery>,
+) -> Result<Json<ExtremesResponse>, Response> {
+    let start = Instant::now();
+
+    let resolved = resolve_request(&state, &LocationParams {
+        city: params.city.as_deref(),
+        country: params.country.as_deref(),
+        lat: params.lat,
+        lon: params.lon,
+        tz: params.tz.as_deref(),
+    })?;
+    let prayer: PrayerKind = params.prayer.parse()
+        .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e).into_response())?;
+    let year = params.year.unwrap_or(state.today().year());
+    check_year(year).map_err(|e| e.into_response())?;
+    let config = parse_config(params.strategy.as_deref(), params.method.as_deref()).map_err(|e| e.into_response())?;
+
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let days = compute_days(&state, &resolved, first.iter_days().take_while(|d| d.year() == year), config).await;
+    let extremes = prayer_extremes(&days, prayer).ok_or_else(|| {
+        api_error(StatusCode::NOT_FOUND, format!("No {} at {} in {}", prayer, resolved.name, year)).into_response()
+    })?;
+
+    eprintln!("[{}] GET /api/extremes {} {} {} -> {} .. {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        resolved.name, prayer, year, extremes.earliest.date, extremes.latest.date,
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(ExtremesResponse { location: resolved.name, year, extremes }))
+}
+
+// ─── GET /api/methods, /api/strategies ───────────────────────────
+
+#[derive(Serialize)]
+pub struct MethodInfo {
+    pub id: String,
+    pub name: String,
+    pub description: &'static str,
+    pub fajr_angle: f64,
+    pub isha: IshaRule,
+    pub maghrib: MaghribRule,
+}
+
+#[derive(Serialize)]
+pub struct StrategyInfo {
+    pub id: String,
+    pub name: String,
+    pub description: &'static str,
+}
+
+pub async fn methods() -> Json<Vec<MethodInfo>> {
+    Json(CalculationMethod::ALL.iter().map(|m| MethodInfo {
+        id: m.to_string().to_lowercase(),
+        name: m.to_string(),
+        description: m.description(),
+        fajr_angle: m.fajr_angle(),
+        isha: m.isha_rule(),
+        maghrib: m.maghrib_rule(),
+    }).collect())
+}
+
+pub async fn strategies() -> Json<Vec<StrategyInfo>> {
+    Json(GapStrategy::ALL.iter().map(|s| StrategyInfo {
+        id: s.to_string().to_lowercase(),
+        name: s.to_string(),
+        description: s.description(),
+    }).collect())
+}
+
+// ─── POST /api/admin/warm ────────────────────────────────────────
+
+/// Most days a single warm request may cover.
+const MAX_WARM_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub struct WarmRequest {
+    pub cities: Vec<String>,
+    /// First date, `YYYY-MM-DD` (default today, UTC).
+    pub from: Option<String>,
+    /// Last date, inclusive (default `from`).
+    pub to: Option<String>,
+    pub strategy: Option<String>,
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WarmFailure {
+    pub city: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct WarmResponse {
+    /// Entries computed and stored by this request.
+    pub warmed: usize,
+    /// Entries that were already cached and left alone.
+    pub already_cached: usize,
+    pub locations: usize,
+    pub days: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<WarmFailure>,
+}
+
+/// Resolve each city and compute its schedules into the caches, so later
+/// `/api/times` requests for the same settings are hits. Re-running is harmless.
+pub async fn warm_cache(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<WarmRequest>,
+) -> Result<Json<WarmResponse>, ApiError> {
+    let start = Instant::now();
+
+    let Some(ref token) = state.admin_token else {
+        return Err(api_error(StatusCode::FORBIDDEN, "Admin endpoints are disabled; start the server with --admin-token"));
+    };
+    let given = headers.get("x-admin-token").and_then(|v| v.to_str().ok());
+    if given != Some(token.as_str()) {
+        return Err(api_error(StatusCode::UNAUTHORIZED, "Missing or invalid X-Admin-Token"));
+    }
+
+    let from = parse_date(req.from.as_deref(), state.today())?;
+    let to = match req.to.as_deref() {
+        Some(d) => parse_date(Some(d), state.today())?,
+        None => from,
+    };
+    let days = (to - from).num_days() + 1;
+    if !(1..=MAX_WARM_DAYS).contains(&days) {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            format!("'to' must be on or after 'from' and span at most {} days", MAX_WARM_DAYS)));
+    }
+    let config = parse_config(req.strategy.as_deref(), req.method.as_deref())?;
+
+    // Geocoding and up to a year of solves per city: keep them off the async workers
+    let warm_state = state.clone();
+    let response = tokio::task::spawn_blocking(move || warm(&warm_state, &req.cities, from, days as usize, config))
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    eprintln!("[{}] POST /api/admin/warm cities={} days={} -> warmed {} ({:.1}ms)",
+        Utc::now().format("%H:%M:%S"),
+        response.locations, days, response.warmed,
+        start.elapsed().as_secs_f64() * 1000.0,
+    );
+
+    Ok(Json(response))
+}
+
+/// Compute and store every missing (city, day) entry. The compute cache is
+/// locked once to find a city's missing days and once per stored entry,
+/// never while solving.
+fn warm(state: &AppState, cities: &[String], from: NaiveDate, days: usize, config: Config) -> WarmResponse {
+    let fingerprint = config.cache_fingerprint();
+    let mut response = WarmResponse { warmed: 0, already_cached: 0, locations: 0, days, failed: Vec::new() };
+    for city in cities {
+        let resolved = state.resolve_city(city, &ResolveOptions::default());
+        let resolved = match resolved {
+            Ok(r) => r,
+            Err(e) => {
+                response.failed.push(WarmFailure { city: city.clone(), error: e.to_string() });
+                continue;
+            }
+        };
+        let solver = match Solver::try_from_resolved(&resolved) {
+            Ok(solver) => solver.with_config(config),
+            Err(e) => {
+                response.failed.push(WarmFailure { city: city.clone(), error: e.to_string() });
+                continue;
+            }
+        };
+        response.locations += 1;
+
+        let missing: Vec<(NaiveDate, String)> = {
+            let mut cache = state.cache.lock().unwrap();
+            from.iter_days().take(days)
+                .map(|date| (date, ComputeCache::key(resolved.lat, resolved.lon, &resolved.tz, &date.to_string(), &fingerprint)))
+                .filter(|(_, key)| cache.get(key).is_none())
+                .collect()
+        };
+        response.already_cached += days - missing.len();
+        for (date, cache_key) in missing {
+            let output = solver.solve_with_info(date, false, false, Some(&resolved));
+            state.cache.lock().unwrap().put(cache_key, output);
+            response.warmed += 1;
+        }
+    }
+    response
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────
+
+/// Location parameters shared by the compute endpoints.
+struct LocationParams<'a> {
+    city: Option<&'a str>,
+    country: Option<&'a str>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    tz: Option<&'a str>,
+}
+
+/// Map a resolver error to a response (300 with options when ambiguous).
+fn location_error_response(err: LocationError) -> Response {
+    match err {
+        LocationError::Ambiguous { query, candidates } => {
+            let resp = AmbiguousResponse {
+                multiple: true,
+                query,
+                options: candidates.iter().map(AmbiguousOption::from).collect(),
+            };
+            (StatusCode::MULTIPLE_CHOICES, Json(resp)).into_response()
+        }
+        LocationError::RateLimited { retry_after } => {
+            let message = LocationError::RateLimited { retry_after }.to_string();
+            let mut response = api_error(StatusCode::TOO_MANY_REQUESTS, message).into_response();
+            if let Some(delay) = retry_after {
+                response.headers_mut().insert(header::RETRY_AFTER, delay.as_secs().into());
+            }
+            response
+        }
+        e => api_error(StatusCode::NOT_FOUND, format!("{}", e)).into_response(),
+    }
+}
+
+/// Resolve a city or lat/lon request and apply the `tz` override.
+#[allow(clippy::result_large_err)]
+fn resolve_request(state: &AppState, params: &LocationParams<'_>) -> Result<ResolvedLocation, Response> {
+    let resolved = if let Some(city) = params.city {
+        let opts = ResolveOptions {
+            country: params.country.map(str::to_string),
+            ..Default::default()
+        };
+        state.resolve_city(city, &opts).map_err(location_error_response)?
+    } else if let (Some(lat), Some(lon)) = (params.lat, params.lon) {
+        check_coords(lat, lon).map_err(|e| e.into_response())?;
+        LocationResolver::from_manual(lat, lon, params.tz)
+    } else {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            "Provide 'city' or 'lat'+'lon' parameters").into_response());
+    };
+
+    let resolved = match params.tz {
+        Some(tz_str) => {
+            let tz = timezone::parse_tz(tz_str).map_err(|e| {
+                api_error(StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            })?;
+            resolved.with_tz_override(tz.name())
+        }
+        None => resolved,
+    };
+    // A corrupt cache entry must not reach `Solver::from_resolved`, which panics
+    Solver::try_from_resolved(&resolved)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()).into_response())?;
+    Ok(resolved)
+}
+
+/// Years the calendar endpoints accept.
+const YEAR_RANGE: std::ops::RangeInclusive<i32> = 1..=9999;
+
+fn check_year(year: i32) -> Result<i32, ApiError> {
+    if YEAR_RANGE.contains(&year) {
+        Ok(year)
+    } else {
+        Err(api_error(StatusCode::BAD_REQUEST,
+            format!("Invalid year: {}. Use {}-{}", year, YEAR_RANGE.start(), YEAR_RANGE.end())))
+    }
+}
+
+/// Length of `month` (1-12) in `year`, or `None` for an invalid month.
+fn days_in_month(year: i32, month: u32) -> Option<u32> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = first.checked_add_months(chrono::Months::new(1))?;
+    Some((next - first).num_days() as u32)
+}
+
+/// Validate query coordinates, returning the longitude wrapped into (-180, 180].
+fn check_coords(lat: f64, lon: f64) -> Result<f64, ApiError> {
+    if !(-90.0..=90.0).contains(&lat) || !lon.is_finite() {
+        return Err(api_error(StatusCode::BAD_REQUEST,
+            "Invalid coordinates. Lat: -90..90, Lon: any number (wraps to -180..180)"));
+    }
+    Ok(normalize_longitude(lon))
+}
+
+/// Parse a `YYYY-MM-DD` query date, defaulting to `today`.
+fn parse_date(date: Option<&str>, today: NaiveDate) -> Result<NaiveDate, ApiError> {
+    match date {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|e| {
+            api_error(StatusCode::BAD_REQUEST, format!("Invalid date '{}': {}", d, e))
+        }),
+        None => Ok(today),
+    }
+}
+
+/// Solve a run of days through the compute cache. Hits are read under one
+/// lock; misses are solved on a blocking thread without it and stored one
+/// by one, like `warm`.
+async fn compute_days(
+    state: &AppState,
+    resolved: &ResolvedLocation,
+    dates: impl Iterator<Item = NaiveDate>,
+    config: Config,
+) -> Vec<SolverOutput> {
+    let fingerprint = config.cache_fingerprint();
+    let mut days: Vec<(NaiveDate, String, Option<SolverOutput>)> = {
+        let mut cache = state.cache.lock().unwrap();
+        dates.map(|date| {
+            let cache_key = ComputeCache::key(resolved.lat, resolved.lon, &resolved.tz, &date.to_string(), &fingerprint);
+            let cached = cache.get(&cache_key);
+            (date, cache_key, cached)
+        }).collect()
+    };
+
+    let missing: Vec<NaiveDate> = days.iter().filter(|(_, _, cached)| cached.is_none()).map(|(date, _, _)| *date).collect();
+    if !missing.is_empty() {
+        let resolved = resolved.clone();
+        let solved = tokio::task::spawn_blocking(move || {
+            let solver = Solver::from_resolved(&resolved).with_config(config);
+            missing.into_iter()
+                .map(|date| solver.solve_with_info(date, false, false, Some(&resolved)))
+                .collect::<Vec<_>>()
+        }).await.expect("computation panicked");
+
+        let slots = days.iter_mut().filter(|(_, _, cached)| cached.is_none());
+        for ((_, cache_key, slot), output) in slots.zip(solved) {
+            state.cache.lock().unwrap().put(cache_key.clone(), output.clone());
+            *slot = Some(output);
+        }
+    }
+
+    days.into_iter().map(|(_, _, output)| output.expect("every day is cached or solved")).collect()
+}
+
+/// Solve one day through the compute cache. Returns `(output, was_cached)`.
+async fn compute_day(state: &AppState, resolved: &ResolvedLocation, date: NaiveDate, config: Config) -> (SolverOutput, bool) {
+    let cache_key = ComputeCache::key(resolved.lat, resolved.lon, &resolved.tz, &date.to_string(), &config.cache_fingerprint());
+    let resolved = resolved.clone();
+    state.get_or_compute(cache_key, move || {
+        Solver::from_resolved(&resolved).with_config(config).solve_with_info(date, false, false, Some(&resolved))
+    }).await
+}
+
+fn parse_method(s: Option<&str>) -> Result<CalculationMethod, ApiError> {
+    match s {
+        None => Ok(CalculationMethod::default()),
+        Some(m) => crate::method::parse_method(m).map_err(|e| api_error(StatusCode::BAD_REQUEST, e)),
+    }
+}
+
+fn parse_display_tz(s: Option<&str>) -> Result<Option<chrono_tz::Tz>, ApiError> {
+    match s {
+        None | Some("") => Ok(None),
+        Some(tz) => timezone::parse_tz(tz).map(Some).map_err(|e| {
+            api_error(StatusCode::BAD_REQUEST, format!("Invalid display_tz: {}", e))
+        }),
+    }
+}
+
+fn parse_config(strategy: Option<&str>, method: Option<&str>) -> Result<Config, ApiError> {
+    Ok(Config::default()
+        .with_strategy(parse_strategy(strategy)?)
+        .with_method(parse_method(method)?))
+}
+
+fn parse_strategy(s: Option<&str>) -> Result<GapStrategy, ApiError> {
+    match s {
+        None => Ok(GapStrategy::Projected45),
+        Some(s) => crate::schedule::parse_strategy(s).map_err(|e| api_error(StatusCode::BAD_REQUEST, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_methods_endpoint_lists_presets() {
+        let Json(list) = methods().await;
+        let names: Vec<&str> = list.iter().map(|m| m.name.as_str()).collect();
+        for expected in ["MWL", "ISNA", "Egyptian", "Karachi", "UmmAlQura", "Tehran", "Jafari"] {
+            assert!(names.contains(&expected), "missing method {}", expected);
+        }
+        let uaq = list.iter().find(|m| m.id == "ummalqura").unwrap();
+        assert_eq!(uaq.isha, IshaRule::FixedAfterMaghrib(90));
+    }
+
+    #[tokio::test]
+    async fn test_strategies_endpoint_lists_variants() {
+        let Json(list) = strategies().await;
+        let ids: Vec<&str> = list.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["strict", "projected45", "auto", "moonsightingseason"]);
+        assert!(list.iter().all(|s| !s.description.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_compute_days_fills_only_the_misses() {
+        let state = AppState::new();
+        let resolved = LocationResolver::from_manual(59.33, 18.07, Some("Europe/Stockholm"));
+        let first = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let config = Config::default();
+
+        let warm = compute_days(&state, &resolved, first.iter_days().skip(1).step_by(2).take(3), config).await;
+        assert_eq!(state.cache.lock().unwrap().len(), 3);
+
+        let days = compute_days(&state, &resolved, first.iter_days().take(7), config).await;
+        assert_eq!(state.cache.lock().unwrap().len(), 7);
+        let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2026-03-01", "2026-03-02", "2026-03-03", "2026-03-04", "2026-03-05", "2026-03-06", "2026-03-07"]);
+        assert_eq!(days[1].date, warm[0].date);
+        assert_eq!(days[1].events.fajr.time, warm[0].events.fajr.time);
+    }
+
+    #[test]
+    fn test_days_in_month_leap_and_rollover() {
+        assert_eq!(days_in_month(2024, 2), Some(29));
+        assert_eq!(days_in_month(2025, 2), Some(28));
+        assert_eq!(days_in_month(2026, 12), Some(31));
+        assert_eq!(days_in_month(9999, 12), Some(31));
+        assert_eq!(days_in_month(2026, 13), None);
+        assert_eq!(days_in_month(2026, 0), None);
+
+        assert!(check_year(0).is_err());
+        assert!(check_year(-5).is_err());
+        assert!(check_year(10000).is_err());
+        assert!(check_year(2026).is_ok());
+    }
+
+    #[test]
+    fn test_sweep_angles_range() {
+        let angles = sweep_angles(15.0, 20.0, 0.5).ok().unwrap();
+        assert_eq!(angles.len(), 11);
+        assert_eq!(angles[0], 15.0);
+        assert_eq!(angles[10], 20.0);
+
+        assert!(sweep_angles(20.0, 15.0, 0.5).is_err());
+        assert!(sweep_angles(15.0, 20.0, 0.0).is_err());
+        assert!(sweep_angles(0.0, 90.0, 0.01).is_err());
     }
 }
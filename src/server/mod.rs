@@ -1,40 +1,32 @@
 mod handlers;
+mod rate_limit;
 mod state;
 mod static_files;
 
 use axum::Router;
-use axum::routing::get;
-use axum::http::HeaderValue;
-use state::AppState;
+use axum::routing::{get, post};
+use axum::http::{header, HeaderName, HeaderValue};
+pub use state::{AppState, ComputeCache};
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::set_header::SetResponseHeaderLayer;
 
-/// Application version, read from Cargo.toml at compile time.
-pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub use crate::VERSION;
 
 pub fn build_router() -> Router {
-    let state = Arc::new(AppState::new());
+    build_router_with_state(Arc::new(AppState::new()))
+}
 
-    // API routes with no-cache + version headers
-    let api_routes = Router::new()
-        .route("/api/resolve", get(handlers::resolve))
-        .route("/api/times", get(handlers::prayer_times))
-        .route("/api/month", get(handlers::month_times))
-        .route("/api/cities", get(handlers::city_list))
-        .route("/api/hijri", get(handlers::hijri_info))
-        .layer(SetResponseHeaderLayer::overriding(
-            axum::http::header::CACHE_CONTROL,
-            HeaderValue::from_static("no-store, no-cache, must-revalidate, max-age=0"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            axum::http::header::PRAGMA,
-            HeaderValue::from_static("no-cache"),
-        ))
-        .layer(SetResponseHeaderLayer::overriding(
-            axum::http::HeaderName::from_static("x-polaris-version"),
-            HeaderValue::from_static(VERSION),
-        ));
+/// Build the router around existing state, without binding a port.
+///
+/// The API is served under `/api/v1`; the unversioned `/api` paths remain
+/// as deprecated aliases that answer identically plus a `Deprecation` header.
+pub fn build_router_with_state(state: Arc<AppState>) -> Router {
+    let legacy_routes = api_routes(&state).layer(SetResponseHeaderLayer::overriding(
+        HeaderName::from_static("deprecation"),
+        HeaderValue::from_static("true"),
+    ));
 
     Router::new()
         .route("/", get(handlers::index))
@@ -42,13 +34,78 @@ pub fn build_router() -> Router {
         .route("/docs", get(handlers::index))
         .route("/style.css", get(handlers::style))
         .route("/app.js", get(handlers::script))
-        .merge(api_routes)
+        .nest("/api/v1", api_routes(&state))
+        .nest("/api", legacy_routes)
         .layer(CorsLayer::permissive())
+        // Added after the layers so probes skip CORS, rate limiting and no-store headers
+        .route("/healthz", get(handlers::healthz))
         .with_state(state)
 }
 
-pub async fn start(host: &str, port: u16) {
-    let app = build_router();
+/// API routes with no-cache + version headers and gzip/deflate bodies,
+/// relative to their mount point, behind the per-client rate limit.
+fn api_routes(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/resolve", get(handlers::resolve))
+        .route("/resolve-batch", post(handlers::resolve_batch))
+        .route("/times", get(handlers::prayer_times))
+        .route("/times.svg", get(handlers::prayer_times_svg))
+        .route("/month", get(handlers::month_times))
+        .route("/year", get(handlers::year_times))
+        .route("/fajr-sweep", get(handlers::fajr_sweep))
+        .route("/crossing", get(handlers::crossing))
+        .route("/window", get(handlers::prayer_window))
+        .route("/qibla", get(handlers::qibla))
+        .route("/cities", get(handlers::city_list))
+        .route("/country", get(handlers::country_times))
+        .route("/compare", get(handlers::compare_methods))
+        .route("/extremes", get(handlers::prayer_extremes_year))
+        .route("/hijri", get(handlers::hijri_info))
+        .route("/ramadan", get(handlers::ramadan))
+        .route("/hijri/convert", get(handlers::hijri_convert))
+        .route("/gregorian", get(handlers::gregorian_convert))
+        .route("/crescent-map", get(handlers::crescent_map))
+        .route("/analemma", get(handlers::analemma))
+        .route("/moon", get(handlers::moon))
+        .route("/methods", get(handlers::methods))
+        .route("/strategies", get(handlers::strategies))
+        .route("/admin/warm", post(handlers::warm_cache))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("no-store, no-cache, must-revalidate, max-age=0"),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::PRAGMA,
+            HeaderValue::from_static("no-cache"),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static("x-polaris-version"),
+            HeaderValue::from_static(VERSION),
+        ))
+        .layer(CompressionLayer::new())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit::limit))
+}
+
+/// Serve on `host:port`; `admin_token` protects the admin endpoints,
+/// `rate_limit` caps API requests per client IP per minute (0 for none),
+/// `trusted_proxy` takes that IP from `X-Forwarded-For` and
+/// `persist_cache` keeps computed days in `~/.polaris/compute_cache.json`
+/// across restarts.
+pub async fn start(host: &str, port: u16, admin_token: Option<String>, rate_limit: u32, trusted_proxy: bool, persist_cache: bool) {
+    let has_token = admin_token.is_some();
+    let mut state = AppState::new()
+        .with_admin_token(admin_token)
+        .with_rate_limit(Some(rate_limit))
+        .with_trusted_proxy(trusted_proxy);
+    if persist_cache {
+        state = state.with_cache_file(ComputeCache::default_path());
+    }
+    let state = Arc::new(state);
+    let cache_line = match persist_cache {
+        true => format!("persisted ({} entries loaded, 6h TTL)", state.cache.lock().unwrap().len()),
+        false => "fresh (in-memory, 6h TTL)".to_string(),
+    };
+    let app = build_router_with_state(state.clone());
     let addr = format!("{}:{}", host, port);
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
@@ -62,26 +119,84 @@ pub async fn start(host: &str, port: u16) {
     eprintln!();
     eprintln!("--------------------------------------------------");
     eprintln!("  Polaris Chronos Server v{}", VERSION);
-    eprintln!("  Cache: fresh (in-memory, 6h TTL)");
+    eprintln!("  Cache: {}", cache_line);
     eprintln!();
     eprintln!("  Local:     {}", base);
     eprintln!("  Docs:      {}/docs", base);
     eprintln!();
     eprintln!("  API:");
-    eprintln!("    {}/api/resolve?query=stockholm", base);
-    eprintln!("    {}/api/times?city=stockholm", base);
-    eprintln!("    {}/api/month?city=stockholm", base);
-    eprintln!("    {}/api/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh", base);
-    eprintln!("    {}/api/cities", base);
+    eprintln!("    {}/api/v1/resolve?query=stockholm", base);
+    eprintln!("    {}/api/v1/times?city=stockholm", base);
+    eprintln!("    {}/api/v1/month?city=stockholm", base);
+    eprintln!("    {}/api/v1/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh", base);
+    eprintln!("    {}/api/v1/cities", base);
+    eprintln!("    {}/api/v1/methods", base);
+    eprintln!();
+    eprintln!("  Admin:     POST {}/api/v1/admin/warm ({})", base,
+        if has_token { "X-Admin-Token required" } else { "disabled; set --admin-token" });
+    match rate_limit {
+        0 => eprintln!("  Rate limit: off"),
+        n => eprintln!("  Rate limit: {} requests/min per client{}", n,
+            if trusted_proxy { " (X-Forwarded-For)" } else { "" }),
+    }
     eprintln!();
     eprintln!("  Press Ctrl+C to stop.");
     eprintln!("--------------------------------------------------");
     eprintln!();
 
-    axum::serve(listener, app)
+    serve_until(listener, app, shutdown_signal())
         .await
         .unwrap_or_else(|e| {
             eprintln!("Server error: {}", e);
             std::process::exit(1);
         });
+    match state.save_cache() {
+        Some(Ok((path, saved))) => eprintln!("  Saved {} cached days to {}", saved, path.display()),
+        Some(Err(e)) => eprintln!("  Warning: could not save the compute cache: {}", e),
+        None => {}
+    }
+    eprintln!("  Stopped.");
+}
+
+/// Serve `app` on `listener` until `shutdown` completes, then stop
+/// accepting connections and let in-flight requests finish.
+///
+/// Nothing is flushed here; with `--persist-cache`, `start` saves the
+/// compute cache to disk once this returns.
+pub async fn serve_until(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await
+}
+
+/// Resolve on Ctrl+C, or SIGTERM on Unix (process managers, `docker stop`).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    eprintln!();
+    eprintln!("  Shutting down\u{2026} (finishing in-flight requests)");
 }
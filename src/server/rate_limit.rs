@@ -0,0 +1,168 @@
+//! Per-client token-bucket rate limiting for the API routes.
+//!
+//! Cache misses fan out to Nominatim and other free services, so one client
+//! must not be able to drive unbounded outbound traffic. Each client IP gets
+//! a bucket of `per_minute` tokens that refills continuously.
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::handlers::api_error;
+use super::state::AppState;
+
+/// How often idle (full) buckets are dropped.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    by_client: HashMap<IpAddr, Bucket>,
+    last_sweep: Instant,
+}
+
+/// The client a request was charged to, for handlers that charge
+/// per unit of work (see [`charge`]).
+#[derive(Debug, Clone, Copy)]
+pub struct Client(pub IpAddr);
+
+/// Token buckets by client IP.
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Allow `per_minute` requests per client, with bursts up to the same.
+    pub fn new(per_minute: u32) -> Self {
+        assert!(per_minute > 0, "rate limit must be at least 1 request per minute");
+        let buckets = Buckets { by_client: HashMap::new(), last_sweep: Instant::now() };
+        Self { per_minute, buckets: Mutex::new(buckets) }
+    }
+
+    /// Take a token for `client` at `now`; on refusal, the seconds until
+    /// the next token.
+    pub fn check(&self, client: IpAddr, now: Instant) -> Result<(), u64> {
+        let capacity = self.per_minute as f64;
+        let per_second = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.last_sweep) >= SWEEP_INTERVAL {
+            // A full bucket behaves exactly like a missing one
+            buckets.by_client.retain(|_, b| b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * per_second < capacity);
+            buckets.last_sweep = now;
+        }
+
+        let bucket = buckets.by_client.entry(client).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_second).ceil() as u64)
+        }
+    }
+
+    fn refusal(&self, retry_after: u64) -> String {
+        format!("Rate limit of {} requests per minute exceeded; retry in {}s", self.per_minute, retry_after)
+    }
+}
+
+/// Take another token from `client` for work beyond the request itself;
+/// passes when the server is unlimited.
+pub fn charge(state: &AppState, client: Option<Client>) -> Result<(), String> {
+    let (Some(limiter), Some(Client(ip))) = (state.rate_limiter.as_ref(), client) else {
+        return Ok(());
+    };
+    limiter.check(ip, Instant::now()).map_err(|retry_after| limiter.refusal(retry_after))
+}
+
+/// The client's address. Without a trusted proxy this is the peer address;
+/// behind one, it is the last `X-Forwarded-For` hop, the one the proxy
+/// appended. Earlier hops come from the client and can be forged.
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trusted_proxy: bool) -> Option<IpAddr> {
+    let forwarded = || headers.get_all("x-forwarded-for").iter().next_back()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|hop| hop.trim().parse().ok());
+    if trusted_proxy {
+        forwarded().or(peer.map(|addr| addr.ip()))
+    } else {
+        peer.map(|addr| addr.ip())
+    }
+}
+
+/// Reject requests over the client's budget with `429 Too Many Requests`.
+pub async fn limit(State(state): State<Arc<AppState>>, mut request: Request, next: Next) -> Response {
+    let Some(limiter) = state.rate_limiter.as_ref() else {
+        return next.run(request).await;
+    };
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    let Some(client) = client_ip(request.headers(), peer, state.trusted_proxy) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(client, Instant::now()) {
+        Ok(()) => {
+            request.extensions_mut().insert(Client(client));
+            next.run(request).await
+        }
+        Err(retry_after) => {
+            let mut response = api_error(StatusCode::TOO_MANY_REQUESTS, limiter.refusal(retry_after)).into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills() {
+        let limiter = RateLimiter::new(2);
+        let (a, b): (IpAddr, IpAddr) = ("203.0.113.1".parse().unwrap(), "203.0.113.2".parse().unwrap());
+        let t0 = Instant::now();
+        assert!(limiter.check(a, t0).is_ok());
+        assert!(limiter.check(a, t0).is_ok());
+        assert_eq!(limiter.check(a, t0), Err(30));
+        assert!(limiter.check(b, t0).is_ok(), "buckets are per client");
+        assert!(limiter.check(a, t0 + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn test_idle_buckets_are_swept() {
+        let limiter = RateLimiter::new(2);
+        let (a, b): (IpAddr, IpAddr) = ("203.0.113.1".parse().unwrap(), "203.0.113.2".parse().unwrap());
+        let t0 = Instant::now();
+        assert!(limiter.check(a, t0).is_ok());
+        assert!(limiter.check(b, t0 + Duration::from_secs(50)).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().by_client.len(), 2);
+
+        // At the sweep, a has refilled and is dropped; b is still spending
+        assert!(limiter.check(b, t0 + SWEEP_INTERVAL).is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.by_client.keys().collect::<Vec<_>>(), [&b]);
+    }
+
+    #[test]
+    fn test_client_ip_trusts_forwarded_for_only_behind_proxy() {
+        let peer: SocketAddr = "10.0.0.5:443".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_ip(&headers, Some(peer), true), Some(peer.ip()));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("192.0.2.66, 198.51.100.7"));
+        assert_eq!(client_ip(&headers, Some(peer), false), Some(peer.ip()));
+        assert_eq!(client_ip(&headers, Some(peer), true), "198.51.100.7".parse().ok());
+        assert_eq!(client_ip(&HeaderMap::new(), None, false), None);
+    }
+}
@@ -1,70 +1,582 @@
-use crate::location::LocationResolver;
+use crate::clock::{Clock, SystemClock};
+use crate::location::{LocationError, LocationResolver, ResolveOptions, ResolvedLocation};
 use crate::solver::SolverOutput;
-use std::collections::HashMap;
-use std::sync::Mutex;
-use std::time::Instant;
+use super::rate_limit::RateLimiter;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// How long a computed day stays cached.
+const COMPUTE_CACHE_TTL_SECS: u64 = 6 * 3600;
+/// Most computed days the server keeps before evicting the least recently used.
+const COMPUTE_CACHE_CAPACITY: usize = 1000;
+
+/// A cached day: computed by this process, or restored from disk as the
+/// JSON it was served as.
+enum CachedDay {
+    Output(Box<SolverOutput>),
+    Json(String),
+}
+
+impl CachedDay {
+    fn to_json(&self) -> String {
+        match self {
+            CachedDay::Output(output) => serde_json::to_string(output).expect("solver output serializes"),
+            CachedDay::Json(json) => json.clone(),
+        }
+    }
+}
 
 /// Cache entry with TTL tracking.
 struct CacheEntry {
-    output: SolverOutput,
+    day: CachedDay,
     created: Instant,
+    /// Position in `ComputeCache::recency`.
+    last_used: u64,
 }
 
-/// In-memory computation cache with TTL eviction.
+/// In-memory computation cache with TTL expiry and LRU eviction.
 pub struct ComputeCache {
     entries: HashMap<String, CacheEntry>,
+    /// Keys by last use, least recent first.
+    recency: BTreeMap<u64, String>,
+    /// Next `last_used` stamp.
+    tick: u64,
     ttl_secs: u64,
+    capacity: usize,
 }
 
 impl ComputeCache {
-    pub fn new(ttl_secs: u64) -> Self {
+    /// A cache holding at most `capacity` entries (at least one), each for
+    /// `ttl_secs`.
+    pub fn new(ttl_secs: u64, capacity: usize) -> Self {
         Self {
             entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
             ttl_secs,
+            capacity: capacity.max(1),
         }
     }
 
     /// Build a cache key from computation parameters.
-    pub fn key(lat: f64, lon: f64, date: &str, strategy: &str) -> String {
-        format!("{:.4},{:.4},{},{}", lat, lon, date, strategy)
+    ///
+    /// `tz` is the location's effective timezone (local times and the solar
+    /// day both depend on it); `fingerprint` is `Config::cache_fingerprint()`,
+    /// covering every option that changes the output.
+    pub fn key(lat: f64, lon: f64, tz: &str, date: &str, fingerprint: &str) -> String {
+        format!("{:.4},{:.4},{},{},{}", lat, lon, tz, date, fingerprint)
+    }
+
+    /// Weak ETag for a response built from the entry at `key`; `variant`
+    /// covers whatever else shapes the body (location name, extras).
+    ///
+    /// FNV-1a over the engine version, key and variant, so it is stable
+    /// across restarts and changes whenever the output could.
+    pub fn etag(key: &str, variant: &str) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in [crate::VERSION, key, variant].join("\n").bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        format!("W/\"{:016x}\"", hash)
     }
 
     /// Get a cached result if it exists and hasn't expired.
+    ///
+    /// Days restored from disk are JSON only and count as misses here; the
+    /// caller's `put` replaces them.
     pub fn get(&mut self, key: &str) -> Option<SolverOutput> {
-        if let Some(entry) = self.entries.get(key) {
-            if entry.created.elapsed().as_secs() < self.ttl_secs {
-                return Some(entry.output.clone());
-            }
+        match &self.touch(key)?.day {
+            CachedDay::Output(output) => Some(SolverOutput::clone(output)),
+            CachedDay::Json(_) => None,
+        }
+    }
+
+    /// The cached result as JSON, including days restored from disk.
+    pub fn get_json(&mut self, key: &str) -> Option<String> {
+        self.touch(key).map(|entry| entry.day.to_json())
+    }
+
+    /// The unexpired entry at `key`, marked most recently used.
+    fn touch(&mut self, key: &str) -> Option<&CacheEntry> {
+        if self.entries.get(key)?.created.elapsed().as_secs() >= self.ttl_secs {
             // Expired — remove it
-            self.entries.remove(key);
+            self.remove(key);
+            return None;
         }
-        None
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.recency.insert(self.tick, key.to_string());
+        self.tick += 1;
+        Some(entry)
+    }
+
+    /// Number of stored entries, expired ones included until read or evicted.
+    pub fn len(&self) -> usize {
+        self.entries.len()
     }
 
-    /// Store a computation result.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Store a computation result, evicting the least recently used entry
+    /// when full.
     pub fn put(&mut self, key: String, output: SolverOutput) {
-        // Evict old entries if cache gets too large
-        if self.entries.len() > 1000 {
-            let cutoff = Instant::now();
-            self.entries.retain(|_, v| cutoff.duration_since(v.created).as_secs() < self.ttl_secs);
+        self.insert(key, CachedDay::Output(Box::new(output)), Instant::now());
+    }
+
+    fn insert(&mut self, key: String, day: CachedDay, created: Instant) {
+        self.remove(&key);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            self.entries.remove(&oldest);
         }
-        self.entries.insert(key, CacheEntry {
-            output,
-            created: Instant::now(),
-        });
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, CacheEntry { day, created, last_used: self.tick });
+        self.tick += 1;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+}
+
+/// On-disk form of the compute cache. `Instant`s cannot be stored, so each
+/// entry keeps its wall-clock creation time and is re-based on load. Days
+/// are stored as the JSON they are served as, so they need no parsing.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    /// Engine version that computed the entries; others are discarded.
+    engine_version: String,
+    entries: Vec<CacheFileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFileEntry {
+    key: String,
+    created_ms: i64,
+    json: String,
+}
+
+impl ComputeCache {
+    /// Default persistence path (~/.polaris/compute_cache.json).
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".polaris")
+            .join("compute_cache.json")
+    }
+
+    /// Write the unexpired entries to `path`, least recently used first;
+    /// returns how many were saved.
+    pub fn save_to(&self, path: &Path) -> std::io::Result<usize> {
+        let (now, now_ms) = (Instant::now(), Utc::now().timestamp_millis());
+        let entries: Vec<CacheFileEntry> = self.recency.values()
+            .map(|key| (key, &self.entries[key]))
+            .filter(|(_, entry)| now.duration_since(entry.created).as_secs() < self.ttl_secs)
+            .map(|(key, entry)| CacheFileEntry {
+                key: key.clone(),
+                created_ms: now_ms - now.duration_since(entry.created).as_millis() as i64,
+                json: entry.day.to_json(),
+            })
+            .collect();
+        let saved = entries.len();
+        let file = CacheFile { engine_version: crate::VERSION.to_string(), entries };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(&file).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)?;
+        Ok(saved)
+    }
+
+    /// A cache holding the still-fresh entries saved at `path`.
+    ///
+    /// A missing or unreadable file, or one written by another engine
+    /// version, gives an empty cache.
+    pub fn load_from(path: &Path, ttl_secs: u64, capacity: usize) -> Self {
+        let mut cache = Self::new(ttl_secs, capacity);
+        let Some(file) = std::fs::read_to_string(path).ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|file| file.engine_version == crate::VERSION)
+        else {
+            return cache;
+        };
+
+        let (now, now_ms) = (Instant::now(), Utc::now().timestamp_millis());
+        for entry in file.entries {
+            let age = Duration::from_millis(now_ms.saturating_sub(entry.created_ms).max(0) as u64);
+            if age.as_secs() >= ttl_secs {
+                continue;
+            }
+            if let Some(created) = now.checked_sub(age) {
+                cache.insert(entry.key, CachedDay::Json(entry.json), created);
+            }
+        }
+        cache
     }
 }
 
 pub struct AppState {
-    pub resolver: Mutex<LocationResolver>,
+    /// Read-locked for cache hits; write-locked only to store what a
+    /// lookup further down the fallback chain found.
+    pub resolver: RwLock<LocationResolver>,
     pub cache: Mutex<ComputeCache>,
+    /// Computations under way, by cache key, so concurrent misses share one.
+    inflight: Mutex<HashMap<String, Arc<OnceCell<SolverOutput>>>>,
+    /// Token required in `X-Admin-Token` for admin endpoints (open when `None`).
+    pub admin_token: Option<String>,
+    /// Source of "today" for requests without a date.
+    pub clock: Box<dyn Clock>,
+    /// When this state (i.e. the server) was created, for `/healthz` uptime.
+    pub started: Instant,
+    /// Per-client request budget for the API routes (unlimited when `None`).
+    pub(super) rate_limiter: Option<RateLimiter>,
+    /// Whether a reverse proxy sits in front, so the rate limit keys on its
+    /// `X-Forwarded-For` hop rather than the peer address.
+    pub(super) trusted_proxy: bool,
+    /// Where the compute cache is reloaded from and saved to on shutdown.
+    cache_file: Option<PathBuf>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        Self::with_resolver(LocationResolver::new())
+    }
+
+    /// State around a specific resolver (e.g. offline with a temp cache, for testing).
+    pub fn with_resolver(resolver: LocationResolver) -> Self {
         Self {
-            resolver: Mutex::new(LocationResolver::new()),
-            cache: Mutex::new(ComputeCache::new(6 * 3600)), // 6 hour TTL
+            resolver: RwLock::new(resolver),
+            cache: Mutex::new(ComputeCache::new(COMPUTE_CACHE_TTL_SECS, COMPUTE_CACHE_CAPACITY)),
+            inflight: Mutex::default(),
+            admin_token: None,
+            clock: Box::new(SystemClock),
+            started: Instant::now(),
+            rate_limiter: None,
+            trusted_proxy: false,
+            cache_file: None,
         }
     }
+
+    /// Reload the compute cache from `path` now and save it back there in
+    /// [`AppState::save_cache`].
+    pub fn with_cache_file(mut self, path: PathBuf) -> Self {
+        self.cache = Mutex::new(ComputeCache::load_from(&path, COMPUTE_CACHE_TTL_SECS, COMPUTE_CACHE_CAPACITY));
+        self.cache_file = Some(path);
+        self
+    }
+
+    /// Save the compute cache to its file, if one is set; returns the path
+    /// and the number of entries written.
+    pub fn save_cache(&self) -> Option<std::io::Result<(&Path, usize)>> {
+        let path = self.cache_file.as_deref()?;
+        Some(self.cache.lock().unwrap().save_to(path).map(|saved| (path, saved)))
+    }
+
+    pub fn with_admin_token(mut self, token: Option<String>) -> Self {
+        self.admin_token = token;
+        self
+    }
+
+    /// Limit each client IP to `per_minute` API requests; `None` or 0 lifts the limit.
+    pub fn with_rate_limit(mut self, per_minute: Option<u32>) -> Self {
+        self.rate_limiter = per_minute.filter(|&n| n > 0).map(RateLimiter::new);
+        self
+    }
+
+    /// Trust `X-Forwarded-For` from the peer: set only when every request
+    /// arrives through a reverse proxy that appends the client's address.
+    pub fn with_trusted_proxy(mut self, trusted_proxy: bool) -> Self {
+        self.trusted_proxy = trusted_proxy;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Resolve a city, answering cache hits under the shared read lock so
+    /// concurrent requests for known cities don't queue behind each other.
+    /// Misses geocode with no lock held and take the write lock only to
+    /// cache the result.
+    pub fn resolve_city(&self, query: &str, opts: &ResolveOptions) -> Result<ResolvedLocation, LocationError> {
+        let geocoder = {
+            let resolver = self.resolver.read().unwrap();
+            if let Some(cached) = resolver.cached(query, opts) {
+                return Ok(cached);
+            }
+            resolver.geocoder()
+        };
+        let resolution = geocoder.lookup(query, opts)?;
+        Ok(self.resolver.write().unwrap().remember(resolution))
+    }
+
+    /// Today's date (UTC) by this state's clock.
+    pub fn today(&self) -> NaiveDate {
+        self.clock.today_utc()
+    }
+
+    /// The cached output for `key`, or `compute`'s result stored under it.
+    /// Returns `(output, was_cached)`.
+    ///
+    /// Concurrent misses on one key are single-flight: the first caller
+    /// computes on the blocking pool and the rest wait for its result. The
+    /// waiters report a miss too, since none of them found it cached.
+    pub async fn get_or_compute(
+        &self,
+        key: String,
+        compute: impl FnOnce() -> SolverOutput + Send + 'static,
+    ) -> (SolverOutput, bool) {
+        // Checking the cache and joining the in-flight computation under one
+        // lock means a miss can never slip between another caller's put and
+        // its removal of the in-flight entry
+        let cell = {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(cached) = cache.get(&key) {
+                return (cached, true);
+            }
+            self.inflight.lock().unwrap().entry(key.clone()).or_default().clone()
+        };
+
+        let output = cell.get_or_init(|| async {
+            let output = tokio::task::spawn_blocking(compute).await.expect("computation panicked");
+            let mut cache = self.cache.lock().unwrap();
+            cache.put(key.clone(), output.clone());
+            self.inflight.lock().unwrap().remove(&key);
+            output
+        }).await.clone();
+        (output, false)
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::method::CalculationMethod;
+    use crate::solver::{Location, Solver};
+    use chrono::NaiveDate;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_misses_compute_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const REQUESTS: usize = 8;
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = crate::location::cache::LocationCache::load_from(dir.path().join("cache.json"));
+        let state = Arc::new(AppState::with_resolver(LocationResolver::with_cache(cache)));
+        let runs = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(tokio::sync::Barrier::new(REQUESTS));
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+
+        let tasks: Vec<_> = (0..REQUESTS).map(|_| {
+            let (state, runs, barrier) = (state.clone(), runs.clone(), barrier.clone());
+            tokio::spawn(async move {
+                barrier.wait().await;
+                state.get_or_compute("mecca,2026-02-14".into(), move || {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                    Solver::with_utc(Location::new(21.4225, 39.8262)).solve(date, false, false)
+                }).await
+            })
+        }).collect();
+
+        for task in tasks {
+            let (output, cached) = task.await.unwrap();
+            assert_eq!(output.date, "2026-02-14");
+            assert!(!cached, "waiters did not find the result cached");
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1, "the computation must run once");
+        assert_eq!(state.cache.lock().unwrap().len(), 1);
+        assert!(state.inflight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_cached_resolves_share_entry() {
+        use crate::location::{GeocodeProvider, Granularity, LocationSource};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const THREADS: usize = 50;
+        static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+        struct CountingGeocoder;
+        impl GeocodeProvider for CountingGeocoder {
+            fn resolve(&self, query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+                LOOKUPS.fetch_add(1, Ordering::SeqCst);
+                Err(LocationError::NotFound(query.to_string()))
+            }
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut cache = crate::location::cache::LocationCache::load_from(dir.path().join("cache.json"));
+        let mut tromso = LocationResolver::from_manual(69.6492, 18.9553, Some("Europe/Oslo"));
+        tromso.name = "Tromsø".into();
+        cache.put_with_key("Tromso", &tromso);
+        let state = AppState::with_resolver(LocationResolver::with_cache(cache).with_provider(CountingGeocoder));
+
+        let barrier = std::sync::Barrier::new(THREADS);
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS).map(|_| scope.spawn(|| {
+                barrier.wait();
+                state.resolve_city("Tromso", &ResolveOptions::default()).unwrap()
+            })).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(LOOKUPS.load(Ordering::SeqCst), 0, "cache hits must not reach the geocoder");
+        for resolved in &results {
+            assert_eq!(resolved.source, LocationSource::Cache);
+            assert_eq!((resolved.lat, resolved.lon, resolved.tz.as_str()), (tromso.lat, tromso.lon, "Europe/Oslo"));
+        }
+
+        // A miss still takes the full fallback chain: geocoder, then the built-in dataset
+        let mecca = state.resolve_city("Mecca", &ResolveOptions::default()).unwrap();
+        assert_eq!(LOOKUPS.load(Ordering::SeqCst), 1);
+        assert_eq!(mecca.source, LocationSource::Fallback);
+    }
+
+    #[test]
+    fn test_geocoding_does_not_hold_the_resolver() {
+        use crate::location::{GeocodeProvider, Granularity};
+        use std::sync::Barrier;
+
+        struct GatedGeocoder(Arc<Barrier>);
+        impl GeocodeProvider for GatedGeocoder {
+            fn resolve(&self, query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+                // Wait until the test has checked the lock, then let it wait for us
+                self.0.wait();
+                self.0.wait();
+                let mut loc = LocationResolver::from_manual(59.8586, 17.6389, Some("Europe/Stockholm"));
+                loc.name = query.to_string();
+                Ok(loc)
+            }
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = crate::location::cache::LocationCache::load_from(dir.path().join("cache.json"));
+        let gate = Arc::new(Barrier::new(2));
+        let state = AppState::with_resolver(LocationResolver::with_cache(cache).with_provider(GatedGeocoder(gate.clone())));
+
+        std::thread::scope(|scope| {
+            let lookup = scope.spawn(|| state.resolve_city("Uppsala", &ResolveOptions::default()));
+            gate.wait();
+            assert!(state.resolver.try_write().is_ok(), "the lookup is in progress without the lock");
+            gate.wait();
+            assert_eq!(lookup.join().unwrap().unwrap().name, "Uppsala");
+        });
+        assert!(state.resolver.read().unwrap().cached("Uppsala", &ResolveOptions::default()).is_some());
+    }
+
+    #[test]
+    fn test_cache_file_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("polaris").join("compute_cache.json");
+        let resolved = LocationResolver::from_manual(69.6492, 18.9553, Some("Europe/Oslo"));
+        // Every opt-in block, a polar day and a shifted display zone
+        let config = Config::default()
+            .with_sidereal(true).with_azimuth(true).with_makruh(true).with_twilight_times(true).with_qibla(true)
+            .with_elevation(120.0)
+            .with_display_tz(Some(chrono_tz::America::New_York));
+        let solver = Solver::from_resolved(&resolved).with_config(config);
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let output = solver.solve_with_info(date, true, true, Some(&resolved)).with_hijri();
+
+        let mut cache = ComputeCache::new(3600, 10);
+        cache.put("tromso,2026-06-21".into(), output.clone());
+        assert_eq!(cache.save_to(&path).unwrap(), 1);
+
+        let mut reloaded = ComputeCache::load_from(&path, 3600, 10);
+        let restored = reloaded.get_json("tromso,2026-06-21").expect("entry survives a restart");
+        assert_eq!(restored, serde_json::to_string(&output).unwrap());
+        // Restored days are served as JSON; typed readers recompute them
+        assert!(reloaded.get("tromso,2026-06-21").is_none());
+        assert_eq!(reloaded.len(), 1);
+        reloaded.put("tromso,2026-06-21".into(), output.clone());
+        assert!(reloaded.get("tromso,2026-06-21").is_some());
+
+        // Too old for the new TTL, unreadable or from another engine: start empty
+        assert!(ComputeCache::load_from(&path, 0, 10).is_empty());
+        std::fs::write(&path, "{\"engine_version\": \"0.0.1\", \"entries\": []}").unwrap();
+        assert!(ComputeCache::load_from(&path, 3600, 10).is_empty());
+        std::fs::write(&path, "not json").unwrap();
+        assert!(ComputeCache::load_from(&path, 3600, 10).is_empty());
+        assert!(ComputeCache::load_from(&dir.path().join("missing.json"), 3600, 10).is_empty());
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let base = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let solver = Solver::with_utc(Location::new(21.4225, 39.8262));
+        let output = solver.solve(base, false, false);
+        let key = |day: usize| format!("mecca,{}", base + chrono::Days::new(day as u64));
+
+        const CAPACITY: usize = 4;
+        let mut cache = ComputeCache::new(3600, CAPACITY);
+        for day in 0..CAPACITY {
+            cache.put(key(day), output.clone());
+        }
+        // Touch the second entry so the first is now the least recently used
+        assert!(cache.get(&key(1)).is_some());
+        cache.put(key(CAPACITY), output.clone());
+
+        assert_eq!(cache.len(), CAPACITY);
+        assert!(cache.get(&key(0)).is_none(), "first-inserted, never-read key must be evicted");
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(CAPACITY)).is_some());
+
+        // Replacing an existing key does not evict anything
+        cache.put(key(2), output);
+        assert_eq!(cache.len(), CAPACITY);
+        assert!(cache.get(&key(3)).is_some());
+    }
+
+    #[test]
+    fn test_etag_stable_and_distinct() {
+        let key = ComputeCache::key(21.4225, 39.8262, "Asia/Riyadh", "2026-02-14", &Config::default().cache_fingerprint());
+        let etag = ComputeCache::etag(&key, "Mecca");
+        println!("{}", etag);
+        assert_eq!(etag, ComputeCache::etag(&key, "Mecca"));
+        assert!(etag.starts_with("W/\"") && etag.ends_with('"'));
+        assert_ne!(etag, ComputeCache::etag(&key, "Mecca;combine"));
+        let other_day = ComputeCache::key(21.4225, 39.8262, "Asia/Riyadh", "2026-02-15", &Config::default().cache_fingerprint());
+        assert_ne!(etag, ComputeCache::etag(&other_day, "Mecca"));
+    }
+
+    #[test]
+    fn test_cache_key_separates_methods() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let mwl = Config::default();
+        let isna = Config::default().with_method(CalculationMethod::ISNA);
+
+        let key_mwl = ComputeCache::key(21.4225, 39.8262, "UTC", &date.to_string(), &mwl.cache_fingerprint());
+        let key_isna = ComputeCache::key(21.4225, 39.8262, "UTC", &date.to_string(), &isna.cache_fingerprint());
+        assert_ne!(key_mwl, key_isna);
+
+        let mut cache = ComputeCache::new(60, 10);
+        let solver = Solver::with_utc(Location::new(21.4225, 39.8262)).with_config(mwl);
+        let output = solver.solve(date, false, false);
+        cache.put(key_mwl.clone(), output.clone());
+
+        assert!(cache.get(&key_isna).is_none(), "ISNA request must not hit the MWL entry");
+        let hit = cache.get(&key_mwl).unwrap();
+        assert_eq!(hit.method, CalculationMethod::MWL);
+        assert_eq!(hit.events.fajr.time, output.events.fajr.time);
+    }
 }
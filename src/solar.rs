@@ -3,11 +3,32 @@
 //! Computes altitude and azimuth for any instant, latitude, and longitude.
 //! Accuracy: ~0.01° for dates within ±50 years of J2000.
 
-use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::Serialize;
 use std::f64::consts::PI;
 
 const DEG: f64 = PI / 180.0;
-const ATMOSPHERIC_REFRACTION: f64 = 0.833;
+/// Refraction lifting the sun at the horizon (degrees).
+const HORIZON_REFRACTION: f64 = 0.583;
+/// Apparent solar semidiameter (degrees).
+const SOLAR_SEMIDIAMETER: f64 = 0.25;
+
+#[cfg(feature = "eval-counter")]
+static EVALUATIONS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Process-wide number of `solar_position` evaluations so far.
+///
+/// Always 0 when built without the `eval-counter` feature.
+pub fn evaluation_count() -> u64 {
+    #[cfg(feature = "eval-counter")]
+    {
+        EVALUATIONS.load(std::sync::atomic::Ordering::Relaxed)
+    }
+    #[cfg(not(feature = "eval-counter"))]
+    {
+        0
+    }
+}
 
 /// Solar position at a specific instant.
 #[derive(Debug, Clone, Copy)]
@@ -16,6 +37,8 @@ pub struct SolarPosition {
     pub azimuth: f64,
     pub declination: f64,
     pub equation_of_time: f64,
+    /// Local hour angle in degrees (-180..180, negative before noon).
+    pub hour_angle: f64,
 }
 
 /// A timestamped altitude sample from a day scan.
@@ -120,6 +143,19 @@ fn equation_of_time(t: f64) -> f64 {
     4.0 * eq / DEG
 }
 
+/// Local sidereal time in degrees (0..360) for a Julian Date and east longitude.
+///
+/// Greenwich mean sidereal time (Meeus, eq. 12.4) shifted by the longitude.
+pub fn local_sidereal_time(jd: f64, lon: f64) -> f64 {
+    let t = julian_century(jd);
+    let gmst = normalize_degrees(
+        280.46061837 + 360.98564736629 * (jd - 2451545.0)
+            + 0.000387933 * t * t
+            - t * t * t / 38710000.0,
+    );
+    normalize_degrees(gmst + lon)
+}
+
 /// Compute the Sun's ecliptic longitude for a given UTC datetime.
 pub fn sun_ecliptic_longitude(dt: &NaiveDateTime) -> f64 {
     let jd = julian_date(dt);
@@ -129,6 +165,9 @@ pub fn sun_ecliptic_longitude(dt: &NaiveDateTime) -> f64 {
 
 /// Compute the solar position for a given UTC datetime, latitude, and longitude.
 pub fn solar_position(dt: &NaiveDateTime, lat: f64, lon: f64) -> SolarPosition {
+    #[cfg(feature = "eval-counter")]
+    EVALUATIONS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
     let jd = julian_date(dt);
     let t = julian_century(jd);
 
@@ -156,25 +195,35 @@ pub fn solar_position(dt: &NaiveDateTime, lat: f64, lon: f64) -> SolarPosition {
     };
     azimuth = normalize_degrees(azimuth);
 
-    SolarPosition { altitude, azimuth, declination: decl, equation_of_time: eqt }
+    let hour_angle = normalize_degrees(hour_angle + 180.0) - 180.0;
+    SolarPosition { altitude, azimuth, declination: decl, equation_of_time: eqt, hour_angle }
 }
 
 /// Scan the full 24-hour solar altitude curve.
 pub fn day_scan(date: NaiveDate, lat: f64, lon: f64, resolution_seconds: u32) -> Vec<AltitudeSample> {
-    let mut samples = Vec::new();
-    let mut sec = 0u32;
-    while sec < 86400 {
-        let h = sec / 3600;
-        let m = (sec % 3600) / 60;
-        let s = sec % 60;
-        if let Some(time) = NaiveTime::from_hms_opt(h, m, s) {
-            let dt = NaiveDateTime::new(date, time);
+    day_scan_from(date, 0, lat, lon, resolution_seconds)
+}
+
+/// Scan 24 hours of solar altitude starting `start_offset` seconds from UTC
+/// midnight of `date`. Sample seconds are relative to that midnight, so they
+/// may be negative or exceed 86400.
+pub fn day_scan_from(
+    date: NaiveDate,
+    start_offset: i64,
+    lat: f64,
+    lon: f64,
+    resolution_seconds: u32,
+) -> Vec<AltitudeSample> {
+    let midnight = NaiveDateTime::new(date, NaiveTime::MIN);
+    (0..86400i64)
+        .step_by(resolution_seconds as usize)
+        .map(|sec| {
+            let offset = start_offset + sec;
+            let dt = midnight + Duration::seconds(offset);
             let pos = solar_position(&dt, lat, lon);
-            samples.push(AltitudeSample { seconds: sec as f64, altitude: pos.altitude });
-        }
-        sec += resolution_seconds;
-    }
-    samples
+            AltitudeSample { seconds: offset as f64, altitude: pos.altitude }
+        })
+        .collect()
 }
 
 pub fn find_peak(samples: &[AltitudeSample]) -> AltitudeSample {
@@ -185,9 +234,117 @@ pub fn find_nadir(samples: &[AltitudeSample]) -> AltitudeSample {
     *samples.iter().min_by(|a, b| a.altitude.partial_cmp(&b.altitude).unwrap()).unwrap()
 }
 
+/// Refine a scan extremum (peak or nadir) near `approx_seconds` by
+/// successive parabolic fits through fresh evaluations.
+///
+/// Sample seconds are relative to UTC midnight of `date`. The result is no
+/// longer tied to the scan grid, so it also works when the true extremum
+/// lies just beyond the edge of the scan window.
+pub fn refine_extremum(date: NaiveDate, lat: f64, lon: f64, approx_seconds: f64) -> AltitudeSample {
+    let midnight = NaiveDateTime::new(date, NaiveTime::MIN);
+    let altitude_at = |secs: f64| {
+        let dt = midnight + Duration::milliseconds((secs * 1000.0).round() as i64);
+        solar_position(&dt, lat, lon).altitude
+    };
+
+    let mut t = approx_seconds;
+    for h in [600.0, 120.0, 30.0, 5.0] {
+        let (y0, y1, y2) = (altitude_at(t - h), altitude_at(t), altitude_at(t + h));
+        let curvature = y0 - 2.0 * y1 + y2;
+        if curvature.abs() < 1e-12 {
+            break;
+        }
+        // Vertex of the parabola through the three points
+        let dx = (0.5 * (y0 - y2) / curvature * h).clamp(-2.0 * h, 2.0 * h);
+        t += dx;
+    }
+
+    AltitudeSample { seconds: t, altitude: altitude_at(t) }
+}
+
+/// Peak-to-nadir range below which the scan is not treated as one clean
+/// wave: near the poles the daily swing can be smaller than the drift in
+/// declination, and the limbs between the extremes stop being monotonic.
+const BISECT_MIN_AMPLITUDE: f64 = 1.0;
+
+/// Samples between the probes that locate the peak and nadir (32 min at 30 s).
+const BISECT_COARSE_STEP: usize = 64;
+
 /// Find the first crossing of a target altitude (ascending or descending).
 /// Returns interpolated seconds from midnight, or None if no crossing occurs.
 pub fn find_crossing(samples: &[AltitudeSample], target: f64, ascending: bool) -> Option<f64> {
+    find_crossing_bisect(samples, target, ascending)
+}
+
+/// [`find_crossing`] by binary search over the monotonic runs of the wave.
+///
+/// The peak and nadir are found from every 64th sample and a short climb to
+/// the true turning point. The runs are split there and at the small turn
+/// of the previous or next day the scan may catch at either edge. When the
+/// wave is too flat to trust it scans linearly. Either way the result is
+/// the window and interpolation the linear scan would pick.
+pub fn find_crossing_bisect(samples: &[AltitudeSample], target: f64, ascending: bool) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let alt = |i: usize| samples[i].altitude;
+    let end = samples.len() - 1;
+
+    // Coarse extremes, then climb to the turning point next to each
+    let coarse = (0..=end).step_by(BISECT_COARSE_STEP).chain([end]);
+    let peak = climb(samples, coarse.clone().max_by(|&a, &b| alt(a).total_cmp(&alt(b)))?, true);
+    let nadir = climb(samples, coarse.min_by(|&a, &b| alt(a).total_cmp(&alt(b)))?, false);
+    if alt(peak) - alt(nadir) < BISECT_MIN_AMPLITUDE {
+        return find_crossing_linear(samples, target, ascending);
+    }
+
+    let (first, last) = (peak.min(nadir), peak.max(nadir));
+    // The head climbs to a first peak (or falls to a first nadir), possibly after a short turn
+    let head_rises = first == peak;
+    let mut head_turn = 0;
+    while head_turn < first && (alt(head_turn + 1) < alt(head_turn)) == head_rises {
+        head_turn += 1;
+    }
+    // The tail leaves the last extremum, possibly turning just before the end
+    let tail_rises = last == nadir;
+    let mut tail_turn = end;
+    while tail_turn > last && (alt(tail_turn - 1) > alt(tail_turn)) == tail_rises {
+        tail_turn -= 1;
+    }
+
+    [(0, head_turn), (head_turn, first), (first, last), (last, tail_turn), (tail_turn, end)]
+        .into_iter()
+        .find_map(|(lo, hi)| crossing_in_run(&samples[lo..=hi], target, ascending))
+}
+
+/// Walk from `i` to the neighbouring local maximum (or minimum).
+fn climb(samples: &[AltitudeSample], mut i: usize, up: bool) -> usize {
+    let better = |j: usize, i: usize| (samples[j].altitude > samples[i].altitude) == up
+        && samples[j].altitude != samples[i].altitude;
+    loop {
+        if i > 0 && better(i - 1, i) {
+            i -= 1;
+        } else if i + 1 < samples.len() && better(i + 1, i) {
+            i += 1;
+        } else {
+            return i;
+        }
+    }
+}
+
+/// Crossing within a monotonic run of samples, by binary search.
+fn crossing_in_run(run: &[AltitudeSample], target: f64, ascending: bool) -> Option<f64> {
+    let rises = run.last()?.altitude > run.first()?.altitude;
+    if rises != ascending {
+        return None;
+    }
+    // First sample past the target; the one before it is on the other side
+    let j = run.partition_point(|s| if ascending { s.altitude <= target } else { s.altitude >= target });
+    (j > 0 && j < run.len()).then(|| interpolate_crossing(run[j - 1], run[j], target))
+}
+
+/// The original window-by-window scan.
+fn find_crossing_linear(samples: &[AltitudeSample], target: f64, ascending: bool) -> Option<f64> {
     for window in samples.windows(2) {
         let (a, b) = (window[0], window[1]);
         let crosses = if ascending {
@@ -196,13 +353,17 @@ pub fn find_crossing(samples: &[AltitudeSample], target: f64, ascending: bool) -
             a.altitude >= target && b.altitude < target
         };
         if crosses {
-            let frac = (target - a.altitude) / (b.altitude - a.altitude);
-            return Some(a.seconds + frac * (b.seconds - a.seconds));
+            return Some(interpolate_crossing(a, b, target));
         }
     }
     None
 }
 
+fn interpolate_crossing(a: AltitudeSample, b: AltitudeSample, target: f64) -> f64 {
+    let frac = (target - a.altitude) / (b.altitude - a.altitude);
+    a.seconds + frac * (b.seconds - a.seconds)
+}
+
 /// Convert seconds from midnight to HH:MM:SS string.
 pub fn seconds_to_hms(secs: f64) -> String {
     let total = secs.round() as i64;
@@ -222,8 +383,92 @@ pub fn normalize_wave(altitude: f64, min_alt: f64, max_alt: f64) -> f64 {
     ((altitude - min_alt) / amplitude).clamp(0.0, 1.0)
 }
 
-/// Refraction-adjusted horizon angle.
-pub const HORIZON_ANGLE: f64 = -ATMOSPHERIC_REFRACTION;
+/// Altitude of the sun's centre when its upper limb touches the horizon:
+/// refraction plus semidiameter (the usual -0.833°).
+pub const HORIZON_ANGLE: f64 = -(HORIZON_REFRACTION + SOLAR_SEMIDIAMETER);
+
+/// Altitude of the sun's centre when the centre itself is on the
+/// refracted horizon (-0.583°).
+pub const CENTER_HORIZON_ANGLE: f64 = -HORIZON_REFRACTION;
+
+/// Air temperature and pressure at the observer.
+///
+/// Refraction grows with air density, so cold or high-pressure air lifts
+/// the sun further above its geometric position at the horizon.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Atmosphere {
+    pub temperature_c: f64,
+    pub pressure_mbar: f64,
+}
+
+impl Atmosphere {
+    /// 10 °C and 1010 mbar, the conditions behind the standard 34' of
+    /// horizon refraction.
+    pub const STANDARD: Atmosphere = Atmosphere { temperature_c: 10.0, pressure_mbar: 1010.0 };
+
+    /// Refraction relative to standard conditions: P/1010 × 283/(273 + T).
+    pub fn refraction_scale(&self) -> f64 {
+        self.pressure_mbar / 1010.0 * 283.0 / (273.0 + self.temperature_c)
+    }
+
+    /// Horizon refraction in degrees under these conditions.
+    pub fn horizon_refraction(&self) -> f64 {
+        HORIZON_REFRACTION * self.refraction_scale()
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == Atmosphere::STANDARD
+    }
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Atmosphere::STANDARD
+    }
+}
+
+/// Dip of the visible horizon in degrees for an observer `elevation_m`
+/// above the surrounding terrain (0.0347° × √h, refraction included).
+pub fn horizon_dip(elevation_m: f64) -> f64 {
+    0.0347 * elevation_m.max(0.0).sqrt()
+}
+
+// ─── Analemma ───────────────────────────────────────────────────
+
+/// The sun at local mean noon on one day of an analemma.
+#[derive(Debug, Clone, Copy)]
+pub struct AnalemmaPoint {
+    pub date: NaiveDate,
+    pub altitude: f64,
+    pub azimuth: f64,
+    pub declination: f64,
+    /// Minutes; positive when the sundial runs ahead of the clock.
+    pub equation_of_time: f64,
+}
+
+/// Sun position at local mean noon (12:00 mean solar time) every
+/// `step_days` days of `year`. Plotting EoT against declination, or
+/// azimuth against altitude, traces the figure-eight.
+pub fn analemma(year: i32, lat: f64, lon: f64, step_days: u32) -> Vec<AnalemmaPoint> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+        return Vec::new();
+    };
+    let mean_noon = Duration::seconds((43200.0 - lon * 240.0).round() as i64);
+    first.iter_days()
+        .take_while(|d| d.year() == year)
+        .step_by(step_days.max(1) as usize)
+        .map(|date| {
+            let pos = solar_position(&(date.and_time(NaiveTime::MIN) + mean_noon), lat, lon);
+            AnalemmaPoint {
+                date,
+                altitude: pos.altitude,
+                azimuth: pos.azimuth,
+                declination: pos.declination,
+                equation_of_time: pos.equation_of_time,
+            }
+        })
+        .collect()
+}
 
 #[cfg(test)]
 mod tests {
@@ -267,6 +512,41 @@ mod tests {
         assert!(find_crossing(&samples, HORIZON_ANGLE, false).is_some());
     }
 
+    #[test]
+    fn test_bisect_matches_linear_crossing() {
+        // Deterministic pseudo-random locations and dates (64-bit LCG)
+        let mut state: u64 = 0x5eed_1267;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let targets = [HORIZON_ANGLE, CENTER_HORIZON_ANGLE, -6.0, -12.0, -15.0, -18.0, 10.0, 30.0];
+
+        let mut found = 0;
+        for _ in 0..50 {
+            let lat = next() * 179.8 - 89.9;
+            let lon = next() * 360.0 - 180.0;
+            let date = start + chrono::Duration::days((next() * 3650.0) as i64);
+            let window_start = -(lon * 240.0 / 30.0).round() as i64 * 30;
+            let samples = day_scan_from(date, window_start, lat, lon, 30);
+
+            for target in targets {
+                for ascending in [true, false] {
+                    let linear = find_crossing_linear(&samples, target, ascending);
+                    let bisect = find_crossing_bisect(&samples, target, ascending);
+                    match (linear, bisect) {
+                        (None, None) => {}
+                        (Some(a), Some(b)) if (a - b).abs() < 1.0 => found += 1,
+                        _ => panic!("{} {} {} {}° ascending={}: {:?} vs {:?}", lat, lon, date, target, ascending, linear, bisect),
+                    }
+                }
+            }
+        }
+        println!("{} crossings compared", found);
+        assert!(found > 300);
+    }
+
     #[test]
     fn test_tromso_feb14() {
         let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
@@ -289,4 +569,35 @@ mod tests {
         assert!((normalize_wave(10.0, -10.0, 10.0) - 1.0).abs() < 1e-10);
         assert!((normalize_wave(-10.0, -10.0, 10.0) - 0.0).abs() < 1e-10);
     }
+
+    #[test]
+    fn test_greenwich_sidereal_time_reference() {
+        // Meeus, Example 12.a: 1987-04-10 0h UT → GMST 13h10m46.3668s
+        let midnight = NaiveDate::from_ymd_opt(1987, 4, 10).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let lst = local_sidereal_time(julian_date(&midnight), 0.0);
+        let expected = (13.0 + 10.0 / 60.0 + 46.3668 / 3600.0) * 15.0;
+        println!("GMST: {:.6}° (expected {:.6}°)", lst, expected);
+        assert!((lst - expected).abs() < 1e-4);
+
+        // Longitude shifts LST one-for-one
+        let east = local_sidereal_time(julian_date(&midnight), 30.0);
+        assert!((east - normalize_degrees(expected + 30.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_analemma_figure_eight() {
+        let points = analemma(2026, 51.48, 0.0, 1);
+        assert_eq!(points.len(), 365);
+        let (min_eot, max_eot) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| {
+            (lo.min(p.equation_of_time), hi.max(p.equation_of_time))
+        });
+        println!("EoT range {:.1}..{:.1} min", min_eot, max_eot);
+        // Roughly -14 min in February, +16 min in early November
+        assert!((-15.0..-13.0).contains(&min_eot));
+        assert!((16.0..17.0).contains(&max_eot));
+
+        // Solstice declinations bound the loop
+        let max_decl = points.iter().map(|p| p.declination).fold(f64::MIN, f64::max);
+        assert!((max_decl - 23.44).abs() < 0.05);
+    }
 }
@@ -3,10 +3,16 @@
 //! Handles timezone conversion, current state detection,
 //! wave debug output, and ASCII visualization.
 
-use crate::location::{LocationSource, ResolvedLocation, country_display_name, format_coords};
-use crate::schedule::{self, DayState, Events, EventMethod, GapStrategy, PrayerEvent};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Computation, Config};
+use crate::hijri::HijriDate;
+use crate::location::{normalize_longitude, LocationSource, ResolvedLocation, TzSource, country_display_name, format_coords};
+use crate::method::CalculationMethod;
+use crate::qibla::Qibla;
+use crate::schedule::{self, DayState, Events, EventMethod, GapStrategy, MakruhWindow, PrayerEvent, PrayerKind, SiderealInfo};
 use crate::solar;
-use chrono::{NaiveDate, Timelike, Utc, FixedOffset, Offset};
+use crate::timezone;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
 use chrono_tz::Tz;
 use serde::Serialize;
 
@@ -18,10 +24,40 @@ pub struct Location {
 }
 
 impl Location {
+    /// Panics on a latitude outside -90..=90 or a non-finite longitude;
+    /// the longitude wraps into (-180, 180] (181 → -179).
     pub fn new(lat: f64, lon: f64) -> Self {
         assert!((-90.0..=90.0).contains(&lat), "Latitude must be between -90 and 90");
-        assert!((-180.0..=180.0).contains(&lon), "Longitude must be between -180 and 180");
-        Self { lat, lon }
+        assert!(lon.is_finite(), "Longitude must be a finite number");
+        Self { lat, lon: normalize_longitude(lon) }
+    }
+}
+
+/// Why a [`ResolvedLocation`] cannot be turned into a [`Solver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// Latitude outside -90..=90 or a non-finite longitude.
+    InvalidCoordinates { lat: f64, lon: f64 },
+    /// `tz` is not an IANA zone.
+    Timezone(timezone::TzError),
+}
+
+impl std::fmt::Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverError::InvalidCoordinates { lat, lon } => {
+                write!(f, "Invalid coordinates ({}, {}). Lat: -90..90, Lon: any finite number", lat, lon)
+            }
+            SolverError::Timezone(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<timezone::TzError> for SolverError {
+    fn from(e: timezone::TzError) -> Self {
+        SolverError::Timezone(e)
     }
 }
 
@@ -30,16 +66,203 @@ impl Location {
 pub struct SolverOutput {
     pub location: LocationInfo,
     pub date: String,
+    /// Zone used for event times when it differs from `location.timezone`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_timezone: Option<String>,
     pub state: DayState,
+    /// Sun sets but never reaches the Fajr/Isha depression (see `Schedule`).
+    pub twilight_persists: bool,
     pub gap_strategy: GapStrategy,
+    pub method: CalculationMethod,
+    /// Settings behind these times, for reproducibility.
+    pub computation: Computation,
     pub events: Events,
+    /// Dhuhr+Asr and Maghrib+Isha combined for travellers (opt-in).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combined: Option<schedule::CombinedTimes>,
+    /// Change from the previous day's times (opt-in, multi-day endpoints).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftReport>,
+    /// Tabular Hijri date of `date` (opt-in, see `with_hijri`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hijri: Option<HijriDate>,
+    /// Times when voluntary prayer is discouraged (opt-in).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub makruh_windows: Option<Vec<MakruhWindow>>,
+    /// Civil, nautical and astronomical dawn and dusk (opt-in).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twilight_times: Option<schedule::TwilightTimes>,
+    /// Bearing and distance to the Kaaba (opt-in).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qibla: Option<Qibla>,
     pub solar: schedule::SolarInfo,
+    /// Local time of the solar anti-transit that anchors the night (polar states only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub virtual_midnight_local: Option<String>,
+    /// Numerical caveats about this day's computation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current: Option<CurrentState>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wave_debug: Option<WaveDebug>,
 }
 
+impl SolverOutput {
+    /// Attach the tabular Hijri date of `date`.
+    pub fn with_hijri(mut self) -> Self {
+        self.hijri = NaiveDate::parse_from_str(&self.date, "%Y-%m-%d")
+            .ok()
+            .map(crate::hijri::gregorian_to_hijri);
+        self
+    }
+}
+
+/// Lean per-day row for calendar grids: local times only.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompactDay {
+    pub date: String,
+    /// Hijri date as YYYY-MM-DD.
+    pub hijri: String,
+    pub fajr: Option<String>,
+    pub sunrise: Option<String>,
+    pub dhuhr: Option<String>,
+    pub asr: Option<String>,
+    pub maghrib: Option<String>,
+    pub isha: Option<String>,
+    pub state: DayState,
+}
+
+impl From<&SolverOutput> for CompactDay {
+    fn from(output: &SolverOutput) -> Self {
+        let hijri = NaiveDate::parse_from_str(&output.date, "%Y-%m-%d")
+            .map(|d| crate::hijri::gregorian_to_hijri(d).to_iso())
+            .unwrap_or_default();
+        let e = &output.events;
+        Self {
+            date: output.date.clone(),
+            hijri,
+            fajr: e.fajr.time.clone(),
+            sunrise: e.sunrise.time.clone(),
+            dhuhr: e.dhuhr.time.clone(),
+            asr: e.asr.time.clone(),
+            maghrib: e.maghrib.time.clone(),
+            isha: e.isha.time.clone(),
+            state: output.state,
+        }
+    }
+}
+
+// ─── Day-to-day drift ───────────────────────────────────────────
+
+/// How one event moved from the previous day.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum EventDrift {
+    /// Signed change in minutes; positive means later.
+    Minutes(f64),
+    /// The event exists on one day only (entering or leaving a polar state).
+    Transition(DriftTransition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftTransition {
+    /// Absent the previous day, present today.
+    Appeared,
+    /// Present the previous day, absent today.
+    Disappeared,
+}
+
+/// Per-event change between two consecutive days; `None` when the event
+/// is absent on both.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriftReport {
+    pub fajr: Option<EventDrift>,
+    pub sunrise: Option<EventDrift>,
+    pub dhuhr: Option<EventDrift>,
+    pub asr: Option<EventDrift>,
+    pub maghrib: Option<EventDrift>,
+    pub isha: Option<EventDrift>,
+}
+
+impl DriftReport {
+    pub fn get(&self, kind: PrayerKind) -> Option<EventDrift> {
+        match kind {
+            PrayerKind::Fajr => self.fajr,
+            PrayerKind::Sunrise => self.sunrise,
+            PrayerKind::Dhuhr => self.dhuhr,
+            PrayerKind::Asr => self.asr,
+            PrayerKind::Maghrib => self.maghrib,
+            PrayerKind::Isha => self.isha,
+        }
+    }
+}
+
+/// Signed minute change of each event from `prev` to `cur` — the next day,
+/// or the same day under another method (`/api/compare`).
+///
+/// Times are compared on the local clock including their day offsets; a
+/// change past ±12 h is taken as wrapping midnight.
+pub fn schedule_drift(prev: &SolverOutput, cur: &SolverOutput) -> DriftReport {
+    let drift = |kind: PrayerKind| {
+        match (clock_seconds(prev.events.get(kind)), clock_seconds(cur.events.get(kind))) {
+            (Some(a), Some(b)) => {
+                let secs = (b - a + 43200.0).rem_euclid(86400.0) - 43200.0;
+                Some(EventDrift::Minutes((secs / 60.0 * 100.0).round() / 100.0))
+            }
+            (None, Some(_)) => Some(EventDrift::Transition(DriftTransition::Appeared)),
+            (Some(_), None) => Some(EventDrift::Transition(DriftTransition::Disappeared)),
+            (None, None) => None,
+        }
+    };
+    DriftReport {
+        fajr: drift(PrayerKind::Fajr),
+        sunrise: drift(PrayerKind::Sunrise),
+        dhuhr: drift(PrayerKind::Dhuhr),
+        asr: drift(PrayerKind::Asr),
+        maghrib: drift(PrayerKind::Maghrib),
+        isha: drift(PrayerKind::Isha),
+    }
+}
+
+/// Seconds on the local clock of `event`'s day, counting its day offset.
+fn clock_seconds(event: &PrayerEvent) -> Option<f64> {
+    let time = NaiveTime::parse_from_str(event.time.as_deref()?, "%H:%M:%S").ok()?;
+    Some(time.num_seconds_from_midnight() as f64 + event.day_offset as f64 * 86400.0)
+}
+
+/// A prayer's local time on one date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatedTime {
+    pub date: NaiveDate,
+    pub time: String,
+}
+
+/// The earliest and latest local time of one prayer over a run of days.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrayerExtremes {
+    pub prayer: PrayerKind,
+    pub earliest: DatedTime,
+    pub latest: DatedTime,
+}
+
+/// Earliest and latest `prayer` across `days` (e.g. a year), skipping days
+/// without one. `None` when no day has the prayer.
+pub fn prayer_extremes(days: &[SolverOutput], prayer: PrayerKind) -> Option<PrayerExtremes> {
+    let timed: Vec<(f64, DatedTime)> = days.iter()
+        .filter_map(|day| {
+            let event = day.events.get(prayer);
+            let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").ok()?;
+            Some((clock_seconds(event)?, DatedTime { date, time: event.time.clone()? }))
+        })
+        .collect();
+    // On ties `min_by` keeps the first day and `max_by` the last; reversing reports the first for both
+    let earliest = timed.iter().min_by(|a, b| a.0.total_cmp(&b.0))?;
+    let latest = timed.iter().rev().max_by(|a, b| a.0.total_cmp(&b.0))?;
+    Some(PrayerExtremes { prayer, earliest: earliest.1.clone(), latest: latest.1.clone() })
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct LocationInfo {
     pub name: String,
@@ -48,6 +271,8 @@ pub struct LocationInfo {
     pub timezone: String,
     pub tz_label: String,
     pub source: LocationSource,
+    /// How `timezone` was obtained.
+    pub tz_source: TzSource,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub country_code: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -68,52 +293,223 @@ pub struct CurrentState {
     pub remaining_minutes: i64,
 }
 
+/// The upcoming event, for one-line status output (`polaris next`).
+#[derive(Debug, Clone, Serialize)]
+pub struct NextPrayer {
+    pub prayer: PrayerKind,
+    /// Local time (HH:MM) in the output timezone.
+    pub time: String,
+    pub remaining_minutes: i64,
+}
+
+impl NextPrayer {
+    /// Fill a template: `{prayer}`, `{time}`, `{remaining}` (e.g. "1h 05m")
+    /// and `{minutes}` (e.g. "65").
+    pub fn format(&self, template: &str) -> String {
+        let remaining = match (self.remaining_minutes / 60, self.remaining_minutes % 60) {
+            (0, m) => format!("{}m", m),
+            (h, m) => format!("{}h {:02}m", h, m),
+        };
+        template
+            .replace("{prayer}", self.prayer.name())
+            .replace("{time}", &self.time)
+            .replace("{remaining}", &remaining)
+            .replace("{minutes}", &self.remaining_minutes.to_string())
+    }
+}
+
+/// The prayer window in effect at an instant (`Solver::current_window`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrayerWindow {
+    pub current: PrayerKind,
+    /// Local time (HH:MM:SS) the window ends, i.e. the next event.
+    pub window_end: Option<String>,
+}
+
 /// Wave debug data (--debug-wave mode).
 #[derive(Debug, Clone, Serialize)]
 pub struct WaveDebug {
     pub sample_count: usize,
     pub peak_index: usize,
     pub nadir_index: usize,
-    /// Compressed altitude curve: one sample per 10 minutes.
+    /// Scan samples per entry of `altitudes`.
+    pub sample_step: usize,
+    /// Compressed altitude curve: about one sample per 10 minutes (every
+    /// scan sample once the resolution is 10 minutes or coarser).
     pub altitudes: Vec<f64>,
 }
 
+/// Target spacing of `WaveDebug::altitudes`, in seconds.
+const WAVE_DEBUG_INTERVAL_S: u32 = 600;
+
 /// The Solver.
 pub struct Solver {
     location: Location,
     tz: Tz,
-    strategy: GapStrategy,
+    config: Config,
+    /// Problems found while setting up (e.g. an unparseable timezone).
+    warnings: Vec<String>,
+    /// Source of "now" for the current/next prayer.
+    clock: Box<dyn Clock>,
 }
 
 impl Solver {
     pub fn new(location: Location, tz: Tz) -> Self {
-        Self { location, tz, strategy: GapStrategy::default() }
+        Self { location, tz, config: Config::default(), warnings: Vec::new(), clock: Box::new(SystemClock) }
     }
 
     pub fn with_utc(location: Location) -> Self {
-        Self { location, tz: chrono_tz::UTC, strategy: GapStrategy::default() }
+        Self::new(location, chrono_tz::UTC)
     }
 
     /// Create a solver from a ResolvedLocation.
+    ///
+    /// Renamed zones are mapped to their current name; an unknown zone falls
+    /// back to UTC and is reported in every output's `warnings`. Panics on
+    /// invalid coordinates (see [`Solver::try_from_resolved`]).
     pub fn from_resolved(resolved: &ResolvedLocation) -> Self {
-        let tz: Tz = resolved.tz.parse().unwrap_or(chrono_tz::UTC);
-        Self {
-            location: Location::new(resolved.lat, resolved.lon),
-            tz,
-            strategy: GapStrategy::default(),
+        match Self::try_from_resolved(resolved) {
+            Ok(solver) => solver,
+            Err(SolverError::Timezone(e)) => Self {
+                warnings: vec![format!("{}; using UTC", e)],
+                ..Self::new(Location::new(resolved.lat, resolved.lon), chrono_tz::UTC)
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// [`Solver::from_resolved`] that reports bad coordinates or an unknown
+    /// timezone (e.g. from a corrupt cache entry) instead of panicking.
+    pub fn try_from_resolved(resolved: &ResolvedLocation) -> Result<Self, SolverError> {
+        let (lat, lon) = (resolved.lat, resolved.lon);
+        if !(-90.0..=90.0).contains(&lat) || !lon.is_finite() {
+            return Err(SolverError::InvalidCoordinates { lat, lon });
         }
+        let tz = timezone::parse_tz(&resolved.tz)?;
+        let solver = Self::new(Location::new(lat, lon), tz);
+        Ok(match resolved.tz_source {
+            TzSource::Estimated => Self {
+                warnings: vec![format!("timezone {} was estimated from longitude; pass tz to override", tz)],
+                ..solver
+            },
+            _ => solver,
+        })
+    }
+
+    /// Read the current time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
     }
 
     /// Set the gap strategy for polar event handling.
     pub fn with_strategy(mut self, strategy: GapStrategy) -> Self {
-        self.strategy = strategy;
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// Set the calculation method (twilight angles and rules).
+    pub fn with_method(mut self, method: CalculationMethod) -> Self {
+        self.config.method = method;
+        self
+    }
+
+    /// Set the Asr shadow convention (Shafi'i or Hanafi).
+    pub fn with_asr_madhab(mut self, madhab: schedule::AsrMadhab) -> Self {
+        self.config.asr_madhab = Some(madhab);
+        self
+    }
+
+    /// Observer height in metres: the horizon dip moves sunrise earlier
+    /// and sunset later; twilight angles are unchanged.
+    pub fn with_elevation(mut self, elevation_m: f64) -> Self {
+        self.config.elevation_m = elevation_m;
+        self
+    }
+
+    /// Scale horizon refraction for the air at the observer (standard is
+    /// 10 °C, 1010 mbar).
+    ///
+    /// Values outside -90..=60 °C and 300..=1100 mbar are clamped into range;
+    /// use [`Solver::try_with_atmosphere`] to reject them instead.
+    pub fn with_atmosphere(mut self, temperature_c: f64, pressure_mbar: f64) -> Self {
+        let (t, p) = (crate::config::TEMPERATURE_RANGE_C, crate::config::PRESSURE_RANGE_MBAR);
+        self.config.atmosphere = solar::Atmosphere {
+            temperature_c: temperature_c.clamp(*t.start(), *t.end()),
+            pressure_mbar: pressure_mbar.clamp(*p.start(), *p.end()),
+        };
+        self
+    }
+
+    /// [`Solver::with_atmosphere`], failing outside -90..=60 °C or 300..=1100 mbar.
+    pub fn try_with_atmosphere(mut self, temperature_c: f64, pressure_mbar: f64) -> Result<Self, String> {
+        self.config.atmosphere = crate::config::check_atmosphere(temperature_c, pressure_mbar)?;
+        Ok(self)
+    }
+
+    /// Attach the sun's azimuth to each Standard event.
+    pub fn with_azimuth(mut self, include_azimuth: bool) -> Self {
+        self.config.include_azimuth = include_azimuth;
+        self
+    }
+
+    /// Sample the sun every `seconds` instead of every 30 s: finer for
+    /// shallow high-latitude crossings, coarser for batch jobs.
+    ///
+    /// Values outside `config::SCAN_RESOLUTION_RANGE` (1..=600) are clamped
+    /// into it; use [`Solver::try_with_resolution`] to reject them instead.
+    pub fn with_resolution(mut self, seconds: u32) -> Self {
+        let range = crate::config::SCAN_RESOLUTION_RANGE;
+        self.config.scan_resolution_s = Some(seconds.clamp(*range.start(), *range.end()));
         self
     }
 
+    /// [`Solver::with_resolution`], failing on values outside 1..=600.
+    pub fn try_with_resolution(mut self, seconds: u32) -> Result<Self, String> {
+        self.config.scan_resolution_s = Some(crate::config::check_scan_resolution(seconds)?);
+        Ok(self)
+    }
+
+    /// Show event times in another timezone (e.g. a traveller's home zone)
+    /// while still computing the day for the location's own timezone.
+    pub fn with_display_tz(mut self, display_tz: Tz) -> Self {
+        self.config.display_tz = Some(display_tz);
+        self
+    }
+
+    /// Replace the full computation configuration.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The computation configuration in effect.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     pub fn solve(&self, date: NaiveDate, now_mode: bool, debug_wave: bool) -> SolverOutput {
         self.solve_with_info(date, now_mode, debug_wave, None)
     }
 
+    /// Solve each day from `start` to `end` (inclusive) lazily, so a year can
+    /// be streamed without holding every output.
+    pub fn solve_range(&self, start: NaiveDate, end: NaiveDate) -> impl Iterator<Item = SolverOutput> + '_ {
+        self.solve_range_with_info(start, end, None)
+    }
+
+    /// [`Solver::solve_range`] with location metadata on every day.
+    pub fn solve_range_with_info<'a>(
+        &'a self,
+        start: NaiveDate,
+        end: NaiveDate,
+        resolved: Option<&'a ResolvedLocation>,
+    ) -> impl Iterator<Item = SolverOutput> + 'a {
+        start.iter_days()
+            .take_while(move |date| *date <= end)
+            .map(move |date| self.solve_with_info(date, false, false, resolved))
+    }
+
     /// Solve with full location metadata from a ResolvedLocation.
     pub fn solve_with_info(
         &self,
@@ -122,12 +518,37 @@ impl Solver {
         debug_wave: bool,
         resolved: Option<&ResolvedLocation>,
     ) -> SolverOutput {
-        let schedule = schedule::compute_schedule(date, self.location.lat, self.location.lon, self.strategy);
+        let scan_date = self.scan_date(date);
+        let schedule = schedule::compute_schedule_with(scan_date, self.location.lat, self.location.lon, &self.config);
 
         let tz_name = self.tz.to_string();
-        let utc_offset_secs = self.utc_offset_seconds(date);
+        let utc_offset_secs = tz_offset_seconds(&self.output_tz(), date);
+
+        let events = self.convert_events(&schedule.events, scan_date, date);
+        let offset_warnings: Vec<String> = events.iter()
+            .filter(|(_, event)| event.day_offset.abs() > 1)
+            .map(|(kind, event)| format!(
+                "{} lands {} days from {}; check the timezone and display_tz",
+                kind.name(), event.day_offset, date,
+            ))
+            .collect();
+
+        // Anti-transit lies about 12h after solar noon
+        let virtual_midnight_local = schedule.solar.virtual_midnight_utc.as_ref().map(|t| {
+            let anchor = 86400.0 - self.location.lon * 240.0;
+            self.to_local_near(t, anchor, scan_date, date).0
+        });
+
+        let makruh_windows = self.config.include_makruh.then(|| {
+            schedule.makruh_windows.iter().map(|w| MakruhWindow {
+                start: self.to_local(&w.start, scan_date, date).0,
+                end: self.to_local(&w.end, scan_date, date).0,
+                reason: w.reason,
+            }).collect()
+        });
 
-        let events = self.convert_events(&schedule.events, utc_offset_secs);
+        let twilight_times = schedule.twilight_times.as_ref()
+            .map(|times| times.map(|t| self.to_local(t, scan_date, date).0));
 
         let current = if now_mode {
             self.detect_current(&events, utc_offset_secs)
@@ -136,7 +557,7 @@ impl Solver {
         };
 
         let wave_debug = if debug_wave {
-            Some(self.build_wave_debug(date))
+            Some(self.build_wave_debug(scan_date))
         } else {
             None
         };
@@ -149,6 +570,7 @@ impl Solver {
                 timezone: tz_name.clone(),
                 tz_label: format!("{} (Local Time)", tz_name),
                 source: r.source.clone(),
+                tz_source: r.tz_source,
                 country_code: r.country_code.clone(),
                 country: r.country_code.as_deref().and_then(|cc| {
                     let name = country_display_name(cc);
@@ -166,6 +588,7 @@ impl Solver {
                 timezone: tz_name.clone(),
                 tz_label: format!("{} (Local Time)", tz_name),
                 source: LocationSource::Manual,
+                tz_source: TzSource::Override,
                 country_code: None,
                 country: None,
                 formatted_coords: format_coords(self.location.lat, self.location.lon),
@@ -178,59 +601,190 @@ impl Solver {
         SolverOutput {
             location: location_info,
             date: date.to_string(),
+            display_timezone: self.config.display_tz.map(|tz| tz.to_string()),
             state: schedule.state,
-            gap_strategy: self.strategy,
+            twilight_persists: schedule.twilight_persists,
+            gap_strategy: self.config.strategy,
+            method: self.config.method,
+            computation: self.config.computation(self.location.lat),
             events,
+            combined: None,
+            drift: None,
+            hijri: None,
+            makruh_windows,
+            twilight_times,
+            qibla: self.config.include_qibla.then(|| Qibla::from_location(self.location.lat, self.location.lon)),
             solar: schedule.solar,
+            virtual_midnight_local,
+            warnings: self.warnings.iter().cloned().chain(schedule.warnings).chain(offset_warnings).collect(),
             current,
             wave_debug,
         }
     }
 
-    /// Get UTC offset in seconds for a given date at this timezone.
-    fn utc_offset_seconds(&self, date: NaiveDate) -> i64 {
-        use chrono::TimeZone;
-        let noon = date.and_hms_opt(12, 0, 0).unwrap();
-        match self.tz.from_local_datetime(&noon).earliest() {
-            Some(dt) => {
-                let fixed: FixedOffset = dt.offset().fix();
-                fixed.local_minus_utc() as i64
+    /// Local time at which the sun crosses `altitude` degrees on `limb`.
+    pub fn crossing(&self, date: NaiveDate, altitude: f64, limb: schedule::Limb) -> Option<String> {
+        let scan_date = self.scan_date(date);
+        schedule::altitude_crossing(scan_date, self.location.lat, self.location.lon, altitude, limb)
+            .map(|t| self.to_local(&t, scan_date, date).0)
+    }
+
+    /// Local Fajr time for each candidate depression angle on `date`.
+    ///
+    /// `None` where the sun never gets that far below the horizon.
+    pub fn fajr_sweep(&self, date: NaiveDate, angles: &[f64]) -> Vec<(f64, Option<String>)> {
+        let scan_date = self.scan_date(date);
+        schedule::fajr_sweep(scan_date, self.location.lat, self.location.lon, angles)
+            .into_iter()
+            .map(|(angle, time)| (angle, time.map(|t| self.to_local(&t, scan_date, date).0)))
+            .collect()
+    }
+
+    /// The first event after `now`, looking into tomorrow once Isha has passed.
+    pub fn next_prayer(&self, now: DateTime<Utc>) -> Option<NextPrayer> {
+        let tz = self.output_tz();
+        let today = now.with_timezone(&tz).date_naive();
+
+        for date in [today, today + Duration::days(1)] {
+            let next = self.event_instants(date).into_iter()
+                .filter(|(_, instant)| *instant > now)
+                .min_by_key(|(_, instant)| *instant);
+
+            if let Some((prayer, instant)) = next {
+                let seconds = (instant - now).num_seconds();
+                return Some(NextPrayer {
+                    prayer,
+                    time: instant.with_timezone(&tz).format("%H:%M").to_string(),
+                    remaining_minutes: (seconds + 59) / 60,
+                });
             }
-            None => 0,
         }
+        None
     }
 
-    /// Convert events from UTC to local time.
-    fn convert_events(&self, events: &Events, offset_secs: i64) -> Events {
-        Events {
-            fajr: self.convert_event(&events.fajr, offset_secs),
-            sunrise: self.convert_event(&events.sunrise, offset_secs),
-            dhuhr: self.convert_event(&events.dhuhr, offset_secs),
-            asr: self.convert_event(&events.asr, offset_secs),
-            maghrib: self.convert_event(&events.maghrib, offset_secs),
-            isha: self.convert_event(&events.isha, offset_secs),
+    /// The next `count` events after `from`, in order, rolling into later
+    /// days as needed. Events without a time are skipped; Projected and
+    /// Virtual ones (polar day and night) are included.
+    pub fn upcoming(&self, from: DateTime<Utc>, count: usize) -> Vec<(PrayerKind, DateTime<Utc>)> {
+        let today = from.with_timezone(&self.output_tz()).date_naive();
+        let mut upcoming = Vec::new();
+        // Yesterday may have an event pushed past midnight (day offset +1)
+        let mut date = today - Duration::days(1);
+        // Dhuhr is timed every day, so this terminates; the extra day catches
+        // events that a day offset moves ahead of the last day's first ones.
+        let mut spare_days = 1;
+        loop {
+            upcoming.extend(self.event_instants(date).into_iter().filter(|(_, instant)| *instant > from));
+            date += Duration::days(1);
+            if upcoming.len() >= count {
+                if spare_days == 0 {
+                    break;
+                }
+                spare_days -= 1;
+            }
         }
+        upcoming.sort_by_key(|(_, instant)| *instant);
+        upcoming.truncate(count);
+        upcoming
     }
 
-    fn convert_event(&self, event: &PrayerEvent, offset_secs: i64) -> PrayerEvent {
-        let mut next_day = false;
+    /// The prayer window containing `at` and the local time it ends.
+    ///
+    /// Before Fajr the window is the previous day's Isha; after Isha it
+    /// runs until tomorrow's Fajr.
+    pub fn current_window(&self, at: DateTime<Tz>) -> PrayerWindow {
+        let tz = self.output_tz();
+        let at = at.with_timezone(&Utc);
+        let today = at.with_timezone(&tz).date_naive();
+
+        let mut instants: Vec<(PrayerKind, DateTime<Utc>)> = [-1, 0, 1].iter()
+            .flat_map(|d| self.event_instants(today + Duration::days(*d)))
+            .collect();
+        instants.sort_by_key(|(_, instant)| *instant);
+
+        // Dhuhr exists every day, so something always precedes `at`
+        let current = instants.iter()
+            .rfind(|(_, instant)| *instant <= at)
+            .map(|(kind, _)| *kind)
+            .unwrap_or(PrayerKind::Isha);
+        let window_end = instants.iter()
+            .find(|(_, instant)| *instant > at)
+            .map(|(_, instant)| instant.with_timezone(&tz).format("%H:%M:%S").to_string());
+
+        PrayerWindow { current, window_end }
+    }
+
+    /// UTC instants of the events that occur on local `date`.
+    fn event_instants(&self, date: NaiveDate) -> Vec<(PrayerKind, DateTime<Utc>)> {
+        let tz = self.output_tz();
+        let output = self.solve(date, false, false);
+        output.events.iter()
+            .filter_map(|(kind, event)| {
+                let time = NaiveTime::parse_from_str(event.time.as_ref()?, "%H:%M:%S").ok()?;
+                let local_date = date + Duration::days(event.day_offset as i64);
+                let instant = tz.from_local_datetime(&local_date.and_time(time)).earliest()?;
+                Some((kind, instant.with_timezone(&Utc)))
+            })
+            .collect()
+    }
+
+    /// Get UTC offset in seconds for a given date at this timezone.
+    fn utc_offset_seconds(&self, date: NaiveDate) -> i64 {
+        tz_offset_seconds(&self.tz, date)
+    }
+
+    /// Zone in which event times are presented.
+    fn output_tz(&self) -> Tz {
+        self.config.display_tz.unwrap_or(self.tz)
+    }
+
+    /// UTC calendar date whose day scan contains the solar day of the local `date`.
+    ///
+    /// The day scan covers one UTC day. Near the date line (e.g. UTC+14 at
+    /// Kiritimati) the local solar noon of `date` falls on the previous or
+    /// next UTC day, so the scan must be shifted to keep events on the
+    /// requested local day.
+    fn scan_date(&self, date: NaiveDate) -> NaiveDate {
+        let offset = self.utc_offset_seconds(date);
+        let approx_noon_utc = 43200 - (self.location.lon * 240.0).round() as i64;
+        let shift = (approx_noon_utc + offset).div_euclid(86400);
+        date - Duration::days(shift)
+    }
+
+    /// Convert events from UTC (relative to `scan_date`) to local time on `date`.
+    fn convert_events(&self, events: &Events, scan_date: NaiveDate, date: NaiveDate) -> Events {
+        events.map(|kind, event| self.convert_event(kind, event, scan_date, date))
+    }
+
+    fn convert_event(&self, kind: PrayerKind, event: &PrayerEvent, scan_date: NaiveDate, date: NaiveDate) -> PrayerEvent {
+        let standard_instant = match (&event.time, event.method) {
+            (Some(t), EventMethod::Standard) => Some(self.utc_instant(t, self.noon_anchor(), scan_date).naive_utc()),
+            _ => None,
+        };
+        let sidereal = standard_instant
+            .filter(|_| self.config.include_sidereal)
+            .map(|dt| SiderealInfo::at(&dt, self.location.lat, self.location.lon));
+        let azimuth = standard_instant
+            .filter(|_| self.config.include_azimuth)
+            .map(|dt| solar::solar_position(&dt, self.location.lat, self.location.lon).azimuth);
+
+        let mut day_offset = 0i8;
         let time = event.time.as_ref().map(|t| {
-            let utc_secs = hms_to_secs(t);
-            let local_secs = utc_secs + offset_secs as f64;
-            if local_secs >= 86400.0 {
-                next_day = true;
+            let mut instant = self.utc_instant(t, self.noon_anchor(), scan_date);
+            if let Some(rounding) = self.config.rounding {
+                // Rounding first lets a roll past midnight move the day offset
+                instant = rounding.round(kind, instant);
             }
-            solar::seconds_to_hms(local_secs)
+            let (local, offset) = self.local_on(instant, date);
+            day_offset = offset;
+            local
         });
 
-        // Append "(next day)" to note when wrapping occurs
-        let note = if next_day {
-            match &event.note {
-                Some(n) => Some(format!("{} (next day)", n)),
-                None => Some("next day".to_string()),
-            }
-        } else {
-            event.note.clone()
+        // Mark events whose local time lands on a neighbouring calendar day
+        let note = match (day_offset, &event.note) {
+            (0, _) => event.note.clone(),
+            (d, Some(n)) => Some(format!("{} ({})", n, day_offset_label(d))),
+            (d, None) => Some(day_offset_label(d).to_string()),
         };
 
         PrayerEvent {
@@ -238,33 +792,62 @@ impl Solver {
             method: event.method,
             confidence: event.confidence,
             note,
-            next_day,
+            day_offset,
+            sidereal,
+            azimuth,
+            wave_fit: event.wave_fit,
         }
     }
 
+    /// Convert a UTC HH:MM:SS from the `scan_date` day scan to a local
+    /// HH:MM:SS and its calendar-day offset from `date`.
+    fn to_local(&self, utc_hms: &str, scan_date: NaiveDate, date: NaiveDate) -> (String, i8) {
+        self.to_local_near(utc_hms, self.noon_anchor(), scan_date, date)
+    }
+
+    /// Approximate solar noon in seconds from UTC midnight. Events lie within
+    /// ±12h of the solar noon they belong to.
+    fn noon_anchor(&self) -> f64 {
+        43200.0 - self.location.lon * 240.0
+    }
+
+    /// The UTC instant of an HH:MM:SS lying within ±12h of `anchor`.
+    fn utc_instant(&self, utc_hms: &str, anchor: f64, scan_date: NaiveDate) -> DateTime<Utc> {
+        let rel = (hms_to_secs(utc_hms) - anchor + 43200.0).rem_euclid(86400.0) - 43200.0;
+        scan_date.and_time(NaiveTime::MIN).and_utc() + Duration::seconds((anchor + rel).round() as i64)
+    }
+
+    /// Like `to_local`, for a time within ±12h of `anchor` (seconds from UTC
+    /// midnight of `scan_date`) rather than solar noon.
+    fn to_local_near(&self, utc_hms: &str, anchor: f64, scan_date: NaiveDate, date: NaiveDate) -> (String, i8) {
+        self.local_on(self.utc_instant(utc_hms, anchor, scan_date), date)
+    }
+
+    /// Local HH:MM:SS of `instant` and its calendar-day offset from `date`.
+    ///
+    /// The offset is normally -1, 0 or +1; anything wider is passed through
+    /// unchanged and reported by `solve_with_info` as a warning.
+    fn local_on(&self, instant: DateTime<Utc>, date: NaiveDate) -> (String, i8) {
+        let local = instant.with_timezone(&self.output_tz());
+        let days = (local.date_naive() - date).num_days();
+        let day_offset = i8::try_from(days).unwrap_or(if days < 0 { i8::MIN } else { i8::MAX });
+        (local.format("%H:%M:%S").to_string(), day_offset)
+    }
+
     /// Detect current and next prayer based on current UTC time.
     fn detect_current(&self, local_events: &Events, offset_secs: i64) -> Option<CurrentState> {
-        let now_utc = Utc::now().naive_utc();
+        let now_utc = self.clock.now_utc().naive_utc();
         let now_local_secs = (now_utc.hour() as f64 * 3600.0
             + now_utc.minute() as f64 * 60.0
             + now_utc.second() as f64)
             + offset_secs as f64;
         let now_local_secs = ((now_local_secs % 86400.0) + 86400.0) % 86400.0;
 
-        let prayer_list = [
-            ("Fajr", &local_events.fajr),
-            ("Sunrise", &local_events.sunrise),
-            ("Dhuhr", &local_events.dhuhr),
-            ("Asr", &local_events.asr),
-            ("Maghrib", &local_events.maghrib),
-            ("Isha", &local_events.isha),
-        ];
-
         // Collect events that have a time
-        let timed: Vec<(&str, f64)> = prayer_list
+        let timed: Vec<(&str, f64)> = local_events
             .iter()
-            .filter_map(|(name, ev)| {
-                ev.time.as_ref().map(|t| (*name, hms_to_secs(t)))
+            .filter_map(|(kind, ev)| {
+                ev.time.as_ref().map(|t| (kind.name(), hms_to_secs(t)))
             })
             .collect();
 
@@ -305,7 +888,8 @@ impl Solver {
     }
 
     fn build_wave_debug(&self, date: NaiveDate) -> WaveDebug {
-        let samples = schedule::day_scan_samples(date, self.location.lat, self.location.lon);
+        let resolution = self.config.scan_resolution();
+        let samples = schedule::day_scan_samples_with(date, self.location.lat, self.location.lon, resolution);
         let peak_idx = samples.iter()
             .enumerate()
             .max_by(|(_, a), (_, b)| a.altitude.partial_cmp(&b.altitude).unwrap())
@@ -317,9 +901,10 @@ impl Solver {
             .map(|(i, _)| i)
             .unwrap_or(0);
 
-        // Compress: pick every 20th sample (30-sec resolution → ~10 min intervals)
+        // Compress to ~10 min intervals (every 20th sample at the default 30 s)
+        let step = (WAVE_DEBUG_INTERVAL_S / resolution).max(1) as usize;
         let altitudes: Vec<f64> = samples.iter()
-            .step_by(20)
+            .step_by(step)
             .map(|s| (s.altitude * 100.0).round() / 100.0)
             .collect();
 
@@ -327,11 +912,21 @@ impl Solver {
             sample_count: samples.len(),
             peak_index: peak_idx,
             nadir_index: nadir_idx,
+            sample_step: step,
             altitudes,
         }
     }
 }
 
+/// UTC offset in seconds of `tz` at local noon on `date`.
+fn tz_offset_seconds(tz: &Tz, date: NaiveDate) -> i64 {
+    timezone::noon_offset(tz, date).local_minus_utc() as i64
+}
+
+fn day_offset_label(day_offset: i8) -> &'static str {
+    if day_offset > 0 { "next day" } else { "previous day" }
+}
+
 fn hms_to_secs(hms: &str) -> f64 {
     let parts: Vec<&str> = hms.split(':').collect();
     if parts.len() != 3 { return 0.0; }
@@ -355,20 +950,16 @@ pub fn render_ascii_timeline(events: &Events, state: DayState, strategy: GapStra
     out.push_str("  ╔══════════════════════════════════════════════════════════════╗\n");
 
     // Build the event list with times
-    let items: Vec<(&str, &PrayerEvent, &str)> = vec![
-        ("Fajr    ", &events.fajr, "░"),
-        ("Sunrise ", &events.sunrise, "▓"),
-        ("Dhuhr   ", &events.dhuhr, "█"),
-        ("Asr     ", &events.asr, "▓"),
-        ("Maghrib ", &events.maghrib, "░"),
-        ("Isha    ", &events.isha, " "),
-    ];
+    let items: Vec<(String, &PrayerEvent)> = events
+        .iter()
+        .map(|(kind, event)| (format!("{:<8}", kind.name()), event))
+        .collect();
 
     // Timeline bar (60 chars = 24 hours)
     let bar_width = 60;
     let mut markers: Vec<(usize, &str)> = Vec::new();
 
-    for (label, event, _sym) in &items {
+    for (label, event) in &items {
         if let Some(ref t) = event.time {
             let secs = hms_to_secs(t);
             let pos = ((secs / 86400.0) * bar_width as f64) as usize;
@@ -401,15 +992,12 @@ pub fn render_ascii_timeline(events: &Events, state: DayState, strategy: GapStra
     out.push_str("  ╠══════════════════════════════════════════════════════════════╣\n");
 
     // Event list
-    for (label, event, _) in &items {
+    for (label, event) in &items {
         let time_str = match &event.time {
-            Some(t) => {
-                if event.next_day {
-                    format!("{} (+1d)", t)
-                } else {
-                    t.clone()
-                }
-            }
+            Some(t) => match event.day_offset {
+                0 => t.clone(),
+                d => format!("{} ({:+}d)", t, d),
+            },
             None => "────────".to_string(),
         };
         let method_tag = match event.method {
@@ -442,10 +1030,55 @@ pub fn render_ascii_timeline(events: &Events, state: DayState, strategy: GapStra
     out
 }
 
+/// Plot the `--debug-wave` altitude curve, `height` rows tall, with the
+/// horizon as a reference line and the peak (▲) and nadir (▼) marked.
+///
+/// The x axis is the 24-hour scan window, centred on solar noon.
+pub fn render_wave_plot(wave: &WaveDebug, height: usize) -> String {
+    const WIDTH: usize = 72;
+    let height = height.max(3);
+    let alts = &wave.altitudes;
+    if alts.is_empty() {
+        return String::new();
+    }
+
+    let hi = alts.iter().cloned().fold(0.0_f64, f64::max);
+    let lo = alts.iter().cloned().fold(0.0_f64, f64::min);
+    let span = (hi - lo).max(1e-6);
+    let row_of = |alt: f64| (((hi - alt) / span) * (height - 1) as f64).round() as usize;
+    let col_of = |i: usize| (i * WIDTH / alts.len()).min(WIDTH - 1);
+
+    let horizon = row_of(0.0);
+    let mut grid = vec![vec![' '; WIDTH]; height];
+    grid[horizon] = vec!['─'; WIDTH];
+    for (i, &alt) in alts.iter().enumerate() {
+        grid[row_of(alt)][col_of(i)] = '•';
+    }
+    for (index, marker) in [(wave.peak_index, '▲'), (wave.nadir_index, '▼')] {
+        let i = (index / wave.sample_step.max(1)).min(alts.len() - 1);
+        grid[row_of(alts[i])][col_of(i)] = marker;
+    }
+
+    let mut out = String::from("  Altitude wave (▲ peak, ▼ nadir)\n");
+    for (r, row) in grid.iter().enumerate() {
+        let label = match r {
+            0 => format!("{:>7.1}°", hi),
+            r if r == horizon => "    0.0°".to_string(),
+            r if r == height - 1 => format!("{:>7.1}°", lo),
+            _ => String::new(),
+        };
+        let axis = if r == horizon { '┼' } else { '┤' };
+        let tail = if r == horizon { " horizon" } else { "" };
+        out.push_str(&format!("  {:>8} {}{}{}\n", label, axis, row.iter().collect::<String>(), tail));
+    }
+    out.push_str(&format!("  {:>8}  {:<24}{:^24}{:>24}\n", "", "-12h", "solar noon", "+12h"));
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schedule::DayState;
+    use crate::schedule::{DayState, HighLatitudeRule, MakruhReason};
     use chrono::NaiveDate;
 
     fn utc_solver(lat: f64, lon: f64) -> Solver {
@@ -475,6 +1108,10 @@ mod tests {
         assert!(output.events.maghrib.time.is_none(), "PolarNight: maghrib must be None");
         assert_eq!(output.events.sunrise.method, EventMethod::None);
         assert_eq!(output.events.maghrib.method, EventMethod::None);
+
+        // UTC display: the anti-transit after Dhuhr lands late in the evening
+        let midnight = output.virtual_midnight_local.as_deref().expect("polar output has virtual midnight");
+        assert!(midnight.starts_with("22:"), "virtual midnight {}", midnight);
     }
 
     #[test]
@@ -511,6 +1148,20 @@ mod tests {
         assert!(!wave.altitudes.is_empty());
         // All altitudes should be negative for polar night
         assert!(wave.altitudes.iter().all(|a| *a < 0.0));
+        assert_eq!(wave.sample_step, 20);
+    }
+
+    #[test]
+    fn test_wave_debug_stride_follows_resolution() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        for (resolution, step) in [(60, 10), (600, 1)] {
+            let output = utc_solver(30.0444, 31.2357).with_resolution(resolution).solve(date, false, true);
+            let wave = output.wave_debug.unwrap();
+            println!("{}s: {} samples, {} altitudes", resolution, wave.sample_count, wave.altitudes.len());
+            assert_eq!(wave.sample_step, step);
+            // Still about one entry per 10 minutes of the 24-hour scan
+            assert!((144..=146).contains(&wave.altitudes.len()), "got {}", wave.altitudes.len());
+        }
     }
 
     #[test]
@@ -534,12 +1185,149 @@ mod tests {
         assert!(ascii.contains("[V]"));
     }
 
+    #[test]
+    fn test_mecca_fajr_extremes_2026() {
+        let solver = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh);
+        let first = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let days: Vec<SolverOutput> = first.iter_days().take(365).map(|d| solver.solve(d, false, false)).collect();
+
+        let fajr = prayer_extremes(&days, PrayerKind::Fajr).unwrap();
+        println!("{:?}", fajr);
+        // Earliest around the June solstice, latest in the January mornings
+        let june = NaiveDate::from_ymd_opt(2026, 5, 25).unwrap()..=NaiveDate::from_ymd_opt(2026, 6, 30).unwrap();
+        let winter = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()..=NaiveDate::from_ymd_opt(2026, 2, 15).unwrap();
+        assert!(june.contains(&fajr.earliest.date), "earliest Fajr on {}", fajr.earliest.date);
+        assert!(winter.contains(&fajr.latest.date), "latest Fajr on {}", fajr.latest.date);
+
+        let spread = (hms_to_secs(&fajr.latest.time) - hms_to_secs(&fajr.earliest.time)) / 60.0;
+        assert!((60.0..=110.0).contains(&spread), "Fajr spread {:.0} min", spread);
+
+        // Days without the prayer are skipped; none at all gives None
+        let tromso = Solver::with_utc(Location::new(69.6492, 18.9553)).with_strategy(GapStrategy::Strict);
+        let winter_days: Vec<SolverOutput> = first.iter_days().take(5).map(|d| tromso.solve(d, false, false)).collect();
+        assert!(prayer_extremes(&winter_days, PrayerKind::Sunrise).is_none());
+    }
+
+    #[test]
+    fn test_drift_tromso_february_and_polar_transition() {
+        let solver = Solver::new(Location::new(69.6492, 18.9553), chrono_tz::Europe::Oslo)
+            .with_strategy(GapStrategy::Strict);
+        let week: Vec<SolverOutput> = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap().iter_days().take(7)
+            .map(|d| solver.solve(d, false, false))
+            .collect();
+        for pair in week.windows(2) {
+            let drift = schedule_drift(&pair[0], &pair[1]);
+            println!("{} fajr {:?} sunrise {:?}", pair[1].date, drift.fajr, drift.sunrise);
+            match drift.fajr {
+                Some(EventDrift::Minutes(m)) => assert!(m < 0.0 && m > -10.0, "Fajr drift {} min", m),
+                other => panic!("expected a numeric Fajr drift, got {:?}", other),
+            }
+        }
+
+        // Polar night ends in mid-January: sunrise appears
+        let days: Vec<SolverOutput> = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap().iter_days().take(10)
+            .map(|d| solver.solve(d, false, false))
+            .collect();
+        let transitions: Vec<(String, Option<EventDrift>)> = days.windows(2)
+            .map(|pair| (pair[1].date.clone(), schedule_drift(&pair[0], &pair[1]).sunrise))
+            .filter(|(_, d)| matches!(d, Some(EventDrift::Transition(_))))
+            .collect();
+        println!("{:?}", transitions);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].1, Some(EventDrift::Transition(DriftTransition::Appeared)));
+    }
+
+    #[test]
+    fn test_current_prayer_from_fixed_clock() {
+        use crate::clock::FixedClock;
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        // Mecca in UTC: Dhuhr ≈ 09:20, Asr ≈ 12:40
+        let now = date.and_hms_opt(11, 0, 0).unwrap().and_utc();
+        let output = utc_solver(21.4225, 39.8262).with_clock(FixedClock(now)).solve(date, true, false);
+        let current = output.current.expect("now mode reports the current prayer");
+        println!("{:?}", current);
+        assert_eq!(current.prayer.to_lowercase(), "dhuhr");
+        assert_eq!(current.next.to_lowercase(), "asr");
+        let asr = hms_to_secs(output.events.asr.time.as_deref().unwrap());
+        assert_eq!(current.remaining_minutes, ((asr - 11.0 * 3600.0) / 60.0).ceil() as i64);
+    }
+
+    #[test]
+    fn test_rounding_policy_on_stockholm() {
+        use crate::schedule::RoundingPolicy;
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let solve = |rounding| Solver::new(Location::new(59.3293, 18.0686), chrono_tz::Europe::Stockholm)
+            .with_config(Config::default().with_rounding(rounding))
+            .solve(date, false, false);
+        let raw = solve(None);
+        let secs = |out: &SolverOutput, kind| hms_to_secs(out.events.get(kind).time.as_deref().unwrap());
+
+        for policy in RoundingPolicy::ALL {
+            let out = solve(Some(policy));
+            for kind in PrayerKind::ALL {
+                let (before, after) = (secs(&raw, kind), secs(&out, kind));
+                assert_eq!(after % 60.0, 0.0, "{} {:?} not on a minute", policy, kind);
+                assert!((after - before).abs() < 60.0);
+            }
+        }
+        let fajr_down = solve(Some(RoundingPolicy::FajrDown));
+        assert_eq!(secs(&fajr_down, PrayerKind::Fajr), (secs(&raw, PrayerKind::Fajr) / 60.0).floor() * 60.0);
+        let isha_up = solve(Some(RoundingPolicy::IshaUp));
+        assert_eq!(secs(&isha_up, PrayerKind::Isha), (secs(&raw, PrayerKind::Isha) / 60.0).ceil() * 60.0);
+        assert_eq!(isha_up.computation.rounding, Some(RoundingPolicy::IshaUp));
+    }
+
+    #[test]
+    fn test_wave_plot_has_horizon() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let output = Solver::with_utc(Location::new(30.0444, 31.2357)).solve(date, false, true);
+        let plot = render_wave_plot(output.wave_debug.as_ref().unwrap(), 12);
+        println!("{}", plot);
+        assert!(plot.lines().count() > 1);
+        assert!(plot.contains("horizon"));
+        assert!(plot.contains('▲') && plot.contains('▼'));
+    }
+
     #[test]
     #[should_panic(expected = "Latitude must be between")]
     fn test_invalid_latitude() {
         Location::new(91.0, 0.0);
     }
 
+    #[test]
+    fn test_try_from_resolved_rejects_bad_input() {
+        let mut resolved = crate::location::LocationResolver::from_manual(21.4225, 39.8262, Some("Asia/Riyadh"));
+        assert!(Solver::try_from_resolved(&resolved).is_ok());
+
+        resolved.lat = 200.0;
+        let err = Solver::try_from_resolved(&resolved).err().unwrap();
+        println!("{}", err);
+        assert_eq!(err, SolverError::InvalidCoordinates { lat: 200.0, lon: 39.8262 });
+
+        resolved.lat = 21.4225;
+        resolved.tz = "Mars/Olympus_Mons".into();
+        assert!(matches!(Solver::try_from_resolved(&resolved), Err(SolverError::Timezone(_))));
+        // The infallible constructor keeps its UTC fallback
+        assert_eq!(Solver::from_resolved(&resolved).tz, chrono_tz::UTC);
+    }
+
+    #[test]
+    fn test_longitude_wraps_at_antimeridian() {
+        assert_eq!(Location::new(0.0, 181.0).lon, -179.0);
+        assert_eq!(Location::new(0.0, -180.0).lon, 180.0);
+        assert_eq!(Location::new(0.0, 540.0).lon, 180.0);
+        assert_eq!(crate::location::LocationResolver::from_manual(-17.7, 181.0, None).lon, -179.0);
+
+        // Fiji-area coordinates given past the antimeridian
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let over = Solver::with_utc(Location::new(-17.7, 181.0)).solve(date, false, false);
+        let wrapped = Solver::with_utc(Location::new(-17.7, -179.0)).solve(date, false, false);
+        assert_eq!(over.events.fajr.time, wrapped.events.fajr.time);
+        assert_eq!(over.events.isha.time, wrapped.events.isha.time);
+    }
+
     #[test]
     fn test_three_cities_integration() {
         let cases = vec![
@@ -630,7 +1418,7 @@ mod tests {
 
         // Isha in Mecca UTC ~16:28 + 13h offset = ~05:28 next day
         let isha = &output.events.isha;
-        assert!(isha.next_day, "Isha in Auckland TZ should wrap to next day");
+        assert_eq!(isha.day_offset, 1, "Isha in Auckland TZ should wrap to next day");
         assert!(isha.note.is_some(), "Wrapped event must have note");
         assert!(isha.note.as_ref().unwrap().contains("next day"),
             "Note must contain 'next day', got: {:?}", isha.note);
@@ -671,4 +1459,430 @@ mod tests {
         // Long tags should NOT appear
         assert!(!ascii.contains("[Virtual]"), "[Virtual] long tag should not appear");
     }
+
+    #[test]
+    fn test_kiritimati_date_line_same_local_day() {
+        // UTC+14: Fajr on the local date happens on the previous UTC calendar day
+        let tz: Tz = "Pacific/Kiritimati".parse().unwrap();
+        let solver = Solver::new(Location::new(1.8721, -157.3626), tz);
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        assert_eq!(solver.scan_date(date), NaiveDate::from_ymd_opt(2026, 2, 13).unwrap());
+
+        let output = solver.solve(date, false, false);
+        let e = &output.events;
+        for ev in [&e.fajr, &e.sunrise, &e.dhuhr, &e.asr, &e.maghrib, &e.isha] {
+            assert_eq!(ev.day_offset, 0, "All Kiritimati events must stay on the local day: {:?}", ev);
+        }
+        let fajr = e.fajr.time.as_ref().unwrap();
+        assert!(fajr.starts_with("05:"), "Kiritimati Fajr should be early morning local, got {}", fajr);
+        let dhuhr = e.dhuhr.time.as_ref().unwrap();
+        assert!(dhuhr.starts_with("12:"), "Kiritimati Dhuhr should be around local noon, got {}", dhuhr);
+        assert!(e.fajr.time < e.sunrise.time && e.maghrib.time < e.isha.time);
+    }
+
+    #[test]
+    fn test_day_offset_beyond_one_day_is_not_clamped() {
+        let solver = Solver::new(Location::new(0.0, 0.0), chrono_tz::UTC);
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let instant = NaiveDate::from_ymd_opt(2026, 2, 16).unwrap().and_hms_opt(1, 0, 0).unwrap().and_utc();
+        assert_eq!(solver.local_on(instant, date), ("01:00:00".to_string(), 2));
+    }
+
+    #[test]
+    fn test_date_wrapping_previous_day() {
+        // Mecca Fajr ~02:35 UTC is 18:35 the previous evening in UTC-8
+        let tz: Tz = "America/Los_Angeles".parse().unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), tz);
+        let output = solver.solve(NaiveDate::from_ymd_opt(2026, 2, 14).unwrap(), false, false);
+
+        let fajr = &output.events.fajr;
+        assert_eq!(fajr.day_offset, -1, "Fajr in Los Angeles TZ should fall on the previous day");
+        assert!(fajr.note.as_deref().unwrap_or("").contains("previous day"));
+        assert_eq!(output.events.isha.day_offset, 0);
+
+        let ascii = render_ascii_timeline(&output.events, output.state, output.gap_strategy, false);
+        assert!(ascii.contains("(-1d)"), "CLI must show (-1d) for previous-day events");
+    }
+
+    #[test]
+    fn test_display_tz_shifts_times_not_computation() {
+        let riyadh: Tz = "Asia/Riyadh".parse().unwrap();
+        let london: Tz = "Europe/London".parse().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+
+        let local = Solver::new(Location::new(21.4225, 39.8262), riyadh).solve(date, false, false);
+        let shown = Solver::new(Location::new(21.4225, 39.8262), riyadh)
+            .with_display_tz(london)
+            .solve(date, false, false);
+
+        assert_eq!(shown.location.timezone, "Asia/Riyadh");
+        assert_eq!(shown.display_timezone.as_deref(), Some("Europe/London"));
+        assert!(local.display_timezone.is_none());
+
+        // Riyadh is UTC+3, London is UTC+0 in February
+        let riyadh_dhuhr = hms_to_secs(local.events.dhuhr.time.as_ref().unwrap());
+        let london_dhuhr = hms_to_secs(shown.events.dhuhr.time.as_ref().unwrap());
+        assert_eq!(riyadh_dhuhr - london_dhuhr, 3.0 * 3600.0);
+        assert_eq!(shown.events.dhuhr.day_offset, 0);
+    }
+
+    #[test]
+    fn test_renamed_timezone_not_utc() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+        let kiev = crate::location::LocationResolver::from_manual(50.45, 30.52, Some("Europe/Kiev"));
+        let out = Solver::from_resolved(&kiev).solve(date, false, false);
+        assert_eq!(out.location.timezone, "Europe/Kyiv");
+        assert!(!out.warnings.iter().any(|w| w.contains("timezone")), "{:?}", out.warnings);
+
+        let bogus = crate::location::LocationResolver::from_manual(50.45, 30.52, Some("Europe/Kyyv"));
+        let out = Solver::from_resolved(&bogus).solve(date, false, false);
+        assert_eq!(out.location.timezone, "UTC");
+        assert!(out.warnings[0].contains("unknown timezone 'Europe/Kyyv'"));
+
+        // Not a near miss of any zone: still reported, never silently UTC
+        let mars = crate::location::LocationResolver::from_manual(50.45, 30.52, Some("Mars/Olympus"));
+        let out = Solver::from_resolved(&mars).solve(date, false, false);
+        assert_eq!(out.warnings[0], "unknown timezone 'Mars/Olympus'; using UTC");
+        assert!(matches!(Solver::try_from_resolved(&mars), Err(SolverError::Timezone(_))));
+    }
+
+    #[test]
+    fn test_makruh_windows_mecca() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh)
+            .with_config(Config::default().with_makruh(true));
+        let out = solver.solve(date, false, false);
+        let windows = out.makruh_windows.expect("makruh windows requested");
+        println!("{:?}", windows);
+
+        let reasons: Vec<_> = windows.iter().map(|w| w.reason).collect();
+        assert_eq!(reasons, [MakruhReason::Sunrise, MakruhReason::Zenith, MakruhReason::Sunset]);
+        assert_eq!(Some(&windows[0].start), out.events.sunrise.time.as_ref());
+        assert_eq!(Some(&windows[1].end), out.events.dhuhr.time.as_ref());
+        assert_eq!(Some(&windows[2].end), out.events.maghrib.time.as_ref());
+        for w in &windows {
+            assert!(w.start < w.end);
+        }
+        assert!(windows.windows(2).all(|p| p[0].end < p[1].start));
+
+        // Polar night under Strict: no sunrise or sunset, only the zenith window
+        let tromso = Solver::new(Location::new(69.6492, 18.9553), chrono_tz::Europe::Oslo)
+            .with_config(Config::default().with_strategy(GapStrategy::Strict).with_makruh(true));
+        let out = tromso.solve(NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(), false, false);
+        let reasons: Vec<_> = out.makruh_windows.unwrap().iter().map(|w| w.reason).collect();
+        assert_eq!(reasons, [MakruhReason::Zenith]);
+
+        let plain = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh).solve(date, false, false);
+        assert!(plain.makruh_windows.is_none());
+    }
+
+    #[test]
+    fn test_twilight_times_cairo_equinox() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let solver = Solver::new(Location::new(30.0444, 31.2357), chrono_tz::Africa::Cairo)
+            .with_config(Config::default().with_twilight_times(true));
+        let out = solver.solve(date, false, false);
+        let t = out.twilight_times.expect("twilight times requested");
+        println!("{:?}", t);
+
+        let e = &out.events;
+        let ordered = [
+            &t.astronomical_dawn, &t.nautical_dawn, &t.civil_dawn, &e.sunrise.time,
+            &e.maghrib.time, &t.civil_dusk, &t.nautical_dusk, &t.astronomical_dusk,
+        ].map(|time| time.clone().expect("every crossing exists in Cairo"));
+        assert!(ordered.windows(2).all(|p| p[0] < p[1]), "{:?}", ordered);
+
+        // MWL Fajr is the 18° dawn
+        assert_eq!(t.astronomical_dawn, e.fajr.time);
+
+        // Tromsø in midsummer never gets below -6°
+        let tromso = Solver::new(Location::new(69.6492, 18.9553), chrono_tz::Europe::Oslo)
+            .with_config(Config::default().with_twilight_times(true));
+        let t = tromso.solve(NaiveDate::from_ymd_opt(2026, 6, 21).unwrap(), false, false).twilight_times.unwrap();
+        assert_eq!(t, schedule::TwilightTimes::default());
+    }
+
+    #[test]
+    fn test_azimuth_mecca_february() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 15).unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh);
+        assert!(solver.solve(date, false, false).events.maghrib.azimuth.is_none());
+
+        let out = solver.with_azimuth(true).solve(date, false, false);
+        let e = &out.events;
+        println!("sunrise {:?} dhuhr {:?} maghrib {:?}", e.sunrise.azimuth, e.dhuhr.azimuth, e.maghrib.azimuth);
+
+        let maghrib = e.maghrib.azimuth.expect("Standard maghrib");
+        assert!((240.0..300.0).contains(&maghrib), "maghrib azimuth {}", maghrib);
+        let sunrise = e.sunrise.azimuth.unwrap();
+        assert!((60.0..120.0).contains(&sunrise), "sunrise azimuth {}", sunrise);
+        // Winter sun transits south of Mecca
+        assert!((e.dhuhr.azimuth.unwrap() - 180.0).abs() < 5.0);
+
+        // Projected polar-night events have no real sun position
+        let tromso = Solver::new(Location::new(69.6492, 18.9553), chrono_tz::Europe::Oslo).with_azimuth(true);
+        let out = tromso.solve(NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(), false, false);
+        assert_eq!(out.events.sunrise.method, EventMethod::Projected);
+        assert!(out.events.sunrise.azimuth.is_none());
+    }
+
+    #[test]
+    fn test_resolution_converges_tromso_sunrise() {
+        // Mid-January Tromsø: the sun barely clears the horizon, so sunrise is a shallow crossing
+        let date = NaiveDate::from_ymd_opt(2026, 1, 17).unwrap();
+        let sunrise = |seconds: u32| {
+            let out = Solver::new(Location::new(69.6492, 18.9553), chrono_tz::Europe::Oslo)
+                .with_resolution(seconds)
+                .solve(date, false, false);
+            assert_eq!(out.computation.sample_resolution_seconds, seconds);
+            assert_eq!(out.events.sunrise.method, EventMethod::Standard);
+            out.events.sunrise.time.unwrap()
+        };
+        let reference = hms_to_secs(&sunrise(1));
+        let errors: Vec<f64> = [600, 120, 30, 5]
+            .iter()
+            .map(|&s| (hms_to_secs(&sunrise(s)) - reference).abs())
+            .collect();
+        println!("sunrise error vs 1 s scan at 600/120/30/5 s: {:?}", errors);
+        assert!(errors[3] <= 1.0, "5 s scan should agree within a second: {:?}", errors);
+        assert!(errors[3] <= errors[0], "{:?}", errors);
+    }
+
+    #[test]
+    fn test_resolution_out_of_range() {
+        let solver = || Solver::new(Location::new(0.0, 0.0), chrono_tz::UTC);
+        assert_eq!(solver().with_resolution(0).config().scan_resolution(), 1);
+        assert_eq!(solver().with_resolution(3600).config().scan_resolution(), 600);
+
+        assert!(matches!(solver().try_with_resolution(0), Err(e) if e.contains("out of range")));
+        assert_eq!(solver().try_with_resolution(5).unwrap().config().scan_resolution(), 5);
+    }
+
+    #[test]
+    fn test_elevation_delays_mecca_maghrib() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let mecca = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh);
+        let sea = mecca.solve(date, false, false).events;
+        let high = mecca.with_elevation(2000.0).solve(date, false, false).events;
+        let secs = |e: &PrayerEvent| hms_to_secs(e.time.as_deref().unwrap());
+        let later = secs(&high.maghrib) - secs(&sea.maghrib);
+        let earlier = secs(&sea.sunrise) - secs(&high.sunrise);
+        println!("2000 m: maghrib +{:.0}s, sunrise -{:.0}s", later, earlier);
+        // 1.55° of dip at a near-vertical equinox sun track
+        assert!((300.0..480.0).contains(&later), "maghrib shift {}", later);
+        assert!((later - earlier).abs() < 30.0);
+        // Twilight angles are measured from the astronomical horizon
+        assert_eq!(high.fajr.time, sea.fajr.time);
+        assert_eq!(high.isha.time, sea.isha.time);
+    }
+
+    #[test]
+    fn test_cold_air_lengthens_the_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let stockholm = || Solver::new(Location::new(59.3293, 18.0686), chrono_tz::Europe::Stockholm);
+        let standard = stockholm().solve(date, false, false);
+        let unchanged = stockholm().with_atmosphere(10.0, 1010.0).solve(date, false, false);
+        assert_eq!(standard.events.sunrise.time, unchanged.events.sunrise.time);
+        assert_eq!(standard.computation.horizon_angle, unchanged.computation.horizon_angle);
+
+        let cold = stockholm().with_atmosphere(-40.0, 1010.0).solve(date, false, false);
+        let secs = |e: &PrayerEvent| hms_to_secs(e.time.as_deref().unwrap());
+        let earlier = secs(&standard.events.sunrise) - secs(&cold.events.sunrise);
+        let later = secs(&cold.events.maghrib) - secs(&standard.events.maghrib);
+        println!("-40 °C: sunrise -{:.0}s, maghrib +{:.0}s, horizon {:.3}°", earlier, later, cold.computation.horizon_angle);
+        // 21% more refraction: ~0.12° lower horizon on a shallow winter track
+        assert!(earlier >= 30.0 && later >= 30.0, "sunrise {} maghrib {}", earlier, later);
+        assert!((cold.computation.horizon_angle - (-0.833 - 0.583 * (283.0 / 233.0 - 1.0))).abs() < 1e-9);
+        // Twilight angles are not refraction-corrected
+        assert_eq!(cold.events.fajr.time, standard.events.fajr.time);
+
+        assert!(matches!(stockholm().try_with_atmosphere(-120.0, 1010.0), Err(e) if e.contains("temperature")));
+        assert!(matches!(stockholm().try_with_atmosphere(10.0, 50.0), Err(e) if e.contains("pressure")));
+        // The infallible builder clamps to -90 °C and 300 mbar instead
+        let clamped = stockholm().with_atmosphere(-120.0, 50.0).solve(date, false, false);
+        let floor = stockholm().with_atmosphere(-90.0, 300.0).solve(date, false, false);
+        assert_eq!(clamped.computation.horizon_angle, floor.computation.horizon_angle);
+    }
+
+    #[test]
+    fn test_qibla_in_output() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        let solver = Solver::new(Location::new(40.7128, -74.0060), chrono_tz::America::New_York)
+            .with_config(Config::default().with_qibla(true));
+        let qibla = solver.solve(date, false, false).qibla.expect("qibla requested");
+        println!("{:?}", qibla);
+        assert!((qibla.bearing - 58.5).abs() < 0.5);
+
+        let plain = Solver::new(Location::new(40.7128, -74.0060), chrono_tz::America::New_York).solve(date, false, false);
+        assert!(plain.qibla.is_none());
+    }
+
+    #[test]
+    fn test_combined_times_mecca() {
+        let solver = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh);
+        let out = solver.solve(NaiveDate::from_ymd_opt(2026, 2, 14).unwrap(), false, false);
+        let combined = schedule::CombinedTimes::from_events(&out.events);
+        println!("{:?}", combined);
+
+        assert_eq!(combined.dhuhr_asr, out.events.dhuhr.time);
+        assert_eq!(combined.maghrib_isha, out.events.maghrib.time);
+        assert!(combined.dhuhr_asr.is_some() && combined.maghrib_isha.is_some());
+
+        // No combination when a member is missing
+        let mut events = out.events.clone();
+        events.isha = PrayerEvent { time: None, method: EventMethod::None, ..events.isha };
+        assert_eq!(schedule::CombinedTimes::from_events(&events).maghrib_isha, None);
+    }
+
+    #[test]
+    fn test_auto_strategy_resolves_by_latitude() {
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        for (lat, lon, expected, rule) in [
+            (21.4225, 39.8262, GapStrategy::Strict, HighLatitudeRule::WaveMapped),
+            (59.3293, 18.0686, GapStrategy::Strict, HighLatitudeRule::AngleBased),
+            (78.2232, 15.6267, GapStrategy::Projected45, HighLatitudeRule::WaveMapped),
+        ] {
+            let auto = utc_solver(lat, lon).with_strategy(GapStrategy::Auto).solve(date, false, false);
+            let config = Config::default().with_strategy(expected).with_high_latitude_rule(rule);
+            let explicit = utc_solver(lat, lon).with_config(config).solve(date, false, false);
+            println!("  lat {:.1}: auto -> {} / {}", lat, auto.computation.gap_strategy, auto.computation.high_latitude_rule);
+
+            assert_eq!(auto.gap_strategy, GapStrategy::Auto);
+            assert_eq!(auto.computation.gap_strategy, expected);
+            assert_eq!(auto.computation.high_latitude_rule, rule);
+            assert_eq!(
+                serde_json::to_value(&auto.events).unwrap(),
+                serde_json::to_value(&explicit.events).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_computation_block_default_mecca() {
+        let solver = Solver::new(Location::new(21.4225, 39.8262), chrono_tz::Asia::Riyadh);
+        let out = solver.solve(NaiveDate::from_ymd_opt(2026, 2, 14).unwrap(), false, false);
+        let json = serde_json::to_value(&out.computation).unwrap();
+        println!("{}", json);
+
+        assert_eq!(json["method"], "MWL");
+        assert_eq!(json["fajr_angle"], -18.0);
+        assert_eq!(json["isha_angle"], -17.0);
+        assert_eq!(json["asr_school"], "Shafi");
+        assert_eq!(json["asr_factor"], 1.0);
+        assert_eq!(json["gap_strategy"], "Projected45");
+        assert_eq!(json["engine_version"], crate::VERSION);
+        assert_eq!(json["refraction"], true);
+        assert!(json.get("isha_minutes").is_none());
+    }
+
+    #[test]
+    fn test_next_prayer_at_fixed_instant() {
+        let riyadh: Tz = "Asia/Riyadh".parse().unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), riyadh);
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let output = solver.solve(date, false, false);
+
+        // 37 minutes before Asr
+        let asr = NaiveTime::parse_from_str(output.events.asr.time.as_ref().unwrap(), "%H:%M:%S").unwrap();
+        let asr_utc = riyadh.from_local_datetime(&date.and_time(asr)).unwrap().with_timezone(&Utc);
+        let now = asr_utc - Duration::minutes(37);
+
+        let next = solver.next_prayer(now).unwrap();
+        assert_eq!(next.prayer, PrayerKind::Asr);
+        assert_eq!(next.remaining_minutes, 37);
+        let line = next.format("{prayer} {time} (in {remaining})");
+        println!("  {}", line);
+        assert_eq!(line, format!("Asr {} (in 37m)", &output.events.asr.time.as_ref().unwrap()[..5]));
+        assert_eq!(next.format("{minutes}"), "37");
+
+        // After Isha the next event is tomorrow's Fajr
+        let late = riyadh.from_local_datetime(&date.and_hms_opt(23, 30, 0).unwrap()).unwrap().with_timezone(&Utc);
+        let next = solver.next_prayer(late).unwrap();
+        assert_eq!(next.prayer, PrayerKind::Fajr);
+        assert!(next.remaining_minutes > 5 * 60);
+        assert!(next.format("{remaining}").contains('h'));
+    }
+
+    #[test]
+    fn test_upcoming_from_fixed_instant() {
+        let riyadh: Tz = "Asia/Riyadh".parse().unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), riyadh);
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let at = |date: NaiveDate, event: &PrayerEvent| {
+            let time = NaiveTime::parse_from_str(event.time.as_ref().unwrap(), "%H:%M:%S").unwrap();
+            riyadh.from_local_datetime(&date.and_time(time)).unwrap().with_timezone(&Utc)
+        };
+        let today = solver.solve(date, false, false).events;
+        let tomorrow = solver.solve(date + Duration::days(1), false, false).events;
+
+        // 03:00 in Mecca, before Fajr
+        let now = riyadh.from_local_datetime(&date.and_hms_opt(3, 0, 0).unwrap()).unwrap().with_timezone(&Utc);
+        let next = solver.upcoming(now, 3);
+        println!("{:?}", next);
+        assert_eq!(next, vec![
+            (PrayerKind::Fajr, at(date, &today.fajr)),
+            (PrayerKind::Sunrise, at(date, &today.sunrise)),
+            (PrayerKind::Dhuhr, at(date, &today.dhuhr)),
+        ]);
+
+        // Eight events roll over into tomorrow's Fajr and Sunrise
+        let next = solver.upcoming(now, 8);
+        assert_eq!(next[6], (PrayerKind::Fajr, at(date + Duration::days(1), &tomorrow.fajr)));
+        assert_eq!(next[7].0, PrayerKind::Sunrise);
+        assert!(solver.upcoming(now, 0).is_empty());
+    }
+
+    #[test]
+    fn test_solve_range_is_inclusive_and_ordered() {
+        let solver = Solver::new(Location::new(59.3293, 18.0686), chrono_tz::Europe::Stockholm);
+        let start = NaiveDate::from_ymd_opt(2026, 3, 27).unwrap();
+        let end = NaiveDate::from_ymd_opt(2026, 4, 2).unwrap();
+        let dates: Vec<String> = solver.solve_range(start, end).map(|out| out.date).collect();
+        println!("{:?}", dates);
+        assert_eq!(dates.len(), 7);
+        assert_eq!(dates.first().unwrap(), "2026-03-27");
+        assert_eq!(dates.last().unwrap(), "2026-04-02");
+        assert_eq!(solver.solve_range(end, start).count(), 0);
+    }
+
+    #[test]
+    fn test_upcoming_polar_day() {
+        let oslo: Tz = "Europe/Oslo".parse().unwrap();
+        let solver = Solver::new(Location::new(69.6492, 18.9553), oslo);
+        let now = oslo.from_local_datetime(&NaiveDate::from_ymd_opt(2026, 6, 21).unwrap().and_hms_opt(12, 0, 0).unwrap())
+            .unwrap().with_timezone(&Utc);
+        let next = solver.upcoming(now, 12);
+        println!("{:?}", next);
+        assert_eq!(next.len(), 12);
+        assert!(next.windows(2).all(|w| w[0].1 < w[1].1));
+        assert!(next.iter().all(|(_, instant)| *instant > now));
+        assert!(next.iter().any(|(kind, _)| *kind == PrayerKind::Sunrise), "virtual sunrise included");
+    }
+
+    #[test]
+    fn test_current_window_between_dhuhr_and_asr() {
+        let riyadh: Tz = "Asia/Riyadh".parse().unwrap();
+        let solver = Solver::new(Location::new(21.4225, 39.8262), riyadh);
+        let date = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let output = solver.solve(date, false, false);
+
+        let at = riyadh.from_local_datetime(&date.and_hms_opt(13, 30, 0).unwrap()).unwrap();
+        let window = solver.current_window(at);
+        println!("  {:?}", window);
+        assert_eq!(window.current, PrayerKind::Dhuhr);
+        assert_eq!(window.window_end, output.events.asr.time);
+
+        // Before Fajr: still in yesterday's Isha window, ending at today's Fajr
+        let early = riyadh.from_local_datetime(&date.and_hms_opt(3, 0, 0).unwrap()).unwrap();
+        let window = solver.current_window(early);
+        assert_eq!(window.current, PrayerKind::Isha);
+        assert_eq!(window.window_end, output.events.fajr.time);
+
+        // After Isha the window ends at tomorrow's Fajr
+        let late = riyadh.from_local_datetime(&date.and_hms_opt(23, 30, 0).unwrap()).unwrap();
+        let tomorrow = solver.solve(date + Duration::days(1), false, false);
+        let window = solver.current_window(late);
+        assert_eq!(window.current, PrayerKind::Isha);
+        assert_eq!(window.window_end, tomorrow.events.fajr.time);
+    }
 }
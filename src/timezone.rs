@@ -0,0 +1,157 @@
+//! Timezone parsing — IANA names from caches, geocoders and user input.
+//!
+//! Zones get renamed (Kiev → Kyiv, Calcutta → Kolkata). Old names in a
+//! cache or a request are mapped to the current zone instead of failing,
+//! and names that still do not parse produce a typed error.
+
+use chrono::{Datelike, Duration, FixedOffset, NaiveDate, Offset, TimeZone};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use std::fmt;
+
+/// Legacy IANA names and the zone that replaced them.
+const RENAMED_ZONES: &[(&str, &str)] = &[
+    ("Europe/Kiev", "Europe/Kyiv"),
+    ("Europe/Uzhgorod", "Europe/Kyiv"),
+    ("Europe/Zaporozhye", "Europe/Kyiv"),
+    ("Asia/Calcutta", "Asia/Kolkata"),
+    ("Asia/Saigon", "Asia/Ho_Chi_Minh"),
+    ("Asia/Katmandu", "Asia/Kathmandu"),
+    ("Asia/Rangoon", "Asia/Yangon"),
+    ("Asia/Dacca", "Asia/Dhaka"),
+    ("Asia/Thimbu", "Asia/Thimphu"),
+    ("Asia/Ulan_Bator", "Asia/Ulaanbaatar"),
+    ("Asia/Istanbul", "Europe/Istanbul"),
+    ("America/Godthab", "America/Nuuk"),
+    ("Atlantic/Faeroe", "Atlantic/Faroe"),
+    ("Pacific/Enderbury", "Pacific/Kanton"),
+];
+
+/// A timezone name that could not be mapped to an IANA zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TzError {
+    pub name: String,
+    /// A zone the caller probably meant, if one is close enough.
+    pub suggestion: Option<&'static str>,
+}
+
+impl fmt::Display for TzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.suggestion {
+            Some(s) => write!(f, "unknown timezone '{}'; did you mean {}?", self.name, s),
+            None => write!(f, "unknown timezone '{}'", self.name),
+        }
+    }
+}
+
+impl std::error::Error for TzError {}
+
+/// The current name for a possibly renamed zone.
+pub fn canonical_name(name: &str) -> &str {
+    RENAMED_ZONES.iter()
+        .find(|(old, _)| old.eq_ignore_ascii_case(name))
+        .map(|(_, new)| *new)
+        .unwrap_or(name)
+}
+
+/// Parse an IANA name, following renames and ignoring case.
+pub fn parse_tz(name: &str) -> Result<Tz, TzError> {
+    let trimmed = name.trim();
+    let canonical = canonical_name(trimmed);
+    if let Ok(tz) = canonical.parse::<Tz>() {
+        return Ok(tz);
+    }
+    if let Some(tz) = TZ_VARIANTS.iter().find(|tz| tz.name().eq_ignore_ascii_case(canonical)) {
+        return Ok(*tz);
+    }
+    Err(TzError { name: trimmed.to_string(), suggestion: suggest(trimmed) })
+}
+
+/// A zone with the same city part, e.g. "Asia/Stockholm" → "Europe/Stockholm".
+fn suggest(name: &str) -> Option<&'static str> {
+    let city = name.rsplit('/').next()?;
+    if city.is_empty() {
+        return None;
+    }
+    TZ_VARIANTS.iter()
+        .map(|tz| tz.name())
+        .find(|n| n.rsplit('/').next().is_some_and(|c| c.eq_ignore_ascii_case(city)))
+}
+
+// ─── Offset transitions ─────────────────────────────────────────
+
+/// UTC offset of `tz` at local noon on `date` — the offset the solver
+/// uses for that day.
+pub fn noon_offset(tz: &Tz, date: NaiveDate) -> FixedOffset {
+    let noon = date.and_hms_opt(12, 0, 0).unwrap();
+    match tz.from_local_datetime(&noon).earliest() {
+        Some(dt) => dt.offset().fix(),
+        None => FixedOffset::east_opt(0).unwrap(),
+    }
+}
+
+/// A change of offset between two consecutive local noons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OffsetTransition {
+    /// First day whose noon has the new offset.
+    pub date: NaiveDate,
+    pub before: FixedOffset,
+    pub after: FixedOffset,
+}
+
+/// Every offset change in `year`, found by comparing each day's noon offset
+/// with the day before (`polaris tz-check`).
+pub fn offset_transitions(tz: &Tz, year: i32) -> Vec<OffsetTransition> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+        return Vec::new();
+    };
+    let mut prev = noon_offset(tz, first - Duration::days(1));
+    first.iter_days()
+        .take_while(|d| d.year() == year)
+        .filter_map(|date| {
+            let offset = noon_offset(tz, date);
+            let before = std::mem::replace(&mut prev, offset);
+            (offset != before).then_some(OffsetTransition { date, before, after: offset })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renamed_zones_map_to_current() {
+        assert_eq!(parse_tz("Europe/Kiev").unwrap(), chrono_tz::Europe::Kyiv);
+        assert_eq!(parse_tz("Asia/Calcutta").unwrap(), chrono_tz::Asia::Kolkata);
+        assert_eq!(parse_tz("america/godthab").unwrap(), chrono_tz::America::Nuuk);
+        assert_eq!(parse_tz(" europe/oslo ").unwrap(), chrono_tz::Europe::Oslo);
+    }
+
+    #[test]
+    fn test_unknown_zone_error() {
+        let err = parse_tz("Asia/Stockholm").unwrap_err();
+        println!("{}", err);
+        assert_eq!(err.suggestion, Some("Europe/Stockholm"));
+        assert!(err.to_string().contains("'Asia/Stockholm'"));
+
+        let err = parse_tz("Mars/Olympus_Mons").unwrap_err();
+        assert_eq!(err.suggestion, None);
+    }
+
+    #[test]
+    fn test_berlin_transitions_2026() {
+        let transitions = offset_transitions(&chrono_tz::Europe::Berlin, 2026);
+        for t in &transitions {
+            println!("{} {} -> {}", t.date, t.before, t.after);
+        }
+        assert_eq!(transitions.len(), 2);
+
+        let (spring, autumn) = (transitions[0], transitions[1]);
+        assert_eq!(spring.date, NaiveDate::from_ymd_opt(2026, 3, 29).unwrap());
+        assert_eq!((spring.before.to_string(), spring.after.to_string()), ("+01:00".into(), "+02:00".into()));
+        assert_eq!(autumn.date, NaiveDate::from_ymd_opt(2026, 10, 25).unwrap());
+        assert_eq!((autumn.before.to_string(), autumn.after.to_string()), ("+02:00".into(), "+01:00".into()));
+
+        assert!(offset_transitions(&chrono_tz::Asia::Riyadh, 2026).is_empty());
+    }
+}
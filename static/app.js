@@ -169,14 +169,14 @@
 
   var api = {
     resolve: function (query) {
-      return fetch('/api/resolve?query=' + encodeURIComponent(query), { cache: 'no-store' })
+      return fetch('/api/v1/resolve?query=' + encodeURIComponent(query), { cache: 'no-store' })
         .then(handleResponse);
     },
     times: function (params) {
       var q = 'lat=' + params.lat + '&lon=' + params.lon +
         '&tz=' + encodeURIComponent(params.tz) + '&date=' + params.date +
         '&strategy=' + (params.strategy || 'projected45');
-      return fetch('/api/times?' + q, { cache: 'no-store' })
+      return fetch('/api/v1/times?' + q, { cache: 'no-store' })
         .then(function (r) {
           if (!r.ok) return r.json().then(function (j) { throw new Error(j.error || 'Failed'); });
           return r.json();
@@ -186,7 +186,7 @@
       var q = 'lat=' + params.lat + '&lon=' + params.lon +
         '&tz=' + encodeURIComponent(params.tz) +
         '&year=' + params.year + '&month=' + params.month;
-      return fetch('/api/month?' + q, { cache: 'no-store' })
+      return fetch('/api/v1/month?' + q, { cache: 'no-store' })
         .then(function (r) {
           if (!r.ok) throw new Error('Failed to fetch month data');
           return r.json();
@@ -196,14 +196,14 @@
       var q = 'lat=' + params.lat + '&lon=' + params.lon +
         '&tz=' + encodeURIComponent(params.tz);
       if (params.hijri_year) q += '&hijri_year=' + params.hijri_year;
-      return fetch('/api/hijri?' + q, { cache: 'no-store' })
+      return fetch('/api/v1/hijri?' + q, { cache: 'no-store' })
         .then(function (r) {
           if (!r.ok) return r.json().then(function (j) { throw new Error(j.error || 'Failed'); });
           return r.json();
         });
     },
     cities: function () {
-      return fetch('/api/cities', { cache: 'no-store' })
+      return fetch('/api/v1/cities', { cache: 'no-store' })
         .then(function (r) { return r.json(); });
     }
   };
@@ -532,7 +532,7 @@
     var times = [];
     PRAYER_NAMES.forEach(function (name) {
       var ev = todayData.events[name];
-      if (ev && ev.time && !ev.next_day) {
+      if (ev && ev.time && !ev.day_offset) {
         var parts = ev.time.split(':');
         var totalSecs = parseInt(parts[0]) * 3600 + parseInt(parts[1]) * 60 +
           (parts.length > 2 ? parseInt(parts[2]) : 0);
@@ -1246,7 +1246,7 @@
       var tdTime = document.createElement('td');
       tdTime.className = 'time-cell';
       if (ev.time) {
-        tdTime.textContent = fmtTime(ev.time) + (ev.next_day ? ' +1' : '');
+        tdTime.textContent = fmtTime(ev.time) + (ev.day_offset > 0 ? ' +1' : ev.day_offset < 0 ? ' -1' : '');
       } else {
         tdTime.textContent = '---';
         tdTime.classList.add('no-time');
@@ -1295,7 +1295,7 @@
 
     PRAYER_NAMES.forEach(function (name) {
       var ev = data.events[name];
-      if (ev.time && !ev.next_day) {
+      if (ev.time && !ev.day_offset) {
         var parts = ev.time.split(':');
         times.push({ name: name, minutes: parseInt(parts[0]) * 60 + parseInt(parts[1]) });
       }
@@ -1334,7 +1334,7 @@
     var prayerTimes = [];
     PRAYER_NAMES.forEach(function (name) {
       var ev = data.events[name];
-      if (ev && ev.time && !ev.next_day) {
+      if (ev && ev.time && !ev.day_offset) {
         var parts = ev.time.split(':');
         var hours = parseInt(parts[0]) + parseInt(parts[1]) / 60;
         prayerTimes.push({ name: name, hours: hours, method: ev.method });
@@ -1531,17 +1531,17 @@
 
     var endpoints = [
       {
-        path: '/api/resolve',
+        path: '/api/v1/resolve',
         desc: 'Resolve a city name to coordinates, timezone, and metadata. Returns a single match or HTTP 300 with multiple candidates for disambiguation.',
         params: [
           { name: 'query', type: 'string', required: true, desc: 'City name to search (e.g. "Stockholm", "Medina")' },
           { name: 'country', type: 'string', required: false, desc: 'ISO 3166-1 alpha-2 country hint (e.g. "SA", "US")' }
         ],
-        curl: "curl 'http://localhost:3000/api/resolve?query=stockholm'",
-        js: "const res = await fetch('/api/resolve?query=stockholm');\nconst loc = await res.json();\nconsole.log(loc.name, loc.lat, loc.lon);"
+        curl: "curl 'http://localhost:3000/api/v1/resolve?query=stockholm'",
+        js: "const res = await fetch('/api/v1/resolve?query=stockholm');\nconst loc = await res.json();\nconsole.log(loc.name, loc.lat, loc.lon);"
       },
       {
-        path: '/api/times',
+        path: '/api/v1/times',
         desc: 'Compute prayer times for a specific location and date. Supports both city-based and coordinate-based queries.',
         params: [
           { name: 'city', type: 'string', required: false, desc: 'City name (alternative to lat/lon)' },
@@ -1552,11 +1552,11 @@
           { name: 'strategy', type: 'string', required: false, desc: '"projected45" (default) or "strict"' },
           { name: 'country', type: 'string', required: false, desc: 'Country hint for city disambiguation' }
         ],
-        curl: "curl 'http://localhost:3000/api/times?city=stockholm&date=2026-03-01'",
-        js: "const res = await fetch('/api/times?city=stockholm&date=2026-03-01');\nconst data = await res.json();\nconsole.log(data.events.fajr.time);"
+        curl: "curl 'http://localhost:3000/api/v1/times?city=stockholm&date=2026-03-01'",
+        js: "const res = await fetch('/api/v1/times?city=stockholm&date=2026-03-01');\nconst data = await res.json();\nconsole.log(data.events.fajr.time);"
       },
       {
-        path: '/api/month',
+        path: '/api/v1/month',
         desc: 'Compute prayer times for every day in a given month. Useful for generating Imsakia calendars.',
         params: [
           { name: 'city', type: 'string', required: false, desc: 'City name (alternative to lat/lon)' },
@@ -1568,11 +1568,11 @@
           { name: 'strategy', type: 'string', required: false, desc: '"projected45" (default) or "strict"' },
           { name: 'country', type: 'string', required: false, desc: 'Country hint' }
         ],
-        curl: "curl 'http://localhost:3000/api/month?city=stockholm&year=2026&month=2'",
-        js: "const res = await fetch('/api/month?city=stockholm&year=2026&month=2');\nconst days = await res.json();\ndays.forEach(d => console.log(d.date, d.events.fajr.time));"
+        curl: "curl 'http://localhost:3000/api/v1/month?city=stockholm&year=2026&month=2'",
+        js: "const res = await fetch('/api/v1/month?city=stockholm&year=2026&month=2');\nconst days = await res.json();\ndays.forEach(d => console.log(d.date, d.events.fajr.time));"
       },
       {
-        path: '/api/hijri',
+        path: '/api/v1/hijri',
         desc: 'Get Hijri calendar data including Ramadan start/end dates computed via astronomical crescent visibility (Odeh 2004 criterion).',
         params: [
           { name: 'lat', type: 'number', required: true, desc: 'Latitude (-90 to 90)' },
@@ -1580,15 +1580,15 @@
           { name: 'tz', type: 'string', required: true, desc: 'IANA timezone' },
           { name: 'hijri_year', type: 'number', required: false, desc: 'Hijri year (defaults to current)' }
         ],
-        curl: "curl 'http://localhost:3000/api/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh'",
-        js: "const res = await fetch('/api/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh');\nconst data = await res.json();\nconsole.log('Ramadan starts:', data.ramadan.start);"
+        curl: "curl 'http://localhost:3000/api/v1/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh'",
+        js: "const res = await fetch('/api/v1/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh');\nconst data = await res.json();\nconsole.log('Ramadan starts:', data.ramadan.start);"
       },
       {
-        path: '/api/cities',
+        path: '/api/v1/cities',
         desc: 'List all built-in cities with their country codes. Useful for autocomplete or dropdown implementations.',
         params: [],
-        curl: "curl 'http://localhost:3000/api/cities'",
-        js: "const res = await fetch('/api/cities');\nconst cities = await res.json();\nconsole.log(cities.length, 'cities available');"
+        curl: "curl 'http://localhost:3000/api/v1/cities'",
+        js: "const res = await fetch('/api/v1/cities');\nconst cities = await res.json();\nconsole.log(cities.length, 'cities available');"
       }
     ];
 
@@ -0,0 +1,141 @@
+//! End-to-end tests for the `polaris` binary.
+//!
+//! `HOME` points at a temporary directory so the location cache of the
+//! machine running the tests is never read or written.
+
+use std::process::{Command, Output};
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+fn polaris(args: &[&str]) -> Output {
+    let home = TempDir::new().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_polaris"))
+        .args(args)
+        .env("HOME", home.path())
+        .output()
+        .expect("failed to run polaris")
+}
+
+#[test]
+fn test_invalid_date_json_error() {
+    let out = polaris(&[
+        "compute", "--lat", "21.42", "--lon", "39.83", "--tz", "Asia/Riyadh",
+        "--date", "2026-13-45", "--offline", "--error-format", "json",
+    ]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert!(!out.status.success());
+
+    let line = stderr.lines().last().expect("no stderr output");
+    let body: Value = serde_json::from_str(line).expect("stderr is not JSON");
+    assert_eq!(body["code"], "invalid_date");
+    assert!(body["error"].as_str().unwrap().contains("2026-13-45"));
+}
+
+#[test]
+fn test_unknown_tz_text_error_unchanged() {
+    let out = polaris(&["compute", "--lat", "59.33", "--lon", "18.07", "--tz", "Asia/Stockholm", "--offline"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert!(!out.status.success());
+    assert!(stderr.contains("Error: unknown timezone 'Asia/Stockholm'; did you mean Europe/Stockholm?. Use IANA format"));
+}
+
+#[test]
+fn test_ical_format() {
+    let out = polaris(&[
+        "compute", "--lat", "59.33", "--lon", "18.07", "--tz", "Europe/Stockholm",
+        "--date", "2026-03-20", "--offline", "--format", "ical",
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    println!("{}", stdout);
+    assert!(out.status.success());
+    assert!(stdout.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(stdout.matches("BEGIN:VEVENT").count(), 5);
+    assert!(stdout.contains("DTSTART:20260320T"));
+}
+
+#[test]
+fn test_ndjson_range() {
+    let out = polaris(&[
+        "compute", "--lat", "59.33", "--lon", "18.07", "--tz", "Europe/Stockholm",
+        "--from", "2026-03-27", "--to", "2026-04-02", "--offline", "--format", "ndjson",
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let days: Vec<Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(days.len(), 7);
+    assert_eq!(days[0]["date"], "2026-03-27");
+    assert_eq!(days[6]["date"], "2026-04-02");
+    assert!(days.iter().all(|day| day["location"]["timezone"] == "Europe/Stockholm"));
+
+    // A range needs the line-per-day format
+    let out = polaris(&[
+        "compute", "--lat", "59.33", "--lon", "18.07", "--from", "2026-03-27", "--to", "2026-04-02", "--offline",
+    ]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--format ndjson"));
+}
+
+#[test]
+fn test_hijri_banner() {
+    let out = polaris(&[
+        "compute", "--lat", "21.42", "--lon", "39.83", "--tz", "Asia/Riyadh",
+        "--date", "2026-03-01", "--offline", "--hijri",
+    ]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert!(out.status.success());
+    assert!(stderr.contains("Ramadan 1447 AH"));
+
+    let json: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json["hijri"]["month"], 9);
+}
+
+#[test]
+fn test_legacy_args_fall_back_to_compute() {
+    // No subcommand: the arguments are parsed as `compute` arguments
+    let out = polaris(&[
+        "--lat", "21.42", "--lon", "39.83", "--tz", "Asia/Riyadh", "--date", "2026-03-01", "--offline",
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let json: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(json["date"], "2026-03-01");
+
+    // A bad value under a named subcommand is reported for that subcommand
+    let out = polaris(&["compute", "--lat", "21.42", "--lon", "39.83", "--adjust", "fajr=abc"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert!(!out.status.success());
+    assert!(stderr.contains("invalid value 'fajr=abc' for '--adjust <ADJUST>'"));
+}
+
+#[test]
+fn test_next_rejects_invalid_coordinates() {
+    let out = polaris(&["next", "--lat", "200", "--lon", "0"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr.contains("Error: Invalid coordinates (200, 0)"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_notify_rejects_invalid_coordinates() {
+    let out = polaris(&["serve-notify", "--lat", "200", "--lon", "0", "--webhook", "http://127.0.0.1:9/hook"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr.contains("Error: Invalid coordinates (200, 0)"));
+    assert!(!stderr.contains("Posting daily schedule"));
+}
+
+#[test]
+fn test_profile_rejects_invalid_coordinates() {
+    let out = polaris(&["profile", "--lat", "-95", "--lon", "0", "--days", "1"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    println!("{}", stderr);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr.contains("Error: Invalid coordinates (-95, 0)"));
+}
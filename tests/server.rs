@@ -0,0 +1,881 @@
+//! End-to-end tests for the HTTP API, driving the axum router in-process.
+//!
+//! The resolver runs offline against a temporary cache, so city lookups
+//! come from the built-in dataset and no network is touched.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use polaris_chronos::location::cache::LocationCache;
+use polaris_chronos::location::{GeocodeProvider, Granularity, LocationError, LocationResolver, ResolvedLocation};
+use polaris_chronos::server::{build_router_with_state, serve_until, AppState};
+use serde_json::Value;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+/// An offline resolver over a fresh cache in the returned directory.
+fn offline_resolver() -> (LocationResolver, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let mut resolver = LocationResolver::with_cache(LocationCache::load_from(dir.path().join("cache.json")));
+    resolver.set_offline(true);
+    (resolver, dir)
+}
+
+fn offline_router() -> (Router, TempDir) {
+    let (resolver, dir) = offline_resolver();
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver)));
+    (router, dir)
+}
+
+/// GET `uri`, returning status, content type, and the raw body.
+async fn get(uri: &str) -> (StatusCode, String, Vec<u8>) {
+    let (router, _dir) = offline_router();
+    let response = router
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response.headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+async fn get_json(uri: &str) -> (StatusCode, Value) {
+    let (status, _, body) = get(uri).await;
+    let json = serde_json::from_slice(&body)
+        .unwrap_or_else(|e| panic!("{} returned invalid JSON: {}", uri, e));
+    (status, json)
+}
+
+#[tokio::test]
+async fn test_times_mecca() {
+    let (status, json) = get_json("/api/times?city=mecca&date=2026-02-14").await;
+    println!("{}", json);
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["location"]["name"], "mecca");
+    assert_eq!(json["location"]["timezone"], "Asia/Riyadh");
+    assert_eq!(json["location"]["tz_source"], "Builtin");
+    assert_eq!(json["date"], "2026-02-14");
+    assert_eq!(json["state"], "Normal");
+    assert_eq!(json["method"], "MWL");
+    for prayer in ["fajr", "sunrise", "dhuhr", "asr", "maghrib", "isha"] {
+        let event = &json["events"][prayer];
+        assert!(event["time"].is_string(), "{} has no time", prayer);
+        assert_eq!(event["method"], "Standard");
+    }
+}
+
+#[tokio::test]
+async fn test_times_headers() {
+    let (router, _dir) = offline_router();
+    let response = router
+        .oneshot(Request::get("/api/times?city=mecca").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let headers = response.headers();
+    assert_eq!(headers["x-polaris-version"], polaris_chronos::server::VERSION);
+    assert!(headers[header::CACHE_CONTROL].to_str().unwrap().contains("no-store"));
+}
+
+#[tokio::test]
+async fn test_times_etag_not_modified() {
+    let (router, _dir) = offline_router();
+    let uri = "/api/v1/times?city=mecca&date=2026-02-14";
+    let response = router.clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG].clone();
+    println!("ETag {:?}", etag);
+
+    let response = router.clone()
+        .oneshot(Request::get(uri).header(header::IF_NONE_MATCH, etag.clone()).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag);
+    assert!(to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+
+    // Another day or extra fields get a fresh body
+    for other in ["/api/v1/times?city=mecca&date=2026-02-15", "/api/v1/times?city=mecca&date=2026-02-14&combine=true"] {
+        let response = router.clone()
+            .oneshot(Request::get(other).header(header::IF_NONE_MATCH, etag.clone()).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", other);
+        assert_ne!(response.headers()[header::ETAG], etag);
+    }
+}
+
+#[tokio::test]
+async fn test_month_gzip() {
+    let (router, _dir) = offline_router();
+    let uri = "/api/month?city=stockholm&year=2026&month=6";
+    let response = router.clone()
+        .oneshot(Request::get(uri).header(header::ACCEPT_ENCODING, "gzip").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers().clone();
+    assert_eq!(headers[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(headers["x-polaris-version"], polaris_chronos::server::VERSION);
+    assert!(headers[header::CACHE_CONTROL].to_str().unwrap().contains("no-store"));
+    let compressed = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    let mut json = String::new();
+    std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&compressed[..]), &mut json).unwrap();
+    println!("{} bytes -> {} gzipped", json.len(), compressed.len());
+    let days: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(days.as_array().unwrap().len(), 30);
+    assert!(compressed.len() * 4 < json.len());
+
+    let response = router.clone()
+        .oneshot(Request::get(uri).header(header::ACCEPT_ENCODING, "deflate").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "deflate");
+
+    // Without Accept-Encoding the body is plain JSON
+    let response = router
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn test_healthz() {
+    let (status, content_type, body) = get("/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("application/json"));
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    println!("{}", json);
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["version"], polaris_chronos::server::VERSION);
+    assert!(json["uptime_secs"].as_u64().is_some());
+
+    let (router, _dir) = offline_router();
+    let response = router.oneshot(Request::get("/healthz").body(Body::empty()).unwrap()).await.unwrap();
+    assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+    assert!(response.headers().get("x-polaris-version").is_none());
+}
+
+#[tokio::test]
+async fn test_v1_and_legacy_paths_match() {
+    let (router, _dir) = offline_router();
+    let mut bodies = Vec::new();
+    for uri in ["/api/v1/times?city=mecca&date=2026-02-14", "/api/times?city=mecca&date=2026-02-14"] {
+        let response = router.clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let deprecated = response.headers().get("deprecation").map(|v| v.to_str().unwrap().to_string());
+        assert_eq!(deprecated.as_deref(), uri.starts_with("/api/times").then_some("true"), "{}", uri);
+        assert_eq!(response.headers()["x-polaris-version"], polaris_chronos::server::VERSION);
+        bodies.push(to_bytes(response.into_body(), usize::MAX).await.unwrap());
+    }
+    assert_eq!(bodies[0], bodies[1]);
+}
+
+#[tokio::test]
+async fn test_times_errors() {
+    let (status, json) = get_json("/api/times").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], 400);
+
+    let (status, json) = get_json("/api/times?city=mecca&date=2026-13-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("Invalid date"));
+
+    let (status, _) = get_json("/api/times?city=mecca&method=bogus").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, json) = get_json("/api/times?city=qwxzvplk").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], 404);
+}
+
+#[tokio::test]
+async fn test_corrupt_cache_entry_is_400() {
+    let dir = TempDir::new().unwrap();
+    let mut cache = LocationCache::load_from(dir.path().join("cache.json"));
+    let mut corrupt = LocationResolver::from_manual(0.0, 0.0, Some("UTC"));
+    corrupt.name = "Atlantis".into();
+    corrupt.lat = 200.0;
+    cache.put_with_key("atlantis", &corrupt);
+    let mut resolver = LocationResolver::with_cache(cache);
+    resolver.set_offline(true);
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver)));
+
+    let response = router
+        .oneshot(Request::get("/api/times?city=atlantis").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    println!("{}", json);
+    assert!(json["error"].as_str().unwrap().contains("Invalid coordinates"));
+}
+
+#[tokio::test]
+async fn test_resolve_empty_query() {
+    let (status, json) = get_json("/api/resolve?query=").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], 400);
+    assert!(json["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_resolve_builtin() {
+    let (status, json) = get_json("/api/resolve?query=stockholm").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["name"], "stockholm");
+    assert_eq!(json["tz"], "Europe/Stockholm");
+    assert_eq!(json["country_code"], "SE");
+    assert!(json["lat"].as_f64().unwrap() > 59.0);
+    assert_eq!(json["granularity"], "city");
+}
+
+#[tokio::test]
+async fn test_resolve_batch() {
+    let (router, _dir) = offline_router();
+    let post = |body: String| Request::post("/api/v1/resolve-batch")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+
+    // Bare "xyz" loosely matches a built-in city, so use a name nothing matches
+    let response = router.clone().oneshot(post(r#"["stockholm","tokyo","xyznonexistentcity123"]"#.into())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["status"], "ok");
+    assert_eq!(results[0]["query"], "stockholm");
+    assert_eq!(results[0]["tz"], "Europe/Stockholm");
+    assert_eq!(results[1]["status"], "ok");
+    assert_eq!(results[1]["tz"], "Asia/Tokyo");
+    assert_eq!(results[2]["status"], "not_found");
+    assert_eq!(results[2]["query"], "xyznonexistentcity123");
+    assert!(results[2]["error"].is_string());
+
+    let too_many = serde_json::to_string(&vec!["mecca"; 51]).unwrap();
+    let response = router.oneshot(post(too_many)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_resolve_batch_charges_uncached_queries() {
+    let (resolver, _dir) = offline_resolver();
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver).with_rate_limit(Some(3))));
+    let peer: SocketAddr = "198.51.100.7:50000".parse().unwrap();
+    let request = Request::post("/api/v1/resolve-batch")
+        .header(header::CONTENT_TYPE, "application/json")
+        .extension(ConnectInfo(peer))
+        .body(Body::from(r#"["stockholm","tokyo","mecca","cairo"]"#))
+        .unwrap();
+
+    // The request takes one token and each lookup one more
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    let statuses: Vec<_> = json.as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, ["ok", "ok", "error", "error"]);
+    assert!(json[2]["error"].as_str().unwrap().contains("Rate limit"));
+}
+
+#[tokio::test]
+async fn test_resolve_granularity() {
+    let (status, json) = get_json("/api/resolve?query=stockholm&granularity=poi").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["granularity"], "poi");
+
+    let (status, json) = get_json("/api/resolve?query=stockholm&granularity=street").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("granularity"));
+}
+
+#[tokio::test]
+async fn test_cities() {
+    let (status, json) = get_json("/api/cities").await;
+    assert_eq!(status, StatusCode::OK);
+    let cities = json.as_array().unwrap();
+    assert!(cities.len() >= 30);
+    assert!(cities.iter().any(|c| c.to_string().to_lowercase().contains("mecca")));
+}
+
+#[tokio::test]
+async fn test_country_saudi_arabia() {
+    let (status, json) = get_json("/api/v1/country?code=sa&date=2026-03-01").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["country"], "SA");
+    assert_eq!(json["date"], "2026-03-01");
+
+    let cities = json["cities"].as_object().unwrap();
+    println!("{:?}", cities.keys().collect::<Vec<_>>());
+    for city in ["mecca", "medina", "riyadh"] {
+        let day = &cities[city];
+        assert_eq!(day["location"]["timezone"], "Asia/Riyadh", "{}", city);
+        assert_eq!(day["state"], "Normal");
+        assert!(day["events"]["fajr"]["time"].is_string() && day["events"]["isha"]["time"].is_string());
+    }
+
+    let (status, _) = get_json("/api/v1/country?code=ZZ").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_month_stockholm_june() {
+    let (status, json) = get_json("/api/month?city=stockholm&year=2026&month=6").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let days = json.as_array().unwrap();
+    assert_eq!(days.len(), 30);
+    for (i, day) in days.iter().enumerate() {
+        assert_eq!(day["date"], format!("2026-06-{:02}", i + 1));
+        assert_eq!(day["location"]["name"], "stockholm");
+    }
+
+    let (status, _) = get_json("/api/month?city=stockholm&month=13").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_month_hijri_dates() {
+    let (status, json) = get_json("/api/v1/month?city=mecca&year=2026&month=3").await;
+    assert_eq!(status, StatusCode::OK);
+    let days = json.as_array().unwrap();
+    println!("{}", days[0]["hijri"]);
+
+    // March 2026 opens in Ramadan 1447 and runs into Shawwal
+    assert_eq!(days[0]["hijri"]["year"], 1447);
+    assert_eq!(days[0]["hijri"]["month_name"], "Ramadan");
+    assert_eq!(days[30]["hijri"]["month_name"], "Shawwal");
+    for pair in days.windows(2) {
+        let (a, b) = (&pair[0]["hijri"], &pair[1]["hijri"]);
+        assert!(b["day"] == 1 || b["day"].as_u64() == a["day"].as_u64().map(|d| d + 1), "{} -> {}", a, b);
+    }
+
+    // Single-day times stay as they were
+    let (_, json) = get_json("/api/v1/times?city=mecca&date=2026-03-01").await;
+    assert!(json.get("hijri").is_none());
+}
+
+#[tokio::test]
+async fn test_month_csv() {
+    let (router, _dir) = offline_router();
+    let response = router
+        .oneshot(Request::get("/api/v1/month?city=stockholm&year=2026&month=3&format=csv").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert!(headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/csv"));
+    assert_eq!(headers[header::CONTENT_DISPOSITION], "attachment; filename=\"polaris-stockholm-2026-03.csv\"");
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    println!("{}", rows[1]);
+    assert_eq!(rows[0], "date,fajr,sunrise,dhuhr,asr,maghrib,isha,state");
+    assert_eq!(rows.len(), 32);
+    assert!(rows[31].starts_with("2026-03-31,"));
+
+    let (status, _) = get_json("/api/v1/month?city=stockholm&format=xml").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_month_lengths_and_year_range() {
+    for (query, days, last) in [
+        ("year=2024&month=2", 29, "2024-02-29"),
+        ("year=2025&month=2", 28, "2025-02-28"),
+        ("year=2026&month=12", 31, "2026-12-31"),
+    ] {
+        let (status, json) = get_json(&format!("/api/v1/month?city=mecca&{}", query)).await;
+        assert_eq!(status, StatusCode::OK, "{}", query);
+        let list = json.as_array().unwrap();
+        assert_eq!(list.len(), days, "{}", query);
+        assert_eq!(list[days - 1]["date"], last);
+    }
+
+    for year in ["0", "-1", "10000"] {
+        let (status, json) = get_json(&format!("/api/v1/month?city=mecca&year={}&month=1", year)).await;
+        println!("{}", json);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "year {}", year);
+        assert!(json["error"].as_str().unwrap().contains("Invalid year"));
+    }
+}
+
+#[tokio::test]
+async fn test_times_svg() {
+    let (status, content_type, body) = get("/api/times.svg?city=cairo&date=2026-03-20").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("image/svg+xml"));
+    let svg = String::from_utf8(body).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(">cairo</text>"));
+}
+
+#[tokio::test]
+async fn test_times_include_sidereal() {
+    let (status, json) = get_json("/api/times?city=mecca&date=2026-02-14&include=sidereal").await;
+    assert_eq!(status, StatusCode::OK);
+    let dhuhr = &json["events"]["dhuhr"]["sidereal"];
+    println!("{}", dhuhr);
+    assert!(dhuhr["hour_angle"].as_f64().unwrap().abs() < 0.5);
+    assert!(dhuhr["lst"].is_string());
+
+    let (_, json) = get_json("/api/times?city=mecca&date=2026-02-14").await;
+    assert!(json["events"]["dhuhr"].get("sidereal").is_none());
+
+    let (status, json) = get_json("/api/times?city=mecca&date=2026-02-14&include=sidereal,makruh").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["makruh_windows"].as_array().unwrap().len(), 3);
+    assert_eq!(json["makruh_windows"][1]["reason"], "zenith");
+
+    let (status, _) = get_json("/api/times?city=mecca&include=bogus").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_times_elevation() {
+    let (status, high) = get_json("/api/times?city=mecca&date=2026-03-20&elevation=2000").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(high["computation"]["elevation_m"], 2000.0);
+    let (_, sea) = get_json("/api/times?city=mecca&date=2026-03-20").await;
+    println!("maghrib {} -> {}", sea["events"]["maghrib"]["time"], high["events"]["maghrib"]["time"]);
+    assert!(high["events"]["maghrib"]["time"].as_str() > sea["events"]["maghrib"]["time"].as_str());
+    assert_eq!(high["events"]["isha"]["time"], sea["events"]["isha"]["time"]);
+
+    let (status, _) = get_json("/api/times?city=mecca&elevation=20000").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_times_combined() {
+    let (status, json) = get_json("/api/times?city=mecca&date=2026-02-14&combine=true").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["combined"]["dhuhr_asr"], json["events"]["dhuhr"]["time"]);
+    assert_eq!(json["combined"]["maghrib_isha"], json["events"]["maghrib"]["time"]);
+
+    let (_, json) = get_json("/api/times?city=mecca&date=2026-02-14").await;
+    assert!(json.get("combined").is_none());
+}
+
+#[tokio::test]
+async fn test_year_compact_2025() {
+    let (status, json) = get_json("/api/year?city=mecca&year=2025&schema=compact").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_days"], 365);
+    assert_eq!(json["total_pages"], 1);
+
+    let days = json["days"].as_array().unwrap();
+    assert_eq!(days.len(), 365);
+    assert_eq!(days[0]["date"], "2025-01-01");
+    assert_eq!(days[364]["date"], "2025-12-31");
+    for day in days {
+        let fields = day.as_object().unwrap();
+        assert_eq!(fields.len(), 9, "unexpected fields: {:?}", fields.keys());
+        assert!(!fields.contains_key("solar") && !fields.contains_key("wave_debug"));
+        assert!(day["fajr"].is_string() && day["hijri"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn test_year_pagination() {
+    let (status, json) = get_json("/api/year?city=stockholm&year=2024&page=3&per_page=30").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["total_days"], 366);
+    assert_eq!(json["total_pages"], 13);
+    let days = json["days"].as_array().unwrap();
+    assert_eq!(days.len(), 30);
+    assert_eq!(days[0]["date"], "2024-03-01");
+    assert!(days[0]["solar"].is_object());
+
+    let (_, json) = get_json("/api/year?city=stockholm&year=2024&page=13&per_page=30").await;
+    assert_eq!(json["days"].as_array().unwrap().len(), 6);
+
+    let (status, _) = get_json("/api/year?city=stockholm&year=2024&page=14&per_page=30").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get_json("/api/year?city=stockholm&schema=tiny").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_window_between_dhuhr_and_asr() {
+    let (status, day) = get_json("/api/times?city=mecca&date=2026-02-14").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, json) = get_json("/api/window?city=mecca&at=2026-02-14T13:30:00%2B03:00").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["current"], "Dhuhr");
+    assert_eq!(json["window_end"], day["events"]["asr"]["time"]);
+
+    let (status, _) = get_json("/api/window?city=mecca&at=yesterday").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_crescent_map() {
+    let (status, json) = get_json("/api/crescent-map?date=2026-02-17&lat_step=30&lon_step=30").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["conjunction_utc"].as_str().unwrap().starts_with("2026-02-17"));
+    let points = json["points"].as_array().unwrap();
+    assert_eq!(points.len(), 5 * 12);
+    assert!(points.iter().all(|p| ["A", "B", "C", "D"].contains(&p["zone"].as_str().unwrap())));
+
+    // Steps below the cap are clamped
+    let (_, json) = get_json("/api/crescent-map?date=2026-02-17&lat_step=60&lon_step=1").await;
+    assert_eq!(json["lon_step"], 5.0);
+}
+
+#[tokio::test]
+async fn test_moon_full_and_new() {
+    let (status, json) = get_json("/api/v1/moon?date=2025-01-13").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["phase"], "Full Moon");
+    assert!(json["illumination"].as_f64().unwrap() > 0.98);
+    assert!(json["elongation"].as_f64().unwrap() > 160.0);
+    let distance = json["distance_km"].as_f64().unwrap();
+    assert!((350000.0..410000.0).contains(&distance));
+
+    let (_, json) = get_json("/api/v1/moon?date=2026-02-17").await;
+    assert_eq!(json["phase"], "New Moon");
+
+    let (status, _) = get_json("/api/v1/moon?date=2026-02-30").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_analemma() {
+    let (status, json) = get_json("/api/v1/analemma?lat=51.48&lon=0&year=2026&step=7").await;
+    assert_eq!(status, StatusCode::OK);
+    let points = json["points"].as_array().unwrap();
+    assert_eq!(points.len(), 365_usize.div_ceil(7));
+    assert_eq!(points[1]["date"], "2026-01-08");
+
+    let eot: Vec<f64> = points.iter().map(|p| p["equation_of_time"].as_f64().unwrap()).collect();
+    assert!(eot.iter().any(|e| *e < -10.0) && eot.iter().any(|e| *e > 10.0));
+
+    let (status, _) = get_json("/api/v1/analemma?lat=95&lon=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_month_tz_override_not_cached_across_zones() {
+    // One router, so the second request would hit a colliding cache entry
+    let (router, _dir) = offline_router();
+    let mut dhuhr = Vec::new();
+    for tz in ["UTC", "Asia/Riyadh"] {
+        let uri = format!("/api/month?city=mecca&year=2026&month=2&tz={}", tz);
+        let response = router.clone()
+            .oneshot(Request::get(uri.as_str()).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["location"]["timezone"], tz);
+        dhuhr.push(json[0]["events"]["dhuhr"]["time"].as_str().unwrap().to_string());
+    }
+    println!("Dhuhr UTC={} Riyadh={}", dhuhr[0], dhuhr[1]);
+    assert_ne!(dhuhr[0], dhuhr[1]);
+    assert!(dhuhr[0].starts_with("09:") && dhuhr[1].starts_with("12:"));
+}
+
+async fn post_warm(router: &Router, body: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::post("/api/v1/admin/warm").header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header("x-admin-token", token);
+    }
+    let response = router.clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_admin_warm_then_cache_hit() {
+    let (resolver, _dir) = offline_resolver();
+    let state = Arc::new(AppState::with_resolver(resolver).with_admin_token(Some("secret".into())));
+    let router = build_router_with_state(state.clone());
+    let body = r#"{"cities": ["mecca", "stockholm"], "from": "2026-03-01", "to": "2026-03-03"}"#;
+
+    // Without a configured token the admin endpoints stay closed
+    let (closed, _dir) = offline_router();
+    let (status, _) = post_warm(&closed, body, Some("secret")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = post_warm(&router, body, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(state.cache.lock().unwrap().is_empty());
+
+    let (status, json) = post_warm(&router, body, Some("secret")).await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["warmed"], 6);
+    assert_eq!(json["locations"], 2);
+    assert_eq!(state.cache.lock().unwrap().len(), 6);
+
+    // Idempotent: a second run finds everything cached
+    let (_, json) = post_warm(&router, body, Some("secret")).await;
+    assert_eq!((json["warmed"].as_u64(), json["already_cached"].as_u64()), (Some(0), Some(6)));
+
+    for city in ["mecca", "stockholm"] {
+        let uri = format!("/api/v1/times?city={}&date=2026-03-02", city);
+        let response = router.clone()
+            .oneshot(Request::get(uri.as_str()).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cache"], "HIT", "{}", city);
+    }
+}
+
+#[tokio::test]
+async fn test_persisted_day_served_after_restart() {
+    let (resolver, dir) = offline_resolver();
+    let path = dir.path().join("compute_cache.json");
+    let uri = "/api/v1/times?city=stockholm&date=2026-03-02";
+    let fetch = |state: Arc<AppState>| async move {
+        let response = build_router_with_state(state)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let cache = response.headers()["x-cache"].to_str().unwrap().to_string();
+        (cache, to_bytes(response.into_body(), usize::MAX).await.unwrap())
+    };
+
+    let state = Arc::new(AppState::with_resolver(resolver).with_cache_file(path.clone()));
+    let (cache, computed) = fetch(state.clone()).await;
+    assert_eq!(cache, "MISS");
+    assert_eq!(state.save_cache().unwrap().unwrap().1, 1);
+
+    let (resolver, _dir) = offline_resolver();
+    let (cache, restored) = fetch(Arc::new(AppState::with_resolver(resolver).with_cache_file(path))).await;
+    assert_eq!(cache, "HIT");
+    assert_eq!(restored, computed);
+}
+
+#[tokio::test]
+async fn test_fixed_clock_sets_today() {
+    use chrono::{TimeZone, Utc};
+    use polaris_chronos::clock::FixedClock;
+
+    let (resolver, _dir) = offline_resolver();
+    let now = Utc.with_ymd_and_hms(2026, 4, 15, 9, 0, 0).unwrap();
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver).with_clock(FixedClock(now))));
+
+    let fetch = |uri: &'static str| {
+        let router = router.clone();
+        async move {
+            let response = router.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        }
+    };
+
+    let json = fetch("/api/v1/times?city=mecca").await;
+    assert_eq!(json["date"], "2026-04-15");
+
+    // Shawwal 1447 has begun, so the upcoming Ramadan is 1448
+    let json = fetch("/api/v1/hijri?lat=21.42&lon=39.83&tz=Asia/Riyadh").await;
+    println!("{}", json["ramadan"]);
+    assert_eq!(json["ramadan"]["hijri_year"], 1448);
+}
+
+#[tokio::test]
+async fn test_ramadan_mecca_1447() {
+    let (status, json) = get_json("/api/v1/ramadan?city=mecca&year=1447").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["hijri_year"], 1447);
+    assert_eq!(json["start"], "2026-02-19");
+
+    let (status, _) = get_json("/api/v1/ramadan?city=mecca&year=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_hijri_gregorian_conversion() {
+    let (status, json) = get_json("/api/v1/hijri/convert?date=2026-02-17").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["gregorian"], "2026-02-17");
+    assert_eq!(json["hijri"]["year"], 1447);
+    let month = json["hijri"]["month"].as_u64().unwrap();
+    assert!(month == 8 || month == 9, "month {}", month);
+    assert_eq!(json["month_name"], if month == 9 { "Ramadan" } else { "Sha'ban" });
+    assert_eq!(json["month_name_ar"], if month == 9 { "رمضان" } else { "شعبان" });
+
+    // The reverse conversion lands back on the same day
+    let (status, back) = get_json(&format!(
+        "/api/v1/gregorian?hy=1447&hm={}&hd={}", month, json["hijri"]["day"],
+    )).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(back["gregorian"], "2026-02-17");
+
+    let (status, json) = get_json("/api/v1/gregorian?hy=1447&hm=13&hd=1").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"].as_str().unwrap().contains("Invalid Hijri date"));
+}
+
+#[tokio::test]
+async fn test_compare_mwl_isna_mecca() {
+    let (status, json) = get_json("/api/v1/compare?city=mecca&date=2026-03-01&method_a=mwl&method_b=isna").await;
+    println!("{}", json["differences"]);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["method_a"], "MWL");
+    assert_eq!(json["method_b"], "ISNA");
+    assert_eq!(json["a"]["method"], "MWL");
+    assert_eq!(json["b"]["method"], "ISNA");
+
+    let diff = |event: &str| json["differences"][event].as_f64().unwrap();
+    // ISNA's shallower 15° angles put Fajr later and Isha earlier than MWL's 18°/17°
+    assert!(diff("fajr") > 0.0);
+    assert!(diff("isha") < 0.0);
+    assert_eq!(diff("dhuhr"), 0.0);
+    assert_eq!(diff("asr"), 0.0);
+
+    let (status, _) = get_json("/api/v1/compare?city=mecca&method_a=mwl&method_b=nope").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// A geocoder that always answers HTTP 429.
+struct RateLimitedGeocoder;
+
+impl GeocodeProvider for RateLimitedGeocoder {
+    fn resolve(&self, _query: &str, _hint: Option<&str>, _granularity: Granularity) -> Result<ResolvedLocation, LocationError> {
+        Err(LocationError::RateLimited { retry_after: Some(std::time::Duration::from_secs(30)) })
+    }
+}
+
+#[tokio::test]
+async fn test_client_rate_limit_is_429() {
+    const LIMIT: u32 = 5;
+    let (resolver, _dir) = offline_resolver();
+    let state = AppState::with_resolver(resolver).with_rate_limit(Some(LIMIT)).with_trusted_proxy(true);
+    let router = build_router_with_state(Arc::new(state));
+    let proxy: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+    let request = |ip: &str, uri: &str| Request::get(uri)
+        .header("x-forwarded-for", format!("192.0.2.1, {}", ip))
+        .extension(ConnectInfo(proxy))
+        .body(Body::empty())
+        .unwrap();
+
+    for i in 0..LIMIT {
+        // The legacy and versioned paths share one budget
+        let uri = if i % 2 == 0 { "/api/v1/cities" } else { "/api/cities" };
+        let response = router.clone().oneshot(request("198.51.100.7", uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "request {}", i + 1);
+    }
+    let response = router.clone().oneshot(request("198.51.100.7", "/api/v1/cities")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    println!("Retry-After: {:?}", response.headers()[header::RETRY_AFTER]);
+    let retry_after: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=12).contains(&retry_after), "one token takes 12s at 5/min");
+
+    // Other clients and the dashboard are unaffected
+    let response = router.clone().oneshot(request("198.51.100.8", "/api/v1/cities")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = router.oneshot(request("198.51.100.7", "/")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Without a trusted proxy the header is ignored and the peer is the client
+    let (resolver, _dir) = offline_resolver();
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver).with_rate_limit(Some(1))));
+    let response = router.clone().oneshot(request("198.51.100.7", "/api/v1/cities")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = router.oneshot(request("198.51.100.8", "/api/v1/cities")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_resolve_rate_limited_is_429() {
+    let dir = TempDir::new().unwrap();
+    let resolver = LocationResolver::with_cache(LocationCache::load_from(dir.path().join("cache.json")))
+        .with_provider(RateLimitedGeocoder);
+    let router = build_router_with_state(Arc::new(AppState::with_resolver(resolver)));
+
+    let response = router.clone()
+        .oneshot(Request::get("/api/v1/resolve?query=Nowhereville").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+
+    // Built-in cities still resolve while the geocoder is limited
+    let response = router
+        .oneshot(Request::get("/api/v1/resolve?query=Mecca").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_extremes_mecca_fajr() {
+    let (status, json) = get_json("/api/v1/extremes?city=mecca&prayer=fajr&year=2026").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["prayer"], "Fajr");
+    assert!(json["earliest"]["date"].as_str().unwrap().starts_with("2026-06"));
+    assert!(json["latest"]["date"].as_str().unwrap().starts_with("2026-01"));
+
+    let (status, _) = get_json("/api/v1/extremes?city=mecca&prayer=tahajjud").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_qibla_stockholm() {
+    let (status, json) = get_json("/api/v1/qibla?city=stockholm").await;
+    println!("{}", json);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["name"], "stockholm");
+    let bearing = json["bearing"].as_f64().unwrap();
+    assert!((bearing - 148.2).abs() < 1.0, "bearing {}", bearing);
+    assert_eq!(json["compass"], "SSE");
+    assert!(json["distance_km"].as_f64().unwrap() > 4_000.0);
+
+    let (status, _) = get_json("/api/v1/qibla?lat=95&lon=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown_returns() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (router, _dir) = offline_router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(serve_until(listener, router, async { stopped.await.ok(); }));
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"GET /api/v1/cities HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", &response[..response.len().min(80)]);
+
+    stop.send(()).unwrap();
+    let result = tokio::time::timeout(std::time::Duration::from_secs(5), server).await
+        .expect("serve returns after the shutdown signal");
+    result.unwrap().unwrap();
+    assert!(tokio::net::TcpStream::connect(addr).await.is_err(), "listener is closed");
+}
@@ -0,0 +1,48 @@
+//! Snapshot tests for the solver across a matrix of cities, dates,
+//! methods and strategies.
+//!
+//! Any change to a serialized `SolverOutput` fails here. After an
+//! intentional change, review the diffs with `cargo insta review` (or rerun
+//! with `INSTA_UPDATE=always`) and commit the updated `.snap` files.
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use polaris_chronos::method::CalculationMethod;
+use polaris_chronos::schedule::GapStrategy;
+use polaris_chronos::solver::{Location, Solver, SolverOutput};
+
+const CITIES: [(&str, f64, f64, Tz); 4] = [
+    ("mecca", 21.4225, 39.8262, chrono_tz::Asia::Riyadh),
+    ("stockholm", 59.3293, 18.0686, chrono_tz::Europe::Stockholm),
+    ("tromso", 69.6492, 18.9553, chrono_tz::Europe::Oslo),
+    ("svalbard", 78.2232, 15.6267, chrono_tz::Arctic::Longyearbyen),
+];
+
+/// March equinox, June solstice, September equinox, December solstice.
+const DATES: [(u32, u32); 4] = [(3, 20), (6, 21), (9, 22), (12, 21)];
+
+const METHODS: [CalculationMethod; 2] = [CalculationMethod::MWL, CalculationMethod::ISNA];
+
+const STRATEGIES: [GapStrategy; 2] = [GapStrategy::Strict, GapStrategy::Projected45];
+
+fn solve_year(lat: f64, lon: f64, tz: Tz, method: CalculationMethod, strategy: GapStrategy) -> Vec<SolverOutput> {
+    let solver = Solver::new(Location::new(lat, lon), tz)
+        .with_method(method)
+        .with_strategy(strategy);
+    DATES.iter()
+        .map(|&(month, day)| solver.solve(NaiveDate::from_ymd_opt(2026, month, day).unwrap(), false, false))
+        .collect()
+}
+
+#[test]
+fn test_solver_output_matrix() {
+    for (city, lat, lon, tz) in CITIES {
+        for method in METHODS {
+            for strategy in STRATEGIES {
+                let name = format!("{}_{}_{}", city, method, strategy).to_lowercase();
+                let outputs = solve_year(lat, lon, tz, method, strategy);
+                insta::assert_json_snapshot!(name, outputs);
+            }
+        }
+    }
+}